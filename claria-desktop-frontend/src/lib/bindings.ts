@@ -52,6 +52,58 @@ async setPreferredModel(modelId: string | null) : Promise<Result<null, string>>
     else return { status: "error", error: e  as any };
 }
 },
+async getEncryptionStatus() : Promise<Result<EncryptionStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_encryption_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Generate a new practice master key and start encrypting all writes.
+ * 
+ * Normally done when the bucket is provisioned; this is for practices set
+ * up before encryption was on by default. Existing plaintext objects stay
+ * readable and are encrypted the next time they are saved. Fails if a key
+ * is already installed, or if the bucket is already encrypted with a key
+ * from another machine.
+ */
+async enableEncryption() : Promise<Result<EncryptionStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("enable_encryption") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Export the practice master key as base64, for backup or for setting up
+ * another machine in the same practice.
+ */
+async exportMasterKey() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_master_key") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Install a practice master key exported from another machine.
+ * 
+ * Refuses to replace a different key that is already installed, since
+ * anything written with it would become unreadable, and refuses a key
+ * other than the one the bucket is encrypted with.
+ */
+async importMasterKey(key: string) : Promise<Result<EncryptionStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_master_key", { key }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * Assess the provided credentials: validates them via STS and classifies
  * them as root / IAM admin / scoped Claria / insufficient.
@@ -244,6 +296,72 @@ async deleteClient(clientId: string) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Permanently purge a client: every version and delete marker of its
+ * record files, chat history and client JSON, plus its search index
+ * entries. This cannot be undone.
+ * 
+ * Unlike [`delete_client`], nothing is left to restore. A tombstone with
+ * the client ID and counts (but no PHI) is kept for the audit trail.
+ */
+async purgeClient(clientId: string) : Promise<Result<PurgeResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("purge_client", { clientId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Search across clients, record files (by their text), chat histories
+ * and core models, narrowed by `filters`, one page of `limit` hits from
+ * `offset` at a time. A blank query matches every document the filters
+ * allow. Returns no hits if nothing has been indexed yet.
+ */
+async searchRecords(query: string, filters: SearchFilters, offset: number, limit: number) : Promise<Result<SearchResponse, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("search_records", { query, filters, offset, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Rebuild the search index from everything in the bucket, replacing the
+ * stored index. The recovery path when the index is missing or corrupted.
+ */
+async rebuildSearchIndex(onProgress: TAURI_CHANNEL<RebuildIndexProgress>) : Promise<Result<RebuildIndexResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rebuild_search_index", { onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The synonym groups search matches as one term.
+ */
+async getSearchSynonyms() : Promise<Result<SearchSynonyms, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_search_synonyms") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Replace the user's synonym groups and rebuild the search index so
+ * existing documents are matched with them. Blank terms and groups with
+ * fewer than two terms are dropped.
+ */
+async saveSearchSynonyms(custom: string[][], onProgress: TAURI_CHANNEL<RebuildIndexProgress>) : Promise<Result<RebuildIndexResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_search_synonyms", { custom, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * List files in a client's record, excluding sidecar `.text` files.
  */
@@ -258,12 +376,21 @@ async listRecordFiles(clientId: string) : Promise<Result<RecordFile[], string>>
 /**
  * Upload a file to a client's record from a local file path.
  * 
+ * The file is streamed from disk (as a multipart upload when large), with
+ * progress reported on `on_progress`. An interrupted upload of the same
+ * file resumes from the parts already in S3.
+ * 
  * If the file is a PDF or DOCX, a sidecar `.text` file is generated
  * via Bedrock document text extraction and uploaded alongside.
+ * 
+ * The file's SHA-256 is stored as object metadata. When the client's
+ * record already holds a file with the same contents under another name,
+ * the result names it in `duplicate_of`, and that file's sidecar is copied
+ * instead of running extraction again.
  */
-async uploadRecordFile(clientId: string, filePath: string) : Promise<Result<RecordFile, string>> {
+async uploadRecordFile(clientId: string, filePath: string, onProgress: TAURI_CHANNEL<RecordUploadProgress>) : Promise<Result<RecordUpload, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("upload_record_file", { clientId, filePath }) };
+    return { status: "ok", data: await TAURI_INVOKE("upload_record_file", { clientId, filePath, onProgress }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -501,6 +628,51 @@ async restorePromptVersion(promptName: string, versionId: string) : Promise<Resu
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * List the text snippets, sorted by title.
+ */
+async listSnippets() : Promise<Result<Snippet[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_snippets") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Save a new text snippet.
+ */
+async createSnippet(title: string, body: string) : Promise<Result<Snippet, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_snippet", { title, body }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Change a snippet's title and body, provided nobody has changed it since
+ * it was read at `etag`.
+ */
+async updateSnippet(id: string, title: string, body: string, etag: string) : Promise<Result<Snippet, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_snippet", { id, title, body, etag }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete a snippet. It stays in the bucket's version history.
+ */
+async deleteSnippet(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_snippet", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * List all versions of a specific file in a client's record.
  */
@@ -584,6 +756,179 @@ async restoreClient(clientId: string, versionId: string) : Promise<Result<null,
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Dry run of [`restore_client_as_of`]: list which files would be restored,
+ * re-deleted or left alone, without writing anything.
+ */
+async previewClientRestore(clientId: string, asOf: string) : Promise<Result<ClientRestoreEntry[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_client_restore", { clientId, asOf }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Restore a client's entire record — files, sidecars, chat histories and
+ * the client JSON — to its state at `as_of` (RFC 3339).
+ * 
+ * Like [`restore_file_version`], old content is written back as new
+ * versions and files created since are deleted with delete markers, so the
+ * full version history is preserved.
+ */
+async restoreClientAsOf(clientId: string, asOf: string) : Promise<Result<ClientRestoreSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_client_as_of", { clientId, asOf }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Write a backup of the whole bucket to a local `.tar.zst` archive at
+ * `path`, encrypted with the practice master key.
+ * 
+ * With `include_versions`, every version and delete marker is archived so
+ * the file history survives a restore; otherwise only current objects are.
+ */
+async exportBackup(path: string, includeVersions: boolean, onProgress: TAURI_CHANNEL<BackupProgress>) : Promise<Result<BackupSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_backup", { path, includeVersions, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Restore a backup archive into the configured bucket, for example after
+ * moving to a new AWS account or region.
+ * 
+ * The bucket must be empty, and the archive is checked in full before
+ * anything is written. Restored versions get new version IDs and
+ * timestamps.
+ */
+async importBackup(path: string, onProgress: TAURI_CHANNEL<BackupProgress>) : Promise<Result<BackupSummary, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_backup", { path, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Check `records/`, `clients/` and `reports/` for inconsistencies between
+ * record files, sidecars and JSON.
+ * 
+ * With `dry_run`, issues are only reported. Otherwise each one with a
+ * repair is fixed: missing sidecars are re-extracted, orphans deleted
+ * (restorable from version history) and unparseable JSON moved to
+ * `_quarantine/`. A failed repair is reported on its issue and does not
+ * stop the others.
+ */
+async scrubBucket(dryRun: boolean) : Promise<Result<ScrubResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("scrub_bucket", { dryRun }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Share a record file through a presigned URL that expires after
+ * `expires_in_hours` (at most 7 days).
+ * 
+ * The recipient gets an unencrypted copy of the file, which is deleted
+ * when the link is revoked or cleaned up after it expires. The share is
+ * recorded under `_audit/shares/` with who issued it, to whom and when.
+ * 
+ * With `max_downloads`, each link counts as one download and lasts at
+ * most an hour; further links come from [`issue_share_link`] until the
+ * limit is used up.
+ */
+async createShareLink(clientId: string, filename: string, recipient: string, expiresInHours: number, maxDownloads: number | null) : Promise<Result<IssuedShareLink, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_share_link", { clientId, filename, recipient, expiresInHours, maxDownloads }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Issue another URL for an outstanding share, for when the recipient
+ * lost theirs or a limited link expired before it was used. The URL is
+ * recorded on the share and counts against its download limit.
+ */
+async issueShareLink(shareId: string) : Promise<Result<IssuedShareLink, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("issue_share_link", { shareId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List issued share links, newest first. Unless `include_inactive` is set,
+ * only links that are neither revoked nor expired are returned.
+ * 
+ * Copies behind expired links are deleted along the way.
+ */
+async listShareLinks(includeInactive: boolean) : Promise<Result<ShareLink[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_share_links", { includeInactive }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Revoke a share link: the shared copy is deleted, so the URL stops
+ * working immediately, and the revocation is added to the audit record.
+ */
+async revokeShareLink(shareId: string) : Promise<Result<ShareLink, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("revoke_share_link", { shareId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List record writes waiting in the offline outbox, oldest first.
+ */
+async listOutbox() : Promise<Result<OutboxItem[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_outbox") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Replay queued record writes against S3.
+ * 
+ * Uploaded files that sync get their text sidecar generated, as they would
+ * have if the upload had gone through at the time.
+ */
+async syncOutbox() : Promise<Result<OutboxSyncResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("sync_outbox") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Resolve a conflicted or failed outbox item. The resolved write is
+ * replayed on the next `sync_outbox`.
+ */
+async resolveOutboxItem(id: string, resolution: OutboxResolution) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_outbox_item", { id, resolution }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * List all Whisper model tiers with their download/active status.
  */
@@ -655,6 +1000,57 @@ async transcribeMemo(audioPcmBase64: string) : Promise<Result<TranscribeMemoResu
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Report whether the embedding model is downloaded.
+ */
+async getEmbeddingModel() : Promise<Result<EmbeddingModelInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_embedding_model") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Download the embedding model from Hugging Face.
+ */
+async downloadEmbeddingModel() : Promise<Result<EmbeddingModelInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("download_embedding_model") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete the embedding model and unload it. Vectors already stored with
+ * the search index are kept for when it is downloaded again.
+ */
+async deleteEmbeddingModel() : Promise<Result<EmbeddingModelInfo, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_embedding_model") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Find record passages by meaning as well as by words, using the local
+ * embedding model. No text is sent off the machine to be embedded.
+ * 
+ * Passages indexed since the last semantic search are embedded first and
+ * their vectors flushed with the index. If another device flushed the
+ * index in the meantime the vectors are only used for this search; the
+ * next search embeds them again.
+ */
+async semanticSearch(query: string, clientId: string | null, limit: number) : Promise<Result<SemanticHit[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("semantic_search", { query, clientId, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * Check whether a newer release exists on GitHub.
  * 
@@ -702,6 +1098,22 @@ async setHourlyCostData(enabled: boolean) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Report stored bytes per client and per kind of file, counting every
+ * version S3 bills for.
+ * 
+ * When `cost` holds a Cost Explorer result grouped by service, the S3
+ * total in it is split between clients and prefixes by stored bytes, so no
+ * extra Cost Explorer request is made.
+ */
+async getStorageUsage(cost: CostAndUsageResult | null) : Promise<Result<StorageUsageReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_storage_usage", { cost }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async openUrl(url: string) : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("open_url", { url }) };
@@ -814,6 +1226,18 @@ assumed_role_arn: string;
  * The account ID of the sub-account we assumed into.
  */
 account_id: string }
+/**
+ * Backup progress, streamed to the frontend via Channel<T>.
+ */
+export type BackupProgress = { entries_done: number; entries_total: number; bytes_done: number }
+/**
+ * What a backup archive holds.
+ */
+export type BackupSummary = { 
+/**
+ * The bucket the backup was taken from.
+ */
+bucket: string; created_at: string; objects: number; delete_markers: number; bytes: number }
 /**
  * The result of a full bootstrap attempt.
  */
@@ -845,6 +1269,22 @@ export type ChatModel = { model_id: string; name: string }
  */
 export type ChatResponse = { chat_id: string; content: string }
 export type ChatRole = "user" | "assistant"
+/**
+ * One entry in the dry-run listing of a point-in-time client restore.
+ * 
+ * `filename` is relative to the client's record, or `client.json` for the
+ * client record itself.
+ */
+export type ClientRestoreEntry = { filename: string; action: RestoreFileAction; version_id: string | null }
+/**
+ * Counts of what a point-in-time client restore wrote.
+ */
+export type ClientRestoreSummary = { restored: number; deleted: number; unchanged: number }
+export type ClientStorageUsage = { client_id: string; 
+/**
+ * `None` for a client that has been deleted.
+ */
+name: string | null; usage: StorageUsage }
 export type ClientSummary = { id: string; name: string; created_at: string }
 /**
  * Redacted config info safe to send to the frontend.
@@ -900,6 +1340,36 @@ export type DeletedClient = { id: string; name: string; deleted_at: string | nul
  * A file that has been deleted (has a delete marker as the latest version).
  */
 export type DeletedFile = { filename: string; deleted_at: string | null; version_id: string }
+export type DocTypeCount = { doc_type: string; count: number }
+/**
+ * Whether client-side encryption is enabled on this machine.
+ */
+/**
+ * Status of the local sentence-embedding model used by [`semantic_search`].
+ */
+export type EmbeddingModelInfo = { 
+/**
+ * The Hugging Face repository the model comes from.
+ */
+model_id: string; download_size: string; downloaded: boolean; model_size_bytes: number | null }
+/**
+ * Whether client-side encryption is enabled on this machine, and which key
+ * the bucket's data is encrypted with.
+ * 
+ * Without a master key every object is written to the bucket in plaintext;
+ * the UI flags that. A `bucket_key_id` that differs from `key_id` (or is
+ * set while `key_id` is not) means the practice key has to be imported
+ * from the machine that created it.
+ */
+export type EncryptionStatus = { enabled: boolean; 
+/**
+ * Short fingerprint of the master key, safe to display.
+ */
+key_id: string | null; 
+/**
+ * Fingerprint of the practice key recorded in the bucket, if any.
+ */
+bucket_key_id: string | null }
 /**
  * Structured before/after for a single field that doesn't match desired state.
  * 
@@ -927,12 +1397,62 @@ actual: JsonValue }
  * A single version of a file in a client's record.
  */
 export type FileVersion = { version_id: string; size: number; last_modified: string | null; is_latest: boolean }
+/**
+ * A newly issued share link and its URL. The URL is only available here;
+ * it is not stored.
+ */
+export type IssuedShareLink = { share: ShareLink; url: string }
 export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
 export type Lifecycle = "data" | "managed"
 /**
  * Fresh credentials created during the bootstrap flow.
  */
 export type NewCredentials = { access_key_id: string; secret_access_key: string; iam_user_arn: string }
+/**
+ * A record write waiting in the offline outbox.
+ */
+export type OutboxItem = { id: string; client_id: string; filename: string; queued_at: string; status: OutboxItemStatus; error: string | null }
+export type OutboxItemStatus = 
+/**
+ * Waiting to be synced.
+ */
+"pending" | 
+/**
+ * The file was changed elsewhere; needs resolving.
+ */
+"conflict" | 
+/**
+ * S3 rejected the write; see `error`.
+ */
+"failed"
+/**
+ * How to resolve a conflicted outbox item.
+ */
+export type OutboxResolution = 
+/**
+ * Replace the other version with this machine's.
+ */
+"overwrite" | 
+/**
+ * Drop this machine's version.
+ */
+"discard" | 
+/**
+ * Save this machine's version as a conflicted copy next to the other.
+ */
+"keep_both"
+/**
+ * How a `sync_outbox` pass went.
+ */
+export type OutboxSyncResult = { synced: number; conflicts: number; failed: number; 
+/**
+ * Entries still queued, including conflicts and failures.
+ */
+remaining: number; 
+/**
+ * S3 was still unreachable; the pass stopped early.
+ */
+offline: boolean }
 /**
  * A single entry in the plan — the spec annotated with what happened.
  * 
@@ -946,7 +1466,27 @@ export type PlanEntry = { spec: ResourceSpec; action: Action; cause: Cause; drif
  * Live state read from AWS (if the resource exists).
  */
 actual: JsonValue | null }
+/**
+ * Usage of objects outside client records, by top-level prefix.
+ */
+export type PrefixStorageUsage = { prefix: string; usage: StorageUsage }
 export type ProvisionerProgress = { kind: "scan_started"; label: string; index: number; total: number } | { kind: "scan_completed"; label: string; index: number; total: number } | { kind: "apply_started"; label: string; action: string; index: number; total: number } | { kind: "apply_completed"; label: string; action: string; index: number; total: number } | { kind: "escalation_step"; label: string; status: string }
+/**
+ * What [`purge_client`] permanently removed.
+ */
+export type PurgeResult = { objects: number; versions: number; delete_markers: number; index_documents: number }
+/**
+ * Progress of a search index rebuild, streamed to the frontend via Channel<T>.
+ */
+export type RebuildIndexProgress = { documents_done: number; documents_total: number }
+/**
+ * What [`rebuild_search_index`] indexed.
+ */
+export type RebuildIndexResult = { documents: number; 
+/**
+ * Objects whose JSON could not be parsed and were left out.
+ */
+skipped: number }
 /**
  * A record file with its readable text content, for chat context.
  */
@@ -955,6 +1495,18 @@ export type RecordContext = { filename: string; text: string }
  * A file in a client's record (S3 object metadata).
  */
 export type RecordFile = { filename: string; size: number; uploaded_at: string | null }
+/**
+ * The result of [`upload_record_file`].
+ */
+export type RecordUpload = { file: RecordFile; 
+/**
+ * Another file in the record with identical contents, if any.
+ */
+duplicate_of: string | null }
+/**
+ * Upload progress for a record file, streamed to the frontend via Channel<T>.
+ */
+export type RecordUploadProgress = { filename: string; bytes_uploaded: number; total_bytes: number }
 /**
  * Every resource in the system is declared as a `ResourceSpec`.
  * 
@@ -996,6 +1548,121 @@ severity: Severity;
  * IAM actions this resource requires (aggregated for policy diff)
  */
 iam_actions: string[] }
+/**
+ * What a point-in-time restore will do to one file.
+ */
+export type RestoreFileAction = 
+/**
+ * Put the version current at the restore point back.
+ */
+"restore" | 
+/**
+ * The file did not exist at the restore point and will be deleted.
+ */
+"delete" | 
+/**
+ * The file is already as it was at the restore point.
+ */
+"unchanged"
+/**
+ * One full-text search hit.
+ */
+export type SearchHit = { 
+/**
+ * `client`, `record_file`, `chat_history`, or a core model such as
+ * `snippet` or `report`.
+ */
+doc_type: string; 
+/**
+ * The client name, filename, start of a chat, or model title.
+ */
+title: string; client_id: string | null; s3_key: string; 
+/**
+ * RFC 3339.
+ */
+updated_at: string; score: number; 
+/**
+ * Where the text matched the body, if it did.
+ */
+snippet: SearchSnippet | null; 
+/**
+ * For a record file, the passages that matched best, best first.
+ */
+passages: SearchPassage[] }
+/**
+ * Filters combined with the text of [`search_records`]. Unset filters
+ * match everything; timestamps are RFC 3339, `*_after` inclusive and
+ * `*_before` exclusive.
+ */
+export type SearchFilters = { client_id: string | null; 
+/**
+ * Any of these document types; empty for all.
+ */
+doc_types: string[]; anonymized: boolean | null; status: string | null; model_id: string | null; created_after: string | null; created_before: string | null; updated_after: string | null; updated_before: string | null; sort: SearchSort }
+/**
+ * A passage of a record file's text that matched.
+ */
+export type SearchPassage = { ordinal: number; 
+/**
+ * Character offsets of the passage in the file's text.
+ */
+start: number; end: number; snippet: SearchSnippet | null }
+export type SearchResponse = { hits: SearchHit[]; 
+/**
+ * Matching documents, including those on other pages.
+ */
+total: number; 
+/**
+ * The `offset` of the next page, or `None` on the last page.
+ */
+next_offset: number | null; 
+/**
+ * Matches per document type, ignoring the `doc_types` filter.
+ */
+doc_type_counts: DocTypeCount[] }
+/**
+ * A fragment of a document's body around the matched terms.
+ */
+export type SearchSnippet = { fragment: string; 
+/**
+ * Matched terms within `fragment`, as UTF-16 offsets so they can be
+ * passed straight to `String.prototype.slice`.
+ */
+highlights: TextRange[] }
+export type SearchSort = "score" | "recency"
+/**
+ * Search synonym groups: the built-in ones (instrument names and common
+ * clinical abbreviations) and the user's own.
+ */
+export type SearchSynonyms = { built_in: string[][]; custom: string[][] }
+export type ScrubIssue = { key: string; kind: ScrubIssueKind; 
+/**
+ * `None` when the issue needs a person to look at it.
+ */
+repair: ScrubRepair | null; detail: string; repaired: boolean; 
+/**
+ * Why the repair failed, if it was attempted.
+ */
+error: string | null }
+export type ScrubIssueKind = "orphaned_sidecar" | "missing_sidecar" | "orphaned_record" | "unparseable_json" | "broken_reference"
+export type ScrubRepair = "reextract" | "delete" | "quarantine"
+export type ScrubResult = { dry_run: boolean; scanned: number; issues: ScrubIssue[] }
+/**
+ * One passage found by [`semantic_search`].
+ */
+export type SemanticHit = { client_id: string | null; 
+/**
+ * The record file's name and key.
+ */
+title: string; s3_key: string; 
+/**
+ * Character offsets of the passage in the file's text.
+ */
+start: number; end: number; text: string; 
+/**
+ * Combined word and meaning score, from 0 to 1.
+ */
+score: number }
 export type Severity = 
 /**
  * Data sources — read-only checks
@@ -1013,11 +1680,54 @@ export type Severity =
  * Data loss risk (bucket deletion during orphan cleanup)
  */
 "destructive"
+/**
+ * One issued share link, as recorded in the audit log.
+ */
+export type ShareLink = { id: string; filename: string; client_id: string | null; recipient: string; 
+/**
+ * The IAM identity that issued the link.
+ */
+created_by: string; created_at: string; expires_at: string; 
+/**
+ * How many links may be issued, each counted as one download.
+ */
+max_downloads: number | null; 
+/**
+ * How many links have been issued so far.
+ */
+links_issued: number; revoked_at: string | null; revoked_by: string | null; 
+/**
+ * Not revoked, not expired, and a link to it may still work.
+ */
+outstanding: boolean }
+/**
+ * A text snippet: an example of phrasing to avoid in reports. `etag` is
+ * the version it was read at, passed back to [`update_snippet`].
+ */
+export type Snippet = { id: string; title: string; body: string; created_at: string; updated_at: string; etag: string }
+/**
+ * Stored bytes for a client, a prefix or the whole bucket.
+ * 
+ * `sidecar_bytes` and `chat_history_bytes` are the parts of
+ * `current_bytes` held by `.text` sidecars and chat histories.
+ */
+export type StorageUsage = { objects: number; current_bytes: number; sidecar_bytes: number; chat_history_bytes: number; noncurrent_versions: number; noncurrent_bytes: number; delete_markers: number; 
+/**
+ * This share of the S3 cost, by stored bytes. Only set when a cost
+ * result was passed to `get_storage_usage`.
+ */
+estimated_cost: number | null }
+export type StorageUsageReport = { 
+/**
+ * Largest first.
+ */
+clients: ClientStorageUsage[]; other: PrefixStorageUsage[]; total: StorageUsage }
 /**
  * Status of an individual bootstrap step.
  */
 export type StepStatus = "pending" | "in_progress" | "succeeded" | "failed"
 export type TAURI_CHANNEL<TSend> = null
+export type TextRange = { start: number; end: number }
 /**
  * Result from transcription, including detected language.
  */
@@ -8,6 +8,8 @@ export type {
   AccessKeyInfo,
   Action,
   AssumeRoleResult,
+  BackupProgress,
+  BackupSummary,
   BootstrapResult,
   BootstrapStep,
   CallerIdentity,
@@ -17,6 +19,9 @@ export type {
   ChatModel,
   ChatResponse,
   ChatRole,
+  ClientRestoreEntry,
+  ClientRestoreSummary,
+  ClientStorageUsage,
   ClientSummary,
   ConfigInfo,
   CredentialAssessment,
@@ -24,16 +29,46 @@ export type {
   CredentialSource,
   DeletedClient,
   DeletedFile,
+  DocTypeCount,
+  EmbeddingModelInfo,
+  EncryptionStatus,
   FieldDrift,
   FileVersion,
+  IssuedShareLink,
   Lifecycle,
   NewCredentials,
+  OutboxItem,
+  OutboxItemStatus,
+  OutboxResolution,
+  OutboxSyncResult,
   PlanEntry,
+  PrefixStorageUsage,
+  PurgeResult,
+  RebuildIndexProgress,
+  RebuildIndexResult,
   RecordContext,
   RecordFile,
+  RecordUpload,
+  RecordUploadProgress,
   ResourceSpec,
+  RestoreFileAction,
+  ScrubIssue,
+  ScrubIssueKind,
+  ScrubRepair,
+  ScrubResult,
+  SearchFilters,
+  SearchHit,
+  SearchPassage,
+  SearchResponse,
+  SearchSnippet,
+  SearchSort,
+  SearchSynonyms,
+  SemanticHit,
   Severity,
+  ShareLink,
   StepStatus,
+  StorageUsage,
+  StorageUsageReport,
 } from "./bindings";
 export type { Result } from "./bindings";
 
@@ -228,6 +263,46 @@ export async function deleteClient(clientId: string): Promise<void> {
   unwrap(await commands.deleteClient(clientId));
 }
 
+export async function purgeClient(clientId: string): Promise<import("./bindings").PurgeResult> {
+  return unwrap(await commands.purgeClient(clientId));
+}
+
+export async function searchRecords(
+  query: string,
+  filters: import("./bindings").SearchFilters,
+  offset: number,
+  limit: number
+): Promise<import("./bindings").SearchResponse> {
+  return unwrap(await commands.searchRecords(query, filters, offset, limit));
+}
+
+export async function rebuildSearchIndex(
+  onProgress?: (p: import("./bindings").RebuildIndexProgress) => void
+): Promise<import("./bindings").RebuildIndexResult> {
+  const { Channel } = await import("@tauri-apps/api/core");
+  const channel = new Channel<import("./bindings").RebuildIndexProgress>();
+  if (onProgress) {
+    channel.onmessage = onProgress;
+  }
+  return unwrap(await commands.rebuildSearchIndex(channel));
+}
+
+export async function getSearchSynonyms(): Promise<import("./bindings").SearchSynonyms> {
+  return unwrap(await commands.getSearchSynonyms());
+}
+
+export async function saveSearchSynonyms(
+  custom: string[][],
+  onProgress?: (p: import("./bindings").RebuildIndexProgress) => void
+): Promise<import("./bindings").RebuildIndexResult> {
+  const { Channel } = await import("@tauri-apps/api/core");
+  const channel = new Channel<import("./bindings").RebuildIndexProgress>();
+  if (onProgress) {
+    channel.onmessage = onProgress;
+  }
+  return unwrap(await commands.saveSearchSynonyms(custom, channel));
+}
+
 // ---------------------------------------------------------------------------
 // Record file wrappers
 // ---------------------------------------------------------------------------
@@ -236,8 +311,17 @@ export async function listRecordFiles(clientId: string): Promise<import("./bindi
   return unwrap(await commands.listRecordFiles(clientId));
 }
 
-export async function uploadRecordFile(clientId: string, filePath: string): Promise<import("./bindings").RecordFile> {
-  return unwrap(await commands.uploadRecordFile(clientId, filePath));
+export async function uploadRecordFile(
+  clientId: string,
+  filePath: string,
+  onProgress?: (p: import("./bindings").RecordUploadProgress) => void
+): Promise<import("./bindings").RecordUpload> {
+  const { Channel } = await import("@tauri-apps/api/core");
+  const channel = new Channel<import("./bindings").RecordUploadProgress>();
+  if (onProgress) {
+    channel.onmessage = onProgress;
+  }
+  return unwrap(await commands.uploadRecordFile(clientId, filePath, channel));
 }
 
 export async function deleteRecordFile(clientId: string, filename: string): Promise<void> {
@@ -300,6 +384,26 @@ export async function setPreferredModel(modelId: string | null): Promise<void> {
   unwrap(await commands.setPreferredModel(modelId));
 }
 
+// ---------------------------------------------------------------------------
+// Encryption wrappers — practice master key for client-side encryption
+// ---------------------------------------------------------------------------
+
+export async function getEncryptionStatus(): Promise<import("./bindings").EncryptionStatus> {
+  return unwrap(await commands.getEncryptionStatus());
+}
+
+export async function enableEncryption(): Promise<import("./bindings").EncryptionStatus> {
+  return unwrap(await commands.enableEncryption());
+}
+
+export async function exportMasterKey(): Promise<string> {
+  return unwrap(await commands.exportMasterKey());
+}
+
+export async function importMasterKey(key: string): Promise<import("./bindings").EncryptionStatus> {
+  return unwrap(await commands.importMasterKey(key));
+}
+
 // ---------------------------------------------------------------------------
 // Prompt wrappers — generic CRUD for named prompts under claria-prompts/
 // ---------------------------------------------------------------------------
@@ -328,6 +432,26 @@ export async function restorePromptVersion(promptName: string, versionId: string
   unwrap(await commands.restorePromptVersion(promptName, versionId));
 }
 
+// ---------------------------------------------------------------------------
+// Text snippet wrappers — edits fail if another device changed the snippet
+// ---------------------------------------------------------------------------
+
+export async function listSnippets(): Promise<import("./bindings").Snippet[]> {
+  return unwrap(await commands.listSnippets());
+}
+
+export async function createSnippet(title: string, body: string): Promise<import("./bindings").Snippet> {
+  return unwrap(await commands.createSnippet(title, body));
+}
+
+export async function updateSnippet(id: string, title: string, body: string, etag: string): Promise<import("./bindings").Snippet> {
+  return unwrap(await commands.updateSnippet(id, title, body, etag));
+}
+
+export async function deleteSnippet(id: string): Promise<void> {
+  unwrap(await commands.deleteSnippet(id));
+}
+
 // ---------------------------------------------------------------------------
 // Version history wrappers
 // ---------------------------------------------------------------------------
@@ -360,6 +484,95 @@ export async function restoreClient(clientId: string, versionId: string): Promis
   unwrap(await commands.restoreClient(clientId, versionId));
 }
 
+export async function previewClientRestore(clientId: string, asOf: string): Promise<import("./bindings").ClientRestoreEntry[]> {
+  return unwrap(await commands.previewClientRestore(clientId, asOf));
+}
+
+export async function restoreClientAsOf(clientId: string, asOf: string): Promise<import("./bindings").ClientRestoreSummary> {
+  return unwrap(await commands.restoreClientAsOf(clientId, asOf));
+}
+
+// ---------------------------------------------------------------------------
+// Backups — the whole bucket as a local encrypted archive
+// ---------------------------------------------------------------------------
+
+export async function exportBackup(
+  path: string,
+  includeVersions: boolean,
+  onProgress?: (p: import("./bindings").BackupProgress) => void
+): Promise<import("./bindings").BackupSummary> {
+  const { Channel } = await import("@tauri-apps/api/core");
+  const channel = new Channel<import("./bindings").BackupProgress>();
+  if (onProgress) {
+    channel.onmessage = onProgress;
+  }
+  return unwrap(await commands.exportBackup(path, includeVersions, channel));
+}
+
+export async function importBackup(
+  path: string,
+  onProgress?: (p: import("./bindings").BackupProgress) => void
+): Promise<import("./bindings").BackupSummary> {
+  const { Channel } = await import("@tauri-apps/api/core");
+  const channel = new Channel<import("./bindings").BackupProgress>();
+  if (onProgress) {
+    channel.onmessage = onProgress;
+  }
+  return unwrap(await commands.importBackup(path, channel));
+}
+
+// ---------------------------------------------------------------------------
+// Integrity scrub
+// ---------------------------------------------------------------------------
+
+export async function scrubBucket(dryRun: boolean): Promise<import("./bindings").ScrubResult> {
+  return unwrap(await commands.scrubBucket(dryRun));
+}
+
+// ---------------------------------------------------------------------------
+// Share links
+// ---------------------------------------------------------------------------
+
+export async function createShareLink(
+  clientId: string,
+  filename: string,
+  recipient: string,
+  expiresInHours: number,
+  maxDownloads: number | null,
+): Promise<import("./bindings").IssuedShareLink> {
+  return unwrap(
+    await commands.createShareLink(clientId, filename, recipient, expiresInHours, maxDownloads),
+  );
+}
+
+export async function issueShareLink(shareId: string): Promise<import("./bindings").IssuedShareLink> {
+  return unwrap(await commands.issueShareLink(shareId));
+}
+
+export async function listShareLinks(includeInactive: boolean): Promise<import("./bindings").ShareLink[]> {
+  return unwrap(await commands.listShareLinks(includeInactive));
+}
+
+export async function revokeShareLink(shareId: string): Promise<import("./bindings").ShareLink> {
+  return unwrap(await commands.revokeShareLink(shareId));
+}
+
+// ---------------------------------------------------------------------------
+// Offline outbox — record writes queued while S3 is unreachable
+// ---------------------------------------------------------------------------
+
+export async function listOutbox(): Promise<import("./bindings").OutboxItem[]> {
+  return unwrap(await commands.listOutbox());
+}
+
+export async function syncOutbox(): Promise<import("./bindings").OutboxSyncResult> {
+  return unwrap(await commands.syncOutbox());
+}
+
+export async function resolveOutboxItem(id: string, resolution: import("./bindings").OutboxResolution): Promise<void> {
+  unwrap(await commands.resolveOutboxItem(id, resolution));
+}
+
 // ---------------------------------------------------------------------------
 // Whisper model management + local transcription
 // ---------------------------------------------------------------------------
@@ -390,6 +603,30 @@ export async function transcribeMemo(audioPcmBase64: string): Promise<import("./
   return unwrap(await commands.transcribeMemo(audioPcmBase64));
 }
 
+// ---------------------------------------------------------------------------
+// Embedding model management + semantic search
+// ---------------------------------------------------------------------------
+
+export async function getEmbeddingModel(): Promise<import("./bindings").EmbeddingModelInfo> {
+  return unwrap(await commands.getEmbeddingModel());
+}
+
+export async function downloadEmbeddingModel(): Promise<import("./bindings").EmbeddingModelInfo> {
+  return unwrap(await commands.downloadEmbeddingModel());
+}
+
+export async function deleteEmbeddingModel(): Promise<import("./bindings").EmbeddingModelInfo> {
+  return unwrap(await commands.deleteEmbeddingModel());
+}
+
+export async function semanticSearch(
+  query: string,
+  clientId: string | null,
+  limit: number
+): Promise<import("./bindings").SemanticHit[]> {
+  return unwrap(await commands.semanticSearch(query, clientId, limit));
+}
+
 // ---------------------------------------------------------------------------
 // Update check
 // ---------------------------------------------------------------------------
@@ -425,6 +662,12 @@ export async function setHourlyCostData(enabled: boolean): Promise<void> {
   unwrap(await commands.setHourlyCostData(enabled));
 }
 
+export async function getStorageUsage(
+  cost: import("./bindings").CostAndUsageResult | null
+): Promise<import("./bindings").StorageUsageReport> {
+  return unwrap(await commands.getStorageUsage(cost));
+}
+
 // ---------------------------------------------------------------------------
 // Shell / URL helpers
 // ---------------------------------------------------------------------------
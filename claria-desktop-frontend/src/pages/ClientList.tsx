@@ -1,15 +1,51 @@
-import { useState, useEffect, useCallback } from "react";
+import { useState, useEffect, useCallback, type ReactNode } from "react";
 import {
   listClients,
   createClient,
   deleteClient,
   listDeletedClients,
   restoreClient,
+  purgeClient,
+  searchRecords,
+  semanticSearch,
+  getEncryptionStatus,
   type ClientSummary,
+  type EncryptionStatus,
+  type SearchFilters,
+  type SearchResponse,
+  type SearchSnippet,
+  type SemanticHit,
   type DeletedClient,
 } from "../lib/tauri";
 import type { Page } from "../App";
 
+const SEARCH_LIMIT = 50;
+
+const SEARCH_HIT_LABELS: Record<string, string> = {
+  client: "Client",
+  record_file: "Record file",
+  chat_history: "Chat",
+  assessment: "Assessment",
+  snippet: "Snippet",
+  goal: "Goal",
+  template: "Template",
+  report: "Report",
+  transaction: "Bedrock request",
+};
+
+const NO_FILTERS: SearchFilters = {
+  client_id: null,
+  doc_types: [],
+  anonymized: null,
+  status: null,
+  model_id: null,
+  created_after: null,
+  created_before: null,
+  updated_after: null,
+  updated_before: null,
+  sort: "score",
+};
+
 export default function ClientList({
   navigate,
   onOpenClient,
@@ -35,6 +71,19 @@ export default function ClientList({
   const [deletedClients, setDeletedClients] = useState<DeletedClient[]>([]);
   const [deletedLoading, setDeletedLoading] = useState(false);
   const [restoringId, setRestoringId] = useState<string | null>(null);
+  const [confirmPurge, setConfirmPurge] = useState<DeletedClient | null>(null);
+  const [purging, setPurging] = useState(false);
+
+  // Full-text search
+  const [query, setQuery] = useState("");
+  const [filters, setFilters] = useState<SearchFilters>(NO_FILTERS);
+  const [results, setResults] = useState<SearchResponse | null>(null);
+  const [byMeaning, setByMeaning] = useState(false);
+  const [meaningHits, setMeaningHits] = useState<SemanticHit[] | null>(null);
+  const [searching, setSearching] = useState(false);
+
+  // Flag a bucket that is written in plaintext
+  const [encryption, setEncryption] = useState<EncryptionStatus | null>(null);
 
   const refresh = useCallback(async () => {
     setLoading(true);
@@ -53,6 +102,12 @@ export default function ClientList({
     refresh();
   }, [refresh]);
 
+  useEffect(() => {
+    getEncryptionStatus()
+      .then(setEncryption)
+      .catch(() => setEncryption(null));
+  }, []);
+
   async function handleCreate() {
     if (!newName.trim()) return;
     setCreating(true);
@@ -82,6 +137,48 @@ export default function ClientList({
     }
   }
 
+  async function handleSearch(next: SearchFilters = filters) {
+    if (!query.trim()) {
+      setResults(null);
+      setMeaningHits(null);
+      return;
+    }
+    setFilters(next);
+    setSearching(true);
+    setError(null);
+    try {
+      if (byMeaning) {
+        setResults(null);
+        setMeaningHits(await semanticSearch(query.trim(), next.client_id, SEARCH_LIMIT));
+      } else {
+        setMeaningHits(null);
+        setResults(await searchRecords(query.trim(), next, 0, SEARCH_LIMIT));
+      }
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSearching(false);
+    }
+  }
+
+  async function handleMoreResults() {
+    if (results?.next_offset == null) return;
+    setSearching(true);
+    setError(null);
+    try {
+      const page = await searchRecords(query.trim(), filters, results.next_offset, SEARCH_LIMIT);
+      setResults({ ...page, hits: [...results.hits, ...page.hits] });
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSearching(false);
+    }
+  }
+
+  function clientName(clientId: string | null): string {
+    return clients.find((c) => c.id === clientId)?.name ?? "Unknown client";
+  }
+
   async function handleToggleMore() {
     const next = !moreMode;
     setMoreMode(next);
@@ -110,6 +207,19 @@ export default function ClientList({
     }
   }
 
+  async function handlePurgeClient(id: string) {
+    setPurging(true);
+    try {
+      await purgeClient(id);
+      setDeletedClients((prev) => prev.filter((c) => c.id !== id));
+      setConfirmPurge(null);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setPurging(false);
+    }
+  }
+
   return (
     <div className="max-w-2xl mx-auto p-8">
       {/* Header */}
@@ -148,6 +258,26 @@ export default function ClientList({
         </div>
       </div>
 
+      {/* Unencrypted bucket */}
+      {encryption &&
+        (!encryption.enabled ||
+          (encryption.bucket_key_id != null &&
+            encryption.key_id !== encryption.bucket_key_id)) && (
+        <div className="bg-amber-50 border border-amber-200 rounded-lg p-4 mb-6 flex items-center justify-between gap-3">
+          <p className="text-amber-800 text-sm">
+            {encryption.bucket_key_id != null
+              ? "This computer does not have your practice's encryption key."
+              : "Client data is stored in your bucket unencrypted."}
+          </p>
+          <button
+            onClick={() => navigate("preferences")}
+            className="shrink-0 px-3 py-1 text-xs text-amber-800 border border-amber-300 rounded-lg hover:bg-amber-100 transition-colors"
+          >
+            Encryption settings
+          </button>
+        </div>
+      )}
+
       {/* New client form */}
       {showNewForm && (
         <div className="bg-white border border-gray-200 rounded-lg p-4 mb-6">
@@ -182,6 +312,193 @@ export default function ClientList({
         </div>
       )}
 
+      {/* Search */}
+      <div className="flex gap-2 mb-6">
+        <input
+          type="search"
+          value={query}
+          onChange={(e) => {
+            setQuery(e.target.value);
+            if (!e.target.value) {
+              setResults(null);
+              setMeaningHits(null);
+            }
+          }}
+          onKeyDown={(e) => e.key === "Enter" && handleSearch()}
+          placeholder="Search names, records and chats"
+          className="flex-1 px-3 py-2 text-sm border border-gray-300 rounded-lg focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+        />
+        <button
+          onClick={() => handleSearch()}
+          disabled={searching || !query.trim()}
+          className="px-4 py-2 text-sm border border-gray-300 rounded-lg hover:bg-gray-50 transition-colors disabled:opacity-50"
+        >
+          {searching ? "Searching..." : "Search"}
+        </button>
+        <label
+          className="flex items-center gap-1 text-xs text-gray-500"
+          title="Also find passages about the same thing in other words. Needs the search model from Preferences."
+        >
+          <input
+            type="checkbox"
+            checked={byMeaning}
+            onChange={(e) => setByMeaning(e.target.checked)}
+            className="rounded border-gray-300"
+          />
+          By meaning
+        </label>
+      </div>
+
+      {/* Semantic search results */}
+      {meaningHits && (
+        <div className="bg-white border border-gray-200 rounded-lg overflow-hidden mb-6">
+          <div className="flex items-center justify-between px-4 py-2 border-b border-gray-100 bg-gray-50">
+            <span className="text-xs font-medium text-gray-500">
+              {meaningHits.length === 0
+                ? "No matching passages"
+                : `${meaningHits.length} passages`}
+            </span>
+            <button
+              onClick={() => {
+                setMeaningHits(null);
+                setQuery("");
+              }}
+              className="text-xs text-gray-500 hover:text-gray-700"
+            >
+              Clear
+            </button>
+          </div>
+          <ul className="divide-y divide-gray-100">
+            {meaningHits.map((hit) => (
+              <li
+                key={`${hit.s3_key}:${hit.start}`}
+                onClick={() => hit.client_id && onOpenClient(hit.client_id, clientName(hit.client_id))}
+                className={`px-4 py-3 transition-colors ${
+                  hit.client_id ? "hover:bg-gray-50 cursor-pointer" : ""
+                }`}
+              >
+                <p className="text-sm font-medium text-gray-900 truncate">{hit.title}</p>
+                <p className="text-xs text-gray-600 line-clamp-3 my-0.5">{hit.text}</p>
+                <p className="text-xs text-gray-400">
+                  {hit.client_id && clientName(hit.client_id)}
+                  {` \u00b7 ${Math.round(hit.score * 100)}% match`}
+                </p>
+              </li>
+            ))}
+          </ul>
+        </div>
+      )}
+
+      {/* Search results */}
+      {results && (
+        <div className="bg-white border border-gray-200 rounded-lg overflow-hidden mb-6">
+          <div className="flex items-center justify-between px-4 py-2 border-b border-gray-100 bg-gray-50">
+            <span className="text-xs font-medium text-gray-500">
+              {results.total === 0
+                ? "No matches"
+                : results.total > results.hits.length
+                  ? `${results.hits.length} of ${results.total} matches`
+                  : `${results.total} matches`}
+            </span>
+            <div className="flex items-center gap-3">
+              <label className="flex items-center gap-1 text-xs text-gray-500">
+                <input
+                  type="checkbox"
+                  checked={filters.sort === "recency"}
+                  onChange={(e) =>
+                    handleSearch({ ...filters, sort: e.target.checked ? "recency" : "score" })
+                  }
+                  disabled={searching}
+                  className="rounded border-gray-300"
+                />
+                Newest first
+              </label>
+              <button
+                onClick={() => {
+                  setResults(null);
+                  setFilters(NO_FILTERS);
+                  setQuery("");
+                }}
+                className="text-xs text-gray-500 hover:text-gray-700"
+              >
+                Clear
+              </button>
+            </div>
+          </div>
+          {results.doc_type_counts.length > 1 && (
+            <div className="flex flex-wrap gap-1 px-4 py-2 border-b border-gray-100">
+              {results.doc_type_counts.map(({ doc_type, count }) => {
+                const active = filters.doc_types.includes(doc_type);
+                return (
+                  <button
+                    key={doc_type}
+                    onClick={() =>
+                      handleSearch({ ...filters, doc_types: active ? [] : [doc_type] })
+                    }
+                    disabled={searching}
+                    className={`px-2 py-0.5 text-xs rounded-full border transition-colors ${
+                      active
+                        ? "bg-blue-50 border-blue-300 text-blue-700"
+                        : "border-gray-200 text-gray-500 hover:bg-gray-50"
+                    }`}
+                  >
+                    {SEARCH_HIT_LABELS[doc_type] ?? doc_type} {count}
+                  </button>
+                );
+              })}
+            </div>
+          )}
+          <ul className="divide-y divide-gray-100">
+            {results.hits.map((hit) => (
+              <li
+                key={hit.s3_key}
+                onClick={() => hit.client_id && onOpenClient(hit.client_id, clientName(hit.client_id))}
+                className={`px-4 py-3 transition-colors ${
+                  hit.client_id ? "hover:bg-gray-50 cursor-pointer" : ""
+                }`}
+              >
+                <p className="text-sm font-medium text-gray-900 truncate">{hit.title}</p>
+                {hit.passages.length > 0 ? (
+                  <ul className="my-0.5 space-y-0.5">
+                    {hit.passages.map(
+                      (passage) =>
+                        passage.snippet && (
+                          <li
+                            key={passage.ordinal}
+                            className="text-xs text-gray-600 line-clamp-2 border-l-2 border-gray-200 pl-2"
+                          >
+                            <Highlighted snippet={passage.snippet} />
+                          </li>
+                        ),
+                    )}
+                  </ul>
+                ) : (
+                  hit.snippet && (
+                    <p className="text-xs text-gray-600 line-clamp-2 my-0.5">
+                      <Highlighted snippet={hit.snippet} />
+                    </p>
+                  )
+                )}
+                <p className="text-xs text-gray-400">
+                  {SEARCH_HIT_LABELS[hit.doc_type] ?? hit.doc_type}
+                  {hit.client_id && hit.doc_type !== "client" && ` \u00b7 ${clientName(hit.client_id)}`}
+                  {` \u00b7 ${formatDate(hit.updated_at)}`}
+                </p>
+              </li>
+            ))}
+          </ul>
+          {results.next_offset != null && (
+            <button
+              onClick={handleMoreResults}
+              disabled={searching}
+              className="w-full px-4 py-2 text-xs text-gray-500 border-t border-gray-100 hover:bg-gray-50 disabled:opacity-50"
+            >
+              {searching ? "Loading..." : `Show more (${results.total - results.hits.length} left)`}
+            </button>
+          )}
+        </div>
+      )}
+
       {/* Error */}
       {error && (
         <div className="bg-red-50 border border-red-200 rounded-lg p-4 mb-6">
@@ -281,7 +598,7 @@ export default function ClientList({
                   <tr className="border-b border-gray-100 bg-gray-50">
                     <th className="text-left text-xs font-medium text-gray-500 px-4 py-2">Name</th>
                     <th className="text-left text-xs font-medium text-gray-500 px-4 py-2">Deleted</th>
-                    <th className="w-24" />
+                    <th className="w-40" />
                   </tr>
                 </thead>
                 <tbody className="divide-y divide-gray-100">
@@ -301,6 +618,13 @@ export default function ClientList({
                         >
                           {restoringId === dc.id ? "Restoring..." : "Restore"}
                         </button>
+                        <button
+                          onClick={() => setConfirmPurge(dc)}
+                          disabled={restoringId === dc.id}
+                          className="ml-2 px-3 py-1 text-xs text-red-600 border border-red-300 rounded hover:bg-red-50 transition-colors disabled:opacity-50"
+                        >
+                          Purge
+                        </button>
                       </td>
                     </tr>
                   ))}
@@ -342,10 +666,60 @@ export default function ClientList({
         </div>
       )}
 
+      {/* Purge confirmation modal */}
+      {confirmPurge && (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/40">
+          <div className="bg-white rounded-xl shadow-lg max-w-sm w-full mx-4 p-6">
+            <h3 className="text-lg font-semibold text-gray-900 mb-2">
+              Purge {confirmPurge.name}?
+            </h3>
+            <p className="text-sm text-gray-600 mb-4">
+              This erases every version of the client's records, files and chat
+              history from your bucket and removes them from search. Unlike
+              delete, a purged client can never be restored.
+            </p>
+            <div className="flex justify-end gap-3">
+              <button
+                onClick={() => setConfirmPurge(null)}
+                disabled={purging}
+                className="px-4 py-2 text-sm text-gray-600 hover:text-gray-800 disabled:opacity-50"
+              >
+                Cancel
+              </button>
+              <button
+                onClick={() => handlePurgeClient(confirmPurge.id)}
+                disabled={purging}
+                className="px-4 py-2 text-sm text-white bg-red-600 rounded-lg hover:bg-red-700 transition-colors disabled:opacity-50"
+              >
+                {purging ? "Purging..." : "Purge permanently"}
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
+
     </div>
   );
 }
 
+/** A snippet fragment with its matched terms marked. */
+function Highlighted({ snippet }: { snippet: SearchSnippet }) {
+  const { fragment, highlights } = snippet;
+  const parts: (string | ReactNode)[] = [];
+  let last = 0;
+  for (const { start, end } of highlights) {
+    if (start > last) parts.push(fragment.slice(last, start));
+    parts.push(
+      <mark key={start} className="bg-yellow-200 rounded px-0.5">
+        {fragment.slice(start, end)}
+      </mark>
+    );
+    last = end;
+  }
+  if (last < fragment.length) parts.push(fragment.slice(last));
+  return <>{parts}</>;
+}
+
 function formatDate(iso: string): string {
   try {
     const d = new Date(iso);
@@ -15,12 +15,18 @@ import {
   restoreDeletedFile,
   getWhisperModels,
   transcribeMemo,
+  listOutbox,
+  syncOutbox,
+  resolveOutboxItem,
+  createShareLink,
   type RecordFile,
   type ChatHistoryDetail,
   type ChatModel,
   type FileVersion,
   type DeletedFile,
   type WhisperModelInfo,
+  type OutboxItem,
+  type OutboxResolution,
 } from "../lib/tauri";
 import { diffLines, type DiffLine } from "../lib/diff";
 import ClientChat from "./ClientChat";
@@ -136,12 +142,21 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
   const [error, setError] = useState<string | null>(null);
   const [dragging, setDragging] = useState(false);
   const [uploading, setUploading] = useState<string[]>([]);
+  const [uploadPercent, setUploadPercent] = useState<Record<string, number>>({});
+  const [duplicates, setDuplicates] = useState<{ filename: string; original: string }[]>([]);
   const [previewText, setPreviewText] = useState<string | null>(null);
   const [previewFilename, setPreviewFilename] = useState<string | null>(null);
   const [editText, setEditText] = useState<string | null>(null);
   const [editFilename, setEditFilename] = useState<string | null>(null);
   const [saving, setSaving] = useState(false);
   const [deleteConfirm, setDeleteConfirm] = useState<string | null>(null);
+  const [shareFile, setShareFile] = useState<string | null>(null);
+  const [shareRecipient, setShareRecipient] = useState("");
+  const [shareHours, setShareHours] = useState(72);
+  const [shareMaxDownloads, setShareMaxDownloads] = useState("");
+  const [shareUrl, setShareUrl] = useState<string | null>(null);
+  const [sharing, setSharing] = useState(false);
+  const [shareError, setShareError] = useState<string | null>(null);
   const [showCreateText, setShowCreateText] = useState(false);
   const [createFilename, setCreateFilename] = useState("");
   const [createContent, setCreateContent] = useState("");
@@ -149,6 +164,10 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
   const [chatFolderOpen, setChatFolderOpen] = useState(false);
   const [resumeLoading, setResumeLoading] = useState<string | null>(null);
 
+  // Offline outbox state
+  const [outbox, setOutbox] = useState<OutboxItem[]>([]);
+  const [syncing, setSyncing] = useState(false);
+
   // More mode state
   const [moreMode, setMoreMode] = useState(false);
   const [deletedFiles, setDeletedFiles] = useState<DeletedFile[]>([]);
@@ -204,6 +223,12 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
     } finally {
       setLoading(false);
     }
+    try {
+      const queued = await listOutbox();
+      setOutbox(queued.filter((item) => item.client_id === clientId));
+    } catch (e) {
+      console.error("Failed to list outbox:", e);
+    }
   }, [clientId]);
 
   useEffect(() => {
@@ -255,15 +280,30 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
   }, [clientId]);
 
   async function handleFileDrop(paths: string[]) {
+    setDuplicates([]);
     for (const path of paths) {
       const filename = path.split("/").pop() ?? path;
       setUploading((prev) => [...prev, filename]);
       try {
-        await uploadRecordFile(clientId, path);
+        const result = await uploadRecordFile(clientId, path, (p) => {
+          if (p.total_bytes > 0) {
+            const percent = Math.floor((p.bytes_uploaded / p.total_bytes) * 100);
+            setUploadPercent((prev) => ({ ...prev, [filename]: percent }));
+          }
+        });
+        const original = result.duplicate_of;
+        if (original) {
+          setDuplicates((prev) => [...prev, { filename, original }]);
+        }
       } catch (e) {
         setError(String(e));
       } finally {
         setUploading((prev) => prev.filter((f) => f !== filename));
+        setUploadPercent((prev) => {
+          const next = { ...prev };
+          delete next[filename];
+          return next;
+        });
       }
     }
     await refresh();
@@ -538,6 +578,30 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
     }
   }
 
+  function handleOpenShare(filename: string) {
+    setShareFile(filename);
+    setShareRecipient("");
+    setShareHours(72);
+    setShareMaxDownloads("");
+    setShareUrl(null);
+    setShareError(null);
+  }
+
+  async function handleCreateShare() {
+    if (!shareFile) return;
+    setSharing(true);
+    setShareError(null);
+    try {
+      const maxDownloads = shareMaxDownloads.trim() ? parseInt(shareMaxDownloads, 10) : null;
+      const issued = await createShareLink(clientId, shareFile, shareRecipient, shareHours, maxDownloads);
+      setShareUrl(issued.url);
+    } catch (e) {
+      setShareError(String(e));
+    } finally {
+      setSharing(false);
+    }
+  }
+
   async function handleResume(filename: string) {
     // Extract UUID from "chat-history/{uuid}.json"
     const chatId = filename.replace(CHAT_HISTORY_PREFIX, "").replace(".json", "");
@@ -569,6 +633,33 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
     }
   }
 
+  async function handleSyncOutbox() {
+    setSyncing(true);
+    setError(null);
+    try {
+      const result = await syncOutbox();
+      if (result.offline) {
+        setError("Still offline. Queued changes will be kept until the connection returns.");
+      }
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setSyncing(false);
+    }
+  }
+
+  async function handleResolveOutboxItem(id: string, resolution: OutboxResolution) {
+    setError(null);
+    try {
+      await resolveOutboxItem(id, resolution);
+    } catch (e) {
+      setError(String(e));
+      return;
+    }
+    await handleSyncOutbox();
+  }
+
   async function handleToggleMore() {
     const next = !moreMode;
     setMoreMode(next);
@@ -700,6 +791,85 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
           </div>
         )}
 
+        {/* Duplicate uploads */}
+        {duplicates.length > 0 && (
+          <div className="bg-amber-50 border border-amber-200 rounded-lg p-4 mb-6">
+            {duplicates.map((d) => (
+              <p key={d.filename} className="text-amber-800 text-sm">
+                <span className="font-medium">{d.filename}</span> has the same contents as{" "}
+                <span className="font-medium">{d.original}</span>.
+              </p>
+            ))}
+            <button
+              onClick={() => setDuplicates([])}
+              className="mt-2 text-xs font-medium text-amber-700 hover:text-amber-900"
+            >
+              Dismiss
+            </button>
+          </div>
+        )}
+
+        {/* Changes waiting to sync */}
+        {outbox.length > 0 && (
+          <div className="bg-amber-50 border border-amber-200 rounded-lg p-4 mb-6">
+            <div className="flex items-center justify-between">
+              <p className="text-amber-800 text-sm font-medium">
+                {outbox.length} change{outbox.length === 1 ? "" : "s"} waiting to sync
+              </p>
+              <button
+                onClick={handleSyncOutbox}
+                disabled={syncing}
+                className="text-sm font-medium text-amber-700 hover:text-amber-900 disabled:opacity-50"
+              >
+                {syncing ? "Syncing..." : "Sync now"}
+              </button>
+            </div>
+            <ul className="mt-2 space-y-1">
+              {outbox.map((item) => (
+                <li key={item.id} className="flex items-center gap-2 text-sm text-amber-800">
+                  <span className="flex-1 truncate">{item.filename}</span>
+                  {item.status === "conflict" && (
+                    <>
+                      <span className="text-xs text-amber-700">Changed on another device</span>
+                      <button
+                        onClick={() => handleResolveOutboxItem(item.id, "keep_both")}
+                        className="text-xs font-medium text-blue-600 hover:text-blue-800"
+                      >
+                        Keep both
+                      </button>
+                      <button
+                        onClick={() => handleResolveOutboxItem(item.id, "overwrite")}
+                        className="text-xs font-medium text-blue-600 hover:text-blue-800"
+                      >
+                        Use mine
+                      </button>
+                      <button
+                        onClick={() => handleResolveOutboxItem(item.id, "discard")}
+                        className="text-xs font-medium text-red-600 hover:text-red-800"
+                      >
+                        Discard mine
+                      </button>
+                    </>
+                  )}
+                  {item.status === "failed" && (
+                    <>
+                      <span className="text-xs text-red-700 truncate" title={item.error ?? undefined}>
+                        Failed: {item.error}
+                      </span>
+                      <button
+                        onClick={() => handleResolveOutboxItem(item.id, "discard")}
+                        className="text-xs font-medium text-red-600 hover:text-red-800"
+                      >
+                        Discard
+                      </button>
+                    </>
+                  )}
+                </li>
+              ))}
+            </ul>
+          </div>
+        )}
+
         {/* File list / drop zone */}
         <div
           className={`border-2 rounded-lg transition-colors ${
@@ -959,6 +1129,17 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
                     </p>
                   </div>
                   <div className="flex gap-1">
+                    {moreMode && (
+                      <button
+                        onClick={() => handleOpenShare(file.filename)}
+                        title="Share link"
+                        className="p-1.5 text-gray-400 hover:text-blue-600 hover:bg-blue-50 rounded transition-colors"
+                      >
+                        <svg className="w-4 h-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+                          <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M13.828 10.172a4 4 0 00-5.656 0l-4 4a4 4 0 105.656 5.656l1.102-1.101m-.758-4.899a4 4 0 005.656 0l4-4a4 4 0 00-5.656-5.656l-1.1 1.1" />
+                        </svg>
+                      </button>
+                    )}
                     {moreMode && (
                       <button
                         onClick={() => handleOpenVersions(file.filename)}
@@ -1053,7 +1234,10 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
                   <Spinner />
                   <div className="flex-1 min-w-0">
                     <p className="text-sm text-gray-500 truncate">
-                      Uploading {filename}...
+                      Uploading {filename}
+                      {uploadPercent[filename] !== undefined
+                        ? ` (${uploadPercent[filename]}%)`
+                        : "..."}
                     </p>
                   </div>
                 </div>
@@ -1197,6 +1381,110 @@ function RecordTab({ clientId, onResumeChat }: { clientId: string; onResumeChat:
         </div>
       )}
 
+      {/* Share link */}
+      {shareFile && (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/40">
+          <div className="bg-white rounded-xl shadow-lg max-w-md w-full mx-4 p-6">
+            <h3 className="text-lg font-semibold text-gray-900 mb-2">
+              Share {shareFile}
+            </h3>
+            {shareUrl ? (
+              <>
+                <p className="text-sm text-gray-600 mb-3">
+                  Send this link to {shareRecipient}. Anyone with it can
+                  download the file until it expires. It is shown only once;
+                  revoke it under Preferences when it is no longer needed.
+                </p>
+                <textarea
+                  readOnly
+                  value={shareUrl}
+                  rows={4}
+                  className="w-full px-3 py-2 text-xs font-mono border border-gray-300 rounded-lg mb-4"
+                />
+                <div className="flex justify-end gap-3">
+                  <button
+                    onClick={() => navigator.clipboard.writeText(shareUrl)}
+                    className="px-4 py-2 text-sm text-gray-600 hover:text-gray-800"
+                  >
+                    Copy link
+                  </button>
+                  <button
+                    onClick={() => setShareFile(null)}
+                    className="px-4 py-2 text-sm text-white bg-blue-600 rounded-lg hover:bg-blue-700 transition-colors"
+                  >
+                    Done
+                  </button>
+                </div>
+              </>
+            ) : (
+              <>
+                <p className="text-sm text-gray-600 mb-4">
+                  The recipient gets an unencrypted copy through a link that
+                  expires. Every link is recorded with who issued it and to
+                  whom.
+                </p>
+                <label className="block text-sm font-medium text-gray-700 mb-1">
+                  Recipient
+                </label>
+                <input
+                  type="text"
+                  value={shareRecipient}
+                  onChange={(e) => setShareRecipient(e.target.value)}
+                  placeholder="Dr. Smith, referring provider"
+                  className="w-full px-3 py-2 text-sm border border-gray-300 rounded-lg mb-3"
+                />
+                <label className="block text-sm font-medium text-gray-700 mb-1">
+                  Expires after
+                </label>
+                <select
+                  value={shareHours}
+                  onChange={(e) => setShareHours(Number(e.target.value))}
+                  className="w-full px-3 py-2 text-sm border border-gray-300 rounded-lg mb-3"
+                >
+                  <option value={1}>1 hour</option>
+                  <option value={24}>1 day</option>
+                  <option value={72}>3 days</option>
+                  <option value={168}>7 days</option>
+                </select>
+                <label className="block text-sm font-medium text-gray-700 mb-1">
+                  Download limit (optional)
+                </label>
+                <input
+                  type="number"
+                  min={1}
+                  value={shareMaxDownloads}
+                  onChange={(e) => setShareMaxDownloads(e.target.value)}
+                  className="w-full px-3 py-2 text-sm border border-gray-300 rounded-lg mb-1"
+                />
+                <p className="text-xs text-gray-400 mb-4">
+                  {shareMaxDownloads.trim()
+                    ? "Each link counts as one download and works for an hour. Issue further links from Preferences \u2192 Share links."
+                    : "Anyone with the link can download the file until it expires, so revoke the link once it has been used."}
+                </p>
+                {shareError && (
+                  <p className="text-sm text-red-600 mb-3">{shareError}</p>
+                )}
+                <div className="flex justify-end gap-3">
+                  <button
+                    onClick={() => setShareFile(null)}
+                    className="px-4 py-2 text-sm text-gray-600 hover:text-gray-800"
+                  >
+                    Cancel
+                  </button>
+                  <button
+                    onClick={handleCreateShare}
+                    disabled={sharing || !shareRecipient.trim()}
+                    className="px-4 py-2 text-sm text-white bg-blue-600 rounded-lg hover:bg-blue-700 transition-colors disabled:opacity-50"
+                  >
+                    {sharing ? "Creating..." : "Create link"}
+                  </button>
+                </div>
+              </>
+            )}
+          </div>
+        </div>
+      )}
+
       {/* Create text file modal */}
       {showCreateText && (
         <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/40">
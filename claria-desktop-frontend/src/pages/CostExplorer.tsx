@@ -4,10 +4,13 @@ import {
   probeCostExplorer,
   enableCostExplorer,
   loadConfig,
+  getStorageUsage,
   type CostGranularity,
   type CostAndUsageResult,
   type CostTimePeriod,
   type CostResultGroup,
+  type StorageUsage,
+  type StorageUsageReport,
 } from "../lib/tauri";
 import type { Page } from "../App";
 
@@ -670,10 +673,139 @@ function CostChart({ hourlyAvailable }: { hourlyAvailable: boolean }) {
           )}
         </>
       )}
+
+      {/* Storage by client — splits the S3 cost when grouped by service */}
+      <StorageUsagePanel cost={result && groupByService && !fetching ? result : null} />
     </div>
   );
 }
 
+// ---------------------------------------------------------------------------
+// Storage usage by client and kind of file
+// ---------------------------------------------------------------------------
+
+function StorageUsagePanel({ cost }: { cost: CostAndUsageResult | null }) {
+  const [report, setReport] = useState<StorageUsageReport | null>(null);
+  const [loading, setLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setLoading(true);
+    setError(null);
+    getStorageUsage(cost)
+      .then(setReport)
+      .catch((e) => setError(String(e)))
+      .finally(() => setLoading(false));
+  }, [cost]);
+
+  const showCost = report?.total.estimated_cost != null;
+
+  return (
+    <div className="bg-white border border-gray-200 rounded-lg p-4">
+      <h3 className="text-sm font-semibold text-gray-900 mb-1">Storage by client</h3>
+      <p className="text-xs text-gray-400 mb-3">
+        S3 bills for every saved version, including earlier edits and deleted
+        files.{" "}
+        {showCost
+          ? "Costs are the S3 total above, split by stored bytes."
+          : "Group the chart by service to see each client's share of the S3 cost."}
+      </p>
+
+      {loading && (
+        <div className="flex items-center gap-2 text-gray-500 text-sm py-2">
+          <Spinner />
+          <span>Measuring storage...</span>
+        </div>
+      )}
+
+      {error && !loading && (
+        <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+          <p className="text-red-800 text-sm">{error}</p>
+        </div>
+      )}
+
+      {report && !loading && !error && (
+        <table className="w-full text-xs">
+          <thead>
+            <tr className="text-left text-gray-500 border-b border-gray-100">
+              <th className="py-1.5 font-medium">Client</th>
+              <th className="py-1.5 font-medium text-right">Files</th>
+              <th className="py-1.5 font-medium text-right">Extracted text</th>
+              <th className="py-1.5 font-medium text-right">Chats</th>
+              <th className="py-1.5 font-medium text-right">Old versions</th>
+              {showCost && <th className="py-1.5 font-medium text-right">Est. cost</th>}
+            </tr>
+          </thead>
+          <tbody>
+            {report.clients.map((c) => (
+              <StorageUsageRow
+                key={c.client_id}
+                label={c.name ?? "Deleted client"}
+                usage={c.usage}
+                showCost={showCost}
+              />
+            ))}
+            {report.other.map((o) => (
+              <StorageUsageRow
+                key={o.prefix}
+                label={o.prefix || "(bucket root)"}
+                usage={o.usage}
+                showCost={showCost}
+                muted
+              />
+            ))}
+            <StorageUsageRow label="Total" usage={report.total} showCost={showCost} bold />
+          </tbody>
+        </table>
+      )}
+    </div>
+  );
+}
+
+function StorageUsageRow({
+  label,
+  usage,
+  showCost,
+  muted,
+  bold,
+}: {
+  label: string;
+  usage: StorageUsage;
+  showCost: boolean;
+  muted?: boolean;
+  bold?: boolean;
+}) {
+  const files = usage.current_bytes - usage.sidecar_bytes - usage.chat_history_bytes;
+  return (
+    <tr
+      className={`border-b border-gray-50 ${muted ? "text-gray-400" : "text-gray-700"} ${
+        bold ? "font-semibold" : ""
+      }`}
+    >
+      <td className="py-1.5 truncate max-w-[12rem]">{label}</td>
+      <td className="py-1.5 text-right">{fmtBytes(files)}</td>
+      <td className="py-1.5 text-right">{fmtBytes(usage.sidecar_bytes)}</td>
+      <td className="py-1.5 text-right">{fmtBytes(usage.chat_history_bytes)}</td>
+      <td
+        className="py-1.5 text-right"
+        title={`${usage.noncurrent_versions} earlier versions, ${usage.delete_markers} deletions`}
+      >
+        {fmtBytes(usage.noncurrent_bytes)}
+      </td>
+      {showCost && (
+        <td className="py-1.5 text-right">${(usage.estimated_cost ?? 0).toFixed(2)}</td>
+      )}
+    </tr>
+  );
+}
+
+function fmtBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
+}
+
 // ---------------------------------------------------------------------------
 // Bar chart
 // ---------------------------------------------------------------------------
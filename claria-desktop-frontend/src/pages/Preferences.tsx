@@ -15,6 +15,31 @@ import {
   loadConfig,
   setHourlyCostData,
   getCostAndUsage,
+  getEncryptionStatus,
+  enableEncryption,
+  exportMasterKey,
+  importMasterKey,
+  exportBackup,
+  importBackup,
+  scrubBucket,
+  rebuildSearchIndex,
+  getSearchSynonyms,
+  saveSearchSynonyms,
+  getEmbeddingModel,
+  downloadEmbeddingModel,
+  deleteEmbeddingModel,
+  issueShareLink,
+  listShareLinks,
+  revokeShareLink,
+  type BackupProgress,
+  type BackupSummary,
+  type EmbeddingModelInfo,
+  type EncryptionStatus,
+  type RebuildIndexProgress,
+  type ScrubIssueKind,
+  type ScrubResult,
+  type SearchSynonyms,
+  type ShareLink,
   type ChatModel,
   type FileVersion,
   type WhisperModelInfo,
@@ -102,6 +127,24 @@ export default function Preferences({
         {/* Cost Explorer section */}
         <CostExplorerSection />
 
+        {/* Encryption section */}
+        <EncryptionSection />
+
+        {/* Backup section */}
+        <BackupSection />
+
+        {/* Data integrity section */}
+        <IntegritySection />
+
+        {/* Search index section */}
+        <SearchIndexSection />
+
+        {/* Search synonyms section */}
+        <SynonymsSection />
+
+        {/* Share links section */}
+        <ShareLinksSection />
+
         {/* Preferred Model section */}
         <details className="border border-gray-200 rounded-lg group">
           <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
@@ -830,6 +873,834 @@ function CostExplorerSection() {
   );
 }
 
+// ---------------------------------------------------------------------------
+// Encryption — practice master key
+// ---------------------------------------------------------------------------
+
+function EncryptionSection() {
+  const [status, setStatus] = useState<EncryptionStatus | null>(null);
+  const [exported, setExported] = useState<string | null>(null);
+  const [importText, setImportText] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    getEncryptionStatus()
+      .then(setStatus)
+      .catch((e) => setError(String(e)));
+  }, []);
+
+  async function run(action: () => Promise<void>) {
+    setBusy(true);
+    setError(null);
+    try {
+      await action();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  const keyNeeded =
+    status?.bucket_key_id != null && status.key_id !== status.bucket_key_id;
+  const plaintext = status != null && !status.enabled && !keyNeeded;
+
+  return (
+    <details
+      className="border border-gray-200 rounded-lg group"
+      open={plaintext || keyNeeded}
+    >
+      <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
+        <div className="flex items-center gap-2">
+          <span className="font-medium text-gray-900">Encryption</span>
+          {plaintext && <span className="text-xs text-red-600">Not encrypted</span>}
+          {keyNeeded && <span className="text-xs text-amber-600">Key needed</span>}
+          {status?.enabled && !keyNeeded && (
+            <span className="text-xs text-green-600">On</span>
+          )}
+        </div>
+        <span className="shrink-0 text-gray-400 text-xs transition-transform group-open:rotate-90">
+          &#9656;
+        </span>
+      </summary>
+      <div className="border-t border-gray-100 p-4 space-y-3">
+        {plaintext && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">
+              Client data is stored in your bucket unencrypted. Anyone who can
+              read the bucket can read it. Turn on encryption to encrypt
+              everything on this computer before it is uploaded.
+            </p>
+          </div>
+        )}
+        {keyNeeded && (
+          <div className="bg-amber-50 border border-amber-200 rounded-lg p-3">
+            <p className="text-amber-800 text-sm">
+              This practice's data is encrypted with key{" "}
+              <span className="font-mono">{status?.bucket_key_id}</span>. Export
+              it on the computer that set up the practice and paste it below.
+            </p>
+          </div>
+        )}
+        {status?.enabled && !keyNeeded && (
+          <p className="text-xs text-gray-400">
+            Everything is encrypted with your practice key{" "}
+            <span className="font-mono">{status.key_id}</span> before it leaves
+            this computer. Keep a copy of the key somewhere safe, and use it to
+            set up other computers in your practice.
+          </p>
+        )}
+
+        <div className="flex gap-2">
+          {plaintext && (
+            <button
+              onClick={() => run(async () => setStatus(await enableEncryption()))}
+              disabled={busy}
+              className="px-3 py-1 text-sm bg-blue-600 text-white rounded hover:bg-blue-700 disabled:opacity-50"
+            >
+              Turn on encryption
+            </button>
+          )}
+          {status?.enabled && (
+            <button
+              onClick={() => run(async () => setExported(await exportMasterKey()))}
+              disabled={busy}
+              className="px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-50 disabled:opacity-50"
+            >
+              Show key
+            </button>
+          )}
+        </div>
+
+        {exported && (
+          <textarea
+            readOnly
+            value={exported}
+            rows={2}
+            className="w-full border border-gray-300 rounded px-2 py-1 text-xs font-mono"
+          />
+        )}
+
+        {(!status?.enabled || keyNeeded) && (
+          <div>
+            <label className="block text-sm text-gray-900 mb-1">Import key</label>
+            <div className="flex gap-2">
+              <input
+                type="password"
+                value={importText}
+                onChange={(e) => setImportText(e.target.value)}
+                placeholder="Key exported from another computer"
+                className="flex-1 border border-gray-300 rounded px-2 py-1 text-sm font-mono"
+              />
+              <button
+                onClick={() =>
+                  run(async () => {
+                    setStatus(await importMasterKey(importText));
+                    setImportText("");
+                  })
+                }
+                disabled={busy || !importText.trim()}
+                className="px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-50 disabled:opacity-50"
+              >
+                Import
+              </button>
+            </div>
+          </div>
+        )}
+
+        {error && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">{error}</p>
+          </div>
+        )}
+      </div>
+    </details>
+  );
+}
+
+// ---------------------------------------------------------------------------
+// Backups
+// ---------------------------------------------------------------------------
+
+function BackupSection() {
+  const [exportPath, setExportPath] = useState("");
+  const [includeVersions, setIncludeVersions] = useState(true);
+  const [importPath, setImportPath] = useState("");
+  const [busy, setBusy] = useState<"export" | "import" | null>(null);
+  const [progress, setProgress] = useState<BackupProgress | null>(null);
+  const [result, setResult] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  function describe(summary: BackupSummary): string {
+    const markers =
+      summary.delete_markers > 0 ? `, ${summary.delete_markers} deletions` : "";
+    return `${summary.objects} objects${markers}, ${formatFileSize(summary.bytes)} from ${summary.bucket} (${formatDate(summary.created_at)})`;
+  }
+
+  async function handleExport() {
+    setBusy("export");
+    setProgress(null);
+    setResult(null);
+    setError(null);
+    try {
+      const summary = await exportBackup(exportPath.trim(), includeVersions, setProgress);
+      setResult(`Backup written: ${describe(summary)}`);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(null);
+    }
+  }
+
+  async function handleImport() {
+    setBusy("import");
+    setProgress(null);
+    setResult(null);
+    setError(null);
+    try {
+      const summary = await importBackup(importPath.trim(), setProgress);
+      setResult(`Backup restored: ${describe(summary)}`);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(null);
+    }
+  }
+
+  return (
+    <details className="border border-gray-200 rounded-lg group">
+      <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
+        <span className="font-medium text-gray-900">Backup</span>
+        <span className="shrink-0 text-gray-400 text-xs transition-transform group-open:rotate-90">
+          &#9656;
+        </span>
+      </summary>
+      <div className="border-t border-gray-100 p-4 space-y-4">
+        <p className="text-xs text-gray-400">
+          Backups are encrypted with your practice master key and need
+          encryption to be enabled. Keep a copy of the key somewhere safe: a
+          backup cannot be restored without it.
+        </p>
+
+        <div>
+          <label className="block text-sm text-gray-900 mb-1">Save backup to</label>
+          <div className="flex gap-2">
+            <input
+              type="text"
+              value={exportPath}
+              onChange={(e) => setExportPath(e.target.value)}
+              placeholder="/path/to/claria-backup.tar.zst"
+              className="flex-1 border border-gray-300 rounded px-2 py-1 text-sm"
+            />
+            <button
+              onClick={handleExport}
+              disabled={busy !== null || !exportPath.trim()}
+              className="px-3 py-1 text-sm bg-blue-600 text-white rounded hover:bg-blue-700 disabled:opacity-50"
+            >
+              Export
+            </button>
+          </div>
+          <label className="flex items-center gap-2 mt-2 text-xs text-gray-500">
+            <input
+              type="checkbox"
+              checked={includeVersions}
+              onChange={(e) => setIncludeVersions(e.target.checked)}
+              className="rounded border-gray-300"
+            />
+            Include file history and deleted files
+          </label>
+        </div>
+
+        <div>
+          <label className="block text-sm text-gray-900 mb-1">Restore backup from</label>
+          <div className="flex gap-2">
+            <input
+              type="text"
+              value={importPath}
+              onChange={(e) => setImportPath(e.target.value)}
+              placeholder="/path/to/claria-backup.tar.zst"
+              className="flex-1 border border-gray-300 rounded px-2 py-1 text-sm"
+            />
+            <button
+              onClick={handleImport}
+              disabled={busy !== null || !importPath.trim()}
+              className="px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-50 disabled:opacity-50"
+            >
+              Restore
+            </button>
+          </div>
+          <p className="text-xs text-gray-400 mt-1">
+            Only into a newly provisioned, empty bucket.
+          </p>
+        </div>
+
+        {busy && (
+          <div className="flex items-center gap-2 text-gray-500 text-sm">
+            <Spinner />
+            <span>
+              {busy === "export" ? "Exporting" : "Restoring"}
+              {progress &&
+                ` ${progress.entries_done} of ${progress.entries_total} (${formatFileSize(progress.bytes_done)})`}
+              ...
+            </span>
+          </div>
+        )}
+
+        {result && (
+          <div className="bg-green-50 border border-green-200 rounded-lg p-3">
+            <p className="text-green-800 text-sm">{result}</p>
+          </div>
+        )}
+
+        {error && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">{error}</p>
+          </div>
+        )}
+      </div>
+    </details>
+  );
+}
+
+// ---------------------------------------------------------------------------
+// Data integrity scrub
+// ---------------------------------------------------------------------------
+
+const SCRUB_ISSUE_LABELS: Record<ScrubIssueKind, string> = {
+  orphaned_sidecar: "Extracted text for a deleted file",
+  missing_sidecar: "Text never extracted",
+  orphaned_record: "File of a deleted client",
+  unparseable_json: "Unreadable data",
+  broken_reference: "Broken reference",
+};
+
+function IntegritySection() {
+  const [result, setResult] = useState<ScrubResult | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  async function run(dryRun: boolean) {
+    setBusy(true);
+    setError(null);
+    try {
+      setResult(await scrubBucket(dryRun));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  const repairable = result?.issues.filter((i) => i.repair && !i.repaired).length ?? 0;
+
+  return (
+    <details className="border border-gray-200 rounded-lg group">
+      <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
+        <div className="flex items-center gap-2">
+          <span className="font-medium text-gray-900">Data integrity</span>
+          {result && (
+            <span className="text-xs text-gray-400">
+              {result.issues.length === 0 ? "No issues" : `${result.issues.length} issues`}
+            </span>
+          )}
+        </div>
+        <span className="shrink-0 text-gray-400 text-xs transition-transform group-open:rotate-90">
+          &#9656;
+        </span>
+      </summary>
+      <div className="border-t border-gray-100 p-4 space-y-3">
+        <p className="text-xs text-gray-400">
+          Looks for extracted text that has drifted from its file, files left
+          behind by deleted clients, and saved data that no longer opens.
+          Checking changes nothing; repairs can be undone from file history.
+        </p>
+
+        <div className="flex gap-2">
+          <button
+            onClick={() => run(true)}
+            disabled={busy}
+            className="px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-50 disabled:opacity-50"
+          >
+            Check
+          </button>
+          {result?.dry_run && repairable > 0 && (
+            <button
+              onClick={() => run(false)}
+              disabled={busy}
+              className="px-3 py-1 text-sm bg-blue-600 text-white rounded hover:bg-blue-700 disabled:opacity-50"
+            >
+              Repair {repairable}
+            </button>
+          )}
+          {busy && (
+            <span className="flex items-center gap-2 text-gray-500 text-sm">
+              <Spinner /> Working...
+            </span>
+          )}
+        </div>
+
+        {result && !busy && (
+          <>
+            <p className="text-xs text-gray-500">
+              Checked {result.scanned} objects.
+            </p>
+            {result.issues.length > 0 && (
+              <ul className="divide-y divide-gray-100 border border-gray-100 rounded">
+                {result.issues.map((issue) => (
+                  <li key={issue.key} className="px-3 py-2 text-xs">
+                    <div className="flex items-center justify-between gap-2">
+                      <span className="font-medium text-gray-800">
+                        {SCRUB_ISSUE_LABELS[issue.kind]}
+                      </span>
+                      {issue.repaired ? (
+                        <span className="text-green-700">Repaired</span>
+                      ) : issue.error ? (
+                        <span className="text-red-700">Repair failed</span>
+                      ) : !issue.repair ? (
+                        <span className="text-amber-700">Needs review</span>
+                      ) : null}
+                    </div>
+                    <p className="text-gray-500 font-mono truncate" title={issue.key}>
+                      {issue.key}
+                    </p>
+                    <p className="text-gray-400">{issue.error ?? issue.detail}</p>
+                  </li>
+                ))}
+              </ul>
+            )}
+          </>
+        )}
+
+        {error && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">{error}</p>
+          </div>
+        )}
+      </div>
+    </details>
+  );
+}
+
+// ---------------------------------------------------------------------------
+// Search index rebuild
+// ---------------------------------------------------------------------------
+
+function SearchIndexSection() {
+  const [busy, setBusy] = useState(false);
+  const [progress, setProgress] = useState<RebuildIndexProgress | null>(null);
+  const [result, setResult] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [model, setModel] = useState<EmbeddingModelInfo | null>(null);
+  const [modelBusy, setModelBusy] = useState(false);
+
+  useEffect(() => {
+    getEmbeddingModel()
+      .then(setModel)
+      .catch((e) => setError(String(e)));
+  }, []);
+
+  async function handleModel(action: () => Promise<EmbeddingModelInfo>) {
+    setModelBusy(true);
+    setError(null);
+    try {
+      setModel(await action());
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setModelBusy(false);
+    }
+  }
+
+  async function handleRebuild() {
+    setBusy(true);
+    setProgress(null);
+    setResult(null);
+    setError(null);
+    try {
+      const summary = await rebuildSearchIndex(setProgress);
+      const skipped =
+        summary.skipped > 0 ? ` ${summary.skipped} unreadable items were left out.` : "";
+      setResult(`Indexed ${summary.documents} items.${skipped}`);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  return (
+    <details className="border border-gray-200 rounded-lg group">
+      <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
+        <span className="font-medium text-gray-900">Search index</span>
+        <span className="shrink-0 text-gray-400 text-xs transition-transform group-open:rotate-90">
+          &#9656;
+        </span>
+      </summary>
+      <div className="border-t border-gray-100 p-4 space-y-3">
+        <p className="text-xs text-gray-400">
+          If search stops finding things or reports a damaged index, rebuild
+          it from your clients, records and saved data. Nothing else is
+          changed.
+        </p>
+
+        <div className="flex gap-2">
+          <button
+            onClick={handleRebuild}
+            disabled={busy}
+            className="px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-50 disabled:opacity-50"
+          >
+            Rebuild
+          </button>
+          {busy && (
+            <span className="flex items-center gap-2 text-gray-500 text-sm">
+              <Spinner />
+              Indexing
+              {progress && ` ${progress.documents_done} of ${progress.documents_total}`}
+              ...
+            </span>
+          )}
+        </div>
+
+        {result && (
+          <div className="bg-green-50 border border-green-200 rounded-lg p-3">
+            <p className="text-green-800 text-sm">{result}</p>
+          </div>
+        )}
+
+        {model && (
+          <div className="border-t border-gray-100 pt-3 space-y-2">
+            <p className="text-xs text-gray-400">
+              Search by meaning finds passages about a topic even when they use
+              other words. It runs on a local model, so record text never
+              leaves your computer.
+            </p>
+            <div className="flex items-center gap-2">
+              {model.downloaded ? (
+                <>
+                  <span className="text-xs text-green-600">Ready</span>
+                  {model.model_size_bytes != null && (
+                    <span className="text-xs text-gray-400">
+                      {formatFileSize(model.model_size_bytes)}
+                    </span>
+                  )}
+                  <button
+                    onClick={() => handleModel(deleteEmbeddingModel)}
+                    disabled={modelBusy}
+                    className="px-2.5 py-1 text-xs text-red-600 border border-red-300 rounded-lg hover:bg-red-50 transition-colors disabled:opacity-50"
+                  >
+                    {modelBusy ? "Removing..." : "Remove"}
+                  </button>
+                </>
+              ) : (
+                <button
+                  onClick={() => handleModel(downloadEmbeddingModel)}
+                  disabled={modelBusy}
+                  className="px-2.5 py-1 text-xs text-white bg-blue-600 rounded-lg hover:bg-blue-700 transition-colors disabled:opacity-50 flex items-center gap-1.5"
+                >
+                  {modelBusy ? (
+                    <>
+                      <Spinner />
+                      <span>Downloading...</span>
+                    </>
+                  ) : (
+                    `Download search model (${model.download_size})`
+                  )}
+                </button>
+              )}
+            </div>
+          </div>
+        )}
+
+        {error && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">{error}</p>
+          </div>
+        )}
+      </div>
+    </details>
+  );
+}
+
+function SynonymsSection() {
+  const [synonyms, setSynonyms] = useState<SearchSynonyms | null>(null);
+  const [text, setText] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [progress, setProgress] = useState<RebuildIndexProgress | null>(null);
+  const [result, setResult] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  async function load() {
+    setError(null);
+    try {
+      const loaded = await getSearchSynonyms();
+      setSynonyms(loaded);
+      setText(loaded.custom.map((group) => group.join(", ")).join("\n"));
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
+  async function handleSave() {
+    setBusy(true);
+    setProgress(null);
+    setResult(null);
+    setError(null);
+    try {
+      const custom = text
+        .split("\n")
+        .map((line) => line.split(",").map((term) => term.trim()).filter(Boolean))
+        .filter((group) => group.length > 1);
+      const summary = await saveSearchSynonyms(custom, setProgress);
+      setResult(`Saved. Re-indexed ${summary.documents} items.`);
+      await load();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  return (
+    <details
+      className="border border-gray-200 rounded-lg group"
+      onToggle={(e) => {
+        if ((e.target as HTMLDetailsElement).open && synonyms === null) load();
+      }}
+    >
+      <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
+        <span className="font-medium text-gray-900">Search synonyms</span>
+        <span className="shrink-0 text-gray-400 text-xs transition-transform group-open:rotate-90">
+          &#9656;
+        </span>
+      </summary>
+      <div className="border-t border-gray-100 p-4 space-y-3">
+        <p className="text-xs text-gray-400">
+          Terms on the same line are searched as one, so an abbreviation finds
+          the spelled-out form. Put one group per line, separated by commas.
+          Saving re-indexes everything.
+        </p>
+
+        <textarea
+          value={text}
+          onChange={(e) => setText(e.target.value)}
+          disabled={synonyms === null || busy}
+          rows={5}
+          placeholder="PECS, picture exchange communication system"
+          className="w-full border border-gray-300 rounded px-2 py-1 text-sm font-mono"
+        />
+
+        <div className="flex gap-2">
+          <button
+            onClick={handleSave}
+            disabled={synonyms === null || busy}
+            className="px-3 py-1 text-sm border border-gray-300 rounded hover:bg-gray-50 disabled:opacity-50"
+          >
+            Save
+          </button>
+          {busy && (
+            <span className="flex items-center gap-2 text-gray-500 text-sm">
+              <Spinner />
+              Indexing
+              {progress && ` ${progress.documents_done} of ${progress.documents_total}`}
+              ...
+            </span>
+          )}
+        </div>
+
+        {synonyms && (
+          <div>
+            <p className="text-xs font-medium text-gray-500 mb-1">Built in</p>
+            <ul className="text-xs text-gray-400 space-y-0.5">
+              {synonyms.built_in.map((group) => (
+                <li key={group[0]}>{group.join(", ")}</li>
+              ))}
+            </ul>
+          </div>
+        )}
+
+        {result && (
+          <div className="bg-green-50 border border-green-200 rounded-lg p-3">
+            <p className="text-green-800 text-sm">{result}</p>
+          </div>
+        )}
+
+        {error && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">{error}</p>
+          </div>
+        )}
+      </div>
+    </details>
+  );
+}
+
+function ShareLinksSection() {
+  const [links, setLinks] = useState<ShareLink[] | null>(null);
+  const [includeInactive, setIncludeInactive] = useState(false);
+  const [busy, setBusy] = useState(false);
+  const [revoking, setRevoking] = useState<string | null>(null);
+  const [issuing, setIssuing] = useState<string | null>(null);
+  const [copied, setCopied] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  async function load(inactive: boolean) {
+    setBusy(true);
+    setError(null);
+    try {
+      setLinks(await listShareLinks(inactive));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function handleRevoke(id: string) {
+    setRevoking(id);
+    setError(null);
+    try {
+      const revoked = await revokeShareLink(id);
+      setLinks((prev) =>
+        (prev ?? [])
+          .map((l) => (l.id === id ? revoked : l))
+          .filter((l) => includeInactive || l.outstanding),
+      );
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setRevoking(null);
+    }
+  }
+
+  async function handleIssue(id: string) {
+    setIssuing(id);
+    setCopied(null);
+    setError(null);
+    try {
+      const issued = await issueShareLink(id);
+      await navigator.clipboard.writeText(issued.url);
+      setCopied(id);
+      setLinks((prev) => (prev ?? []).map((l) => (l.id === id ? issued.share : l)));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setIssuing(null);
+    }
+  }
+
+  return (
+    <details
+      className="border border-gray-200 rounded-lg group"
+      onToggle={(e) => {
+        if ((e.target as HTMLDetailsElement).open && links === null) load(includeInactive);
+      }}
+    >
+      <summary className="flex items-center justify-between p-4 cursor-pointer list-none [&::-webkit-details-marker]:hidden">
+        <div className="flex items-center gap-2">
+          <span className="font-medium text-gray-900">Share links</span>
+          {links && !includeInactive && (
+            <span className="text-xs text-gray-400">{links.length} outstanding</span>
+          )}
+        </div>
+        <span className="shrink-0 text-gray-400 text-xs transition-transform group-open:rotate-90">
+          &#9656;
+        </span>
+      </summary>
+      <div className="border-t border-gray-100 p-4 space-y-3">
+        <p className="text-xs text-gray-400">
+          Links issued from a client&apos;s record. Revoking a link deletes the
+          shared copy, so it stops working at once. The log of who shared
+          what is kept.
+        </p>
+
+        <div className="flex items-center gap-3">
+          <label className="flex items-center gap-2 text-sm text-gray-600">
+            <input
+              type="checkbox"
+              checked={includeInactive}
+              onChange={(e) => {
+                setIncludeInactive(e.target.checked);
+                load(e.target.checked);
+              }}
+            />
+            Include revoked and expired
+          </label>
+          {busy && (
+            <span className="flex items-center gap-2 text-gray-500 text-sm">
+              <Spinner /> Loading...
+            </span>
+          )}
+        </div>
+
+        {links && !busy && links.length === 0 && (
+          <p className="text-xs text-gray-500">No share links.</p>
+        )}
+        {links && !busy && links.length > 0 && (
+          <ul className="divide-y divide-gray-100 border border-gray-100 rounded">
+            {links.map((link) => (
+              <li key={link.id} className="px-3 py-2 text-xs">
+                <div className="flex items-center justify-between gap-2">
+                  <span className="font-medium text-gray-800 truncate">
+                    {link.filename} &rarr; {link.recipient}
+                  </span>
+                  {link.outstanding ? (
+                    <div className="flex shrink-0 gap-2">
+                      {(link.max_downloads === null || link.links_issued < link.max_downloads) && (
+                        <button
+                          onClick={() => handleIssue(link.id)}
+                          disabled={issuing === link.id}
+                          className="px-2 py-0.5 text-blue-600 border border-blue-200 rounded hover:bg-blue-50 disabled:opacity-50"
+                        >
+                          {issuing === link.id
+                            ? "Issuing..."
+                            : copied === link.id
+                              ? "Copied"
+                              : "Copy new link"}
+                        </button>
+                      )}
+                      <button
+                        onClick={() => handleRevoke(link.id)}
+                        disabled={revoking === link.id}
+                        className="px-2 py-0.5 text-red-600 border border-red-200 rounded hover:bg-red-50 disabled:opacity-50"
+                      >
+                        {revoking === link.id ? "Revoking..." : "Revoke"}
+                      </button>
+                    </div>
+                  ) : (
+                    <span className="text-gray-400">
+                      {link.revoked_at
+                        ? "Revoked"
+                        : link.max_downloads !== null && link.links_issued >= link.max_downloads
+                          ? "Used up"
+                          : "Expired"}
+                    </span>
+                  )}
+                </div>
+                <p className="text-gray-500">
+                  Shared {formatDate(link.created_at)}, expires {formatDate(link.expires_at)}
+                  {link.max_downloads !== null
+                    ? ` \u00b7 ${link.links_issued} of ${link.max_downloads} downloads issued`
+                    : ` \u00b7 ${link.links_issued} links issued`}
+                </p>
+                <p className="text-gray-400 font-mono truncate" title={link.created_by}>
+                  {link.created_by}
+                </p>
+              </li>
+            ))}
+          </ul>
+        )}
+
+        {error && (
+          <div className="bg-red-50 border border-red-200 rounded-lg p-3">
+            <p className="text-red-800 text-sm">{error}</p>
+          </div>
+        )}
+      </div>
+    </details>
+  );
+}
+
 // ---------------------------------------------------------------------------
 // Shared utilities
 // ---------------------------------------------------------------------------
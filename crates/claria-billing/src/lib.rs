@@ -1,9 +1,11 @@
 pub mod client;
 pub mod error;
 pub mod query;
+pub mod storage;
 pub mod types;
 
 pub use client::{get_cost_and_usage, probe_cost_explorer};
 pub use error::BillingError;
 pub use query::{parse_response, validate_query};
+pub use storage::{apportion, service_total, S3_SERVICE};
 pub use types::{CostAndUsageResult, CostGranularity, CostQuery, CostResultGroup, CostTimePeriod};
@@ -0,0 +1,35 @@
+use crate::types::CostAndUsageResult;
+
+/// The Cost Explorer service name S3 charges are grouped under.
+pub const S3_SERVICE: &str = "Amazon Simple Storage Service";
+
+/// Total spend on `service` across every period of a result grouped by
+/// service.
+///
+/// Pure function — no network calls.
+pub fn service_total(result: &CostAndUsageResult, service: &str) -> f64 {
+    result
+        .periods
+        .iter()
+        .flat_map(|p| &p.groups)
+        .filter(|g| g.key == service)
+        .filter_map(|g| g.amount.parse::<f64>().ok())
+        .sum()
+}
+
+/// Split `total` between items in proportion to their stored bytes.
+///
+/// S3 bills for requests and transfer as well as storage, so this is an
+/// estimate of what each item contributes, not an exact attribution.
+///
+/// Pure function — no network calls.
+pub fn apportion(total: f64, bytes: &[i64]) -> Vec<f64> {
+    let sum: i64 = bytes.iter().sum();
+    if sum <= 0 {
+        return vec![0.0; bytes.len()];
+    }
+    bytes
+        .iter()
+        .map(|&b| total * b as f64 / sum as f64)
+        .collect()
+}
@@ -0,0 +1,43 @@
+use claria_billing::{
+    apportion, service_total, CostAndUsageResult, CostResultGroup, CostTimePeriod, S3_SERVICE,
+};
+
+fn group(key: &str, amount: &str) -> CostResultGroup {
+    CostResultGroup {
+        key: key.into(),
+        amount: amount.into(),
+        unit: "USD".into(),
+    }
+}
+
+#[test]
+fn test_service_total_sums_every_period() {
+    let result = CostAndUsageResult {
+        periods: vec![
+            CostTimePeriod {
+                start: "2025-03-01".into(),
+                end: "2025-03-02".into(),
+                groups: vec![group("Amazon Bedrock", "8.50"), group(S3_SERVICE, "1.25")],
+            },
+            CostTimePeriod {
+                start: "2025-03-02".into(),
+                end: "2025-03-03".into(),
+                groups: vec![group(S3_SERVICE, "0.75")],
+            },
+        ],
+    };
+    assert!((service_total(&result, S3_SERVICE) - 2.0).abs() < 1e-9);
+    assert_eq!(service_total(&result, "Amazon EC2"), 0.0);
+}
+
+#[test]
+fn test_apportion_by_bytes() {
+    let shares = apportion(10.0, &[300, 100, 0]);
+    assert_eq!(shares, vec![7.5, 2.5, 0.0]);
+}
+
+#[test]
+fn test_apportion_with_no_bytes() {
+    assert_eq!(apportion(10.0, &[0, 0]), vec![0.0, 0.0]);
+    assert!(apportion(10.0, &[]).is_empty());
+}
@@ -0,0 +1,173 @@
+//! The clinical text analyzer used for titles and bodies.
+//!
+//! Words are split on whitespace and punctuation, but a hyphen between two
+//! words joins them, so "ADOS-2" and "ADOS2" are the same token. Words are
+//! lowercased, then any phrase in a [`SynonymDictionary`] group is replaced
+//! by one token shared by the whole group, then stemmed. Documents and
+//! queries go through the same analyzer, so "ASD", "autistic" and "autism
+//! spectrum disorder" all match each other once they share a group.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tantivy::tokenizer::{
+    Language, RemoveLongFilter, Stemmer, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+
+use crate::models::synonyms::SynonymDictionary;
+
+/// Tokens longer than this many bytes are dropped, as Tantivy's default
+/// analyzer does.
+const MAX_TOKEN_BYTES: usize = 40;
+
+/// Build the clinical analyzer for `synonyms`.
+pub fn clinical_analyzer(synonyms: &SynonymDictionary) -> TextAnalyzer {
+    TextAnalyzer::builder(ClinicalTokenizer {
+        synonyms: Arc::new(SynonymMap::new(synonyms)),
+    })
+    .filter(RemoveLongFilter::limit(MAX_TOKEN_BYTES))
+    .filter(Stemmer::new(Language::English))
+    .build()
+}
+
+/// Phrases, as lowercased words joined by spaces, mapped to the token
+/// standing in for their group.
+#[derive(Debug, Default)]
+struct SynonymMap {
+    phrases: HashMap<String, String>,
+    /// The most words in a phrase starting with each word.
+    starts: HashMap<String, usize>,
+}
+
+impl SynonymMap {
+    /// The token for a group is its first term's words joined with `_`. A
+    /// term listed in more than one group keeps the first.
+    fn new(dictionary: &SynonymDictionary) -> Self {
+        let mut map = Self::default();
+        for group in &dictionary.groups {
+            let mut terms = group
+                .iter()
+                .map(|term| words(term))
+                .filter(|w| !w.is_empty());
+            let Some(first) = terms.next() else {
+                continue;
+            };
+            let canonical = first
+                .iter()
+                .map(|w| w.text.as_str())
+                .collect::<Vec<_>>()
+                .join("_");
+            for term in std::iter::once(first).chain(terms) {
+                let longest = map.starts.entry(term[0].text.clone()).or_default();
+                *longest = (*longest).max(term.len());
+                map.phrases
+                    .entry(phrase(&term))
+                    .or_insert_with(|| canonical.clone());
+            }
+        }
+        map
+    }
+}
+
+#[derive(Clone)]
+struct ClinicalTokenizer {
+    synonyms: Arc<SynonymMap>,
+}
+
+impl Tokenizer for ClinicalTokenizer {
+    type TokenStream<'a> = ClinicalTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> ClinicalTokenStream {
+        let words = words(text);
+        let map = &self.synonyms;
+        let mut tokens = Vec::with_capacity(words.len());
+        let mut i = 0;
+        while i < words.len() {
+            // The longest phrase starting here wins.
+            let longest = map
+                .starts
+                .get(&words[i].text)
+                .map_or(0, |&n| n.min(words.len() - i));
+            let replaced = (1..=longest).rev().find_map(|len| {
+                let canonical = map.phrases.get(&phrase(&words[i..i + len]))?;
+                Some((len, canonical))
+            });
+            let (len, text) = match replaced {
+                Some((len, canonical)) => (len, canonical.clone()),
+                None => (1, words[i].text.clone()),
+            };
+            tokens.push(Token {
+                offset_from: words[i].offset_from,
+                offset_to: words[i + len - 1].offset_to,
+                position: tokens.len(),
+                text,
+                position_length: 1,
+            });
+            i += len;
+        }
+        ClinicalTokenStream { tokens, next: 0 }
+    }
+}
+
+struct ClinicalTokenStream {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl TokenStream for ClinicalTokenStream {
+    fn advance(&mut self) -> bool {
+        self.next += 1;
+        self.next <= self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.next - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.next - 1]
+    }
+}
+
+/// A lowercased word and its byte offsets in the text.
+struct Word {
+    offset_from: usize,
+    offset_to: usize,
+    text: String,
+}
+
+fn phrase(words: &[Word]) -> String {
+    words
+        .iter()
+        .map(|w| w.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split `text` into words: runs of alphanumeric characters, where a single
+/// hyphen between two runs joins them and is dropped.
+fn words(text: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut current: Option<Word> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        if c.is_alphanumeric() {
+            let word = current.get_or_insert_with(|| Word {
+                offset_from: offset,
+                offset_to: offset,
+                text: String::new(),
+            });
+            word.text.extend(c.to_lowercase());
+            word.offset_to = offset + c.len_utf8();
+            continue;
+        }
+        let joins = matches!(c, '-' | '\u{2010}' | '\u{2011}')
+            && current.is_some()
+            && chars.peek().is_some_and(|(_, next)| next.is_alphanumeric());
+        if !joins {
+            words.extend(current.take());
+        }
+    }
+    words.extend(current);
+    words
+}
@@ -3,6 +3,7 @@
 //! Pure domain types, Tantivy schema, and S3 key conventions.
 //! No AWS SDK dependency — this is the shared vocabulary of the Claria system.
 
+pub mod analyzer;
 pub mod error;
 pub mod models;
 pub mod s3_keys;
@@ -7,6 +7,7 @@ pub mod cost;
 pub mod goal;
 pub mod report;
 pub mod snippet;
+pub mod synonyms;
 pub mod template;
 pub mod token_count;
 pub mod transaction;
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Groups of terms that search treats as the same: abbreviations, spelled
+/// out forms and variants. Stored in the bucket so it can be edited, and
+/// with each index so the index is always read with the dictionary it was
+/// built with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SynonymDictionary {
+    /// Each group lists its terms, which may be phrases, e.g.
+    /// `["ASD", "autism spectrum disorder", "autistic"]`.
+    #[serde(default)]
+    pub groups: Vec<Vec<String>>,
+}
+
+impl SynonymDictionary {
+    /// `self`'s groups followed by `other`'s. Where a term is in both, the
+    /// group from `self` wins.
+    pub fn merged(&self, other: &SynonymDictionary) -> SynonymDictionary {
+        SynonymDictionary {
+            groups: self.groups.iter().chain(&other.groups).cloned().collect(),
+        }
+    }
+}
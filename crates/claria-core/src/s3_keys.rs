@@ -5,22 +5,41 @@
 
 use uuid::Uuid;
 
+pub const ASSESSMENTS_PREFIX: &str = "assessments/";
+
 pub fn assessment(id: Uuid) -> String {
     format!("assessments/{id}.json")
 }
 
+pub const SNIPPETS_PREFIX: &str = "snippets/";
+
 pub fn snippet(id: Uuid) -> String {
     format!("snippets/{id}.json")
 }
 
+pub const GOALS_PREFIX: &str = "goals/";
+
 pub fn goal(id: Uuid) -> String {
     format!("goals/{id}.json")
 }
 
+pub const TEMPLATES_PREFIX: &str = "templates/";
+
 pub fn template(id: Uuid) -> String {
     format!("templates/{id}.tera")
 }
 
+/// Metadata for a template; the template body lives at [`template`].
+pub fn template_metadata(id: Uuid) -> String {
+    format!("templates/{id}.json")
+}
+
+pub const REPORTS_PREFIX: &str = "reports/";
+
+pub fn report(id: Uuid) -> String {
+    format!("reports/{id}/report.json")
+}
+
 pub fn report_answer(id: Uuid) -> String {
     format!("reports/{id}/answer.json")
 }
@@ -69,6 +88,50 @@ pub const EXTRACTION_PROMPT: &str = "claria-prompts/pdf-extraction.md";
 /// Used as a read fallback so existing buckets keep working.
 pub const LEGACY_SYSTEM_PROMPT: &str = "system-prompt.md";
 
+/// The global search index: clients, assessments, snippets, goals,
+/// templates and reports.
 pub const INDEX: &str = "_index/tantivy.tar.zst";
 
+pub const CLIENT_INDEXES_PREFIX: &str = "_index/clients/";
+
+/// The search index shard holding one client's record files and chat
+/// histories.
+pub fn client_index(client_id: Uuid) -> String {
+    format!("_index/clients/{client_id}.tar.zst")
+}
+
+/// The user's search synonyms and abbreviations, added to the built-in ones.
+pub const SYNONYMS: &str = "_index/synonyms.json";
+
 pub const PROVISIONER_STATE: &str = "_state/provisioner.json";
+
+/// Fingerprint of the practice master key the bucket is encrypted with.
+/// Holds no key material.
+pub const PRACTICE_KEY: &str = "_state/practice-key.json";
+
+/// Tombstone left behind when a client is permanently purged. Holds no PHI.
+pub fn purge_tombstone(client_id: Uuid) -> String {
+    format!("_audit/purges/{client_id}.json")
+}
+
+pub const SHARES_AUDIT_PREFIX: &str = "_audit/shares/";
+
+/// Audit record of a share link: who shared what, with whom and when.
+pub fn share_record(share_id: Uuid) -> String {
+    format!("_audit/shares/{share_id}.json")
+}
+
+/// Prefix of the unencrypted share copies. A bucket lifecycle rule expires
+/// everything under it.
+pub const SHARES_PREFIX: &str = "_shares/";
+
+/// The unencrypted copy of a shared file that a share link points at.
+pub fn shared_file(share_id: Uuid, filename: &str) -> String {
+    format!("_shares/{share_id}/{filename}")
+}
+
+/// Where the integrity scrub moves an object that no longer parses, so it
+/// stops breaking listings but is kept for inspection.
+pub fn quarantine(key: &str) -> String {
+    format!("_quarantine/{key}")
+}
@@ -1,4 +1,16 @@
-use tantivy::schema::{self, Schema, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::schema::{
+    self, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, STORED,
+    STRING,
+};
+use tantivy::Index;
+
+use crate::analyzer::clinical_analyzer;
+use crate::models::synonyms::SynonymDictionary;
+
+/// Name of the clinical analyzer (see [`crate::analyzer`]) that title and
+/// body are indexed with. Register it with [`register_tokenizers`] before
+/// using an index.
+pub const CLINICAL_TOKENIZER: &str = "clinical";
 
 /// Field names used in the Tantivy index.
 pub mod field {
@@ -17,6 +29,13 @@ pub mod field {
     pub const COST_USD: &str = "cost_usd";
     pub const TEMPLATE_ID: &str = "template_id";
     pub const TRANSACTION_ID: &str = "transaction_id";
+    pub const CLIENT_ID: &str = "client_id";
+    /// The S3 key of the record file a passage was taken from.
+    pub const PARENT_KEY: &str = "parent_key";
+    pub const CHUNK_ORDINAL: &str = "chunk_ordinal";
+    /// Character offsets of a passage in its record file's text.
+    pub const CHUNK_START: &str = "chunk_start";
+    pub const CHUNK_END: &str = "chunk_end";
 }
 
 /// Document types stored in the Tantivy index.
@@ -27,8 +46,37 @@ pub mod doc_type {
     pub const TEMPLATE: &str = "template";
     pub const REPORT: &str = "report";
     pub const TRANSACTION: &str = "transaction";
+    pub const CLIENT: &str = "client";
+    /// A file in a client's record, indexed with its `.text` sidecar.
+    pub const RECORD_FILE: &str = "record_file";
+    pub const CHAT_HISTORY: &str = "chat_history";
+    /// A passage of a record file's text, searched on behalf of its file.
+    pub const PASSAGE: &str = "passage";
+
+    /// Every document type returned as a search hit, in the order facet
+    /// counts are reported. Passages are reported through their file.
+    pub const ALL: [&str; 9] = [
+        CLIENT,
+        RECORD_FILE,
+        CHAT_HISTORY,
+        ASSESSMENT,
+        SNIPPET,
+        GOAL,
+        TEMPLATE,
+        REPORT,
+        TRANSACTION,
+    ];
 }
 
+/// Version of [`build_schema`], stored with each index. Bump it whenever
+/// fields are added, removed or change options; an index built with another
+/// version is rebuilt from the bucket when it is next downloaded.
+///
+/// The clinical analyzer counts as part of the schema: bump the version when
+/// it changes how text is split, too, and when documents move between the
+/// global index and the per-client shards.
+pub const SCHEMA_VERSION: u32 = 6;
+
 /// Build the Tantivy schema used by the Claria index.
 pub fn build_schema() -> Schema {
     let mut builder = Schema::builder();
@@ -40,9 +88,16 @@ pub fn build_schema() -> Schema {
     // Boolean stored as text ("true"/"false") for filtering
     builder.add_text_field(field::ANONYMIZED, STRING | STORED);
 
-    // Full-text searchable fields
-    builder.add_text_field(field::TITLE, TEXT | STORED);
-    builder.add_text_field(field::BODY, TEXT);
+    // Full-text searchable fields; the body is stored for highlighted snippets
+    let clinical_text = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CLINICAL_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+    builder.add_text_field(field::TITLE, clinical_text.clone());
+    builder.add_text_field(field::BODY, clinical_text);
 
     // Stored-only metadata
     builder.add_text_field(field::S3_KEY, STORED);
@@ -65,10 +120,27 @@ pub fn build_schema() -> Schema {
     // Foreign keys — filterable
     builder.add_text_field(field::TEMPLATE_ID, STRING | STORED);
     builder.add_text_field(field::TRANSACTION_ID, STRING | STORED);
+    builder.add_text_field(field::CLIENT_ID, STRING | STORED);
+
+    // Passages — the parent key is indexed so a file's passages can be
+    // replaced together; positions are stored only
+    builder.add_text_field(field::PARENT_KEY, STRING | STORED);
+    builder.add_u64_field(field::CHUNK_ORDINAL, STORED);
+    builder.add_u64_field(field::CHUNK_START, STORED);
+    builder.add_u64_field(field::CHUNK_END, STORED);
 
     builder.build()
 }
 
+/// Register the clinical analyzer with `synonyms` on `index`. Every index
+/// must be registered with the dictionary it was built with, or its text
+/// fields are searched differently from how they were indexed.
+pub fn register_tokenizers(index: &Index, synonyms: &SynonymDictionary) {
+    index
+        .tokenizers()
+        .register(CLINICAL_TOKENIZER, clinical_analyzer(synonyms));
+}
+
 /// Resolve a field by name from the schema, returning the Tantivy `Field` handle.
 ///
 /// # Panics
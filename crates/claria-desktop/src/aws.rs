@@ -1,7 +1,25 @@
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use claria_core::s3_keys;
+use claria_storage::cache::{self, CachedStore, ObjectCache};
+use claria_storage::crypto::MasterKey;
+use claria_storage::encrypted::EncryptedStore;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::retry::{RetryPolicy, RetryingStore};
+use claria_storage::store::ObjectStore;
 
 use crate::config::CredentialSource;
 
+/// Environment variable that points Claria at a local directory instead of
+/// the S3 data bucket. Intended for development and testing without an AWS
+/// account; Bedrock and Transcribe calls still go to AWS.
+pub const LOCAL_STORE_ENV: &str = "CLARIA_LOCAL_STORE";
+
 /// Build an `SdkConfig` from a region and credential source.
 ///
 /// This is the only place in the desktop app that knows how to translate
@@ -38,6 +56,191 @@ pub async fn build_aws_config(
     builder.load().await
 }
 
+/// Build the object store for the data bucket.
+///
+/// Returns a [`LocalStore`] rooted at `$CLARIA_LOCAL_STORE` when that variable
+/// is set, otherwise an S3 client built from `sdk_config`, wrapped in a
+/// [`RetryingStore`] that retries throttled and failed-network requests with
+/// jittered backoff (the SDK's own retries are turned off so attempts do not
+/// multiply). When a practice master key is installed (one is created when
+/// the bucket is provisioned, see [`provision_practice_key`]) the store is
+/// wrapped in an [`EncryptedStore`] so every object body is encrypted before it
+/// leaves the machine. S3 reads then go through a [`CachedStore`] whose
+/// entries are sealed with the machine's local key, so repeated reads of the
+/// same record files are revalidated with `If-None-Match` instead of
+/// downloaded.
+pub fn build_object_store(
+    sdk_config: &aws_config::SdkConfig,
+) -> eyre::Result<Arc<dyn ObjectStore>> {
+    let (store, remote) = build_base_store(sdk_config);
+
+    let store: Arc<dyn ObjectStore> = match crate::config::load_master_key()? {
+        Some(key) => Arc::new(EncryptedStore::new(store, key)),
+        None => store,
+    };
+
+    if !remote {
+        return Ok(store);
+    }
+    match open_object_cache() {
+        Ok(cache) => Ok(Arc::new(CachedStore::new(store, cache))),
+        Err(e) => {
+            tracing::warn!(error = %e, "object cache unavailable, reading from S3 directly");
+            Ok(store)
+        }
+    }
+}
+
+/// Build the store share links are written through: the same bucket as
+/// [`build_object_store`], but without client-side encryption or caching,
+/// since recipients of a presigned URL have no key to decrypt with.
+pub fn build_share_store(sdk_config: &aws_config::SdkConfig) -> Arc<dyn ObjectStore> {
+    build_base_store(sdk_config).0
+}
+
+/// Fail unless the practice master key is installed, for callers about to
+/// read or write the search index.
+///
+/// The index holds the tokenized text of every record, so it is only ever
+/// stored sealed with the practice master key, which every machine sharing
+/// the bucket holds. [`build_object_store`] seals it like every other object
+/// when the key is installed, but writes in the clear when it is not, so
+/// without the key the index is not read or written at all.
+pub fn require_search_key() -> eyre::Result<()> {
+    if crate::config::load_master_key()?.is_none() {
+        eyre::bail!(
+            "Search needs the practice encryption key. Set it up or import it in Preferences."
+        );
+    }
+    Ok(())
+}
+
+/// The fingerprint of the practice master key the bucket's data is
+/// encrypted with, as recorded when the key was created or imported.
+///
+/// Read without the [`EncryptedStore`] so a machine that has no key yet can
+/// tell that it needs one.
+pub async fn practice_key_id(
+    sdk_config: &aws_config::SdkConfig,
+    bucket: &str,
+) -> eyre::Result<Option<String>> {
+    let (store, _) = build_base_store(sdk_config);
+    match objects::get_object(&*store, bucket, s3_keys::PRACTICE_KEY).await {
+        Ok(output) => {
+            let marker: PracticeKeyMarker = serde_json::from_slice(&output.body)?;
+            Ok(Some(marker.key_id))
+        }
+        Err(StorageError::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Record `key` as the bucket's practice master key. Only its fingerprint
+/// is written.
+pub async fn record_practice_key(
+    sdk_config: &aws_config::SdkConfig,
+    bucket: &str,
+    key: &MasterKey,
+) -> eyre::Result<()> {
+    let (store, _) = build_base_store(sdk_config);
+    let body = serde_json::to_vec(&PracticeKeyMarker {
+        key_id: key.key_id_hex(),
+    })?;
+    objects::put_object(&*store, bucket, s3_keys::PRACTICE_KEY, body, Some("application/json"))
+        .await?;
+    Ok(())
+}
+
+/// Make sure a newly provisioned bucket is encrypted by default.
+///
+/// If neither this machine nor the bucket has a practice master key yet, a
+/// new one is generated, installed and recorded in the bucket. If only this
+/// machine has one, it is recorded. If the bucket already names a key this
+/// machine does not have, or already holds encrypted client data, nothing
+/// is generated: that key has to be imported from the machine that created
+/// it.
+pub async fn provision_practice_key(
+    sdk_config: &aws_config::SdkConfig,
+    bucket: &str,
+) -> eyre::Result<()> {
+    let recorded = practice_key_id(sdk_config, bucket).await?;
+    match (crate::config::load_master_key()?, recorded) {
+        (None, None) if written_with_unknown_key(sdk_config, bucket).await? => {
+            tracing::warn!("bucket holds encrypted data but no practice key is recorded");
+        }
+        (None, None) => {
+            let key = MasterKey::generate();
+            crate::config::save_master_key(&key)?;
+            record_practice_key(sdk_config, bucket, &key).await?;
+            tracing::info!(key_id = %key.key_id_hex(), "practice master key created");
+        }
+        (Some(key), None) => record_practice_key(sdk_config, bucket, &key).await?,
+        (Some(key), Some(id)) if key.key_id_hex() != id => {
+            tracing::warn!(
+                installed = %key.key_id_hex(),
+                recorded = %id,
+                "installed master key does not match the bucket's"
+            );
+        }
+        (_, Some(_)) => {}
+    }
+    Ok(())
+}
+
+/// Whether the bucket already has client data encrypted with a key that
+/// was never recorded, from a practice set up before keys were.
+async fn written_with_unknown_key(
+    sdk_config: &aws_config::SdkConfig,
+    bucket: &str,
+) -> eyre::Result<bool> {
+    let (store, _) = build_base_store(sdk_config);
+    let keys = objects::list_objects(&*store, bucket, s3_keys::CLIENTS_PREFIX).await?;
+    let Some(key) = keys.first() else {
+        return Ok(false);
+    };
+    let output = objects::get_object(&*store, bucket, key).await?;
+    Ok(claria_storage::crypto::is_encrypted(&output.body))
+}
+
+#[derive(Serialize, Deserialize)]
+struct PracticeKeyMarker {
+    key_id: String,
+}
+
+/// The local or retrying S3 store, and whether it is remote.
+fn build_base_store(sdk_config: &aws_config::SdkConfig) -> (Arc<dyn ObjectStore>, bool) {
+    match std::env::var_os(LOCAL_STORE_ENV) {
+        Some(dir) if !dir.is_empty() => {
+            tracing::debug!(dir = ?dir, "using local object store");
+            (Arc::new(LocalStore::new(dir)), false)
+        }
+        _ => {
+            let s3_config = aws_sdk_s3::config::Builder::from(sdk_config)
+                .retry_config(aws_sdk_s3::config::retry::RetryConfig::disabled())
+                .build();
+            let store = RetryingStore::new(
+                Arc::new(aws_sdk_s3::Client::from_conf(s3_config)),
+                RetryPolicy::default(),
+            );
+            (Arc::new(store), true)
+        }
+    }
+}
+
+fn open_object_cache() -> eyre::Result<ObjectCache> {
+    Ok(ObjectCache::open(
+        crate::config::object_cache_dir()?,
+        crate::config::load_or_create_local_key()?,
+        cache::DEFAULT_MAX_BYTES,
+    )?)
+}
+
+/// The ETag of the copy of `key` this machine last read, if it is still in
+/// the object cache. Used as the base version of edits queued offline.
+pub fn cached_etag(bucket: &str, key: &str) -> Option<String> {
+    open_object_cache().ok()?.get(bucket, key, None)?.etag
+}
+
 /// Parse AWS profile names from `~/.aws/credentials` and `~/.aws/config`.
 pub fn list_aws_profiles() -> Vec<String> {
     let home = match dirs::home_dir() {
@@ -11,6 +11,8 @@ use claria_provisioner::account_setup::{
     StepStatus,
 };
 use claria_provisioner::{Action, Manifest, PlanEntry};
+use claria_storage::outbox::{EntryStatus, Precondition, Resolution};
+use claria_storage::store::ObjectStore;
 
 use crate::console::{ConsoleBuffer, ConsoleEntry};
 use crate::state::DesktopState;
@@ -191,6 +193,148 @@ pub async fn set_preferred_model(
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Encryption commands — the practice master key for client-side encryption
+// ---------------------------------------------------------------------------
+
+/// Whether client-side encryption is enabled on this machine, and which key
+/// the bucket's data is encrypted with.
+///
+/// Without a master key every object is written to the bucket in plaintext;
+/// the UI flags that. A `bucket_key_id` that differs from `key_id` (or is
+/// set while `key_id` is not) means the practice key has to be imported
+/// from the machine that created it.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    /// Short fingerprint of the master key, safe to display.
+    pub key_id: Option<String>,
+    /// Fingerprint of the practice key recorded in the bucket, if any.
+    pub bucket_key_id: Option<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_encryption_status(
+    state: State<'_, DesktopState>,
+) -> Result<EncryptionStatus, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let key = config::load_master_key().map_err(|e| e.to_string())?;
+    let bucket_key_id = claria_desktop::aws::practice_key_id(&sdk_config, &bucket_name(&cfg))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(EncryptionStatus {
+        enabled: key.is_some(),
+        key_id: key.map(|k| k.key_id_hex()),
+        bucket_key_id,
+    })
+}
+
+/// Generate a new practice master key and start encrypting all writes.
+///
+/// Normally done when the bucket is provisioned; this is for practices set
+/// up before encryption was on by default. Existing plaintext objects stay
+/// readable and are encrypted the next time they are saved. Fails if a key
+/// is already installed, or if the bucket is already encrypted with a key
+/// from another machine.
+#[tauri::command]
+#[specta::specta]
+pub async fn enable_encryption(
+    state: State<'_, DesktopState>,
+) -> Result<EncryptionStatus, String> {
+    if config::has_master_key() {
+        return Err("Encryption is already enabled on this machine".to_string());
+    }
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let bucket = bucket_name(&cfg);
+    if let Some(id) = claria_desktop::aws::practice_key_id(&sdk_config, &bucket)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        return Err(format!(
+            "This practice's data is already encrypted with key {id}; \
+             import that key from the machine that created it"
+        ));
+    }
+
+    let key = claria_storage::crypto::MasterKey::generate();
+    config::save_master_key(&key).map_err(|e| e.to_string())?;
+    claria_desktop::aws::record_practice_key(&sdk_config, &bucket, &key)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(EncryptionStatus {
+        enabled: true,
+        key_id: Some(key.key_id_hex()),
+        bucket_key_id: Some(key.key_id_hex()),
+    })
+}
+
+/// Export the practice master key as base64, for backup or for setting up
+/// another machine in the same practice.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_master_key() -> Result<String, String> {
+    use base64::Engine;
+
+    let key = config::load_master_key()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Encryption is not enabled on this machine".to_string())?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(key.as_bytes()))
+}
+
+/// Install a practice master key exported from another machine.
+///
+/// Refuses to replace a different key that is already installed, since
+/// anything written with it would become unreadable, and refuses a key
+/// other than the one the bucket is encrypted with.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_master_key(
+    state: State<'_, DesktopState>,
+    key: String,
+) -> Result<EncryptionStatus, String> {
+    use base64::Engine;
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(key.trim())
+        .map_err(|e| format!("Invalid master key: {e}"))?;
+    let key = claria_storage::crypto::MasterKey::from_bytes(&bytes).map_err(|e| e.to_string())?;
+
+    if let Some(existing) = config::load_master_key().map_err(|e| e.to_string())?
+        && existing.key_id() != key.key_id()
+    {
+        return Err(format!(
+            "A different master key ({}) is already installed on this machine",
+            existing.key_id_hex()
+        ));
+    }
+
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let bucket = bucket_name(&cfg);
+    match claria_desktop::aws::practice_key_id(&sdk_config, &bucket)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Some(id) if id != key.key_id_hex() => {
+            return Err(format!(
+                "This practice's data is encrypted with key {id}, not {}",
+                key.key_id_hex()
+            ));
+        }
+        Some(_) => {}
+        None => claria_desktop::aws::record_practice_key(&sdk_config, &bucket, &key)
+            .await
+            .map_err(|e| e.to_string())?,
+    }
+
+    config::save_master_key(&key).map_err(|e| e.to_string())?;
+    Ok(EncryptionStatus {
+        enabled: true,
+        key_id: Some(key.key_id_hex()),
+        bucket_key_id: Some(key.key_id_hex()),
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Credential commands — thin wrappers that delegate to the provisioner
 // ---------------------------------------------------------------------------
@@ -757,6 +901,11 @@ pub async fn apply(
     execute_with_progress(&entries, &syncers, &mut prov_state, &persistence, &on_progress)
         .await?;
 
+    // Encrypt the new bucket by default.
+    claria_desktop::aws::provision_practice_key(&sdk_config, &bucket_name(&cfg))
+        .await
+        .map_err(|e| e.to_string())?;
+
     // Re-scan to show updated state (with progress).
     scan_with_progress(&syncers, &prov_state, &on_progress).await
 }
@@ -826,17 +975,19 @@ pub async fn list_clients(
     state: State<'_, DesktopState>,
 ) -> Result<Vec<ClientSummary>, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
+    // Index anything a previous session queued but never flushed.
+    state.index.resume(store.clone(), &bucket);
 
-    let keys = claria_storage::objects::list_objects(&s3, &bucket, claria_core::s3_keys::CLIENTS_PREFIX)
+    let keys = claria_storage::objects::list_objects(&*store, &bucket, claria_core::s3_keys::CLIENTS_PREFIX)
         .await
         .map_err(|e| e.to_string())?;
 
     let mut clients: Vec<ClientSummary> = Vec::new();
 
     for key in &keys {
-        let output = match claria_storage::objects::get_object(&s3, &bucket, key).await {
+        let output = match claria_storage::objects::get_object(&*store, &bucket, key).await {
             Ok(o) => o,
             Err(e) => {
                 tracing::warn!(key, error = %e, "skipping unreadable client object");
@@ -873,7 +1024,7 @@ pub async fn create_client(
     name: String,
 ) -> Result<ClientSummary, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id = uuid::Uuid::new_v4();
@@ -888,10 +1039,11 @@ pub async fn create_client(
     let body = serde_json::to_vec_pretty(&client).map_err(|e| e.to_string())?;
     let key = claria_core::s3_keys::client(id);
 
-    claria_storage::objects::put_object(&s3, &bucket, &key, body, Some("application/json"))
+    claria_storage::objects::put_object(&*store, &bucket, &key, body, Some("application/json"))
         .await
         .map_err(|e| e.to_string())?;
 
+    state.index.enqueue(store, &bucket, [key]);
     tracing::info!(client_id = %id, name = %name, "client record created");
 
     Ok(ClientSummary {
@@ -909,28 +1061,452 @@ pub async fn delete_client(
     client_id: String,
 ) -> Result<(), String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
 
     // Delete all record files (includes chat history, sidecars, etc.)
     let records_prefix = claria_core::s3_keys::client_records_prefix(id);
-    let deleted = claria_storage::objects::delete_objects_by_prefix(&s3, &bucket, &records_prefix)
+    let mut keys = claria_storage::objects::list_objects(&*store, &bucket, &records_prefix)
+        .await
+        .map_err(|e| e.to_string())?;
+    let deleted = claria_storage::objects::delete_objects_by_prefix(&*store, &bucket, &records_prefix)
         .await
         .map_err(|e| e.to_string())?;
 
     // Delete the client JSON itself.
     let client_key = claria_core::s3_keys::client(id);
-    claria_storage::objects::delete_object(&s3, &bucket, &client_key)
+    claria_storage::objects::delete_object(&*store, &bucket, &client_key)
         .await
         .map_err(|e| e.to_string())?;
 
+    keys.push(client_key);
+    state.index.enqueue(store, &bucket, keys);
+
     tracing::info!(client_id = %id, deleted_records = deleted, "client deleted");
 
     Ok(())
 }
 
+/// One full-text search hit.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct SearchHit {
+    /// `client`, `record_file`, `chat_history`, or a core model such as
+    /// `snippet` or `report`.
+    pub doc_type: String,
+    /// The client name, filename, start of a chat, or model title.
+    pub title: String,
+    pub client_id: Option<String>,
+    pub s3_key: String,
+    /// RFC 3339.
+    pub updated_at: String,
+    pub score: f64,
+    /// Where the text matched the body, if it did.
+    pub snippet: Option<SearchSnippet>,
+    /// For a record file, the passages that matched best, best first.
+    pub passages: Vec<SearchPassage>,
+}
+
+/// A passage of a record file's text that matched.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct SearchPassage {
+    pub ordinal: i32,
+    /// Character offsets of the passage in the file's text.
+    pub start: i32,
+    pub end: i32,
+    pub snippet: Option<SearchSnippet>,
+}
+
+/// A fragment of a document's body around the matched terms.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct SearchSnippet {
+    pub fragment: String,
+    /// Matched terms within `fragment`, as UTF-16 offsets so they can be
+    /// passed straight to `String.prototype.slice`.
+    pub highlights: Vec<TextRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct TextRange {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl From<claria_search::query::Snippet> for SearchSnippet {
+    fn from(snippet: claria_search::query::Snippet) -> Self {
+        let utf16 = |byte: usize| snippet.fragment[..byte].encode_utf16().count() as i32;
+        let highlights = snippet
+            .highlights
+            .iter()
+            .map(|r| TextRange {
+                start: utf16(r.start),
+                end: utf16(r.end),
+            })
+            .collect();
+        Self {
+            fragment: snippet.fragment,
+            highlights,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    #[default]
+    Score,
+    Recency,
+}
+
+/// Filters combined with the text of [`search_records`]. Unset filters
+/// match everything; timestamps are RFC 3339, `*_after` inclusive and
+/// `*_before` exclusive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, specta::Type)]
+pub struct SearchFilters {
+    pub client_id: Option<String>,
+    /// Any of these document types; empty for all.
+    pub doc_types: Vec<String>,
+    pub anonymized: Option<bool>,
+    pub status: Option<String>,
+    pub model_id: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub updated_after: Option<String>,
+    pub updated_before: Option<String>,
+    pub sort: SearchSort,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct DocTypeCount {
+    pub doc_type: String,
+    pub count: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct SearchResponse {
+    pub hits: Vec<SearchHit>,
+    /// Matching documents, including those on other pages.
+    pub total: i32,
+    /// The `offset` of the next page, or `None` on the last page.
+    pub next_offset: Option<i32>,
+    /// Matches per document type, ignoring the `doc_types` filter.
+    pub doc_type_counts: Vec<DocTypeCount>,
+}
+
+/// An RFC 3339 timestamp as Unix seconds.
+fn unix_seconds(timestamp: Option<&str>) -> Result<Option<i64>, String> {
+    timestamp
+        .map(|t| {
+            t.parse::<jiff::Timestamp>()
+                .map(|t| t.as_second())
+                .map_err(|e| format!("Invalid timestamp {t:?}: {e}"))
+        })
+        .transpose()
+}
+
+/// The shards kept between searches, creating the cache on first use. The
+/// caller holds the lock for the whole search, since shards are searched in
+/// place.
+fn search_shard_cache(
+    cache: &mut Option<claria_search::index::ShardCache>,
+) -> Result<&mut claria_search::index::ShardCache, String> {
+    let shards = match cache.take() {
+        Some(shards) => shards,
+        None => claria_search::index::ShardCache::new().map_err(|e| e.to_string())?,
+    };
+    Ok(cache.insert(shards))
+}
+
+/// Search across clients, record files (by their text), chat histories
+/// and core models, narrowed by `filters`, one page of `limit` hits from
+/// `offset` at a time. A blank query matches every document the filters
+/// allow. Returns no hits if nothing has been indexed yet.
+///
+/// Filtered to one client, only the global index and that client's shard
+/// are searched; otherwise every client's shard is searched too. Shards are
+/// kept between searches and only downloaded again once they change.
+#[tauri::command]
+#[specta::specta]
+pub async fn search_records(
+    state: State<'_, DesktopState>,
+    query: String,
+    filters: SearchFilters,
+    offset: i32,
+    limit: i32,
+) -> Result<SearchResponse, String> {
+    use claria_search::query::{SearchQuery, SortOrder, TimeRange};
+
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let empty = SearchResponse {
+        hits: Vec::new(),
+        total: 0,
+        next_offset: None,
+        doc_type_counts: Vec::new(),
+    };
+    let client_id = filters
+        .client_id
+        .as_deref()
+        .map(str::parse::<uuid::Uuid>)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let search_query = SearchQuery {
+        text: Some(query.trim().to_string()).filter(|q| !q.is_empty()),
+        client_id: filters.client_id,
+        doc_types: filters.doc_types,
+        anonymized: filters.anonymized,
+        status: filters.status,
+        model_id: filters.model_id,
+        created_at: TimeRange {
+            from: unix_seconds(filters.created_after.as_deref())?,
+            to: unix_seconds(filters.created_before.as_deref())?,
+        },
+        updated_at: TimeRange {
+            from: unix_seconds(filters.updated_after.as_deref())?,
+            to: unix_seconds(filters.updated_before.as_deref())?,
+        },
+        sort: match filters.sort {
+            SearchSort::Score => SortOrder::Score,
+            SearchSort::Recency => SortOrder::Recency,
+        },
+        offset: offset.max(0) as usize,
+        limit: limit.max(1) as usize,
+    };
+
+    // A client's search only needs the global index and that client's shard.
+    let shards = claria_search::shard::shards_for_search(&*store, &bucket, client_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    claria_desktop::aws::require_search_key().map_err(|e| e.to_string())?;
+    let mut shard_cache = state.search_shards.lock().await;
+    let shard_cache = search_shard_cache(&mut shard_cache)?;
+    let loaded = shard_cache
+        .download_shards(&*store, &bucket, &shards)
+        .await
+        .map_err(|e| e.to_string())?;
+    if loaded.is_empty() {
+        return Ok(empty);
+    }
+    let indexes: Vec<_> = loaded.iter().map(|(_, loaded)| &loaded.index).collect();
+    let results = claria_search::query::search_across(&indexes, &search_query)
+        .map_err(|e| e.to_string())?;
+
+    Ok(SearchResponse {
+        hits: results
+            .hits
+            .into_iter()
+            .map(|r| SearchHit {
+                doc_type: r.doc_type,
+                title: r.title,
+                client_id: r.client_id,
+                s3_key: r.s3_key,
+                updated_at: jiff::Timestamp::from_second(r.updated_at)
+                    .unwrap_or_default()
+                    .to_string(),
+                score: r.score as f64,
+                snippet: r.snippet.map(SearchSnippet::from),
+                passages: r
+                    .passages
+                    .into_iter()
+                    .map(|p| SearchPassage {
+                        ordinal: p.ordinal as i32,
+                        start: p.start as i32,
+                        end: p.end as i32,
+                        snippet: p.snippet.map(SearchSnippet::from),
+                    })
+                    .collect(),
+            })
+            .collect(),
+        total: results.total as i32,
+        next_offset: results.next_offset.map(|n| n as i32),
+        doc_type_counts: results
+            .doc_type_counts
+            .into_iter()
+            .map(|(doc_type, count)| DocTypeCount {
+                doc_type,
+                count: count as i32,
+            })
+            .collect(),
+    })
+}
+
+/// Progress of a search index rebuild, streamed to the frontend via Channel<T>.
+#[derive(Clone, Serialize, Deserialize, specta::Type)]
+pub struct RebuildIndexProgress {
+    pub documents_done: i32,
+    pub documents_total: i32,
+}
+
+/// What [`rebuild_search_index`] indexed.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct RebuildIndexResult {
+    pub documents: i32,
+    /// Objects whose JSON could not be parsed and were left out.
+    pub skipped: i32,
+}
+
+/// Rebuild the search index from everything in the bucket, replacing the
+/// stored index. The recovery path when the index is missing or corrupted.
+#[tauri::command]
+#[specta::specta]
+pub async fn rebuild_search_index(
+    state: State<'_, DesktopState>,
+    on_progress: tauri::ipc::Channel<RebuildIndexProgress>,
+) -> Result<RebuildIndexResult, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    claria_desktop::aws::require_search_key().map_err(|e| e.to_string())?;
+    let summary = claria_search::rebuild::rebuild_index(
+        &*store,
+        &bucket,
+        &mut |p| {
+            let _ = on_progress.send(RebuildIndexProgress {
+                documents_done: p.documents_done as i32,
+                documents_total: p.documents_total as i32,
+            });
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(RebuildIndexResult {
+        documents: summary.documents as i32,
+        skipped: summary.skipped as i32,
+    })
+}
+
+/// Search synonym groups: the built-in ones (instrument names and common
+/// clinical abbreviations) and the user's own.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct SearchSynonyms {
+    pub built_in: Vec<Vec<String>>,
+    pub custom: Vec<Vec<String>>,
+}
+
+/// The synonym groups search matches as one term.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_search_synonyms(state: State<'_, DesktopState>) -> Result<SearchSynonyms, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let custom = claria_search::synonyms::load_synonyms(&*store, &bucket)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(SearchSynonyms {
+        built_in: claria_search::synonyms::built_in().groups,
+        custom: custom.groups,
+    })
+}
+
+/// Replace the user's synonym groups and rebuild the search index so
+/// existing documents are matched with them. Blank terms and groups with
+/// fewer than two terms are dropped.
+#[tauri::command]
+#[specta::specta]
+pub async fn save_search_synonyms(
+    state: State<'_, DesktopState>,
+    custom: Vec<Vec<String>>,
+    on_progress: tauri::ipc::Channel<RebuildIndexProgress>,
+) -> Result<RebuildIndexResult, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let groups = custom
+        .into_iter()
+        .map(|group| {
+            group
+                .into_iter()
+                .map(|term| term.trim().to_string())
+                .filter(|term| !term.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|group| group.len() > 1)
+        .collect();
+    let synonyms = claria_core::models::synonyms::SynonymDictionary { groups };
+    claria_search::synonyms::save_synonyms(&*store, &bucket, &synonyms)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    claria_desktop::aws::require_search_key().map_err(|e| e.to_string())?;
+    let summary = claria_search::rebuild::rebuild_index(
+        &*store,
+        &bucket,
+        &mut |p| {
+            let _ = on_progress.send(RebuildIndexProgress {
+                documents_done: p.documents_done as i32,
+                documents_total: p.documents_total as i32,
+            });
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(RebuildIndexResult {
+        documents: summary.documents as i32,
+        skipped: summary.skipped as i32,
+    })
+}
+
+/// What [`purge_client`] permanently removed.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct PurgeResult {
+    pub objects: i32,
+    pub versions: i32,
+    pub delete_markers: i32,
+    pub index_documents: i32,
+}
+
+/// Permanently purge a client: every version and delete marker of its
+/// record files, chat history, client JSON and search index shard, plus its
+/// entry in the global search index. This cannot be undone.
+///
+/// Unlike [`delete_client`], nothing is left to restore. A tombstone with
+/// the client ID and counts (but no PHI) is kept for the audit trail.
+#[tauri::command]
+#[specta::specta]
+pub async fn purge_client(
+    state: State<'_, DesktopState>,
+    client_id: String,
+) -> Result<PurgeResult, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+
+    // Drop index entries first: if the index is busy the purge fails before
+    // anything irreversible has happened, and can simply be retried.
+    let records_prefix = claria_core::s3_keys::client_records_prefix(id);
+    let client_key = claria_core::s3_keys::client(id);
+    claria_desktop::aws::require_search_key().map_err(|e| e.to_string())?;
+    let index_documents = claria_search::purge::purge_key_prefixes(
+        &*store,
+        &bucket,
+        &[&records_prefix, &client_key],
+    )
+    .await
+    .map_err(|e| format!("Failed to remove client from search index: {e}"))?;
+
+    let tombstone = claria_storage::purge::purge_client(&*store, &bucket, id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(PurgeResult {
+        objects: tombstone.objects as i32,
+        versions: tombstone.versions as i32,
+        delete_markers: tombstone.delete_markers as i32,
+        index_documents: index_documents as i32,
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Record file commands — files attached to a client record
 // ---------------------------------------------------------------------------
@@ -943,6 +1519,14 @@ pub struct RecordFile {
     pub uploaded_at: Option<String>,
 }
 
+/// The result of [`upload_record_file`].
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct RecordUpload {
+    pub file: RecordFile,
+    /// Another file in the record with identical contents, if any.
+    pub duplicate_of: Option<String>,
+}
+
 /// The Bedrock model ID used for document text extraction.
 ///
 /// Uses a Claude Sonnet inference profile — good quality at lower cost.
@@ -956,13 +1540,13 @@ pub async fn list_record_files(
     client_id: String,
 ) -> Result<Vec<RecordFile>, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let prefix = claria_core::s3_keys::client_records_prefix(id);
 
-    let objects = claria_storage::objects::list_objects_with_metadata(&s3, &bucket, &prefix)
+    let objects = claria_storage::objects::list_objects_with_metadata(&*store, &bucket, &prefix)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -996,19 +1580,37 @@ pub async fn list_record_files(
     Ok(files)
 }
 
+/// Upload progress for a record file, streamed to the frontend via Channel<T>.
+#[derive(Clone, Serialize, Deserialize, specta::Type)]
+pub struct RecordUploadProgress {
+    pub filename: String,
+    pub bytes_uploaded: f64,
+    pub total_bytes: f64,
+}
+
 /// Upload a file to a client's record from a local file path.
 ///
+/// The file is streamed from disk (as a multipart upload when large), with
+/// progress reported on `on_progress`. An interrupted upload of the same
+/// file resumes from the parts already in S3.
+///
 /// If the file is a PDF or DOCX, a sidecar `.text` file is generated
 /// via Bedrock document text extraction and uploaded alongside.
+///
+/// The file's SHA-256 is stored as object metadata. When the client's
+/// record already holds a file with the same contents under another name,
+/// the result names it in `duplicate_of`, and that file's sidecar is copied
+/// instead of running extraction again.
 #[tauri::command]
 #[specta::specta]
 pub async fn upload_record_file(
     state: State<'_, DesktopState>,
     client_id: String,
     file_path: String,
-) -> Result<RecordFile, String> {
+    on_progress: tauri::ipc::Channel<RecordUploadProgress>,
+) -> Result<RecordUpload, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
@@ -1019,8 +1621,9 @@ pub async fn upload_record_file(
         .and_then(|n| n.to_str())
         .ok_or_else(|| "Invalid file path".to_string())?;
 
-    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {e}"))?;
-    let file_size = bytes.len() as i32;
+    let file_size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read file: {e}"))?
+        .len() as i32;
 
     // Determine content type from extension.
     let extension = path
@@ -1047,20 +1650,199 @@ pub async fn upload_record_file(
         _ => None,
     };
 
-    // Upload the original file.
     let key = claria_core::s3_keys::client_record_file(id, filename);
-    claria_storage::objects::put_object(&s3, &bucket, &key, bytes.clone(), content_type)
+    let queued = RecordUpload {
+        file: RecordFile {
+            filename: filename.to_string(),
+            size: file_size,
+            uploaded_at: None,
+        },
+        duplicate_of: None,
+    };
+
+    // Earlier changes to this file are still waiting to sync; go after them.
+    // The earliest of them carries the check against changes made elsewhere.
+    {
+        let _guard = state.outbox.lock().await;
+        let outbox = claria_desktop::outbox::open().map_err(|e| e.to_string())?;
+        if outbox.has_entries_for(&bucket, &key).map_err(|e| e.to_string())? {
+            outbox
+                .enqueue_file(&bucket, &key, path, content_type, Precondition::None)
+                .map_err(|e| e.to_string())?;
+            return Ok(queued);
+        }
+    }
+
+    // Neither this upload nor a replay of it may overwrite a version of the
+    // file written elsewhere after this one was seen.
+    let precondition = upload_precondition(&*store, &bucket, &key).await;
+
+    let sha256 = claria_storage::objects::sha256_file(path)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| format!("Failed to read file: {e}"))?;
+    let duplicate_of = find_duplicate(&*store, &bucket, id, &key, &sha256).await;
+
+    // Upload the original file, resuming an earlier interrupted attempt.
+    let resumable = claria_desktop::uploads::find_resumable(&bucket, &key, path);
+    let options = claria_storage::objects::UploadOptions {
+        content_type,
+        metadata: claria_storage::objects::Metadata::from([(
+            claria_storage::objects::SHA256_METADATA_KEY.to_string(),
+            sha256.clone(),
+        )]),
+        resume: resumable.as_ref().map(|(upload_id, sha256)| {
+            claria_storage::objects::ResumeUpload { upload_id, sha256 }
+        }),
+        precondition: precondition.clone(),
+        ..Default::default()
+    };
+    let mut remembered: Option<String> = None;
+    let uploaded =
+        claria_storage::objects::upload_file(&*store, &bucket, &key, path, &options, &mut |p| {
+            if let Some(upload_id) = &p.upload_id
+                && remembered.as_ref() != Some(upload_id)
+            {
+                if let Err(e) =
+                    claria_desktop::uploads::remember(&bucket, &key, upload_id, path, &sha256)
+                {
+                    tracing::warn!(error = %e, "failed to record pending upload");
+                }
+                remembered = Some(upload_id.clone());
+            }
+            let _ = on_progress.send(RecordUploadProgress {
+                filename: filename.to_string(),
+                bytes_uploaded: p.bytes_uploaded as f64,
+                total_bytes: p.total_bytes as f64,
+            });
+        })
+        .await;
+
+    match uploaded {
+        Ok(_) => {}
+        Err(e) if e.is_retryable() => {
+            tracing::warn!(client_id = %id, filename, error = %e, "S3 unreachable, queuing upload");
+            let _guard = state.outbox.lock().await;
+            claria_desktop::outbox::open()
+                .map_err(|e| e.to_string())?
+                .enqueue_file(&bucket, &key, path, content_type, precondition)
+                .map_err(|e| e.to_string())?;
+            return Ok(queued);
+        }
+        Err(claria_storage::error::StorageError::PreconditionFailed { .. }) => {
+            return Err(format!("{filename} was changed on another device; upload it again"));
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+
+    if let Err(e) = claria_desktop::uploads::forget(&bucket, &key) {
+        tracing::warn!(error = %e, "failed to clear pending upload");
+    }
 
     tracing::info!(client_id = %id, filename, "record file uploaded");
 
-    // Generate sidecar text extraction for supported document types.
-    if let Some(format) = claria_bedrock::extract::document_format_for_extension(&extension) {
-        let sidecar_key = format!("{key}.text");
-        let extraction_prompt = load_prompt(&s3, &bucket, "pdf-extraction").await?;
+    // A duplicate's sidecar holds the same text, so skip paying for
+    // extraction again.
+    let copied = match &duplicate_of {
+        Some(original) => copy_sidecar(&*store, &bucket, id, original, &key).await,
+        None => false,
+    };
+    let sidecar = if copied {
+        Ok(())
+    } else {
+        generate_sidecar(&sdk_config, &*store, &bucket, id, &key, path).await
+    };
+    // Index the file even if extraction failed; its name is still searchable.
+    state.index.enqueue(store, &bucket, [key]);
+    sidecar?;
+
+    Ok(RecordUpload {
+        file: RecordFile {
+            filename: filename.to_string(),
+            size: file_size,
+            uploaded_at: Some(jiff::Timestamp::now().to_string()),
+        },
+        duplicate_of,
+    })
+}
+
+/// Another file in the client's record with the given SHA-256, if any.
+/// Lookup failures are logged and treated as no duplicate, so they never
+/// block an upload.
+async fn find_duplicate(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    id: uuid::Uuid,
+    key: &str,
+    sha256: &str,
+) -> Option<String> {
+    let prefix = claria_core::s3_keys::client_records_prefix(id);
+    match claria_storage::objects::find_by_sha256(store, bucket, &prefix, sha256).await {
+        Ok(keys) => keys
+            .into_iter()
+            .find(|k| k != key)
+            .and_then(|k| k.strip_prefix(&prefix).map(|f| f.to_string())),
+        Err(e) => {
+            tracing::warn!(client_id = %id, error = %e, "duplicate check failed");
+            None
+        }
+    }
+}
+
+/// Copy the `.text` sidecar of `original` (a filename in the client's
+/// record) to `key`. Returns whether there was a sidecar to copy.
+async fn copy_sidecar(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    id: uuid::Uuid,
+    original: &str,
+    key: &str,
+) -> bool {
+    let from = format!("{}.text", claria_core::s3_keys::client_record_file(id, original));
+    let text = match claria_storage::objects::get_object(store, bucket, &from).await {
+        Ok(output) => output.body,
+        Err(claria_storage::error::StorageError::NotFound { .. }) => return false,
+        Err(e) => {
+            tracing::warn!(client_id = %id, error = %e, "failed to read duplicate's sidecar");
+            return false;
+        }
+    };
+    let to = format!("{key}.text");
+    match claria_storage::objects::put_object(store, bucket, &to, text, Some("text/plain")).await {
+        Ok(_) => {
+            tracing::info!(client_id = %id, "sidecar copied from duplicate");
+            true
+        }
+        Err(e) => {
+            tracing::warn!(client_id = %id, error = %e, "failed to copy duplicate's sidecar");
+            false
+        }
+    }
+}
+
+/// Generate the `.text` sidecar for an uploaded document (via Bedrock
+/// extraction) or audio file (via transcription). Extraction failures are
+/// logged rather than returned, since the file itself is already uploaded.
+async fn generate_sidecar(
+    sdk_config: &aws_config::SdkConfig,
+    store: &dyn ObjectStore,
+    bucket: &str,
+    id: uuid::Uuid,
+    key: &str,
+    path: &std::path::Path,
+) -> Result<(), String> {
+    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or(key);
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    if let Some(format) = claria_bedrock::extract::document_format_for_extension(&extension) {
+        let sidecar_key = format!("{key}.text");
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {e}"))?;
+        let extraction_prompt = load_prompt(store, bucket, "pdf-extraction").await?;
         match claria_bedrock::extract::extract_document_text(
-            &sdk_config,
+            sdk_config,
             EXTRACTION_MODEL_ID,
             &bytes,
             filename,
@@ -1071,8 +1853,8 @@ pub async fn upload_record_file(
         {
             Ok(text) => {
                 claria_storage::objects::put_object(
-                    &s3,
-                    &bucket,
+                    store,
+                    bucket,
                     &sidecar_key,
                     text.into_bytes(),
                     Some("text/plain"),
@@ -1096,12 +1878,11 @@ pub async fn upload_record_file(
         claria_transcribe::media_format_for_extension(&extension)
     {
         let sidecar_key = format!("{key}.text");
-        match claria_transcribe::transcribe_audio(&sdk_config, &bucket, &key, media_format).await
-        {
+        match transcribe_record_audio(sdk_config, store, bucket, key, media_format).await {
             Ok(text) => {
                 claria_storage::objects::put_object(
-                    &s3,
-                    &bucket,
+                    store,
+                    bucket,
                     &sidecar_key,
                     text.into_bytes(),
                     Some("text/plain"),
@@ -1123,11 +1904,7 @@ pub async fn upload_record_file(
         }
     }
 
-    Ok(RecordFile {
-        filename: filename.to_string(),
-        size: file_size,
-        uploaded_at: Some(jiff::Timestamp::now().to_string()),
-    })
+    Ok(())
 }
 
 /// Delete a file from a client's record, including its sidecar if present.
@@ -1139,7 +1916,7 @@ pub async fn delete_record_file(
     filename: String,
 ) -> Result<(), String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
@@ -1147,7 +1924,7 @@ pub async fn delete_record_file(
     let key = claria_core::s3_keys::client_record_file(id, &filename);
 
     // Delete the original file.
-    claria_storage::objects::delete_object(&s3, &bucket, &key)
+    claria_storage::objects::delete_object(&*store, &bucket, &key)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -1157,9 +1934,10 @@ pub async fn delete_record_file(
     // delete marker.
     if !filename.ends_with(".txt") {
         let sidecar_key = format!("{key}.text");
-        let _ = claria_storage::objects::delete_object(&s3, &bucket, &sidecar_key).await;
+        let _ = claria_storage::objects::delete_object(&*store, &bucket, &sidecar_key).await;
     }
 
+    state.index.enqueue(store, &bucket, [key]);
     tracing::info!(client_id = %id, filename, "record file deleted");
 
     Ok(())
@@ -1177,16 +1955,26 @@ pub async fn get_record_file_text(
     filename: String,
 ) -> Result<String, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
 
     let key = claria_core::s3_keys::client_record_file(id, &filename);
 
-    // Plain text files: return the file content directly.
+    // Plain text files: return the file content directly, including edits
+    // still waiting in the outbox.
     if filename.ends_with(".txt") {
-        return match claria_storage::objects::get_object(&s3, &bucket, &key).await {
+        let queued = {
+            let _guard = state.outbox.lock().await;
+            claria_desktop::outbox::open()
+                .and_then(|outbox| Ok(outbox.queued_body(&bucket, &key)?))
+                .map_err(|e| e.to_string())?
+        };
+        if let Some(body) = queued {
+            return String::from_utf8(body).map_err(|e| e.to_string());
+        }
+        return match claria_storage::objects::get_object(&*store, &bucket, &key).await {
             Ok(output) => String::from_utf8(output.body).map_err(|e| e.to_string()),
             Err(e) => Err(e.to_string()),
         };
@@ -1195,7 +1983,7 @@ pub async fn get_record_file_text(
     // Other files: look for the `.text` sidecar.
     let sidecar_key = format!("{key}.text");
 
-    match claria_storage::objects::get_object(&s3, &bucket, &sidecar_key).await {
+    match claria_storage::objects::get_object(&*store, &bucket, &sidecar_key).await {
         Ok(output) => String::from_utf8(output.body).map_err(|e| e.to_string()),
         Err(claria_storage::error::StorageError::NotFound { .. }) => {
             Ok("No text extraction available for this file.".to_string())
@@ -1217,7 +2005,7 @@ pub async fn create_text_record_file(
     content: String,
 ) -> Result<RecordFile, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
@@ -1233,16 +2021,18 @@ pub async fn create_text_record_file(
     let file_size = bytes.len() as i32;
 
     let key = claria_core::s3_keys::client_record_file(id, &filename);
-    claria_storage::objects::put_object(&s3, &bucket, &key, bytes, Some("text/plain"))
-        .await
-        .map_err(|e| e.to_string())?;
+    let queued = put_or_queue(&state, &*store, &bucket, &key, bytes, Precondition::Absent).await?;
 
-    tracing::info!(client_id = %id, filename, "text record file created");
+    // Queued writes are indexed when the outbox syncs.
+    if !queued {
+        state.index.enqueue(store, &bucket, [key]);
+    }
+    tracing::info!(client_id = %id, filename, queued, "text record file created");
 
     Ok(RecordFile {
         filename,
         size: file_size,
-        uploaded_at: Some(jiff::Timestamp::now().to_string()),
+        uploaded_at: (!queued).then(|| jiff::Timestamp::now().to_string()),
     })
 }
 
@@ -1256,18 +2046,257 @@ pub async fn update_text_record_file(
     content: String,
 ) -> Result<(), String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
 
     let key = claria_core::s3_keys::client_record_file(id, &filename);
-    claria_storage::objects::put_object(&s3, &bucket, &key, content.into_bytes(), Some("text/plain"))
-        .await
+    // Replay must not overwrite an edit made elsewhere since this machine
+    // last read the file.
+    let precondition = match claria_desktop::aws::cached_etag(&bucket, &key) {
+        Some(etag) => Precondition::IfMatch(etag),
+        None => Precondition::None,
+    };
+    let queued =
+        put_or_queue(&state, &*store, &bucket, &key, content.into_bytes(), precondition).await?;
+
+    // Queued writes are indexed when the outbox syncs.
+    if !queued {
+        state.index.enqueue(store, &bucket, [key]);
+    }
+    tracing::info!(client_id = %id, filename, queued, "text record file updated");
+
+    Ok(())
+}
+
+/// The precondition an upload to `key` is made under: the version of the
+/// object there now, or that it must still be absent. When S3 cannot be
+/// reached, the version this machine last read, if any.
+async fn upload_precondition(store: &dyn ObjectStore, bucket: &str, key: &str) -> Precondition {
+    match claria_storage::objects::head_object(store, bucket, key).await {
+        Ok(head) => head.etag.map_or(Precondition::None, Precondition::IfMatch),
+        Err(claria_storage::error::StorageError::NotFound { .. }) => Precondition::Absent,
+        Err(e) => {
+            tracing::debug!(key, error = %e, "cannot read current version for upload");
+            claria_desktop::aws::cached_etag(bucket, key)
+                .map_or(Precondition::None, Precondition::IfMatch)
+        }
+    }
+}
+
+/// Write a plain text record file, or queue it in the offline outbox if S3
+/// cannot be reached. A file that already has queued changes is queued
+/// behind them, so edits replay in the order they were made. Returns whether
+/// the write was queued.
+async fn put_or_queue(
+    state: &DesktopState,
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    body: Vec<u8>,
+    precondition: Precondition,
+) -> Result<bool, String> {
+    let _guard = state.outbox.lock().await;
+    let outbox = claria_desktop::outbox::open().map_err(|e| e.to_string())?;
+
+    if !outbox.has_entries_for(bucket, key).map_err(|e| e.to_string())? {
+        let put = claria_storage::objects::put_object(
+            store,
+            bucket,
+            key,
+            body.clone(),
+            Some("text/plain"),
+        );
+        match put.await {
+            Ok(_) => return Ok(false),
+            Err(e) if e.is_retryable() => {
+                tracing::warn!(key, error = %e, "S3 unreachable, queuing write");
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    outbox
+        .enqueue(bucket, key, body, Some("text/plain"), precondition)
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// ---------------------------------------------------------------------------
+// Offline outbox — record writes queued while S3 is unreachable
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxItemStatus {
+    /// Waiting to be synced.
+    Pending,
+    /// The file was changed elsewhere; needs resolving.
+    Conflict,
+    /// S3 rejected the write; see `error`.
+    Failed,
+}
+
+/// A record write waiting in the offline outbox.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct OutboxItem {
+    pub id: String,
+    pub client_id: String,
+    pub filename: String,
+    pub queued_at: String,
+    pub status: OutboxItemStatus,
+    pub error: Option<String>,
+}
+
+/// How a `sync_outbox` pass went.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct OutboxSyncResult {
+    pub synced: i32,
+    pub conflicts: i32,
+    pub failed: i32,
+    /// Entries still queued, including conflicts and failures.
+    pub remaining: i32,
+    /// S3 was still unreachable; the pass stopped early.
+    pub offline: bool,
+}
+
+/// How to resolve a conflicted outbox item.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxResolution {
+    /// Replace the other version with this machine's.
+    Overwrite,
+    /// Drop this machine's version.
+    Discard,
+    /// Save this machine's version as a conflicted copy next to the other.
+    KeepBoth,
+}
+
+/// List record writes waiting in the offline outbox, oldest first.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_outbox(state: State<'_, DesktopState>) -> Result<Vec<OutboxItem>, String> {
+    let _guard = state.outbox.lock().await;
+    let entries = claria_desktop::outbox::open()
+        .and_then(|outbox| Ok(outbox.entries()?))
         .map_err(|e| e.to_string())?;
 
-    tracing::info!(client_id = %id, filename, "text record file updated");
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (client_id, filename) = claria_desktop::outbox::record_file_parts(&entry.key)?;
+            let (status, error) = match entry.status {
+                EntryStatus::Pending => (OutboxItemStatus::Pending, None),
+                EntryStatus::Conflict => (OutboxItemStatus::Conflict, None),
+                EntryStatus::Failed { message } => (OutboxItemStatus::Failed, Some(message)),
+            };
+            Some(OutboxItem {
+                id: entry.id,
+                client_id: client_id.to_string(),
+                filename: filename.to_string(),
+                queued_at: entry.queued_at.to_string(),
+                status,
+                error,
+            })
+        })
+        .collect())
+}
+
+/// Replay queued record writes against S3.
+///
+/// Uploaded files that sync get their text sidecar generated, as they would
+/// have if the upload had gone through at the time.
+#[tauri::command]
+#[specta::specta]
+pub async fn sync_outbox(state: State<'_, DesktopState>) -> Result<OutboxSyncResult, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let summary = {
+        let _guard = state.outbox.lock().await;
+        let outbox = claria_desktop::outbox::open().map_err(|e| e.to_string())?;
+        outbox.replay(&*store).await.map_err(|e| e.to_string())?
+    };
+
+    for entry in &summary.synced {
+        let (Some(path), Some((client_id, _))) = (
+            &entry.source_path,
+            claria_desktop::outbox::record_file_parts(&entry.key),
+        ) else {
+            continue;
+        };
+        if entry.bucket != bucket {
+            continue;
+        }
+        if let Err(e) =
+            generate_sidecar(&sdk_config, &*store, &bucket, client_id, &entry.key, path).await
+        {
+            tracing::warn!(key = entry.key, error = %e, "sidecar generation after sync failed");
+        }
+    }
+    state.index.enqueue(
+        store,
+        &bucket,
+        summary
+            .synced
+            .iter()
+            .filter(|entry| entry.bucket == bucket)
+            .map(|entry| entry.key.clone()),
+    );
+
+    tracing::info!(
+        synced = summary.synced.len(),
+        conflicts = summary.conflicts,
+        failed = summary.failed,
+        remaining = summary.remaining,
+        offline = summary.offline,
+        "outbox synced"
+    );
+
+    Ok(OutboxSyncResult {
+        synced: summary.synced.len() as i32,
+        conflicts: summary.conflicts as i32,
+        failed: summary.failed as i32,
+        remaining: summary.remaining as i32,
+        offline: summary.offline,
+    })
+}
+
+/// Resolve a conflicted or failed outbox item. The resolved write is
+/// replayed on the next `sync_outbox`.
+#[tauri::command]
+#[specta::specta]
+pub async fn resolve_outbox_item(
+    state: State<'_, DesktopState>,
+    id: String,
+    resolution: OutboxResolution,
+) -> Result<(), String> {
+    let _guard = state.outbox.lock().await;
+    let outbox = claria_desktop::outbox::open().map_err(|e| e.to_string())?;
+
+    let resolution = match resolution {
+        OutboxResolution::Overwrite => Resolution::Overwrite,
+        OutboxResolution::Discard => Resolution::Discard,
+        OutboxResolution::KeepBoth => {
+            let entries = outbox.entries().map_err(|e| e.to_string())?;
+            let entry = entries
+                .iter()
+                .find(|entry| entry.id == id)
+                .ok_or_else(|| format!("No queued change with ID {id}"))?;
+            let (client_id, filename) = claria_desktop::outbox::record_file_parts(&entry.key)
+                .ok_or_else(|| format!("Not a record file: {}", entry.key))?;
+            let today = jiff::Zoned::now().date();
+            let copy = claria_desktop::outbox::conflicted_copy_name(filename, today);
+            Resolution::SaveAs {
+                key: claria_core::s3_keys::client_record_file(client_id, &copy),
+            }
+        }
+    };
 
+    outbox.resolve(&id, resolution).map_err(|e| e.to_string())?;
+    tracing::info!(id, "outbox item resolved");
     Ok(())
 }
 
@@ -1294,13 +2323,13 @@ pub async fn list_record_context(
     client_id: String,
 ) -> Result<Vec<RecordContext>, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let prefix = claria_core::s3_keys::client_records_prefix(id);
 
-    let keys = claria_storage::objects::list_objects(&s3, &bucket, &prefix)
+    let keys = claria_storage::objects::list_objects(&*store, &bucket, &prefix)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -1329,14 +2358,14 @@ pub async fn list_record_context(
 
         let text = if filename.ends_with(".txt") {
             // Plain text: read directly.
-            match claria_storage::objects::get_object(&s3, &bucket, key).await {
+            match claria_storage::objects::get_object(&*store, &bucket, key).await {
                 Ok(output) => String::from_utf8(output.body).ok(),
                 Err(_) => None,
             }
         } else {
             // Other files: read the `.text` sidecar.
             let sidecar_key = format!("{key}.text");
-            match claria_storage::objects::get_object(&s3, &bucket, &sidecar_key).await {
+            match claria_storage::objects::get_object(&*store, &bucket, &sidecar_key).await {
                 Ok(output) => String::from_utf8(output.body).ok(),
                 Err(_) => None,
             }
@@ -1366,13 +2395,34 @@ pub async fn extract_record_file(
     filename: String,
 ) -> Result<RecordContext, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let key = claria_core::s3_keys::client_record_file(id, &filename);
 
-    let extension = std::path::Path::new(&filename)
+    let text = extract_sidecar(&sdk_config, &*store, &bucket, &key, &filename).await?;
+    state.index.enqueue(store, &bucket, [key]);
+
+    tracing::info!(client_id = %id, filename, "re-extracted text for record file");
+
+    Ok(RecordContext {
+        filename,
+        text,
+    })
+}
+
+/// Extract the text of a record file already in S3 (Bedrock document
+/// extraction, or transcription for audio) and upload it as the file's
+/// `.text` sidecar.
+async fn extract_sidecar(
+    sdk_config: &aws_config::SdkConfig,
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    filename: &str,
+) -> Result<String, String> {
+    let extension = std::path::Path::new(filename)
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("")
@@ -1384,15 +2434,15 @@ pub async fn extract_record_file(
         claria_bedrock::extract::document_format_for_extension(&extension)
     {
         // Document extraction (PDF, DOCX).
-        let output = claria_storage::objects::get_object(&s3, &bucket, &key)
+        let output = claria_storage::objects::get_object(store, bucket, key)
             .await
             .map_err(|e| e.to_string())?;
-        let extraction_prompt = load_prompt(&s3, &bucket, "pdf-extraction").await?;
+        let extraction_prompt = load_prompt(store, bucket, "pdf-extraction").await?;
         let text = claria_bedrock::extract::extract_document_text(
-            &sdk_config,
+            sdk_config,
             EXTRACTION_MODEL_ID,
             &output.body,
-            &filename,
+            filename,
             format,
             &extraction_prompt,
         )
@@ -1400,8 +2450,8 @@ pub async fn extract_record_file(
         .map_err(|e| e.to_string())?;
 
         claria_storage::objects::put_object(
-            &s3,
-            &bucket,
+            store,
+            bucket,
             &sidecar_key,
             text.clone().into_bytes(),
             Some("text/plain"),
@@ -1414,14 +2464,11 @@ pub async fn extract_record_file(
         claria_transcribe::media_format_for_extension(&extension)
     {
         // Audio transcription.
-        let text =
-            claria_transcribe::transcribe_audio(&sdk_config, &bucket, &key, media_format)
-                .await
-                .map_err(|e| e.to_string())?;
+        let text = transcribe_record_audio(sdk_config, store, bucket, key, media_format).await?;
 
         claria_storage::objects::put_object(
-            &s3,
-            &bucket,
+            store,
+            bucket,
             &sidecar_key,
             text.clone().into_bytes(),
             Some("text/plain"),
@@ -1436,24 +2483,101 @@ pub async fn extract_record_file(
         ));
     };
 
-    tracing::info!(client_id = %id, filename, "re-extracted text for record file");
+    Ok(text)
+}
 
-    Ok(RecordContext {
-        filename,
-        text,
-    })
+/// Helper: transcribe a record audio file with Amazon Transcribe.
+///
+/// Transcribe reads the audio straight from S3, so when client-side
+/// encryption is enabled the decrypted audio is staged under `_transcribe/`
+/// for the duration of the job, and every version of the staged copy is
+/// permanently deleted afterwards. The audio is streamed through a local
+/// temporary file rather than held in memory.
+async fn transcribe_record_audio(
+    sdk_config: &aws_config::SdkConfig,
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    media_format: claria_transcribe::MediaFormat,
+) -> Result<String, String> {
+    if !claria_desktop::config::has_master_key() {
+        return claria_transcribe::transcribe_audio(sdk_config, bucket, key, media_format)
+            .await
+            .map_err(|e| e.to_string());
+    }
+
+    // Decrypt to a local temporary file, then stream that to the staging key.
+    let staging_name = format!("{}.{}", uuid::Uuid::new_v4(), media_format.as_str());
+    let local_copy = std::env::temp_dir().join(format!("claria-{staging_name}"));
+    let s3 = aws_sdk_s3::Client::new(sdk_config);
+    let staging_key = format!("_transcribe/staging/{staging_name}");
+
+    let mut staging_upload_id: Option<String> = None;
+    let staged = async {
+        claria_storage::objects::download_to_file(store, bucket, key, None, &local_copy, &mut |_| {})
+            .await?;
+        claria_storage::objects::upload_file(
+            &s3,
+            bucket,
+            &staging_key,
+            &local_copy,
+            &claria_storage::objects::UploadOptions::default(),
+            &mut |p| staging_upload_id = p.upload_id.clone(),
+        )
+        .await
+    }
+    .await;
+    // Never leave plaintext parts of a failed staging upload behind.
+    if staged.is_err()
+        && let Some(upload_id) = &staging_upload_id
+        && let Err(e) =
+            claria_storage::objects::abort_upload(&s3, bucket, &staging_key, upload_id).await
+    {
+        tracing::warn!(key = %staging_key, error = %e, "failed to abort staged audio upload");
+    }
+    if let Err(e) = std::fs::remove_file(&local_copy)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!(path = %local_copy.display(), error = %e, "failed to remove decrypted audio");
+    }
+    staged.map_err(|e| e.to_string())?;
+
+    let result =
+        claria_transcribe::transcribe_audio(sdk_config, bucket, &staging_key, media_format).await;
+
+    match claria_storage::objects::list_object_versions(&s3, bucket, &staging_key).await {
+        Ok(versions) => {
+            for v in versions {
+                if let Err(e) = claria_storage::objects::delete_object_version(
+                    &s3,
+                    bucket,
+                    &staging_key,
+                    &v.version_id,
+                )
+                .await
+                {
+                    tracing::warn!(key = %staging_key, error = %e, "failed to remove staged audio");
+                }
+            }
+        }
+        Err(e) => {
+            tracing::warn!(key = %staging_key, error = %e, "failed to list staged audio versions");
+        }
+    }
+
+    result.map_err(|e| e.to_string())
 }
 
 /// Helper: load all record context for a client, converting to bedrock types.
 async fn load_record_context(
-    s3: &aws_sdk_s3::Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     client_id: &str,
 ) -> Result<Vec<claria_bedrock::context::ContextFile>, String> {
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let prefix = claria_core::s3_keys::client_records_prefix(id);
 
-    let keys = claria_storage::objects::list_objects(s3, bucket, &prefix)
+    let keys = claria_storage::objects::list_objects(store, bucket, &prefix)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -1473,13 +2597,13 @@ async fn load_record_context(
         };
 
         let text = if filename.ends_with(".txt") {
-            match claria_storage::objects::get_object(s3, bucket, key).await {
+            match claria_storage::objects::get_object(store, bucket, key).await {
                 Ok(output) => String::from_utf8(output.body).ok(),
                 Err(_) => None,
             }
         } else {
             let sidecar_key = format!("{key}.text");
-            match claria_storage::objects::get_object(s3, bucket, &sidecar_key).await {
+            match claria_storage::objects::get_object(store, bucket, &sidecar_key).await {
                 Ok(output) => String::from_utf8(output.body).ok(),
                 Err(_) => None,
             }
@@ -1539,14 +2663,14 @@ fn resolve_prompt(name: &str) -> Result<(&'static str, Option<&'static str>, &'s
 /// Load a prompt from S3 by name, falling back to the legacy path and then the
 /// hardcoded default.
 async fn load_prompt(
-    s3: &aws_sdk_s3::Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     prompt_name: &str,
 ) -> Result<String, String> {
     let (key, legacy_key, default_text) = resolve_prompt(prompt_name)?;
 
     // Try the canonical claria-prompts/ key first.
-    match claria_storage::objects::get_object(s3, bucket, key).await {
+    match claria_storage::objects::get_object(store, bucket, key).await {
         Ok(output) => return String::from_utf8(output.body).map_err(|e| e.to_string()),
         Err(claria_storage::error::StorageError::NotFound { .. }) => {}
         Err(e) => return Err(e.to_string()),
@@ -1555,13 +2679,13 @@ async fn load_prompt(
     // Fall back to the legacy key if one exists (system-prompt.md at bucket root).
     // When found, migrate it to the new path and delete the legacy key.
     if let Some(legacy) = legacy_key {
-        match claria_storage::objects::get_object(s3, bucket, legacy).await {
+        match claria_storage::objects::get_object(store, bucket, legacy).await {
             Ok(output) => {
                 let text = String::from_utf8(output.body).map_err(|e| e.to_string())?;
 
                 // Copy to the new claria-prompts/ path.
                 if let Err(e) = claria_storage::objects::put_object(
-                    s3,
+                    store,
                     bucket,
                     key,
                     text.as_bytes().to_vec(),
@@ -1575,7 +2699,7 @@ async fn load_prompt(
 
                 // Remove the legacy key.
                 if let Err(e) =
-                    claria_storage::objects::delete_object(s3, bucket, legacy).await
+                    claria_storage::objects::delete_object(store, bucket, legacy).await
                 {
                     tracing::warn!(legacy, error = %e, "failed to delete legacy prompt after migration");
                 }
@@ -1637,13 +2761,13 @@ pub async fn chat_message(
     context_filenames: Vec<String>,
 ) -> Result<ChatResponse, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    let system_prompt = load_prompt(&s3, &bucket, "system-prompt").await?;
+    let system_prompt = load_prompt(&*store, &bucket, "system-prompt").await?;
 
     // Load record context and filter to the frontend's active set.
-    let all_files = load_record_context(&s3, &bucket, &client_id).await?;
+    let all_files = load_record_context(&*store, &bucket, &client_id).await?;
     let context_files: Vec<_> = if context_filenames.is_empty() {
         all_files
     } else {
@@ -1719,7 +2843,7 @@ pub async fn chat_message(
     match serde_json::to_vec_pretty(&history) {
         Ok(body) => {
             if let Err(e) =
-                claria_storage::objects::put_object(&s3, &bucket, &key, body, Some("application/json"))
+                claria_storage::objects::put_object(&*store, &bucket, &key, body, Some("application/json"))
                     .await
             {
                 tracing::warn!(
@@ -1729,6 +2853,7 @@ pub async fn chat_message(
                     "failed to persist chat history"
                 );
             } else {
+                state.index.enqueue(store.clone(), &bucket, [key.clone()]);
                 tracing::info!(
                     chat_id = %chat_uuid,
                     client_id = %client_uuid,
@@ -1874,14 +2999,14 @@ pub async fn load_chat_history(
     chat_id: String,
 ) -> Result<ChatHistoryDetail, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let client_uuid: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let chat_uuid: uuid::Uuid = chat_id.parse().map_err(|e: uuid::Error| e.to_string())?;
 
     let key = claria_core::s3_keys::chat_history(client_uuid, chat_uuid);
-    let output = claria_storage::objects::get_object(&s3, &bucket, &key)
+    let output = claria_storage::objects::get_object(&*store, &bucket, &key)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -1941,10 +3066,10 @@ pub async fn get_prompt(
     prompt_name: String,
 ) -> Result<String, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    load_prompt(&s3, &bucket, &prompt_name).await
+    load_prompt(&*store, &bucket, &prompt_name).await
 }
 
 /// Save a named prompt to S3.
@@ -1961,11 +3086,11 @@ pub async fn save_prompt(
     let (key, _, _) = resolve_prompt(&prompt_name)?;
 
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     claria_storage::objects::put_object(
-        &s3,
+        &*store,
         &bucket,
         key,
         content.into_bytes(),
@@ -1987,10 +3112,10 @@ pub async fn delete_prompt(
     let (key, _, _) = resolve_prompt(&prompt_name)?;
 
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    claria_storage::objects::delete_object(&s3, &bucket, key)
+    claria_storage::objects::delete_object(&*store, &bucket, key)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -2011,10 +3136,10 @@ pub async fn list_prompt_versions(
     let (key, _, _) = resolve_prompt(&prompt_name)?;
 
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    let versions = claria_storage::objects::list_object_versions(&s3, &bucket, key)
+    let versions = claria_storage::objects::list_object_versions(&*store, &bucket, key)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -2041,10 +3166,10 @@ pub async fn get_prompt_version(
     let (key, _, _) = resolve_prompt(&prompt_name)?;
 
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    let output = claria_storage::objects::get_object_version(&s3, &bucket, key, &version_id)
+    let output = claria_storage::objects::get_object_version(&*store, &bucket, key, &version_id)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -2062,14 +3187,14 @@ pub async fn restore_prompt_version(
     let (key, _, _) = resolve_prompt(&prompt_name)?;
 
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    let output = claria_storage::objects::get_object_version(&s3, &bucket, key, &version_id)
+    let output = claria_storage::objects::get_object_version(&*store, &bucket, key, &version_id)
         .await
         .map_err(|e| e.to_string())?;
 
-    claria_storage::objects::put_object(&s3, &bucket, key, output.body, Some("text/markdown"))
+    claria_storage::objects::put_object(&*store, &bucket, key, output.body, Some("text/markdown"))
         .await
         .map_err(|e| e.to_string())?;
 
@@ -2079,83 +3204,245 @@ pub async fn restore_prompt_version(
 }
 
 // ---------------------------------------------------------------------------
-// Version history commands — S3 versioning surface
+// Text snippet commands — typed records through claria_storage::repository
 // ---------------------------------------------------------------------------
 
-/// A single version of a file in a client's record.
+/// A text snippet: an example of phrasing to avoid in reports. `etag` is
+/// the version it was read at, passed back to [`update_snippet`].
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
-pub struct FileVersion {
-    pub version_id: String,
-    pub size: i32,
-    pub last_modified: Option<String>,
-    pub is_latest: bool,
+pub struct Snippet {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub etag: String,
 }
 
-/// A file that has been deleted (has a delete marker as the latest version).
-#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
-pub struct DeletedFile {
-    pub filename: String,
-    pub deleted_at: Option<String>,
-    pub version_id: String,
+impl From<claria_storage::repository::Stored<claria_core::models::snippet::TextSnippet>>
+    for Snippet
+{
+    fn from(
+        stored: claria_storage::repository::Stored<claria_core::models::snippet::TextSnippet>,
+    ) -> Self {
+        let snippet = stored.record;
+        Self {
+            id: snippet.id.to_string(),
+            title: snippet.title,
+            body: snippet.body,
+            created_at: snippet.created_at.to_string(),
+            updated_at: snippet.updated_at.to_string(),
+            etag: stored.etag,
+        }
+    }
 }
 
-/// A client that has been deleted (has a delete marker on the client JSON).
-#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
-pub struct DeletedClient {
-    pub id: String,
-    pub name: String,
-    pub deleted_at: Option<String>,
-    pub version_id: String,
+/// List the text snippets, sorted by title.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_snippets(state: State<'_, DesktopState>) -> Result<Vec<Snippet>, String> {
+    use claria_core::models::snippet::TextSnippet;
+    use claria_storage::repository::Repository;
+
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let mut snippets: Vec<Snippet> = Repository::<TextSnippet>::new(&*store, &bucket)
+        .list()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(Snippet::from)
+        .collect();
+    snippets.sort_by_cached_key(|s| s.title.to_lowercase());
+    Ok(snippets)
 }
 
-/// List all versions of a specific file in a client's record.
+/// Save a new text snippet.
 #[tauri::command]
 #[specta::specta]
-pub async fn list_file_versions(
+pub async fn create_snippet(
     state: State<'_, DesktopState>,
-    client_id: String,
-    filename: String,
-) -> Result<Vec<FileVersion>, String> {
+    title: String,
+    body: String,
+) -> Result<Snippet, String> {
+    use claria_core::models::snippet::TextSnippet;
+    use claria_storage::repository::Repository;
+
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
-    let key = claria_core::s3_keys::client_record_file(id, &filename);
-
-    let versions = claria_storage::objects::list_object_versions(&s3, &bucket, &key)
+    let id = uuid::Uuid::new_v4();
+    let now = jiff::Timestamp::now();
+    let snippet = TextSnippet {
+        id,
+        title,
+        body,
+        s3_key: claria_core::s3_keys::snippet(id),
+        created_at: now,
+        updated_at: now,
+    };
+    let stored = Repository::new(&*store, &bucket)
+        .create(snippet)
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(versions
-        .into_iter()
-        .filter(|v| !v.is_delete_marker)
-        .map(|v| FileVersion {
-            version_id: v.version_id,
-            size: v.size as i32,
-            last_modified: v.last_modified,
-            is_latest: v.is_latest,
-        })
-        .collect())
+    tracing::info!(snippet_id = %id, "snippet created");
+    state
+        .index
+        .enqueue(store, &bucket, [stored.record.s3_key.clone()]);
+    Ok(stored.into())
 }
 
-/// Get the text content of a specific version of a file.
+/// Change a snippet's title and body, provided nobody has changed it since
+/// it was read at `etag`.
 #[tauri::command]
 #[specta::specta]
-pub async fn get_file_version_text(
+pub async fn update_snippet(
     state: State<'_, DesktopState>,
-    client_id: String,
-    filename: String,
-    version_id: String,
+    id: String,
+    title: String,
+    body: String,
+    etag: String,
+) -> Result<Snippet, String> {
+    use claria_core::models::snippet::TextSnippet;
+    use claria_storage::error::StorageError;
+    use claria_storage::repository::Repository;
+
+    let id: uuid::Uuid = id.parse().map_err(|e| format!("invalid snippet ID: {e}"))?;
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let repository = Repository::<TextSnippet>::new(&*store, &bucket);
+    let mut snippet = repository
+        .get(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .record;
+    snippet.title = title;
+    snippet.body = body;
+    let stored = match repository.update(snippet, &etag).await {
+        Ok(stored) => stored,
+        Err(StorageError::PreconditionFailed { .. }) => {
+            return Err(
+                "This snippet was changed on another device. Reload it and try again.".into(),
+            );
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    tracing::info!(snippet_id = %id, "snippet updated");
+    state
+        .index
+        .enqueue(store, &bucket, [stored.record.s3_key.clone()]);
+    Ok(stored.into())
+}
+
+/// Delete a snippet. It stays in the bucket's version history.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_snippet(state: State<'_, DesktopState>, id: String) -> Result<(), String> {
+    use claria_core::models::snippet::TextSnippet;
+    use claria_storage::repository::Repository;
+
+    let id: uuid::Uuid = id.parse().map_err(|e| format!("invalid snippet ID: {e}"))?;
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    Repository::<TextSnippet>::new(&*store, &bucket)
+        .delete(id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(snippet_id = %id, "snippet deleted");
+    state
+        .index
+        .enqueue(store, &bucket, [claria_core::s3_keys::snippet(id)]);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Version history commands — S3 versioning surface
+// ---------------------------------------------------------------------------
+
+/// A single version of a file in a client's record.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct FileVersion {
+    pub version_id: String,
+    pub size: i32,
+    pub last_modified: Option<String>,
+    pub is_latest: bool,
+}
+
+/// A file that has been deleted (has a delete marker as the latest version).
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct DeletedFile {
+    pub filename: String,
+    pub deleted_at: Option<String>,
+    pub version_id: String,
+}
+
+/// A client that has been deleted (has a delete marker on the client JSON).
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct DeletedClient {
+    pub id: String,
+    pub name: String,
+    pub deleted_at: Option<String>,
+    pub version_id: String,
+}
+
+/// List all versions of a specific file in a client's record.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_file_versions(
+    state: State<'_, DesktopState>,
+    client_id: String,
+    filename: String,
+) -> Result<Vec<FileVersion>, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+    let key = claria_core::s3_keys::client_record_file(id, &filename);
+
+    let versions = claria_storage::objects::list_object_versions(&*store, &bucket, &key)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(versions
+        .into_iter()
+        .filter(|v| !v.is_delete_marker)
+        .map(|v| FileVersion {
+            version_id: v.version_id,
+            size: v.size as i32,
+            last_modified: v.last_modified,
+            is_latest: v.is_latest,
+        })
+        .collect())
+}
+
+/// Get the text content of a specific version of a file.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_file_version_text(
+    state: State<'_, DesktopState>,
+    client_id: String,
+    filename: String,
+    version_id: String,
 ) -> Result<String, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let key = claria_core::s3_keys::client_record_file(id, &filename);
 
-    let output = claria_storage::objects::get_object_version(&s3, &bucket, &key, &version_id)
+    let output = claria_storage::objects::get_object_version(&*store, &bucket, &key, &version_id)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -2172,20 +3459,20 @@ pub async fn restore_file_version(
     version_id: String,
 ) -> Result<(), String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let key = claria_core::s3_keys::client_record_file(id, &filename);
 
     // Fetch the old version's content.
-    let output = claria_storage::objects::get_object_version(&s3, &bucket, &key, &version_id)
+    let output = claria_storage::objects::get_object_version(&*store, &bucket, &key, &version_id)
         .await
         .map_err(|e| e.to_string())?;
 
     // Write it back as the current version.
     claria_storage::objects::put_object(
-        &s3,
+        &*store,
         &bucket,
         &key,
         output.body,
@@ -2194,6 +3481,7 @@ pub async fn restore_file_version(
     .await
     .map_err(|e| e.to_string())?;
 
+    state.index.enqueue(store, &bucket, [key]);
     tracing::info!(client_id = %id, filename, version_id, "file version restored");
 
     Ok(())
@@ -2207,13 +3495,13 @@ pub async fn list_deleted_files(
     client_id: String,
 ) -> Result<Vec<DeletedFile>, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let prefix = claria_core::s3_keys::client_records_prefix(id);
 
-    let deleted = claria_storage::objects::list_deleted_objects(&s3, &bucket, &prefix)
+    let deleted = claria_storage::objects::list_deleted_objects(&*store, &bucket, &prefix)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -2268,14 +3556,14 @@ pub async fn restore_deleted_file(
 ) -> Result<(), String> {
     let _ = version_id; // kept for API compatibility; we find the latest real version ourselves
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let key = claria_core::s3_keys::client_record_file(id, &filename);
 
     // Find the most recent non-delete-marker version.
-    let versions = claria_storage::objects::list_object_versions(&s3, &bucket, &key)
+    let versions = claria_storage::objects::list_object_versions(&*store, &bucket, &key)
         .await
         .map_err(|e| e.to_string())?;
     let real = versions
@@ -2285,12 +3573,12 @@ pub async fn restore_deleted_file(
 
     // Fetch that version's content and write it back as a new current version.
     let output =
-        claria_storage::objects::get_object_version(&s3, &bucket, &key, &real.version_id)
+        claria_storage::objects::get_object_version(&*store, &bucket, &key, &real.version_id)
             .await
             .map_err(|e| e.to_string())?;
 
     claria_storage::objects::put_object(
-        &s3,
+        &*store,
         &bucket,
         &key,
         output.body,
@@ -2299,6 +3587,7 @@ pub async fn restore_deleted_file(
     .await
     .map_err(|e| e.to_string())?;
 
+    state.index.enqueue(store, &bucket, [key]);
     tracing::info!(client_id = %id, filename, "deleted file restored");
 
     Ok(())
@@ -2311,11 +3600,11 @@ pub async fn list_deleted_clients(
     state: State<'_, DesktopState>,
 ) -> Result<Vec<DeletedClient>, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let deleted = claria_storage::objects::list_deleted_objects(
-        &s3,
+        &*store,
         &bucket,
         claria_core::s3_keys::CLIENTS_PREFIX,
     )
@@ -2325,7 +3614,7 @@ pub async fn list_deleted_clients(
     let mut clients = Vec::new();
     for d in &deleted {
         // Fetch the most recent real version to get the client name.
-        let versions = claria_storage::objects::list_object_versions(&s3, &bucket, &d.key)
+        let versions = claria_storage::objects::list_object_versions(&*store, &bucket, &d.key)
             .await
             .map_err(|e| e.to_string())?;
 
@@ -2337,7 +3626,7 @@ pub async fn list_deleted_clients(
                 "Unknown".to_string()
             } else {
                 match claria_storage::objects::get_object_version(
-                    &s3,
+                    &*store,
                     &bucket,
                     &d.key,
                     &v.version_id,
@@ -2398,14 +3687,14 @@ pub async fn restore_client(
 ) -> Result<(), String> {
     let _ = version_id; // kept for API compatibility; we find the latest real version ourselves
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
     let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
     let key = claria_core::s3_keys::client(id);
 
     // Find the most recent non-delete-marker version.
-    let versions = claria_storage::objects::list_object_versions(&s3, &bucket, &key)
+    let versions = claria_storage::objects::list_object_versions(&*store, &bucket, &key)
         .await
         .map_err(|e| e.to_string())?;
     let real = versions
@@ -2415,12 +3704,12 @@ pub async fn restore_client(
 
     // Fetch that version's content and write it back as a new current version.
     let output =
-        claria_storage::objects::get_object_version(&s3, &bucket, &key, &real.version_id)
+        claria_storage::objects::get_object_version(&*store, &bucket, &key, &real.version_id)
             .await
             .map_err(|e| e.to_string())?;
 
     claria_storage::objects::put_object(
-        &s3,
+        &*store,
         &bucket,
         &key,
         output.body,
@@ -2429,11 +3718,632 @@ pub async fn restore_client(
     .await
     .map_err(|e| e.to_string())?;
 
+    state.index.enqueue(store, &bucket, [key]);
     tracing::info!(client_id = %id, "deleted client restored");
 
     Ok(())
 }
 
+/// What a point-in-time restore will do to one file.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreFileAction {
+    /// Put the version current at the restore point back.
+    Restore,
+    /// The file did not exist at the restore point and will be deleted.
+    Delete,
+    /// The file is already as it was at the restore point.
+    Unchanged,
+}
+
+/// One entry in the dry-run listing of a point-in-time client restore.
+///
+/// `filename` is relative to the client's record, or `client.json` for the
+/// client record itself.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ClientRestoreEntry {
+    pub filename: String,
+    pub action: RestoreFileAction,
+    pub version_id: Option<String>,
+}
+
+/// Counts of what a point-in-time client restore wrote.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ClientRestoreSummary {
+    pub restored: i32,
+    pub deleted: i32,
+    pub unchanged: i32,
+}
+
+/// Parse the client ID and RFC 3339 restore point shared by the restore commands.
+fn parse_restore_args(
+    client_id: &str,
+    as_of: &str,
+) -> Result<(uuid::Uuid, jiff::Timestamp), String> {
+    let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+    let as_of: jiff::Timestamp = as_of
+        .parse()
+        .map_err(|e| format!("Invalid restore time {as_of:?}: {e}"))?;
+    if as_of > jiff::Timestamp::now() {
+        return Err("Restore time is in the future".to_string());
+    }
+    Ok((id, as_of))
+}
+
+/// Dry run of [`restore_client_as_of`]: list which files would be restored,
+/// re-deleted or left alone, without writing anything.
+#[tauri::command]
+#[specta::specta]
+pub async fn preview_client_restore(
+    state: State<'_, DesktopState>,
+    client_id: String,
+    as_of: String,
+) -> Result<Vec<ClientRestoreEntry>, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let (id, as_of) = parse_restore_args(&client_id, &as_of)?;
+    let plan = claria_storage::restore::plan_client_restore(&*store, &bucket, id, as_of)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let prefix = claria_core::s3_keys::client_records_prefix(id);
+    Ok(plan
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let filename = entry
+                .key
+                .strip_prefix(&prefix)
+                .unwrap_or("client.json")
+                .to_string();
+            let (action, version_id) = match entry.action {
+                claria_storage::restore::RestoreAction::Restore { version_id } => {
+                    (RestoreFileAction::Restore, Some(version_id))
+                }
+                claria_storage::restore::RestoreAction::Delete => (RestoreFileAction::Delete, None),
+                claria_storage::restore::RestoreAction::Unchanged => {
+                    (RestoreFileAction::Unchanged, entry.current_version_id)
+                }
+            };
+            ClientRestoreEntry {
+                filename,
+                action,
+                version_id,
+            }
+        })
+        .collect())
+}
+
+/// Restore a client's entire record — files, sidecars, chat histories and
+/// the client JSON — to its state at `as_of` (RFC 3339).
+///
+/// Like [`restore_file_version`], old content is written back as new
+/// versions and files created since are deleted with delete markers, so the
+/// full version history is preserved.
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_client_as_of(
+    state: State<'_, DesktopState>,
+    client_id: String,
+    as_of: String,
+) -> Result<ClientRestoreSummary, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let (id, as_of) = parse_restore_args(&client_id, &as_of)?;
+    let plan = claria_storage::restore::plan_client_restore(&*store, &bucket, id, as_of)
+        .await
+        .map_err(|e| e.to_string())?;
+    let applied = claria_storage::restore::apply_plan(&*store, &bucket, &plan).await;
+    // Index whatever was written, even if the restore stopped part way.
+    state.index.enqueue(
+        store,
+        &bucket,
+        plan.entries
+            .into_iter()
+            .filter(|entry| {
+                !matches!(entry.action, claria_storage::restore::RestoreAction::Unchanged)
+            })
+            .map(|entry| entry.key),
+    );
+    let summary = applied.map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        client_id = %id,
+        %as_of,
+        restored = summary.restored,
+        deleted = summary.deleted,
+        "client restored to point in time"
+    );
+
+    Ok(ClientRestoreSummary {
+        restored: summary.restored as i32,
+        deleted: summary.deleted as i32,
+        unchanged: summary.unchanged as i32,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Backups — the whole bucket as a local encrypted archive
+// ---------------------------------------------------------------------------
+
+/// Backup progress, streamed to the frontend via Channel<T>.
+#[derive(Clone, Serialize, Deserialize, specta::Type)]
+pub struct BackupProgress {
+    pub entries_done: i32,
+    pub entries_total: i32,
+    pub bytes_done: f64,
+}
+
+/// What a backup archive holds.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct BackupSummary {
+    /// The bucket the backup was taken from.
+    pub bucket: String,
+    pub created_at: String,
+    pub objects: i32,
+    pub delete_markers: i32,
+    pub bytes: f64,
+}
+
+impl From<&claria_storage::backup::Manifest> for BackupSummary {
+    fn from(manifest: &claria_storage::backup::Manifest) -> Self {
+        Self {
+            bucket: manifest.bucket.clone(),
+            created_at: manifest.created_at.to_string(),
+            objects: manifest.objects() as i32,
+            delete_markers: manifest.delete_markers() as i32,
+            bytes: manifest.bytes() as f64,
+        }
+    }
+}
+
+/// Backups are sealed with the practice master key, so one is required even
+/// if the bucket itself was written unencrypted.
+fn backup_key() -> Result<claria_storage::crypto::MasterKey, String> {
+    config::load_master_key()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Enable encryption before taking or restoring a backup".to_string())
+}
+
+fn send_backup_progress(
+    on_progress: &tauri::ipc::Channel<BackupProgress>,
+    p: &claria_storage::backup::BackupProgress,
+) {
+    let _ = on_progress.send(BackupProgress {
+        entries_done: p.entries_done as i32,
+        entries_total: p.entries_total as i32,
+        bytes_done: p.bytes_done as f64,
+    });
+}
+
+/// Write a backup of the whole bucket to a local `.tar.zst` archive at
+/// `path`, encrypted with the practice master key.
+///
+/// With `include_versions`, every version and delete marker is archived so
+/// the file history survives a restore; otherwise only current objects are.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_backup(
+    state: State<'_, DesktopState>,
+    path: String,
+    include_versions: bool,
+    on_progress: tauri::ipc::Channel<BackupProgress>,
+) -> Result<BackupSummary, String> {
+    let key = backup_key()?;
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let options = claria_storage::backup::ExportOptions { include_versions };
+    let manifest = claria_storage::backup::export(
+        &*store,
+        &bucket,
+        std::path::Path::new(&path),
+        &key,
+        &options,
+        &mut |p| send_backup_progress(&on_progress, p),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(BackupSummary::from(&manifest))
+}
+
+/// Restore a backup archive into the configured bucket, for example after
+/// moving to a new AWS account or region.
+///
+/// The bucket must be empty, and the archive is checked in full before
+/// anything is written. Restored versions get new version IDs and
+/// timestamps.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_backup(
+    state: State<'_, DesktopState>,
+    path: String,
+    on_progress: tauri::ipc::Channel<BackupProgress>,
+) -> Result<BackupSummary, String> {
+    let key = backup_key()?;
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let manifest = claria_storage::backup::import(
+        &*store,
+        &bucket,
+        std::path::Path::new(&path),
+        &key,
+        &mut |p| send_backup_progress(&on_progress, p),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(BackupSummary::from(&manifest))
+}
+
+// ---------------------------------------------------------------------------
+// Integrity scrub — orphaned sidecars, missing extractions, broken JSON
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrubIssueKind {
+    OrphanedSidecar,
+    MissingSidecar,
+    OrphanedRecord,
+    UnparseableJson,
+    BrokenReference,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrubRepair {
+    Reextract,
+    Delete,
+    Quarantine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ScrubIssue {
+    pub key: String,
+    pub kind: ScrubIssueKind,
+    /// `None` when the issue needs a person to look at it.
+    pub repair: Option<ScrubRepair>,
+    pub detail: String,
+    pub repaired: bool,
+    /// Why the repair failed, if it was attempted.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ScrubResult {
+    pub dry_run: bool,
+    pub scanned: i32,
+    pub issues: Vec<ScrubIssue>,
+}
+
+/// Whether a record file should have a `.text` sidecar: documents Bedrock
+/// can extract and audio Transcribe can transcribe.
+fn needs_sidecar(filename: &str) -> bool {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    claria_bedrock::extract::document_format_for_extension(&extension).is_some()
+        || claria_transcribe::media_format_for_extension(&extension).is_some()
+}
+
+/// Check `records/`, `clients/` and `reports/` for inconsistencies between
+/// record files, sidecars and JSON.
+///
+/// With `dry_run`, issues are only reported. Otherwise each one with a
+/// repair is fixed: missing sidecars are re-extracted, orphans deleted
+/// (restorable from version history) and unparseable JSON moved to
+/// `_quarantine/`. A failed repair is reported on its issue and does not
+/// stop the others.
+#[tauri::command]
+#[specta::specta]
+pub async fn scrub_bucket(
+    state: State<'_, DesktopState>,
+    dry_run: bool,
+) -> Result<ScrubResult, String> {
+    use claria_storage::scrub::{IssueKind, Repair};
+
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let report = claria_storage::scrub::scan(&*store, &bucket, &needs_sidecar)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut issues = Vec::with_capacity(report.issues.len());
+    let mut repaired_keys = Vec::new();
+    for issue in &report.issues {
+        let mut repaired = false;
+        let mut error = None;
+        if !dry_run {
+            let result = match issue.repair {
+                Some(Repair::Reextract) => {
+                    let filename = issue.key.rsplit_once('/').map_or(&*issue.key, |(_, f)| f);
+                    extract_sidecar(&sdk_config, &*store, &bucket, &issue.key, filename)
+                        .await
+                        .map(|_| true)
+                }
+                _ => claria_storage::scrub::repair(&*store, &bucket, issue)
+                    .await
+                    .map_err(|e| e.to_string()),
+            };
+            match result {
+                Ok(done) => {
+                    repaired = done;
+                    if done {
+                        repaired_keys.push(issue.key.clone());
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(key = issue.key, error = %e, "scrub repair failed");
+                    error = Some(e);
+                }
+            }
+        }
+
+        issues.push(ScrubIssue {
+            key: issue.key.clone(),
+            kind: match issue.kind {
+                IssueKind::OrphanedSidecar => ScrubIssueKind::OrphanedSidecar,
+                IssueKind::MissingSidecar => ScrubIssueKind::MissingSidecar,
+                IssueKind::OrphanedRecord => ScrubIssueKind::OrphanedRecord,
+                IssueKind::UnparseableJson => ScrubIssueKind::UnparseableJson,
+                IssueKind::BrokenReference => ScrubIssueKind::BrokenReference,
+            },
+            repair: issue.repair.map(|r| match r {
+                Repair::Reextract => ScrubRepair::Reextract,
+                Repair::Delete => ScrubRepair::Delete,
+                Repair::Quarantine => ScrubRepair::Quarantine,
+            }),
+            detail: issue.detail.clone(),
+            repaired,
+            error,
+        });
+    }
+
+    state.index.enqueue(store, &bucket, repaired_keys);
+
+    Ok(ScrubResult {
+        dry_run,
+        scanned: report.scanned as i32,
+        issues,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Share links — time-limited presigned URLs with an audit trail
+// ---------------------------------------------------------------------------
+
+/// One issued share link, as recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ShareLink {
+    pub id: String,
+    pub filename: String,
+    pub client_id: Option<String>,
+    pub recipient: String,
+    /// The IAM identity that issued the link.
+    pub created_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+    /// How many links may be issued, each counted as one download.
+    pub max_downloads: Option<i32>,
+    /// How many links have been issued so far.
+    pub links_issued: i32,
+    pub revoked_at: Option<String>,
+    pub revoked_by: Option<String>,
+    /// Not revoked, not expired, and a link to it may still work.
+    pub outstanding: bool,
+}
+
+impl From<&claria_storage::share::Share> for ShareLink {
+    fn from(share: &claria_storage::share::Share) -> Self {
+        Self {
+            id: share.id.to_string(),
+            filename: share.filename.clone(),
+            client_id: share.client_id.map(|id| id.to_string()),
+            recipient: share.recipient.clone(),
+            created_by: share.created_by.clone(),
+            created_at: share.created_at.to_string(),
+            expires_at: share.expires_at.to_string(),
+            max_downloads: share.max_downloads.map(|n| n as i32),
+            links_issued: share.links.len() as i32,
+            revoked_at: share.revoked_at.map(|t| t.to_string()),
+            revoked_by: share.revoked_by.clone(),
+            outstanding: share.is_outstanding(jiff::Timestamp::now()),
+        }
+    }
+}
+
+/// A newly issued share link and its URL. The URL is only available here;
+/// it is not stored, but its issue is.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct IssuedShareLink {
+    pub share: ShareLink,
+    pub url: String,
+}
+
+/// The ARN of the IAM identity the app is signed in as, for the audit log.
+async fn caller_arn(sdk_config: &aws_config::SdkConfig) -> String {
+    let sts = aws_sdk_sts::Client::new(sdk_config);
+    match sts.get_caller_identity().send().await {
+        Ok(identity) => identity.arn().unwrap_or("unknown").to_string(),
+        Err(e) => {
+            tracing::warn!(error = %e, "could not look up caller identity");
+            "unknown".to_string()
+        }
+    }
+}
+
+/// Share a record file through a presigned URL that expires after
+/// `expires_in_hours` (at most 7 days).
+///
+/// The recipient gets an unencrypted copy of the file, which is deleted
+/// when the link is revoked or cleaned up after it expires. The share is
+/// recorded under `_audit/shares/` with who issued it, to whom and when.
+///
+/// With `max_downloads`, each link counts as one download and lasts at
+/// most an hour; further links come from [`issue_share_link`] until the
+/// limit is used up.
+#[tauri::command]
+#[specta::specta]
+pub async fn create_share_link(
+    state: State<'_, DesktopState>,
+    client_id: String,
+    filename: String,
+    recipient: String,
+    expires_in_hours: i32,
+    max_downloads: Option<i32>,
+) -> Result<IssuedShareLink, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let public = claria_desktop::aws::build_share_store(&sdk_config);
+    let bucket = bucket_name(&cfg);
+
+    let id: uuid::Uuid = client_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+    let recipient = recipient.trim();
+    if recipient.is_empty() {
+        return Err("Enter who the link is for".to_string());
+    }
+    let hours = u64::try_from(expires_in_hours)
+        .map_err(|_| "Expiry must be a positive number of hours".to_string())?;
+    let max_downloads = match max_downloads {
+        Some(n) => Some(u32::try_from(n).map_err(|_| "Download limit cannot be negative")?),
+        None => None,
+    };
+    let created_by = caller_arn(&sdk_config).await;
+
+    let key = claria_core::s3_keys::client_record_file(id, &filename);
+    let request = claria_storage::share::ShareRequest {
+        source_key: &key,
+        filename: &filename,
+        client_id: Some(id),
+        recipient,
+        created_by: &created_by,
+        expires_in: std::time::Duration::from_secs(hours * 3600),
+        max_downloads,
+    };
+    let share = claria_storage::share::create_share(&*store, &*public, &bucket, &request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let issued = claria_storage::share::issue_link(&*store, &*public, &bucket, share.id, &created_by)
+        .await;
+    let (share, url) = match issued {
+        Ok(issued) => issued,
+        Err(e) => {
+            // Don't leave an unencrypted copy behind a link nobody has.
+            let _ = claria_storage::share::revoke_share(
+                &*store,
+                &*public,
+                &bucket,
+                share.id,
+                &created_by,
+            )
+            .await;
+            return Err(e.to_string());
+        }
+    };
+
+    Ok(IssuedShareLink {
+        share: ShareLink::from(&share),
+        url,
+    })
+}
+
+/// Issue another URL for an outstanding share, for when the recipient
+/// lost theirs or a limited link expired before it was used. The URL is
+/// recorded on the share and counts against its download limit.
+#[tauri::command]
+#[specta::specta]
+pub async fn issue_share_link(
+    state: State<'_, DesktopState>,
+    share_id: String,
+) -> Result<IssuedShareLink, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let public = claria_desktop::aws::build_share_store(&sdk_config);
+    let bucket = bucket_name(&cfg);
+
+    let id: uuid::Uuid = share_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+    let issued_by = caller_arn(&sdk_config).await;
+    let (share, url) =
+        match claria_storage::share::issue_link(&*store, &*public, &bucket, id, &issued_by).await {
+            Ok(issued) => issued,
+            Err(claria_storage::error::StorageError::PreconditionFailed { .. }) => {
+                return Err(
+                    "This share was changed on another device. Reload and try again.".to_string(),
+                );
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+    Ok(IssuedShareLink {
+        share: ShareLink::from(&share),
+        url,
+    })
+}
+
+/// List issued share links, newest first. Unless `include_inactive` is set,
+/// only links that are neither revoked nor expired are returned.
+///
+/// Copies behind expired links are deleted along the way.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_share_links(
+    state: State<'_, DesktopState>,
+    include_inactive: bool,
+) -> Result<Vec<ShareLink>, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let public = claria_desktop::aws::build_share_store(&sdk_config);
+    let bucket = bucket_name(&cfg);
+
+    if let Err(e) = claria_storage::share::remove_expired(&*store, &*public, &bucket).await {
+        tracing::warn!(error = %e, "failed to remove expired shares");
+    }
+
+    let now = jiff::Timestamp::now();
+    let shares = claria_storage::share::list_shares(&*store, &bucket)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(shares
+        .iter()
+        .filter(|s| include_inactive || s.is_outstanding(now))
+        .map(ShareLink::from)
+        .collect())
+}
+
+/// Revoke a share link: the shared copy is deleted, so the URL stops
+/// working immediately, and the revocation is added to the audit record.
+#[tauri::command]
+#[specta::specta]
+pub async fn revoke_share_link(
+    state: State<'_, DesktopState>,
+    share_id: String,
+) -> Result<ShareLink, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let public = claria_desktop::aws::build_share_store(&sdk_config);
+    let bucket = bucket_name(&cfg);
+
+    let id: uuid::Uuid = share_id.parse().map_err(|e: uuid::Error| e.to_string())?;
+    let revoked_by = caller_arn(&sdk_config).await;
+    let share = claria_storage::share::revoke_share(&*store, &*public, &bucket, id, &revoked_by)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(ShareLink::from(&share))
+}
+
 // ---------------------------------------------------------------------------
 // Whisper model management + local transcription
 // ---------------------------------------------------------------------------
@@ -2697,21 +4607,36 @@ pub async fn download_whisper_model(
     tier: WhisperModelTier,
 ) -> Result<Vec<WhisperModelInfo>, String> {
     let dir = whisper_model_dir(&tier)?;
-    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create models dir: {e}"))?;
-
-    let hf_base = format!(
-        "https://huggingface.co/{}/resolve/main",
-        tier.hf_repo()
-    );
 
     tracing::info!(tier = tier.tag(), "downloading whisper model files");
+    download_hf_files(tier.hf_repo(), WHISPER_FILES, dir).await?;
+    tracing::info!(tier = tier.tag(), "whisper model download complete");
+
+    // Auto-activate if no model is currently active.
+    if effective_active_tier().is_none() {
+        write_active_tier(&tier)?;
+    }
+
+    build_whisper_models_list()
+}
+
+/// Download `files` from the main branch of a Hugging Face repository into
+/// `dir`. Each file is written to a temporary name and renamed once
+/// complete, so an interrupted download never looks finished.
+async fn download_hf_files(
+    hf_repo: &str,
+    files: &'static [&'static str],
+    dir: std::path::PathBuf,
+) -> Result<(), String> {
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create models dir: {e}"))?;
+
+    let hf_base = format!("https://huggingface.co/{hf_repo}/resolve/main");
 
-    let dir_clone = dir.clone();
     tokio::task::spawn_blocking(move || -> Result<(), String> {
-        for filename in WHISPER_FILES {
+        for filename in files {
             let url = format!("{hf_base}/{filename}");
-            let dest = dir_clone.join(filename);
-            let tmp = dir_clone.join(format!("{filename}.tmp"));
+            let dest = dir.join(filename);
+            let tmp = dir.join(format!("{filename}.tmp"));
 
             tracing::info!(url = %url, file = %filename, "downloading");
 
@@ -2731,16 +4656,7 @@ pub async fn download_whisper_model(
         Ok(())
     })
     .await
-    .map_err(|e| format!("download task failed: {e}"))??;
-
-    tracing::info!(tier = tier.tag(), "whisper model download complete");
-
-    // Auto-activate if no model is currently active.
-    if effective_active_tier().is_none() {
-        write_active_tier(&tier)?;
-    }
-
-    build_whisper_models_list()
+    .map_err(|e| format!("download task failed: {e}"))?
 }
 
 /// Delete a specific Whisper model tier and clear the in-memory cache if needed.
@@ -2918,6 +4834,234 @@ pub async fn transcribe_memo(
     .map_err(|e| format!("transcription task failed: {e}"))?
 }
 
+// ---------------------------------------------------------------------------
+// Embedding model management + semantic search
+// ---------------------------------------------------------------------------
+
+/// Status of the local sentence-embedding model used by [`semantic_search`].
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct EmbeddingModelInfo {
+    /// The Hugging Face repository the model comes from.
+    pub model_id: String,
+    pub download_size: String,
+    pub downloaded: bool,
+    pub model_size_bytes: Option<i32>,
+}
+
+/// One passage found by [`semantic_search`].
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct SemanticHit {
+    pub client_id: Option<String>,
+    /// The record file's name and key.
+    pub title: String,
+    pub s3_key: String,
+    /// Character offsets of the passage in the file's text.
+    pub start: i32,
+    pub end: i32,
+    pub text: String,
+    /// Combined word and meaning score, from 0 to 1.
+    pub score: f64,
+}
+
+/// Kept apart from the Whisper models directory, whose unknown
+/// subdirectories are listed as removable orphans.
+fn embedding_model_dir() -> Result<std::path::PathBuf, String> {
+    let base = dirs::data_dir().ok_or_else(|| "no data directory found".to_string())?;
+    Ok(base
+        .join("com.claria.desktop")
+        .join("embedding-models")
+        .join("all-minilm-l6-v2"))
+}
+
+fn embedding_model_info() -> Result<EmbeddingModelInfo, String> {
+    let dir = embedding_model_dir()?;
+    let downloaded = claria_search::embed::MODEL_FILES
+        .iter()
+        .all(|f| dir.join(f).exists());
+    Ok(EmbeddingModelInfo {
+        model_id: claria_search::embed::DEFAULT_MODEL.to_string(),
+        download_size: "~91 MB".to_string(),
+        downloaded,
+        model_size_bytes: if downloaded { dir_size_bytes(&dir) } else { None },
+    })
+}
+
+/// Report whether the embedding model is downloaded.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_embedding_model() -> Result<EmbeddingModelInfo, String> {
+    embedding_model_info()
+}
+
+/// Download the embedding model from Hugging Face.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_embedding_model() -> Result<EmbeddingModelInfo, String> {
+    tracing::info!("downloading embedding model files");
+    download_hf_files(
+        claria_search::embed::DEFAULT_MODEL,
+        &claria_search::embed::MODEL_FILES,
+        embedding_model_dir()?,
+    )
+    .await?;
+    tracing::info!("embedding model download complete");
+    embedding_model_info()
+}
+
+/// Delete the embedding model and unload it. Vectors already stored with
+/// the search index are kept for when it is downloaded again.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_embedding_model(
+    state: State<'_, DesktopState>,
+) -> Result<EmbeddingModelInfo, String> {
+    if let Ok(mut guard) = state.embedder.lock() {
+        *guard = None;
+    }
+    let dir = embedding_model_dir()?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| format!("failed to delete model: {e}"))?;
+        tracing::info!("embedding model deleted");
+    }
+    embedding_model_info()
+}
+
+/// Find record passages by meaning as well as by words, using the local
+/// embedding model. No text is sent off the machine to be embedded.
+///
+/// Only the index shard of `client_id`, or of every client, is searched.
+/// Passages indexed since the last semantic search are embedded first and
+/// their vectors flushed with their shard. If another device flushed the
+/// shard in the meantime the vectors are only used for this search; the
+/// next search embeds them again.
+#[tauri::command]
+#[specta::specta]
+pub async fn semantic_search(
+    state: State<'_, DesktopState>,
+    query: String,
+    client_id: Option<String>,
+    limit: i32,
+) -> Result<Vec<SemanticHit>, String> {
+    use claria_search::error::SearchError;
+    use claria_search::vector::{VectorIndex, refresh_vectors};
+
+    let model_dir = embedding_model_dir()?;
+    if !embedding_model_info()?.downloaded {
+        return Err("The search model is not downloaded. Download it from Preferences.".into());
+    }
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    // Passages live in the client shards. The global index holds none, but
+    // downloading it first rebuilds an index from an older schema.
+    let client = client_id
+        .as_deref()
+        .map(str::parse::<uuid::Uuid>)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let shards = claria_search::shard::shards_for_search(&*store, &bucket, client)
+        .await
+        .map_err(|e| e.to_string())?;
+    claria_desktop::aws::require_search_key().map_err(|e| e.to_string())?;
+    let mut shard_cache = state.search_shards.lock().await;
+    let shard_cache = search_shard_cache(&mut shard_cache)?;
+    let loaded = shard_cache
+        .download_shards(&*store, &bucket, &shards)
+        .await
+        .map_err(|e| e.to_string())?;
+    if loaded.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let embedder = state.embedder.clone();
+    let limit = limit.max(1) as usize;
+    let (loaded, hits) = tokio::task::spawn_blocking(move || {
+        let mut guard = match embedder.lock() {
+            Ok(g) => g,
+            Err(poisoned) => {
+                tracing::warn!("embedder lock was poisoned, recovering");
+                let mut g = poisoned.into_inner();
+                *g = None;
+                g
+            }
+        };
+        if guard.is_none() {
+            tracing::info!("loading embedding model into memory");
+            let model = claria_search::embed::SentenceEmbedder::load(
+                &model_dir,
+                claria_search::embed::DEFAULT_MODEL,
+            )
+            .map_err(|e| e.to_string())?;
+            *guard = Some(model);
+        }
+        let embedder = guard.as_ref().expect("model just loaded");
+
+        let mut refreshed = Vec::with_capacity(loaded.len());
+        let mut vectors = Vec::with_capacity(loaded.len());
+        for (shard, loaded) in loaded {
+            let refresh = refresh_vectors(&loaded.index_dir, &loaded.index, embedder)
+                .map_err(|e| e.to_string())?;
+            vectors.push(VectorIndex::open(&loaded.index_dir).map_err(|e| e.to_string())?);
+            refreshed.push((shard, loaded, refresh));
+        }
+        let shards: Vec<_> = refreshed
+            .iter()
+            .zip(&vectors)
+            .map(|((_, loaded, _), vectors)| (&loaded.index, vectors))
+            .collect();
+        let hits = claria_search::hybrid::hybrid_search(
+            &shards,
+            embedder,
+            &query,
+            client_id.as_deref(),
+            limit,
+        )
+        .map_err(|e| e.to_string())?;
+        Ok::<_, String>((refreshed, hits))
+    })
+    .await
+    .map_err(|e| format!("search task failed: {e}"))??;
+
+    for (shard, loaded, refresh) in loaded {
+        if !refresh.changed() {
+            continue;
+        }
+        let flushed = claria_search::flush::flush_shard(
+            &*store,
+            &bucket,
+            shard,
+            &loaded.index_dir,
+            &loaded.etag,
+        )
+        .await;
+        match flushed {
+            Ok(etag) => shard_cache.flushed(&bucket, shard, etag),
+            Err(SearchError::ETagMismatch) => {
+                tracing::info!(%shard, "index changed during semantic search, vectors not saved");
+            }
+            Err(e) => tracing::warn!(%shard, error = %e, "failed to save passage vectors"),
+        }
+    }
+
+    Ok(hits
+        .into_iter()
+        .map(|h| SemanticHit {
+            client_id: h.client_id,
+            title: h.title,
+            s3_key: h.s3_key,
+            start: h.start as i32,
+            end: h.end as i32,
+            text: h.text,
+            score: h.score as f64,
+        })
+        .collect())
+}
+
 // ---------------------------------------------------------------------------
 // Update check
 // ---------------------------------------------------------------------------
@@ -3061,6 +5205,147 @@ pub async fn set_hourly_cost_data(
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Storage usage — which clients and kinds of files drive the S3 bill
+// ---------------------------------------------------------------------------
+
+/// Stored bytes for a client, a prefix or the whole bucket.
+///
+/// `sidecar_bytes` and `chat_history_bytes` are the parts of
+/// `current_bytes` held by `.text` sidecars and chat histories.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct StorageUsage {
+    pub objects: i32,
+    pub current_bytes: f64,
+    pub sidecar_bytes: f64,
+    pub chat_history_bytes: f64,
+    pub noncurrent_versions: i32,
+    pub noncurrent_bytes: f64,
+    pub delete_markers: i32,
+    /// This share of the S3 cost, by stored bytes. Only set when a cost
+    /// result was passed to `get_storage_usage`.
+    pub estimated_cost: Option<f64>,
+}
+
+impl From<&claria_storage::usage::StorageUsage> for StorageUsage {
+    fn from(usage: &claria_storage::usage::StorageUsage) -> Self {
+        Self {
+            objects: usage.objects as i32,
+            current_bytes: usage.current_bytes as f64,
+            sidecar_bytes: usage.sidecar_bytes as f64,
+            chat_history_bytes: usage.chat_history_bytes as f64,
+            noncurrent_versions: usage.noncurrent_versions as i32,
+            noncurrent_bytes: usage.noncurrent_bytes as f64,
+            delete_markers: usage.delete_markers as i32,
+            estimated_cost: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ClientStorageUsage {
+    pub client_id: String,
+    /// `None` for a client that has been deleted.
+    pub name: Option<String>,
+    pub usage: StorageUsage,
+}
+
+/// Usage of objects outside client records, by top-level prefix.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct PrefixStorageUsage {
+    pub prefix: String,
+    pub usage: StorageUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct StorageUsageReport {
+    /// Largest first.
+    pub clients: Vec<ClientStorageUsage>,
+    pub other: Vec<PrefixStorageUsage>,
+    pub total: StorageUsage,
+}
+
+/// Report stored bytes per client and per kind of file, counting every
+/// version S3 bills for.
+///
+/// When `cost` holds a Cost Explorer result grouped by service, the S3
+/// total in it is split between clients and prefixes by stored bytes, so no
+/// extra Cost Explorer request is made.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_storage_usage(
+    state: State<'_, DesktopState>,
+    cost: Option<claria_billing::CostAndUsageResult>,
+) -> Result<StorageUsageReport, String> {
+    let (cfg, sdk_config) = load_sdk_config(&state).await?;
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
+    let bucket = bucket_name(&cfg);
+
+    let report = claria_storage::usage::bucket_usage(&*store, &bucket)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut clients = Vec::with_capacity(report.clients.len());
+    for (id, usage) in &report.clients {
+        let key = claria_core::s3_keys::client(*id);
+        let name = match claria_storage::objects::get_object(&*store, &bucket, &key).await {
+            Ok(output) => serde_json::from_slice::<claria_core::models::client::Client>(&output.body)
+                .map(|c| c.name)
+                .ok(),
+            Err(claria_storage::error::StorageError::NotFound { .. }) => None,
+            Err(e) => return Err(e.to_string()),
+        };
+        clients.push(ClientStorageUsage {
+            client_id: id.to_string(),
+            name,
+            usage: StorageUsage::from(usage),
+        });
+    }
+    let mut other: Vec<PrefixStorageUsage> = report
+        .other
+        .iter()
+        .map(|(prefix, usage)| PrefixStorageUsage {
+            prefix: prefix.clone(),
+            usage: StorageUsage::from(usage),
+        })
+        .collect();
+
+    let s3_cost = cost
+        .as_ref()
+        .map(|c| claria_billing::service_total(c, claria_billing::S3_SERVICE));
+    if let Some(s3_cost) = s3_cost {
+        let bytes: Vec<i64> = report
+            .clients
+            .values()
+            .chain(report.other.values())
+            .map(|u| u.total_bytes())
+            .collect();
+        let shares = claria_billing::apportion(s3_cost, &bytes);
+        for (usage, share) in clients
+            .iter_mut()
+            .map(|c| &mut c.usage)
+            .chain(other.iter_mut().map(|o| &mut o.usage))
+            .zip(shares)
+        {
+            usage.estimated_cost = Some(share);
+        }
+    }
+
+    clients.sort_by(|a, b| {
+        let size = |c: &ClientStorageUsage| c.usage.current_bytes + c.usage.noncurrent_bytes;
+        size(b).total_cmp(&size(a))
+    });
+
+    let mut total = StorageUsage::from(&report.total);
+    total.estimated_cost = s3_cost;
+
+    Ok(StorageUsageReport {
+        clients,
+        other,
+        total,
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Shell / URL helpers
 // ---------------------------------------------------------------------------
@@ -3112,11 +5397,11 @@ pub async fn count_client_context_tokens(
     context_filenames: Vec<String>,
 ) -> Result<u32, String> {
     let (cfg, sdk_config) = load_sdk_config(&state).await?;
-    let s3 = aws_sdk_s3::Client::new(&sdk_config);
+    let store = claria_desktop::aws::build_object_store(&sdk_config).map_err(|e| e.to_string())?;
     let bucket = bucket_name(&cfg);
 
-    let system_prompt = load_prompt(&s3, &bucket, "system-prompt").await?;
-    let all_files = load_record_context(&s3, &bucket, &client_id).await?;
+    let system_prompt = load_prompt(&*store, &bucket, "system-prompt").await?;
+    let all_files = load_record_context(&*store, &bucket, &client_id).await?;
     let files: Vec<_> = if context_filenames.is_empty() {
         all_files
     } else {
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use claria_storage::crypto::MasterKey;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
@@ -61,7 +62,7 @@ pub struct ConfigInfo {
     pub hourly_cost_data: bool,
 }
 
-fn config_dir() -> eyre::Result<PathBuf> {
+pub(crate) fn config_dir() -> eyre::Result<PathBuf> {
     let base = dirs::config_dir().ok_or_else(|| eyre::eyre!("no config directory found"))?;
     Ok(base.join("com.claria.desktop"))
 }
@@ -206,6 +207,158 @@ pub fn delete_config() -> eyre::Result<()> {
     Ok(())
 }
 
+fn master_key_path() -> eyre::Result<PathBuf> {
+    Ok(config_dir()?.join("master.key"))
+}
+
+/// Whether a practice master key is installed, i.e. whether client-side
+/// encryption is enabled.
+pub fn has_master_key() -> bool {
+    master_key_path().map(|p| p.exists()).unwrap_or(false)
+}
+
+/// Load the practice master key used for client-side encryption, if one has
+/// been created or imported on this machine.
+///
+/// The key is kept apart from `config.json` so that deleting the config to
+/// reconnect an account never destroys the only copy of the key.
+pub fn load_master_key() -> eyre::Result<Option<MasterKey>> {
+    let path = master_key_path()?;
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(eyre::eyre!(
+                "failed to read master key at {}: {e}",
+                path.display()
+            ));
+        }
+    };
+    Ok(Some(MasterKey::from_bytes(&bytes)?))
+}
+
+/// Persist the practice master key with owner-only permissions.
+pub fn save_master_key(key: &MasterKey) -> eyre::Result<()> {
+    let dir = config_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let path = master_key_path()?;
+    let tmp_path = dir.join("master.key.tmp");
+    std::fs::write(&tmp_path, key.as_bytes())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    std::fs::rename(&tmp_path, &path)?;
+
+    tracing::info!(path = %path.display(), key_id = %key.key_id_hex(), "master key saved");
+    Ok(())
+}
+
+fn local_key_path() -> eyre::Result<PathBuf> {
+    Ok(config_dir()?.join("local.key"))
+}
+
+/// Directory of the encrypted object cache.
+pub fn object_cache_dir() -> eyre::Result<PathBuf> {
+    let base = dirs::cache_dir().ok_or_else(|| eyre::eyre!("no cache directory found"))?;
+    Ok(base.join("com.claria.desktop").join("objects"))
+}
+
+/// Directory of the offline outbox.
+pub fn outbox_dir() -> eyre::Result<PathBuf> {
+    let base =
+        dirs::data_local_dir().ok_or_else(|| eyre::eyre!("no local data directory found"))?;
+    Ok(base.join("com.claria.desktop").join("outbox"))
+}
+
+/// File of record keys waiting to be added to the search index, kept next
+/// to the outbox so they survive a quit or crash.
+pub fn index_backlog_path() -> eyre::Result<PathBuf> {
+    let base =
+        dirs::data_local_dir().ok_or_else(|| eyre::eyre!("no local data directory found"))?;
+    Ok(base.join("com.claria.desktop").join("index-backlog.sealed"))
+}
+
+/// Directory for Claria's temporary files, extracted search indexes among
+/// them.
+pub fn private_temp_dir() -> eyre::Result<PathBuf> {
+    let base =
+        dirs::data_local_dir().ok_or_else(|| eyre::eyre!("no local data directory found"))?;
+    Ok(base.join("com.claria.desktop").join("tmp"))
+}
+
+/// Empty the private temp directory, creating it readable by the owner only.
+///
+/// Temporary files hold decrypted record text and search indexes, so they
+/// are kept here rather than in the shared system temp directory, and
+/// whatever an earlier run left behind after a crash is removed.
+pub fn reset_private_temp_dir() -> eyre::Result<PathBuf> {
+    let dir = private_temp_dir()?;
+    wipe_private_temp_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+
+    Ok(dir)
+}
+
+/// Delete the private temp directory and everything in it.
+pub fn wipe_private_temp_dir() -> eyre::Result<()> {
+    let dir = private_temp_dir()?;
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => {
+            tracing::info!(path = %dir.display(), "private temp directory wiped");
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Load the key that seals data kept only on this machine (the object cache,
+/// the offline outbox and the search index backlog), creating it on first use.
+///
+/// Unlike the master key this key is never exported: losing it loses the
+/// cache, which is refilled from S3, and any writes still queued offline.
+pub fn load_or_create_local_key() -> eyre::Result<MasterKey> {
+    let path = local_key_path()?;
+    match std::fs::read(&path) {
+        Ok(bytes) => return Ok(MasterKey::from_bytes(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(eyre::eyre!(
+                "failed to read local key at {}: {e}",
+                path.display()
+            ));
+        }
+    }
+
+    let dir = config_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    let key = MasterKey::generate();
+    let tmp_path = dir.join("local.key.tmp");
+    std::fs::write(&tmp_path, key.as_bytes())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    std::fs::rename(&tmp_path, &path)?;
+    tracing::info!(path = %path.display(), "local key created");
+    Ok(key)
+}
+
 pub fn config_info(config: &ClariaConfig) -> ConfigInfo {
     let (credential_type, profile_name, access_key_hint) = match &config.credentials {
         CredentialSource::Inline {
@@ -0,0 +1,300 @@
+//! Background search indexing.
+//!
+//! Commands that write, restore or delete objects report the keys they
+//! touched with [`IndexQueue::enqueue`] and return without waiting. A
+//! background task collects keys until writes have been quiet for
+//! [`BATCH_WINDOW`], or for at most [`MAX_BATCH_DELAY`] while they keep
+//! coming, then brings the index up to date with one
+//! `claria_search::sync::sync_keys` call per bucket, which downloads the
+//! index once and flushes it once with ETag locking.
+//!
+//! Queued keys are kept in a backlog file next to the outbox, sealed with
+//! the machine's local key, until their batch has been flushed. A batch that
+//! fails stays queued and is retried with exponential backoff; keys still
+//! queued when the app quit are picked up by [`IndexQueue::resume`] on the
+//! next run.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use claria_storage::crypto::{self, MasterKey};
+use claria_storage::store::ObjectStore;
+
+use crate::config;
+
+/// How long to wait for further writes before flushing a batch.
+pub const BATCH_WINDOW: Duration = Duration::from_secs(2);
+
+/// The longest a batch waits for writes to go quiet before it is flushed
+/// anyway.
+pub const MAX_BATCH_DELAY: Duration = Duration::from_secs(30);
+
+/// Delay before retrying a failed batch, doubled on each further failure
+/// up to [`MAX_RETRY_DELAY`].
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+struct Pending {
+    store: Arc<dyn ObjectStore>,
+    bucket: String,
+    keys: Vec<String>,
+}
+
+/// Handle to the background indexing task, started on first use.
+#[derive(Default)]
+pub struct IndexQueue {
+    tx: OnceLock<mpsc::UnboundedSender<Pending>>,
+}
+
+impl IndexQueue {
+    /// Queue `keys` for indexing. Must be called from within a Tokio
+    /// runtime.
+    pub fn enqueue<I, K>(&self, store: Arc<dyn ObjectStore>, bucket: &str, keys: I)
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+        if keys.is_empty() {
+            return;
+        }
+        self.send(Pending {
+            store,
+            bucket: bucket.to_string(),
+            keys,
+        });
+    }
+
+    /// Index any keys for `bucket` left in the backlog by an earlier run.
+    /// Must be called from within a Tokio runtime.
+    pub fn resume(&self, store: Arc<dyn ObjectStore>, bucket: &str) {
+        self.send(Pending {
+            store,
+            bucket: bucket.to_string(),
+            keys: Vec::new(),
+        });
+    }
+
+    fn send(&self, pending: Pending) {
+        let tx = self.tx.get_or_init(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(run(rx));
+            tx
+        });
+        let _ = tx.send(pending);
+    }
+}
+
+/// Keys waiting to be indexed, by bucket, mirrored to a sealed file.
+#[derive(Default)]
+struct Backlog {
+    file: Option<(PathBuf, MasterKey)>,
+    keys: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Backlog {
+    /// Load the backlog left by an earlier run. If it cannot be read, keys
+    /// are still indexed but only kept in memory.
+    fn open() -> Self {
+        let file = match config::index_backlog_path()
+            .and_then(|path| Ok((path, config::load_or_create_local_key()?)))
+        {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::warn!(error = %e, "search index backlog will not be persisted");
+                return Self::default();
+            }
+        };
+
+        let keys = match std::fs::read(&file.0) {
+            Ok(sealed) => crypto::open(&file.1, sealed)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_slice(&json).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    tracing::warn!(error = %e, "discarding unreadable search index backlog");
+                    BTreeMap::new()
+                }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to read search index backlog");
+                BTreeMap::new()
+            }
+        };
+        Self {
+            file: Some(file),
+            keys,
+        }
+    }
+
+    fn add(&mut self, bucket: &str, keys: Vec<String>) {
+        if keys.is_empty() {
+            return;
+        }
+        self.keys
+            .entry(bucket.to_string())
+            .or_default()
+            .extend(keys);
+        self.save();
+    }
+
+    fn remove(&mut self, bucket: &str, keys: &[String]) {
+        if let Some(queued) = self.keys.get_mut(bucket) {
+            for key in keys {
+                queued.remove(key);
+            }
+            if queued.is_empty() {
+                self.keys.remove(bucket);
+            }
+        }
+        self.save();
+    }
+
+    fn queued(&self, bucket: &str) -> Vec<String> {
+        self.keys
+            .get(bucket)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let Some((path, key)) = &self.file else {
+            return;
+        };
+        if let Err(e) = self.write(path, key) {
+            tracing::warn!(error = %e, "failed to save search index backlog");
+        }
+    }
+
+    fn write(&self, path: &Path, key: &MasterKey) -> eyre::Result<()> {
+        if self.keys.is_empty() {
+            return match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let sealed = crypto::seal(key, &serde_json::to_vec(&self.keys)?)?;
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, sealed)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+async fn run(mut rx: mpsc::UnboundedReceiver<Pending>) {
+    let mut backlog = Backlog::open();
+    // Bucket → latest store. Only buckets with a store can be flushed.
+    let mut stores: BTreeMap<String, Arc<dyn ObjectStore>> = BTreeMap::new();
+    let mut failures: u32 = 0;
+
+    loop {
+        if !stores
+            .keys()
+            .any(|bucket| backlog.keys.contains_key(bucket))
+        {
+            match rx.recv().await {
+                Some(pending) => add(&mut backlog, &mut stores, pending),
+                None => return,
+            }
+            continue;
+        }
+
+        // Collect further writes into the batch. After a failure, wait out
+        // the retry delay instead.
+        let (quiet, limit) = match failures {
+            0 => (BATCH_WINDOW, MAX_BATCH_DELAY),
+            n => {
+                let delay = retry_delay(n);
+                (delay, delay)
+            }
+        };
+        let deadline = Instant::now() + limit;
+        let mut closed = false;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let wait = quiet.min(deadline - now);
+            match tokio::time::timeout(wait, rx.recv()).await {
+                Ok(Some(pending)) => add(&mut backlog, &mut stores, pending),
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        let mut failed = false;
+        for (bucket, store) in &stores {
+            let keys = backlog.queued(bucket);
+            if keys.is_empty() {
+                continue;
+            }
+            match flush(&**store, bucket, &keys).await {
+                Ok(summary) => {
+                    tracing::debug!(
+                        bucket,
+                        keys = keys.len(),
+                        upserted = summary.upserted,
+                        deleted = summary.deleted,
+                        "search index batch flushed"
+                    );
+                    backlog.remove(bucket, &keys);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        bucket,
+                        keys = keys.len(),
+                        retry_in = ?retry_delay(failures + 1),
+                        error = %e,
+                        "search index batch failed"
+                    );
+                    failed = true;
+                }
+            }
+        }
+        failures = if failed {
+            failures.saturating_add(1)
+        } else {
+            0
+        };
+
+        if closed {
+            return;
+        }
+    }
+}
+
+fn add(
+    backlog: &mut Backlog,
+    stores: &mut BTreeMap<String, Arc<dyn ObjectStore>>,
+    pending: Pending,
+) {
+    backlog.add(&pending.bucket, pending.keys);
+    stores.insert(pending.bucket, pending.store);
+}
+
+async fn flush(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    keys: &[String],
+) -> eyre::Result<claria_search::sync::SyncSummary> {
+    crate::aws::require_search_key()?;
+    Ok(claria_search::sync::sync_keys(store, bucket, keys).await?)
+}
+
+/// How long to wait before the `failures`-th retry.
+fn retry_delay(failures: u32) -> Duration {
+    RETRY_DELAY
+        .saturating_mul(1 << failures.saturating_sub(1).min(10))
+        .min(MAX_RETRY_DELAY)
+}
@@ -5,4 +5,7 @@
 //! through the Tauri command layer.
 
 pub mod aws;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod indexer;
+pub mod outbox;
+pub mod uploads;
\ No newline at end of file
@@ -26,6 +26,17 @@ fn main() -> Result<()> {
         .with(console_layer)
         .init();
 
+    // Temporary files, extracted search indexes among them, go to a private
+    // directory that is emptied now and again on exit.
+    match claria_desktop::config::reset_private_temp_dir() {
+        Ok(dir) => {
+            if let Err(dir) = tempfile::env::override_temp_dir(&dir) {
+                tracing::warn!(path = %dir.display(), "temp directory already set");
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "private temp directory unavailable"),
+    }
+
     let builder = Builder::<tauri::Wry>::new()
         .commands(collect_commands![
             commands::has_config,
@@ -33,6 +44,10 @@ fn main() -> Result<()> {
             commands::save_config,
             commands::delete_config,
             commands::set_preferred_model,
+            commands::get_encryption_status,
+            commands::enable_encryption,
+            commands::export_master_key,
+            commands::import_master_key,
             commands::assess_credentials,
             commands::assume_role,
             commands::list_aws_profiles,
@@ -47,6 +62,11 @@ fn main() -> Result<()> {
             commands::list_clients,
             commands::create_client,
             commands::delete_client,
+            commands::purge_client,
+            commands::search_records,
+            commands::rebuild_search_index,
+            commands::get_search_synonyms,
+            commands::save_search_synonyms,
             commands::list_record_files,
             commands::upload_record_file,
             commands::delete_record_file,
@@ -66,6 +86,10 @@ fn main() -> Result<()> {
             commands::list_prompt_versions,
             commands::get_prompt_version,
             commands::restore_prompt_version,
+            commands::list_snippets,
+            commands::create_snippet,
+            commands::update_snippet,
+            commands::delete_snippet,
             commands::list_file_versions,
             commands::get_file_version_text,
             commands::restore_file_version,
@@ -73,17 +97,34 @@ fn main() -> Result<()> {
             commands::restore_deleted_file,
             commands::list_deleted_clients,
             commands::restore_client,
+            commands::preview_client_restore,
+            commands::restore_client_as_of,
+            commands::export_backup,
+            commands::import_backup,
+            commands::scrub_bucket,
+            commands::create_share_link,
+            commands::issue_share_link,
+            commands::list_share_links,
+            commands::revoke_share_link,
+            commands::list_outbox,
+            commands::sync_outbox,
+            commands::resolve_outbox_item,
             commands::get_whisper_models,
             commands::download_whisper_model,
             commands::delete_whisper_model,
             commands::delete_whisper_model_dir,
             commands::set_active_whisper_model,
             commands::transcribe_memo,
+            commands::get_embedding_model,
+            commands::download_embedding_model,
+            commands::delete_embedding_model,
+            commands::semantic_search,
             commands::check_for_updates,
             commands::get_cost_and_usage,
             commands::probe_cost_explorer,
             commands::enable_cost_explorer,
             commands::set_hourly_cost_data,
+            commands::get_storage_usage,
             commands::open_url,
             commands::count_client_context_tokens,
             commands::count_infra_context_tokens,
@@ -143,8 +184,24 @@ fn main() -> Result<()> {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .map_err(|e| eyre::eyre!("tauri error: {e}"))?;
+        .build(tauri::generate_context!())
+        .map_err(|e| eyre::eyre!("tauri error: {e}"))?
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // The process exits without running destructors, so the
+                // extracted search shards are dropped (and their directory
+                // removed) here. A search still holding them leaves them to
+                // the wipe below.
+                let state = app.state::<state::DesktopState>();
+                match state.search_shards.try_lock() {
+                    Ok(mut shards) => drop(shards.take()),
+                    Err(_) => tracing::warn!("search in progress, shard cache not dropped"),
+                }
+                if let Err(e) = claria_desktop::config::wipe_private_temp_dir() {
+                    tracing::warn!(error = %e, "failed to wipe private temp directory");
+                }
+            }
+        });
 
     Ok(())
 }
\ No newline at end of file
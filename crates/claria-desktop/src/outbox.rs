@@ -0,0 +1,36 @@
+//! The offline outbox for record writes.
+//!
+//! Record edits and uploads that cannot reach S3 are queued in a
+//! [`claria_storage::outbox::Outbox`] under the local data directory, sealed
+//! with the machine's local key, and replayed by the `sync_outbox` command.
+
+use claria_storage::outbox::Outbox;
+
+use crate::config;
+
+/// Open this machine's outbox.
+pub fn open() -> eyre::Result<Outbox> {
+    Ok(Outbox::open(
+        config::outbox_dir()?,
+        config::load_or_create_local_key()?,
+    )?)
+}
+
+/// Split a record file key (`records/{client_id}/{filename}`) into its
+/// client ID and filename.
+pub fn record_file_parts(key: &str) -> Option<(uuid::Uuid, &str)> {
+    let rest = key.strip_prefix("records/")?;
+    let (client_id, filename) = rest.split_once('/')?;
+    Some((client_id.parse().ok()?, filename))
+}
+
+/// A filename for keeping this machine's version of a conflicted file next
+/// to the other one: `notes.txt` becomes `notes (conflicted copy 2026-03-04).txt`.
+pub fn conflicted_copy_name(filename: &str, date: jiff::civil::Date) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => {
+            format!("{stem} (conflicted copy {date}).{ext}")
+        }
+        _ => format!("{filename} (conflicted copy {date})"),
+    }
+}
@@ -3,10 +3,20 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use claria_desktop::config::ClariaConfig;
+use claria_desktop::indexer::IndexQueue;
 
 pub struct DesktopState {
     pub config: Arc<Mutex<Option<ClariaConfig>>>,
     pub whisper: Arc<std::sync::Mutex<Option<claria_whisper::WhisperModel>>>,
+    /// The semantic search model, loaded on first use.
+    pub embedder: Arc<std::sync::Mutex<Option<claria_search::embed::SentenceEmbedder>>>,
+    /// Serialises writes to the offline outbox with its replay.
+    pub outbox: Arc<Mutex<()>>,
+    /// Batches search index updates for record writes.
+    pub index: IndexQueue,
+    /// Index shards kept between searches, created on first use and dropped
+    /// on exit, which removes their directory.
+    pub search_shards: Arc<Mutex<Option<claria_search::index::ShardCache>>>,
 }
 
 impl Default for DesktopState {
@@ -14,6 +24,10 @@ impl Default for DesktopState {
         Self {
             config: Arc::new(Mutex::new(None)),
             whisper: Arc::new(std::sync::Mutex::new(None)),
+            embedder: Arc::new(std::sync::Mutex::new(None)),
+            outbox: Arc::new(Mutex::new(())),
+            index: IndexQueue::default(),
+            search_shards: Arc::new(Mutex::new(None)),
         }
     }
 }
@@ -0,0 +1,110 @@
+//! Bookkeeping for resumable record uploads.
+//!
+//! Large files are sent as multipart uploads. When one is interrupted (the
+//! app quits, the network drops) its upload ID is kept in
+//! `pending-uploads.json` next to `config.json`, so uploading the same file
+//! to the same record again picks up from the parts S3 already has.
+
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_dir;
+
+const PENDING_UPLOADS_FILE: &str = "pending-uploads.json";
+
+/// An in-progress multipart upload of a local file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingUpload {
+    bucket: String,
+    key: String,
+    upload_id: String,
+    file_path: String,
+    file_size: u64,
+    /// Modification time in whole seconds, so a file edited since the
+    /// interrupted attempt is uploaded from scratch.
+    modified_secs: u64,
+    /// Hex SHA-256 of the file when the upload started, checked again by
+    /// `upload_file` before any part is reused.
+    #[serde(default)]
+    sha256: String,
+}
+
+fn load() -> eyre::Result<Vec<PendingUpload>> {
+    let path = config_dir()?.join(PENDING_UPLOADS_FILE);
+    match std::fs::read(&path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save(pending: &[PendingUpload]) -> eyre::Result<()> {
+    let dir = config_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let tmp_path = dir.join(format!("{PENDING_UPLOADS_FILE}.tmp"));
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(pending)?)?;
+    std::fs::rename(&tmp_path, dir.join(PENDING_UPLOADS_FILE))?;
+    Ok(())
+}
+
+/// Size and whole-second modification time of a local file.
+fn fingerprint(file_path: &Path) -> eyre::Result<(u64, u64)> {
+    let meta = std::fs::metadata(file_path)?;
+    let modified = meta.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+    Ok((meta.len(), modified))
+}
+
+/// The upload ID and starting SHA-256 of an interrupted upload of this
+/// exact file to `key`, if there is one.
+pub fn find_resumable(bucket: &str, key: &str, file_path: &Path) -> Option<(String, String)> {
+    let (file_size, modified_secs) = fingerprint(file_path).ok()?;
+    let path = file_path.to_string_lossy();
+    load()
+        .ok()?
+        .into_iter()
+        .find(|p| {
+            p.bucket == bucket
+                && p.key == key
+                && p.file_path == path
+                && p.file_size == file_size
+                && p.modified_secs == modified_secs
+        })
+        .map(|p| (p.upload_id, p.sha256))
+}
+
+/// Remember an upload of a file hashing to `sha256` so it can be resumed if
+/// it does not complete.
+pub fn remember(
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    file_path: &Path,
+    sha256: &str,
+) -> eyre::Result<()> {
+    let (file_size, modified_secs) = fingerprint(file_path)?;
+    let mut pending = load()?;
+    pending.retain(|p| !(p.bucket == bucket && p.key == key));
+    pending.push(PendingUpload {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        upload_id: upload_id.to_string(),
+        file_path: file_path.to_string_lossy().into_owned(),
+        file_size,
+        modified_secs,
+        sha256: sha256.to_string(),
+    });
+    save(&pending)
+}
+
+/// Drop the bookkeeping for `key` once its upload has completed.
+pub fn forget(bucket: &str, key: &str) -> eyre::Result<()> {
+    let mut pending = load()?;
+    let before = pending.len();
+    pending.retain(|p| !(p.bucket == bucket && p.key == key));
+    if pending.len() != before {
+        save(&pending)?;
+    }
+    Ok(())
+}
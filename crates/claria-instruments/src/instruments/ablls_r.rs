@@ -14,6 +14,10 @@ impl Instrument for AbllsR {
         "ABLLS-R"
     }
 
+    fn full_name(&self) -> &str {
+        "Assessment of Basic Language and Learning Skills – Revised"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let rating_range = ScoreRange {
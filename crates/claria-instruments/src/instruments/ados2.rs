@@ -14,6 +14,10 @@ impl Instrument for Ados2 {
         "ADOS-2"
     }
 
+    fn full_name(&self) -> &str {
+        "Autism Diagnostic Observation Schedule, Second Edition"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let algorithm_range = ScoreRange {
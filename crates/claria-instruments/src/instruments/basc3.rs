@@ -15,6 +15,10 @@ impl Instrument for Basc3 {
         "BASC-3"
     }
 
+    fn full_name(&self) -> &str {
+        "Behavior Assessment System for Children, Third Edition"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let t_score = ScoreRange {
@@ -14,6 +14,10 @@ impl Instrument for Cars2 {
         "CARS-2"
     }
 
+    fn full_name(&self) -> &str {
+        "Childhood Autism Rating Scale, Second Edition"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let item_range = ScoreRange {
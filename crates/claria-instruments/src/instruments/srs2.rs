@@ -15,6 +15,10 @@ impl Instrument for Srs2 {
         "SRS-2"
     }
 
+    fn full_name(&self) -> &str {
+        "Social Responsiveness Scale, Second Edition"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let t_score = ScoreRange {
@@ -14,6 +14,10 @@ impl Instrument for VbMapp {
         "VB-MAPP"
     }
 
+    fn full_name(&self) -> &str {
+        "Verbal Behavior Milestones Assessment and Placement Program"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let milestone_range = ScoreRange {
@@ -15,6 +15,10 @@ impl Instrument for Vineland3 {
         "Vineland-3"
     }
 
+    fn full_name(&self) -> &str {
+        "Vineland Adaptive Behavior Scales, Third Edition"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let v_scale = ScoreRange {
@@ -15,6 +15,10 @@ impl Instrument for WaisIv {
         "WAIS-IV"
     }
 
+    fn full_name(&self) -> &str {
+        "Wechsler Adult Intelligence Scale, Fourth Edition"
+    }
+
     fn domains(&self) -> &[Domain] {
         static DOMAINS: std::sync::LazyLock<Vec<Domain>> = std::sync::LazyLock::new(|| {
             let scaled = ScoreRange {
@@ -18,6 +18,10 @@ pub trait Instrument: Send + Sync {
     /// Human-readable name (e.g., "VB-MAPP", "Vineland-3").
     fn name(&self) -> &str;
 
+    /// Published title (e.g., "Vineland Adaptive Behavior Scales, Third
+    /// Edition").
+    fn full_name(&self) -> &str;
+
     /// The domains and subscales this instrument measures.
     fn domains(&self) -> &[Domain];
 
@@ -694,10 +694,15 @@ fn claria_policy_document(system_name: &str, account_id: &str) -> String {
                     "s3:PutBucketPublicAccessBlock",
                     "s3:GetBucketPolicy",
                     "s3:PutBucketPolicy",
+                    "s3:GetLifecycleConfiguration",
+                    "s3:PutLifecycleConfiguration",
                     "s3:GetObject",
                     "s3:GetObjectVersion",
                     "s3:PutObject",
                     "s3:DeleteObject",
+                    "s3:DeleteObjectVersion",
+                    "s3:ListMultipartUploadParts",
+                    "s3:AbortMultipartUpload",
                     "s3:ListBucket",
                     "s3:ListBucketVersions"
                 ],
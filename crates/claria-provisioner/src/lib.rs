@@ -15,6 +15,9 @@
 
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use claria_storage::store::ObjectStore;
 
 pub mod account_setup;
 pub mod addr;
@@ -102,6 +105,12 @@ pub fn build_syncers(
                         s3.clone(),
                     ))
                 }
+                "s3_bucket_lifecycle" => {
+                    Box::new(syncers::s3_bucket_lifecycle::S3BucketLifecycleSyncer::new(
+                        spec.clone(),
+                        s3.clone(),
+                    ))
+                }
                 "s3_bucket_public_access_block" => Box::new(
                     syncers::s3_bucket_public_access_block::S3BucketPublicAccessBlockSyncer::new(
                         spec.clone(),
@@ -148,7 +157,7 @@ pub fn build_persistence(
     system_name: &str,
     account_id: &str,
 ) -> Result<StatePersistence, ProvisionerError> {
-    let s3_client = aws_sdk_s3::Client::new(config);
+    let store: Arc<dyn ObjectStore> = Arc::new(aws_sdk_s3::Client::new(config));
     let bucket = format!("{account_id}-{system_name}-data");
     let s3_key = "_state/provisioner.json".to_string();
 
@@ -160,7 +169,7 @@ pub fn build_persistence(
     let local_path = local_dir.join("provisioner-state.json");
 
     Ok(StatePersistence {
-        s3: s3_client,
+        store,
         bucket,
         s3_key,
         local_path,
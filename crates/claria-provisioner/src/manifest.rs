@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use specta::Type;
 
+use claria_core::s3_keys;
+
 use crate::addr::ResourceAddr;
 
 /// Every resource in the system is declared as a `ResourceSpec`.
@@ -99,7 +101,7 @@ pub struct Manifest {
 
 impl Manifest {
     /// Bump when adding, removing, or changing resource specs.
-    pub const VERSION: u32 = 6;
+    pub const VERSION: u32 = 7;
 
     /// Build the default Claria manifest from runtime config.
     pub fn claria(account_id: &str, system_name: &str, region: &str) -> Self {
@@ -167,6 +169,9 @@ impl Manifest {
                         "s3:GetObjectVersion".into(),
                         "s3:PutObject".into(),
                         "s3:DeleteObject".into(),
+                        "s3:DeleteObjectVersion".into(),
+                        "s3:ListMultipartUploadParts".into(),
+                        "s3:AbortMultipartUpload".into(),
                     ],
                 },
                 ResourceSpec {
@@ -249,6 +254,26 @@ impl Manifest {
                         "s3:PutBucketPolicy".into(),
                     ],
                 },
+                ResourceSpec {
+                    resource_type: "s3_bucket_lifecycle".into(),
+                    resource_name: bucket.clone(),
+                    lifecycle: Lifecycle::Managed,
+                    // Share links last at most 7 days; a day later the copy
+                    // is gone, and its old versions a day after that.
+                    desired: json!({
+                        "prefix": s3_keys::SHARES_PREFIX,
+                        "expiration_days": 8,
+                        "noncurrent_expiration_days": 1,
+                    }),
+                    label: "Share Link Cleanup".into(),
+                    description: "Deletes the unencrypted copies behind share links after they expire"
+                        .into(),
+                    severity: Severity::Normal,
+                    iam_actions: vec![
+                        "s3:GetLifecycleConfiguration".into(),
+                        "s3:PutLifecycleConfiguration".into(),
+                    ],
+                },
                 ResourceSpec {
                     resource_type: "cloudtrail_trail".into(),
                     resource_name: trail.clone(),
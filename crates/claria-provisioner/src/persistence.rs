@@ -1,13 +1,14 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use aws_sdk_s3::Client as S3Client;
+use claria_storage::store::ObjectStore;
 
 use crate::error::ProvisionerError;
 use crate::state::{migrate_state_v1_to_v2, ProvisionerState};
 
-/// Dual-write state persistence: local disk (safety net) + S3 (authoritative).
+/// Dual-write state persistence: local disk (safety net) + object store (authoritative).
 pub struct StatePersistence {
-    pub s3: S3Client,
+    pub store: Arc<dyn ObjectStore>,
     pub bucket: String,
     pub s3_key: String,
     pub local_path: PathBuf,
@@ -30,7 +31,7 @@ impl StatePersistence {
         tracing::debug!(path = %self.local_path.display(), "state flushed to local disk");
 
         // 2. Upload to S3
-        match claria_storage::state::save_state(&self.s3, &self.bucket, &self.s3_key, state).await
+        match claria_storage::state::save_state(&*self.store, &self.bucket, &self.s3_key, state).await
         {
             Ok(_) => {
                 tracing::debug!(
@@ -107,7 +108,7 @@ impl StatePersistence {
         }
 
         // Delete S3 object (ignore not-found).
-        match claria_storage::objects::delete_object(&self.s3, &self.bucket, &self.s3_key).await {
+        match claria_storage::objects::delete_object(&*self.store, &self.bucket, &self.s3_key).await {
             Ok(()) => {
                 tracing::debug!(
                     bucket = %self.bucket,
@@ -125,7 +126,7 @@ impl StatePersistence {
     /// Try loading state from S3 with migration fallback.
     async fn load_from_s3(&self) -> Result<ProvisionerState, LoadError> {
         let output = match claria_storage::objects::get_object(
-            &self.s3,
+            &*self.store,
             &self.bucket,
             &self.s3_key,
         )
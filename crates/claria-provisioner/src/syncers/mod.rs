@@ -7,6 +7,7 @@ pub mod iam_user;
 pub mod iam_user_policy;
 pub mod s3_bucket;
 pub mod s3_bucket_encryption;
+pub mod s3_bucket_lifecycle;
 pub mod s3_bucket_policy;
 pub mod s3_bucket_public_access_block;
 pub mod s3_bucket_versioning;
@@ -0,0 +1,168 @@
+use aws_sdk_s3::Client;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::types::{
+    BucketLifecycleConfiguration, ExpirationStatus, LifecycleExpiration, LifecycleRule,
+    LifecycleRuleFilter, NoncurrentVersionExpiration,
+};
+use serde_json::json;
+
+use crate::error::{format_err_chain, ProvisionerError};
+use crate::manifest::ResourceSpec;
+use crate::syncer::{BoxFuture, ResourceSyncer};
+
+/// ID of the one lifecycle rule Claria manages. Rules with other IDs are
+/// left as they are.
+const RULE_ID: &str = "claria-expire-shares";
+
+/// Expires the unencrypted share copies under `_shares/` once every link to
+/// them has expired, so they never outlive their share even if the app is
+/// not opened to clean them up.
+pub struct S3BucketLifecycleSyncer {
+    spec: ResourceSpec,
+    client: Client,
+}
+
+impl S3BucketLifecycleSyncer {
+    pub fn new(spec: ResourceSpec, client: Client) -> Self {
+        Self { spec, client }
+    }
+
+    fn bucket_name(&self) -> &str {
+        &self.spec.resource_name
+    }
+
+    /// The rules currently on the bucket, or none if it has no lifecycle
+    /// configuration. Any other error is returned, so that a failed read is
+    /// never mistaken for an empty configuration and the bucket's other
+    /// rules overwritten.
+    async fn current_rules(&self) -> Result<Vec<LifecycleRule>, String> {
+        match self
+            .client
+            .get_bucket_lifecycle_configuration()
+            .bucket(self.bucket_name())
+            .send()
+            .await
+        {
+            Ok(resp) => Ok(resp.rules().to_vec()),
+            Err(e) if e.code() == Some("NoSuchLifecycleConfiguration") => Ok(Vec::new()),
+            Err(e) => Err(format!(
+                "s3:GetBucketLifecycleConfiguration failed: {}",
+                format_err_chain(&e)
+            )),
+        }
+    }
+
+    fn desired_rule(&self) -> Result<LifecycleRule, ProvisionerError> {
+        let desired = &self.spec.desired;
+        let prefix = desired["prefix"].as_str().unwrap_or_default();
+        let expiration_days = desired["expiration_days"].as_i64().unwrap_or_default() as i32;
+        let noncurrent_days = desired["noncurrent_expiration_days"]
+            .as_i64()
+            .unwrap_or_default() as i32;
+
+        LifecycleRule::builder()
+            .id(RULE_ID)
+            .filter(LifecycleRuleFilter::builder().prefix(prefix).build())
+            .status(ExpirationStatus::Enabled)
+            .expiration(LifecycleExpiration::builder().days(expiration_days).build())
+            .noncurrent_version_expiration(
+                NoncurrentVersionExpiration::builder()
+                    .noncurrent_days(noncurrent_days)
+                    .build(),
+            )
+            .build()
+            .map_err(|e| ProvisionerError::CreateFailed(format_err_chain(&e)))
+    }
+
+    /// Replace the bucket's lifecycle rules with `rules`, or remove the
+    /// configuration entirely if there are none.
+    async fn put_rules(&self, rules: Vec<LifecycleRule>) -> Result<(), String> {
+        if rules.is_empty() {
+            return self
+                .client
+                .delete_bucket_lifecycle()
+                .bucket(self.bucket_name())
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|e| format_err_chain(&e));
+        }
+        let config = BucketLifecycleConfiguration::builder()
+            .set_rules(Some(rules))
+            .build()
+            .map_err(|e| format_err_chain(&e))?;
+        self.client
+            .put_bucket_lifecycle_configuration()
+            .bucket(self.bucket_name())
+            .lifecycle_configuration(config)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| format_err_chain(&e))
+    }
+}
+
+impl ResourceSyncer for S3BucketLifecycleSyncer {
+    fn spec(&self) -> &ResourceSpec {
+        &self.spec
+    }
+
+    fn read(&self) -> BoxFuture<'_, Result<Option<serde_json::Value>, ProvisionerError>> {
+        Box::pin(async {
+            let rules = self.current_rules().await.map_err(ProvisionerError::Aws)?;
+            let Some(rule) = rules.iter().find(|r| r.id() == Some(RULE_ID)) else {
+                return Ok(None);
+            };
+            let enabled = *rule.status() == ExpirationStatus::Enabled;
+            Ok(Some(json!({
+                "prefix": rule.filter().and_then(|f| f.prefix()),
+                "expiration_days": rule
+                    .expiration()
+                    .and_then(|e| e.days())
+                    .filter(|_| enabled),
+                "noncurrent_expiration_days": rule
+                    .noncurrent_version_expiration()
+                    .and_then(|e| e.noncurrent_days())
+                    .filter(|_| enabled),
+            })))
+        })
+    }
+
+    fn create(&self) -> BoxFuture<'_, Result<serde_json::Value, ProvisionerError>> {
+        Box::pin(async {
+            let mut rules: Vec<LifecycleRule> = self
+                .current_rules()
+                .await
+                .map_err(ProvisionerError::CreateFailed)?
+                .into_iter()
+                .filter(|r| r.id() != Some(RULE_ID))
+                .collect();
+            rules.push(self.desired_rule()?);
+            self.put_rules(rules)
+                .await
+                .map_err(ProvisionerError::CreateFailed)?;
+
+            Ok(self.spec.desired.clone())
+        })
+    }
+
+    fn update(&self) -> BoxFuture<'_, Result<serde_json::Value, ProvisionerError>> {
+        // Idempotent — same as create
+        self.create()
+    }
+
+    fn destroy(&self) -> BoxFuture<'_, Result<(), ProvisionerError>> {
+        Box::pin(async {
+            let rules: Vec<LifecycleRule> = self
+                .current_rules()
+                .await
+                .map_err(ProvisionerError::DeleteFailed)?
+                .into_iter()
+                .filter(|r| r.id() != Some(RULE_ID))
+                .collect();
+            self.put_rules(rules)
+                .await
+                .map_err(ProvisionerError::DeleteFailed)
+        })
+    }
+}
@@ -320,6 +320,7 @@ async fn plan_fresh_account() {
         e(&format!("s3_bucket_encryption.{BUCKET}"),           Action::Create,             Cause::ManifestChanged),
         e(&format!("s3_bucket_public_access_block.{BUCKET}"),  Action::Create,             Cause::ManifestChanged),
         e(&format!("s3_bucket_policy.{BUCKET}"),               Action::Create,             Cause::ManifestChanged),
+        e(&format!("s3_bucket_lifecycle.{BUCKET}"),            Action::Create,             Cause::ManifestChanged),
         e(&format!("cloudtrail_trail.{TRAIL}"),                Action::Create,             Cause::ManifestChanged),
         e(&format!("cloudtrail_trail_logging.{TRAIL}"),        Action::Create,             Cause::ManifestChanged),
         e("bedrock_model_agreement.anthropic.claude-sonnet-4", Action::Modify,             Cause::ManifestChanged),
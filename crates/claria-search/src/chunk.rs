@@ -0,0 +1,66 @@
+//! Split long text into overlapping passages.
+//!
+//! A hit on a 60-page evaluation says little about where the match is, so
+//! record file text is also indexed as passages of about
+//! [`PASSAGE_CHARS`] characters. Consecutive passages share
+//! [`PASSAGE_OVERLAP`] characters so a phrase that straddles a boundary is
+//! still found whole in one of them. Boundaries fall on whitespace where
+//! possible.
+
+/// Target passage length, in characters.
+pub const PASSAGE_CHARS: usize = 1_000;
+
+/// Characters shared by consecutive passages.
+pub const PASSAGE_OVERLAP: usize = 200;
+
+/// One passage of a longer text. `start` and `end` are character (not
+/// byte) offsets into the full text, `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passage {
+    pub ordinal: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Split `text` into overlapping passages. Blank text has none; text up to
+/// [`PASSAGE_CHARS`] long is a single passage.
+pub fn split_passages(text: &str) -> Vec<Passage> {
+    let chars: Vec<char> = text.chars().collect();
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let mut passages = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = (start + PASSAGE_CHARS).min(chars.len());
+        if end < chars.len() {
+            // Pull the end back to a word boundary in the second half.
+            let floor = start + PASSAGE_CHARS / 2;
+            if let Some(space) = (floor..end).rev().find(|&i| chars[i].is_whitespace()) {
+                end = space;
+            }
+        }
+        passages.push(Passage {
+            ordinal: passages.len(),
+            start,
+            end,
+            text: chars[start..end].iter().collect(),
+        });
+        if chars[end..].iter().all(|c| c.is_whitespace()) {
+            return passages;
+        }
+
+        // Step back by the overlap, then forward to the start of a word.
+        let mut next = end.saturating_sub(PASSAGE_OVERLAP).max(start + 1);
+        while next < end && !chars[next - 1].is_whitespace() {
+            next += 1;
+        }
+        start = if next < end {
+            next
+        } else {
+            end.saturating_sub(PASSAGE_OVERLAP).max(start + 1)
+        };
+    }
+}
@@ -0,0 +1,472 @@
+//! What each object in the bucket is indexed as.
+//!
+//! Documents are keyed by S3 key. Indexed objects are:
+//!
+//! - clients (`clients/{id}.json`), record files (`records/{id}/{filename}`)
+//!   and chat histories (`records/{id}/chat-history/{chat}.json`). A `.text`
+//!   sidecar is indexed as the body of its record file, so writing or
+//!   deleting one re-indexes the file it belongs to. Record file text is
+//!   also indexed as passages, with IDs `{key}#{ordinal}`;
+//! - assessments, snippets, goals and template metadata (`{prefix}{id}.json`);
+//! - reports (`reports/{id}/report.json`) and their Bedrock transactions
+//!   (`reports/{id}/transaction.json`).
+
+use tantivy::schema::Value;
+use tantivy::{Index, TantivyDocument, doc};
+use uuid::Uuid;
+
+use claria_core::models::assessment::Assessment;
+use claria_core::models::chat_history::{ChatHistory, ChatHistoryRole};
+use claria_core::models::client::Client;
+use claria_core::models::goal::Goal;
+use claria_core::models::report::Report;
+use claria_core::models::snippet::TextSnippet;
+use claria_core::models::template::Template;
+use claria_core::models::token_count::TokenUsage;
+use claria_core::models::transaction::BedrockTransaction;
+use claria_core::s3_keys;
+use claria_core::schema::{doc_type, field, get_field};
+use claria_storage::error::StorageError;
+use claria_storage::objects;
+use claria_storage::store::ObjectStore;
+
+use crate::chunk::split_passages;
+use crate::error::SearchError;
+use crate::query::find_by_id;
+
+/// Longest chat-history title taken from its first message, in characters.
+const CHAT_TITLE_CHARS: usize = 80;
+
+/// Prefixes holding one `{id}.json` object per core model.
+const MODEL_PREFIXES: [(&str, &str); 4] = [
+    (s3_keys::ASSESSMENTS_PREFIX, doc_type::ASSESSMENT),
+    (s3_keys::SNIPPETS_PREFIX, doc_type::SNIPPET),
+    (s3_keys::GOALS_PREFIX, doc_type::GOAL),
+    (s3_keys::TEMPLATES_PREFIX, doc_type::TEMPLATE),
+];
+
+/// Every prefix with indexed objects under it.
+pub const INDEXED_PREFIXES: [&str; 7] = [
+    s3_keys::CLIENTS_PREFIX,
+    "records/",
+    s3_keys::ASSESSMENTS_PREFIX,
+    s3_keys::SNIPPETS_PREFIX,
+    s3_keys::GOALS_PREFIX,
+    s3_keys::TEMPLATES_PREFIX,
+    s3_keys::REPORTS_PREFIX,
+];
+
+/// The contents of one index document, before it is converted to a
+/// Tantivy document.
+#[derive(Debug, Clone, Default)]
+pub struct IndexDocument {
+    pub id: String,
+    pub doc_type: &'static str,
+    pub anonymized: bool,
+    /// The client a client, record file or chat history belongs to.
+    pub client_id: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub s3_key: String,
+    pub status: Option<String>,
+    pub model_id: Option<String>,
+    pub template_id: Option<String>,
+    pub transaction_id: Option<String>,
+    pub usage: Option<TokenUsage>,
+    /// Unix seconds. `None` keeps the value of an existing document, or
+    /// uses `updated_at` for a new one.
+    pub created_at: Option<i64>,
+    pub updated_at: i64,
+    /// Also index `body` as overlapping passages (see [`crate::chunk`]).
+    pub chunked: bool,
+}
+
+impl IndexDocument {
+    /// Convert to Tantivy documents for `index`: this document, followed by
+    /// its passages if it is [`chunked`](Self::chunked). Looks up the
+    /// `created_at` of an existing document with the same ID if needed.
+    pub fn into_tantivy(self, index: &Index) -> Vec<TantivyDocument> {
+        let schema = index.schema();
+        let created_at = self
+            .created_at
+            .or_else(|| existing_created_at(index, &self.id))
+            .unwrap_or(self.updated_at);
+
+        let mut documents = Vec::new();
+        if self.chunked {
+            for passage in split_passages(&self.body) {
+                let mut document = doc!(
+                    get_field(&schema, field::ID) => passage_id(&self.id, passage.ordinal),
+                    get_field(&schema, field::DOC_TYPE) => doc_type::PASSAGE,
+                    get_field(&schema, field::ANONYMIZED) => if self.anonymized { "true" } else { "false" },
+                    get_field(&schema, field::TITLE) => self.title.clone(),
+                    get_field(&schema, field::BODY) => passage.text,
+                    get_field(&schema, field::S3_KEY) => self.s3_key.clone(),
+                    get_field(&schema, field::CREATED_AT) => created_at,
+                    get_field(&schema, field::UPDATED_AT) => self.updated_at,
+                    get_field(&schema, field::PARENT_KEY) => self.id.clone(),
+                    get_field(&schema, field::CHUNK_ORDINAL) => passage.ordinal as u64,
+                    get_field(&schema, field::CHUNK_START) => passage.start as u64,
+                    get_field(&schema, field::CHUNK_END) => passage.end as u64,
+                );
+                if let Some(client_id) = &self.client_id {
+                    document.add_text(get_field(&schema, field::CLIENT_ID), client_id);
+                }
+                documents.push(document);
+            }
+        }
+
+        let mut document = doc!(
+            get_field(&schema, field::ID) => self.id,
+            get_field(&schema, field::DOC_TYPE) => self.doc_type,
+            get_field(&schema, field::ANONYMIZED) => if self.anonymized { "true" } else { "false" },
+            get_field(&schema, field::TITLE) => self.title,
+            get_field(&schema, field::BODY) => self.body,
+            get_field(&schema, field::S3_KEY) => self.s3_key,
+            get_field(&schema, field::CREATED_AT) => created_at,
+            get_field(&schema, field::UPDATED_AT) => self.updated_at,
+        );
+        let optional = [
+            (field::CLIENT_ID, self.client_id),
+            (field::STATUS, self.status),
+            (field::MODEL_ID, self.model_id),
+            (field::TEMPLATE_ID, self.template_id),
+            (field::TRANSACTION_ID, self.transaction_id),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                document.add_text(get_field(&schema, name), value);
+            }
+        }
+        if let Some(usage) = self.usage {
+            document.add_u64(
+                get_field(&schema, field::TOKEN_COUNT_INPUT),
+                usage.tokens.input,
+            );
+            document.add_u64(
+                get_field(&schema, field::TOKEN_COUNT_OUTPUT),
+                usage.tokens.output,
+            );
+            document.add_f64(get_field(&schema, field::COST_USD), usage.cost_usd);
+        }
+        documents.insert(0, document);
+        documents
+    }
+}
+
+/// The ID of passage `ordinal` of the document `parent_id`.
+pub fn passage_id(parent_id: &str, ordinal: usize) -> String {
+    format!("{parent_id}#{ordinal}")
+}
+
+/// The key a changed object is indexed under: its own key, or for a
+/// `.text` sidecar the record file it belongs to. `None` for keys that are
+/// not indexed.
+///
+/// A record file may itself be named `*.text`, so a sidecar's parent is only
+/// taken to be the indexed key if the parent exists.
+pub async fn indexed_key(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+) -> Result<Option<String>, SearchError> {
+    if is_client_key(key) || is_chat_history_key(key) || is_model_key(key) {
+        return Ok(Some(key.to_string()));
+    }
+    if record_filename(key).is_none() {
+        return Ok(None);
+    }
+    if let Some(parent) = key.strip_suffix(".text")
+        && record_filename(parent).is_some()
+        && exists(store, bucket, parent).await?
+    {
+        return Ok(Some(parent.to_string()));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Read the current state of `key` and build the document it is indexed
+/// as. Returns `None` if the object no longer exists or is not indexed, and
+/// [`SearchError::Serialization`] if its JSON does not parse.
+pub async fn build_document(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+) -> Result<Option<IndexDocument>, SearchError> {
+    if let Some(filename) = record_filename(key) {
+        return record_file_document(store, bucket, key, filename).await;
+    }
+    if !(is_client_key(key) || is_chat_history_key(key) || is_model_key(key)) {
+        return Ok(None);
+    }
+    let Some(body) = read(store, bucket, key).await? else {
+        return Ok(None);
+    };
+    json_document(key, &body).map(Some)
+}
+
+/// Build the document for a JSON object whose key [`is_model_key`],
+/// [`is_client_key`] or [`is_chat_history_key`].
+fn json_document(key: &str, body: &[u8]) -> Result<IndexDocument, SearchError> {
+    let base = IndexDocument {
+        id: key.to_string(),
+        client_id: client_id_of(key),
+        s3_key: key.to_string(),
+        ..Default::default()
+    };
+
+    if is_client_key(key) {
+        let client: Client = serde_json::from_slice(body)?;
+        return Ok(IndexDocument {
+            doc_type: doc_type::CLIENT,
+            title: client.name.clone(),
+            body: client.name,
+            created_at: Some(client.created_at.as_second()),
+            updated_at: client.updated_at.as_second(),
+            ..base
+        });
+    }
+
+    if is_chat_history_key(key) {
+        let history: ChatHistory = serde_json::from_slice(body)?;
+        let title = history
+            .messages
+            .iter()
+            .find(|m| matches!(m.role, ChatHistoryRole::User))
+            .map(|m| m.content.chars().take(CHAT_TITLE_CHARS).collect())
+            .unwrap_or_else(|| "Chat".to_string());
+        let body = history
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        return Ok(IndexDocument {
+            doc_type: doc_type::CHAT_HISTORY,
+            title,
+            body,
+            model_id: Some(history.model_id),
+            created_at: Some(history.created_at.as_second()),
+            updated_at: history.updated_at.as_second(),
+            ..base
+        });
+    }
+
+    if key.ends_with("/report.json") {
+        let report: Report = serde_json::from_slice(body)?;
+        return Ok(IndexDocument {
+            doc_type: doc_type::REPORT,
+            body: report.title.clone(),
+            title: report.title,
+            status: Some(enum_str(serde_json::to_value(report.status)?)),
+            template_id: Some(report.template_id.to_string()),
+            transaction_id: report.transaction_id.map(|id| id.to_string()),
+            created_at: Some(report.created_at.as_second()),
+            updated_at: report.updated_at.as_second(),
+            ..base
+        });
+    }
+
+    if key.ends_with("/transaction.json") {
+        let transaction: BedrockTransaction = serde_json::from_slice(body)?;
+        let title = enum_str(serde_json::to_value(transaction.transaction_type)?);
+        return Ok(IndexDocument {
+            doc_type: doc_type::TRANSACTION,
+            body: title.clone(),
+            title,
+            status: Some(enum_str(serde_json::to_value(transaction.status)?)),
+            model_id: Some(transaction.model_id),
+            transaction_id: Some(transaction.id.to_string()),
+            usage: Some(transaction.usage),
+            created_at: Some(transaction.created_at.as_second()),
+            updated_at: transaction.created_at.as_second(),
+            ..base
+        });
+    }
+
+    let kind = MODEL_PREFIXES
+        .iter()
+        .find(|(prefix, _)| key.starts_with(prefix))
+        .map(|(_, kind)| *kind)
+        .unwrap_or_default();
+    match kind {
+        doc_type::ASSESSMENT => {
+            let assessment: Assessment = serde_json::from_slice(body)?;
+            let body = [
+                Some(assessment.title.as_str()),
+                Some(assessment.instrument_id.as_str()),
+                Some(assessment.client_name.as_str()),
+                assessment.notes.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n\n");
+            Ok(IndexDocument {
+                doc_type: doc_type::ASSESSMENT,
+                anonymized: assessment.anonymized,
+                title: assessment.title,
+                body,
+                created_at: Some(assessment.created_at.as_second()),
+                updated_at: assessment.updated_at.as_second(),
+                ..base
+            })
+        }
+        doc_type::SNIPPET => {
+            let snippet: TextSnippet = serde_json::from_slice(body)?;
+            Ok(IndexDocument {
+                doc_type: doc_type::SNIPPET,
+                title: snippet.title,
+                body: snippet.body,
+                created_at: Some(snippet.created_at.as_second()),
+                updated_at: snippet.updated_at.as_second(),
+                ..base
+            })
+        }
+        doc_type::GOAL => {
+            let goal: Goal = serde_json::from_slice(body)?;
+            let mut body = goal.description;
+            for recommendation in &goal.recommendations {
+                body.push_str("\n\n");
+                body.push_str(&recommendation.title);
+                body.push('\n');
+                body.push_str(&recommendation.description);
+            }
+            Ok(IndexDocument {
+                doc_type: doc_type::GOAL,
+                title: goal.title,
+                body,
+                created_at: Some(goal.created_at.as_second()),
+                updated_at: goal.updated_at.as_second(),
+                ..base
+            })
+        }
+        _ => {
+            let template: Template = serde_json::from_slice(body)?;
+            Ok(IndexDocument {
+                doc_type: doc_type::TEMPLATE,
+                title: template.name,
+                body: template.description,
+                template_id: Some(template.id.to_string()),
+                created_at: Some(template.created_at.as_second()),
+                updated_at: template.updated_at.as_second(),
+                ..base
+            })
+        }
+    }
+}
+
+async fn record_file_document(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    filename: &str,
+) -> Result<Option<IndexDocument>, SearchError> {
+    if !exists(store, bucket, key).await? {
+        return Ok(None);
+    }
+    // Plain text notes are their own text; anything else is searchable
+    // through its sidecar, if extraction has produced one.
+    let text = if filename.ends_with(".txt") {
+        read(store, bucket, key).await?
+    } else {
+        read(store, bucket, &format!("{key}.text")).await?
+    };
+    Ok(Some(IndexDocument {
+        id: key.to_string(),
+        doc_type: doc_type::RECORD_FILE,
+        client_id: client_id_of(key),
+        title: filename.to_string(),
+        body: text
+            .map(|t| String::from_utf8_lossy(&t).into_owned())
+            .unwrap_or_default(),
+        s3_key: key.to_string(),
+        created_at: None,
+        updated_at: jiff::Timestamp::now().as_second(),
+        chunked: true,
+        ..Default::default()
+    }))
+}
+
+/// `created_at` of the document already indexed under `id`, so updates keep
+/// the time a record file was first indexed.
+fn existing_created_at(index: &Index, id: &str) -> Option<i64> {
+    let schema = index.schema();
+    find_by_id(index, id)
+        .ok()
+        .flatten()?
+        .get_first(get_field(&schema, field::CREATED_AT))
+        .and_then(|v| v.as_i64())
+}
+
+/// The snake_case name a model enum serializes as.
+fn enum_str(value: serde_json::Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// The client ID in a client, record file or chat history key.
+fn client_id_of(key: &str) -> Option<String> {
+    let id = match key.strip_prefix(s3_keys::CLIENTS_PREFIX) {
+        Some(rest) => rest.strip_suffix(".json")?,
+        None => key.strip_prefix("records/")?.split_once('/')?.0,
+    };
+    id.parse::<Uuid>().ok().map(|id| id.to_string())
+}
+
+fn is_client_key(key: &str) -> bool {
+    key.strip_prefix(s3_keys::CLIENTS_PREFIX)
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .is_some_and(|id| id.parse::<Uuid>().is_ok())
+}
+
+fn is_chat_history_key(key: &str) -> bool {
+    key.strip_prefix("records/")
+        .and_then(|rest| rest.split_once("/chat-history/"))
+        .is_some_and(|(id, rest)| {
+            id.parse::<Uuid>().is_ok() && rest.ends_with(".json") && !rest.contains('/')
+        })
+}
+
+/// Assessments, snippets, goals, template metadata, reports and their
+/// transactions.
+fn is_model_key(key: &str) -> bool {
+    let is_id = |id: &str| id.parse::<Uuid>().is_ok();
+    if let Some(rest) = key.strip_prefix(s3_keys::REPORTS_PREFIX) {
+        return rest
+            .strip_suffix("/report.json")
+            .or_else(|| rest.strip_suffix("/transaction.json"))
+            .is_some_and(is_id);
+    }
+    MODEL_PREFIXES.iter().any(|(prefix, _)| {
+        key.strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(".json"))
+            .is_some_and(is_id)
+    })
+}
+
+/// The filename of a record file key, `records/{id}/{filename}`.
+fn record_filename(key: &str) -> Option<&str> {
+    let (id, filename) = key.strip_prefix("records/")?.split_once('/')?;
+    (id.parse::<Uuid>().is_ok() && !filename.is_empty() && !filename.contains('/'))
+        .then_some(filename)
+}
+
+async fn read(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+) -> Result<Option<Vec<u8>>, SearchError> {
+    match objects::get_object(store, bucket, key).await {
+        Ok(output) => Ok(Some(output.body)),
+        Err(StorageError::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn exists(store: &dyn ObjectStore, bucket: &str, key: &str) -> Result<bool, SearchError> {
+    match objects::head_object(store, bucket, key).await {
+        Ok(_) => Ok(true),
+        Err(StorageError::NotFound { .. }) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,146 @@
+//! On-device sentence embeddings.
+//!
+//! Passages are embedded locally with a small BERT sentence model run by
+//! candle, so semantic search never sends record text off the machine.
+//! Anything that turns text into comparable vectors implements
+//! [`Embedder`]; [`SentenceEmbedder`] is the candle implementation.
+
+use std::path::Path;
+
+use candle_core::{Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{self, BertModel, Config};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+use tracing::info;
+
+use crate::error::SearchError;
+
+/// The Hugging Face repository of the model [`SentenceEmbedder`] is built
+/// and tested against.
+pub const DEFAULT_MODEL: &str = "sentence-transformers/all-MiniLM-L6-v2";
+
+/// Files [`SentenceEmbedder::load`] reads from the model directory.
+pub const MODEL_FILES: [&str; 3] = ["model.safetensors", "config.json", "tokenizer.json"];
+
+/// Tokens per text; the rest of a longer text is ignored. A passage of
+/// [`crate::chunk::PASSAGE_CHARS`] characters fits.
+const MAX_TOKENS: usize = 256;
+
+/// Turns text into vectors whose dot product measures similarity of
+/// meaning.
+pub trait Embedder: Send + Sync {
+    /// Identifies the model. Vectors from different models are not
+    /// comparable.
+    fn model_id(&self) -> &str;
+
+    /// Length of every vector.
+    fn dimensions(&self) -> usize;
+
+    /// One unit-length vector per text, in order.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, SearchError>;
+}
+
+/// A BERT sentence-embedding model: token embeddings are mean-pooled over
+/// the attention mask and normalized.
+pub struct SentenceEmbedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    model_id: String,
+    dimensions: usize,
+}
+
+impl SentenceEmbedder {
+    /// Load a model from `model_dir`, which must contain [`MODEL_FILES`]
+    /// (the Hugging Face layout). `model_id` names it in the vector index.
+    pub fn load(model_dir: &Path, model_id: &str) -> Result<Self, SearchError> {
+        let device = best_device();
+        let load_error = |what: &str, e: &dyn std::fmt::Display| {
+            SearchError::Embedding(format!("loading {what}: {e}"))
+        };
+
+        let config = std::fs::read_to_string(model_dir.join("config.json"))?;
+        let config: Config =
+            serde_json::from_str(&config).map_err(|e| load_error("config.json", &e))?;
+
+        let mut tokenizer = Tokenizer::from_file(model_dir.join("tokenizer.json"))
+            .map_err(|e| load_error("tokenizer.json", &e))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| load_error("tokenizer.json", &e))?;
+
+        let weights = model_dir.join("model.safetensors");
+        info!(path = %weights.display(), "loading embedding model");
+        let vb = unsafe {
+            VarBuilder::from_mmaped_safetensors(&[weights], bert::DTYPE, &device)
+                .map_err(|e| load_error("model.safetensors", &e))?
+        };
+        let model = BertModel::load(vb, &config).map_err(|e| load_error("model", &e))?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+            model_id: model_id.to_string(),
+            dimensions: config.hidden_size,
+        })
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> candle_core::Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(candle_core::Error::wrap)?;
+        let rows = |f: fn(&tokenizers::Encoding) -> &[u32]| {
+            let rows = encodings
+                .iter()
+                .map(|e| Tensor::new(f(e), &self.device))
+                .collect::<candle_core::Result<Vec<_>>>()?;
+            Tensor::stack(&rows, 0)
+        };
+        let input_ids = rows(|e| e.get_ids())?;
+        let type_ids = rows(|e| e.get_type_ids())?;
+        let mask = rows(|e| e.get_attention_mask())?;
+
+        let hidden = self.model.forward(&input_ids, &type_ids, Some(&mask))?;
+        // Mean over the real tokens, not the padding.
+        let mask = mask.to_dtype(bert::DTYPE)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let pooled = summed.broadcast_div(&mask.sum(1)?)?;
+        let norms = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+        pooled.broadcast_div(&norms)?.to_vec2()
+    }
+}
+
+impl Embedder for SentenceEmbedder {
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, SearchError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.embed_batch(texts)
+            .map_err(|e| SearchError::Embedding(e.to_string()))
+    }
+}
+
+/// Apple Metal with the `metal` feature, if available, otherwise the CPU.
+fn best_device() -> Device {
+    #[cfg(feature = "metal")]
+    {
+        if let Ok(device) = Device::new_metal(0) {
+            return device;
+        }
+    }
+    Device::Cpu
+}
@@ -8,6 +8,9 @@ pub enum SearchError {
     #[error("index corrupted: {0}")]
     IndexCorrupted(String),
 
+    #[error("index could not be decrypted: {0}")]
+    IndexUnreadable(String),
+
     #[error("tantivy error: {0}")]
     Tantivy(#[from] tantivy::TantivyError),
 
@@ -23,9 +26,15 @@ pub enum SearchError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("index schema version {found} does not match version {expected}")]
+    SchemaMismatch { found: u32, expected: u32 },
+
     #[error("ETag mismatch: index was modified by another writer")]
     ETagMismatch,
 
+    #[error("embedding failed: {0}")]
+    Embedding(String),
+
     #[error("document not found: {0}")]
     DocumentNotFound(String),
 }
@@ -1,31 +1,47 @@
 use std::path::Path;
 
-use aws_sdk_s3::Client;
 use tracing::info;
 
-use claria_core::s3_keys;
 use claria_storage::objects;
+use claria_storage::store::ObjectStore;
 
 use crate::error::SearchError;
+use crate::shard::Shard;
 
-/// Compress the index directory to a tar.zst blob and upload to S3.
+/// Compress the global index directory to a tar.zst blob and upload to S3.
+/// See [`flush_shard`].
+pub async fn flush_index(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    index_dir: &Path,
+    expected_etag: &str,
+) -> Result<String, SearchError> {
+    flush_shard(store, bucket, Shard::Global, index_dir, expected_etag).await
+}
+
+/// Compress a shard's index directory to a tar.zst blob and upload it to
+/// S3 at [`Shard::object_key`].
 ///
-/// Uses `If-Match` with the provided ETag for optimistic locking.
+/// The blob is encrypted by `store`, which must seal it with the practice
+/// master key (see [`crate::index::download_index`]). Uses `If-Match` with
+/// the provided ETag for optimistic locking.
 /// Returns the new ETag on success.
-pub async fn flush_index(
-    client: &Client,
+pub async fn flush_shard(
+    store: &dyn ObjectStore,
     bucket: &str,
+    shard: Shard,
     index_dir: &Path,
     expected_etag: &str,
 ) -> Result<String, SearchError> {
-    info!("flushing Tantivy index to s3://{}/{}", bucket, s3_keys::INDEX);
+    let object_key = shard.object_key();
+    info!("flushing Tantivy index to s3://{}/{}", bucket, object_key);
 
     let blob = compress_index_dir(index_dir)?;
 
     let new_etag = objects::put_object_if_match(
-        client,
+        store,
         bucket,
-        s3_keys::INDEX,
+        &object_key,
         blob,
         Some("application/zstd"),
         expected_etag,
@@ -42,20 +58,68 @@ pub async fn flush_index(
     Ok(new_etag)
 }
 
-/// Upload a fresh index (no ETag precondition). Used for initial index creation.
+/// Upload a shard the bucket does not have yet, through `store` as in
+/// [`flush_shard`]. Uses `If-None-Match: *`, so if another writer created
+/// the shard first this returns [`SearchError::ETagMismatch`] instead of
+/// overwriting it.
+pub async fn flush_shard_if_absent(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shard: Shard,
+    index_dir: &Path,
+) -> Result<String, SearchError> {
+    let object_key = shard.object_key();
+    info!("creating Tantivy index at s3://{}/{}", bucket, object_key);
+
+    let blob = compress_index_dir(index_dir)?;
+
+    let etag = objects::put_object_if_absent(
+        store,
+        bucket,
+        &object_key,
+        blob,
+        Some("application/zstd"),
+    )
+    .await
+    .map_err(|e| match e {
+        claria_storage::error::StorageError::PreconditionFailed { .. } => {
+            SearchError::ETagMismatch
+        }
+        other => SearchError::Storage(other),
+    })?;
+
+    info!("index created, etag={}", etag);
+    Ok(etag)
+}
+
+/// Upload a fresh global index (no ETag precondition), through `store` as
+/// in [`flush_index`]. Used for initial index creation.
 pub async fn flush_index_unconditional(
-    client: &Client,
+    store: &dyn ObjectStore,
+    bucket: &str,
+    index_dir: &Path,
+) -> Result<String, SearchError> {
+    flush_shard_unconditional(store, bucket, Shard::Global, index_dir).await
+}
+
+/// Upload a shard over whatever is stored (no precondition), through
+/// `store` as in [`flush_index`]. Used by a full rebuild, which replaces
+/// every shard; use [`flush_shard_if_absent`] to create a shard.
+pub async fn flush_shard_unconditional(
+    store: &dyn ObjectStore,
     bucket: &str,
+    shard: Shard,
     index_dir: &Path,
 ) -> Result<String, SearchError> {
-    info!("uploading initial Tantivy index to s3://{}/{}", bucket, s3_keys::INDEX);
+    let object_key = shard.object_key();
+    info!("uploading initial Tantivy index to s3://{}/{}", bucket, object_key);
 
     let blob = compress_index_dir(index_dir)?;
 
     let etag = objects::put_object(
-        client,
+        store,
         bucket,
-        s3_keys::INDEX,
+        &object_key,
         blob,
         Some("application/zstd"),
     )
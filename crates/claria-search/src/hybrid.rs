@@ -0,0 +1,93 @@
+//! Hybrid passage retrieval: BM25 and vector similarity combined.
+//!
+//! BM25 finds passages that share the query's words; vector similarity
+//! finds passages about the same thing in other words ("trouble falling
+//! asleep" for "sleep problems"). [`hybrid_search`] takes the best
+//! candidates of each from every index shard searched and ranks them
+//! together by a weighted sum of the two scores.
+
+use std::collections::HashMap;
+
+use tantivy::Index;
+
+use crate::document::passage_id;
+use crate::embed::Embedder;
+use crate::error::SearchError;
+use crate::query::{PassageHit, find_by_id, passage_hit, search_passages};
+use crate::vector::VectorIndex;
+
+/// Candidates taken from each retriever before merging.
+const CANDIDATES: usize = 50;
+
+/// Share of the combined score from BM25; vector similarity has the rest.
+pub const LEXICAL_WEIGHT: f32 = 0.4;
+
+/// Passages best matching `query_text` by words and meaning across
+/// `shards`, best first. Each hit's `score` is the combined score in
+/// `0.0..=1.0`.
+///
+/// BM25 scores are scaled by the best one among every shard's candidates,
+/// so the two are comparable and a shard's weak matches are not ranked as
+/// highly as another's strong ones; cosine similarity is used as is,
+/// clamped at zero. Each shard's vectors should be refreshed against its
+/// index with the same `embedder` first (see
+/// [`crate::vector::refresh_vectors`]).
+pub fn hybrid_search(
+    shards: &[(&Index, &VectorIndex)],
+    embedder: &dyn Embedder,
+    query_text: &str,
+    client_id: Option<&str>,
+    limit: usize,
+) -> Result<Vec<PassageHit>, SearchError> {
+    if query_text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    for (_, vectors) in shards {
+        if vectors.model_id != embedder.model_id() {
+            return Err(SearchError::Embedding(format!(
+                "vectors were computed with {}, not {}",
+                vectors.model_id,
+                embedder.model_id()
+            )));
+        }
+    }
+
+    // Passage ID -> (hit, BM25 score, cosine similarity).
+    let mut merged: HashMap<String, (PassageHit, f32, f32)> = HashMap::new();
+    let mut lexical = Vec::new();
+    for (index, _) in shards {
+        lexical.extend(search_passages(index, query_text, client_id, CANDIDATES)?);
+    }
+    let best = lexical.iter().map(|h| h.score).fold(0.0, f32::max);
+    for hit in lexical {
+        let score = if best > 0.0 { hit.score / best } else { 0.0 };
+        let id = passage_id(&hit.parent_key, hit.ordinal);
+        merged.insert(id, (hit, score, 0.0));
+    }
+
+    let query = embedder.embed(&[query_text])?.pop().unwrap_or_default();
+    for (index, vectors) in shards {
+        let schema = index.schema();
+        for (entry, similarity) in vectors.nearest(&query, client_id, CANDIDATES) {
+            let similarity = similarity.max(0.0);
+            if let Some((_, _, semantic)) = merged.get_mut(&entry.passage_id) {
+                *semantic = similarity;
+            } else if let Some(doc) = find_by_id(index, &entry.passage_id)? {
+                // A vector whose passage has since been removed is skipped.
+                let hit = passage_hit(&schema, &doc, 0.0);
+                merged.insert(entry.passage_id.clone(), (hit, 0.0, similarity));
+            }
+        }
+    }
+
+    let mut hits: Vec<PassageHit> = merged
+        .into_values()
+        .map(|(mut hit, lexical, semantic)| {
+            hit.score = LEXICAL_WEIGHT * lexical + (1.0 - LEXICAL_WEIGHT) * semantic;
+            hit
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
@@ -1,14 +1,21 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use aws_sdk_s3::Client;
 use tantivy::Index;
-use tracing::info;
+use tracing::{debug, info, warn};
 
-use claria_core::s3_keys;
-use claria_core::schema::build_schema;
-use claria_storage::objects;
+use claria_core::schema::{SCHEMA_VERSION, build_schema};
+use claria_storage::crypto;
+use claria_storage::error::StorageError;
+use claria_storage::objects::{self, GetObjectOutput};
+use claria_storage::store::ObjectStore;
+
+use claria_core::models::synonyms::SynonymDictionary;
 
 use crate::error::SearchError;
+use crate::rebuild::{create_missing_client_shards, rebuild_shard};
+use crate::shard::Shard;
+use crate::synonyms::{built_in, read_synonyms, write_synonyms};
 
 /// A loaded Tantivy index with its S3 ETag for optimistic locking.
 pub struct LoadedIndex {
@@ -17,28 +24,251 @@ pub struct LoadedIndex {
     pub etag: String,
 }
 
-/// Download the Tantivy index from S3 and open it.
-///
-/// The index is stored as `_index/tantivy.tar.zst` in the bucket.
-/// It is downloaded, decompressed, and extracted to `dest_dir`.
+/// File in the index directory holding the [`SCHEMA_VERSION`] the index was
+/// built with. It is kept out of Tantivy's managed files, so it travels in
+/// the tar.zst blob without being garbage-collected.
+pub const SCHEMA_VERSION_FILE: &str = "claria-schema-version";
+
+/// The version assumed for indexes uploaded before versions were recorded,
+/// which were built with the first schema.
+const UNVERSIONED_SCHEMA: u32 = 1;
+
+/// The first schema with per-client shards. A global index older than this
+/// was the only blob, holding every client's records too.
+const FIRST_SHARDED_SCHEMA: u32 = 6;
+
+/// Download the global index from S3 and open it. See [`download_shard`].
 pub async fn download_index(
-    client: &Client,
+    store: &dyn ObjectStore,
+    bucket: &str,
+    dest_dir: &Path,
+) -> Result<LoadedIndex, SearchError> {
+    download_shard(store, bucket, Shard::Global, dest_dir).await
+}
+
+/// Download one index shard from S3 and open it.
+///
+/// The shard is stored at [`Shard::object_key`] in the bucket. It is
+/// downloaded and decrypted through `store`, which must hold the practice
+/// master key (see [`crate::flush::flush_index`]), then decompressed and
+/// extracted to `dest_dir`.
+///
+/// A shard that `store` cannot decrypt, or that is still sealed after it
+/// has, is reported as [`SearchError::IndexUnreadable`] and left as it is:
+/// it belongs to whoever holds that key, and rebuilding it under this one
+/// would lock them out.
+///
+/// A shard built with an older [`SCHEMA_VERSION`] is rebuilt on its own
+/// from the objects it indexes and the rebuilt shard is returned. The
+/// rebuild only replaces the version that was downloaded (see
+/// [`rebuild_shard`]); if a writer still on the older schema flushed it
+/// meanwhile, it is reported as [`SearchError::SchemaMismatch`]. A global
+/// index from before shards also has the client shards it stood in for
+/// created (see [`create_missing_client_shards`]). A shard
+/// from a newer version of Claria is left alone and reported the same way.
+pub async fn download_shard(
+    store: &dyn ObjectStore,
     bucket: &str,
+    shard: Shard,
     dest_dir: &Path,
 ) -> Result<LoadedIndex, SearchError> {
-    info!("downloading Tantivy index from s3://{}/{}", bucket, s3_keys::INDEX);
+    let loaded = fetch_index(store, bucket, shard, dest_dir).await?;
+    current_schema(store, bucket, shard, dest_dir, loaded).await
+}
+
+/// Return `loaded`, extracted to `dest_dir`, if it was built with the
+/// current [`SCHEMA_VERSION`], or rebuild it if it is older. See
+/// [`download_shard`].
+async fn current_schema(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shard: Shard,
+    dest_dir: &Path,
+    loaded: LoadedIndex,
+) -> Result<LoadedIndex, SearchError> {
+    let found = schema_version(dest_dir)?;
+    if found == SCHEMA_VERSION {
+        return Ok(loaded);
+    }
+    if found > SCHEMA_VERSION {
+        return Err(SearchError::SchemaMismatch {
+            found,
+            expected: SCHEMA_VERSION,
+        });
+    }
+    warn!(
+        %shard,
+        found,
+        expected = SCHEMA_VERSION,
+        "index schema is out of date, rebuilding"
+    );
+    clear_dir(dest_dir)?;
+    rebuild_shard(store, bucket, shard, &loaded.etag).await?;
+    if shard == Shard::Global && found < FIRST_SHARDED_SCHEMA {
+        create_missing_client_shards(store, bucket).await?;
+    }
+
+    let loaded = fetch_index(store, bucket, shard, dest_dir).await?;
+    let found = schema_version(dest_dir)?;
+    if found != SCHEMA_VERSION {
+        return Err(SearchError::SchemaMismatch {
+            found,
+            expected: SCHEMA_VERSION,
+        });
+    }
+    Ok(loaded)
+}
+
+/// Download each of `shards` into its own directory under `dest_dir`, in
+/// order. Shards the bucket does not have, such as that of a client with no
+/// records yet, are left out.
+pub async fn download_shards(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shards: &[Shard],
+    dest_dir: &Path,
+) -> Result<Vec<(Shard, LoadedIndex)>, SearchError> {
+    let mut loaded = Vec::with_capacity(shards.len());
+    for &shard in shards {
+        let dir = dest_dir.join(shard.to_string());
+        std::fs::create_dir_all(&dir)?;
+        match download_shard(store, bucket, shard, &dir).await {
+            Ok(index) => loaded.push((shard, index)),
+            Err(SearchError::IndexNotFound) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(loaded)
+}
+
+/// Index shards kept extracted between searches, in a temporary directory
+/// removed when the cache is dropped.
+///
+/// Before a shard is used again it is revalidated by its ETag, so a search
+/// across every client only downloads the shards that changed since the
+/// last one. Shards are opened in place, which is why the cache must not be
+/// shared by two searches at once.
+pub struct ShardCache {
+    dir: tempfile::TempDir,
+    /// (Bucket, shard) -> ETag of the copy extracted for it.
+    etags: HashMap<(String, Shard), String>,
+}
+
+impl ShardCache {
+    pub fn new() -> Result<Self, SearchError> {
+        Ok(Self {
+            dir: tempfile::tempdir()?,
+            etags: HashMap::new(),
+        })
+    }
+
+    /// Like [`download_shards`], but reusing the copy of each shard kept
+    /// from an earlier call if it has not changed in the bucket since.
+    pub async fn download_shards(
+        &mut self,
+        store: &dyn ObjectStore,
+        bucket: &str,
+        shards: &[Shard],
+    ) -> Result<Vec<(Shard, LoadedIndex)>, SearchError> {
+        let mut loaded = Vec::with_capacity(shards.len());
+        for &shard in shards {
+            match self.download_shard(store, bucket, shard).await {
+                Ok(index) => loaded.push((shard, index)),
+                Err(SearchError::IndexNotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Record that the copy of `shard` was flushed as `etag`, so the next
+    /// search does not download what it already has.
+    pub fn flushed(&mut self, bucket: &str, shard: Shard, etag: String) {
+        self.etags.insert((bucket.to_string(), shard), etag);
+    }
+
+    async fn download_shard(
+        &mut self,
+        store: &dyn ObjectStore,
+        bucket: &str,
+        shard: Shard,
+    ) -> Result<LoadedIndex, SearchError> {
+        let dir = self.dir.path().join(bucket).join(shard.to_string());
+        // Forgotten until the copy is usable again, so a failure part way
+        // through is never mistaken for an up-to-date copy.
+        let cached = self.etags.remove(&(bucket.to_string(), shard));
+        let loaded = match cached {
+            Some(etag) => {
+                let object_key = shard.object_key();
+                let fresh = objects::get_object_if_none_match(store, bucket, &object_key, &etag)
+                    .await
+                    .map_err(get_error)?;
+                let loaded = match fresh {
+                    None => {
+                        debug!(%shard, etag, "index unchanged, reusing extracted copy");
+                        open_extracted(&dir, etag)?
+                    }
+                    Some(output) => {
+                        clear_dir(&dir)?;
+                        unpack(output, &dir)?
+                    }
+                };
+                current_schema(store, bucket, shard, &dir, loaded).await?
+            }
+            None => {
+                std::fs::create_dir_all(&dir)?;
+                clear_dir(&dir)?;
+                download_shard(store, bucket, shard, &dir).await?
+            }
+        };
+        self.etags
+            .insert((bucket.to_string(), shard), loaded.etag.clone());
+        Ok(loaded)
+    }
+}
 
-    let output = objects::get_object(client, bucket, s3_keys::INDEX)
+/// Download and open a shard without checking its schema version.
+async fn fetch_index(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shard: Shard,
+    dest_dir: &Path,
+) -> Result<LoadedIndex, SearchError> {
+    let object_key = shard.object_key();
+    info!("downloading Tantivy index from s3://{}/{}", bucket, object_key);
+    let output = objects::get_object(store, bucket, &object_key)
         .await
-        .map_err(|e| match e {
-            claria_storage::error::StorageError::NotFound { .. } => SearchError::IndexNotFound,
-            other => SearchError::Storage(other),
-        })?;
+        .map_err(get_error)?;
+    unpack(output, dest_dir)
+}
+
+/// Map a failed shard download to the [`SearchError`] it is reported as.
+fn get_error(e: StorageError) -> SearchError {
+    match e {
+        StorageError::NotFound { .. } => SearchError::IndexNotFound,
+        StorageError::Decryption(reason) => SearchError::IndexUnreadable(reason),
+        other => SearchError::Storage(other),
+    }
+}
 
+/// Extract a downloaded shard to `dest_dir` and open it.
+fn unpack(
+    output: GetObjectOutput,
+    dest_dir: &Path,
+) -> Result<LoadedIndex, SearchError> {
     let etag = output.etag.unwrap_or_default();
 
+    // `store` has decrypted the blob; one still sealed was read without the
+    // practice key or sealed twice, and is not fed to the decompressor
+    if crypto::is_encrypted(&output.body) {
+        return Err(SearchError::IndexUnreadable(
+            "index is still sealed after download".to_string(),
+        ));
+    }
+    let blob = output.body;
+
     // Decompress zstd
-    let decoder = zstd::Decoder::new(output.body.as_slice())?;
+    let decoder = zstd::Decoder::new(blob.as_slice())?;
 
     // Extract tar archive
     let mut archive = tar::Archive::new(decoder);
@@ -46,8 +276,14 @@ pub async fn download_index(
 
     info!("index extracted to {:?}, etag={}", dest_dir, etag);
 
+    open_extracted(dest_dir, etag)
+}
+
+/// Open a shard already extracted to `dest_dir`.
+fn open_extracted(dest_dir: &Path, etag: String) -> Result<LoadedIndex, SearchError> {
     let index = Index::open_in_dir(dest_dir)
         .map_err(|e| SearchError::IndexCorrupted(e.to_string()))?;
+    read_synonyms(&index, dest_dir)?;
 
     Ok(LoadedIndex {
         index,
@@ -56,9 +292,49 @@ pub async fn download_index(
     })
 }
 
-/// Create a new empty Tantivy index in the given directory.
+/// Create a new empty Tantivy index in the given directory, recording the
+/// current [`SCHEMA_VERSION`] alongside it. Text is analyzed with the
+/// built-in synonyms only.
 pub fn create_empty_index(dest_dir: &Path) -> Result<Index, SearchError> {
+    create_empty_index_with(dest_dir, &built_in())
+}
+
+/// Create a new empty Tantivy index whose text is analyzed with `synonyms`,
+/// recording them and the current [`SCHEMA_VERSION`] alongside it.
+pub fn create_empty_index_with(
+    dest_dir: &Path,
+    synonyms: &SynonymDictionary,
+) -> Result<Index, SearchError> {
     let schema = build_schema();
     let index = Index::create_in_dir(dest_dir, schema)?;
+    std::fs::write(
+        dest_dir.join(SCHEMA_VERSION_FILE),
+        SCHEMA_VERSION.to_string(),
+    )?;
+    write_synonyms(&index, dest_dir, synonyms)?;
     Ok(index)
 }
+
+/// The schema version recorded in an extracted index directory.
+fn schema_version(index_dir: &Path) -> Result<u32, SearchError> {
+    match std::fs::read_to_string(index_dir.join(SCHEMA_VERSION_FILE)) {
+        Ok(version) => version.trim().parse().map_err(|_| {
+            SearchError::IndexCorrupted(format!("invalid schema version {version:?}"))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(UNVERSIONED_SCHEMA),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Remove everything inside `dir`, keeping `dir` itself.
+fn clear_dir(dir: &Path) -> Result<(), SearchError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)?;
+        } else {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+//! Mutation journal for flushing through ETag conflicts.
+//!
+//! The index is one blob in S3, so two devices indexing at the same time
+//! race to flush it: the loser gets [`SearchError::ETagMismatch`]. Changes
+//! are therefore recorded in a [`Journal`] rather than applied straight to
+//! a downloaded index. [`commit_journal`] applies the journal to the latest
+//! index and flushes it; on a mismatch it downloads the index the other
+//! writer flushed, replays the journal onto it and tries again, so neither
+//! device's documents are lost.
+//!
+//! Each mutation belongs to one index shard (see [`crate::shard`]), and
+//! each shard touched is committed under its own ETag.
+
+use std::collections::BTreeMap;
+
+use tantivy::Index;
+use tracing::{info, warn};
+
+use claria_storage::store::ObjectStore;
+
+use crate::document::IndexDocument;
+use crate::error::SearchError;
+use crate::flush::{flush_shard, flush_shard_if_absent};
+use crate::index::download_shard;
+use crate::mutate::{
+    WRITER_HEAP_BYTES, commit, delete_document, delete_documents_by_key_prefix, delete_passages,
+    insert_document, update_document,
+};
+use crate::rebuild::build_from_bucket;
+use crate::shard::{Shard, list_client_shards};
+
+/// How many times [`commit_journal`] downloads, replays and flushes before
+/// giving up with [`SearchError::ETagMismatch`].
+pub const MAX_FLUSH_ATTEMPTS: usize = 5;
+
+/// One pending change to the index.
+#[derive(Debug, Clone)]
+pub enum Mutation {
+    /// Add a document that is not in the index yet.
+    Insert(IndexDocument),
+    /// Replace the document with the same ID and its passages, or add it.
+    Update(IndexDocument),
+    /// Remove the document with this ID, and its passages.
+    Delete(String),
+    /// Remove every document whose S3 key starts with this prefix.
+    DeleteKeyPrefix(String),
+}
+
+impl Mutation {
+    /// The shard this mutation changes, or `None` for a key prefix that
+    /// can match documents in any shard.
+    pub fn shard(&self) -> Option<Shard> {
+        match self {
+            Mutation::Insert(document) | Mutation::Update(document) => {
+                Some(Shard::for_key(&document.id))
+            }
+            Mutation::Delete(id) => Some(Shard::for_key(id)),
+            Mutation::DeleteKeyPrefix(prefix) => Shard::for_prefix(prefix),
+        }
+    }
+}
+
+/// Pending changes, in the order they were made.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    mutations: Vec<Mutation>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, document: IndexDocument) {
+        self.mutations.push(Mutation::Insert(document));
+    }
+
+    pub fn update(&mut self, document: IndexDocument) {
+        self.mutations.push(Mutation::Update(document));
+    }
+
+    pub fn delete(&mut self, id: impl Into<String>) {
+        self.mutations.push(Mutation::Delete(id.into()));
+    }
+
+    pub fn delete_key_prefix(&mut self, prefix: impl Into<String>) {
+        self.mutations
+            .push(Mutation::DeleteKeyPrefix(prefix.into()));
+    }
+
+    pub fn mutations(&self) -> &[Mutation] {
+        &self.mutations
+    }
+
+    pub fn len(&self) -> usize {
+        self.mutations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mutations.is_empty()
+    }
+
+    /// Apply every mutation to `index` and commit. Documents are converted
+    /// against `index`, so an update keeps the `created_at` of whatever
+    /// version of the document that index holds.
+    ///
+    /// Returns the number of documents removed by key prefix.
+    pub fn apply(&self, index: &Index) -> Result<usize, SearchError> {
+        let mut writer = index.writer(WRITER_HEAP_BYTES)?;
+        let mut removed = 0;
+        for mutation in &self.mutations {
+            match mutation {
+                Mutation::Insert(document) => {
+                    for tantivy_doc in document.clone().into_tantivy(index) {
+                        insert_document(&writer, tantivy_doc)?;
+                    }
+                }
+                Mutation::Update(document) => {
+                    // The file's old passages go, whatever the new text splits into.
+                    delete_passages(index, &writer, &document.id)?;
+                    let mut tantivy_docs = document.clone().into_tantivy(index).into_iter();
+                    if let Some(first) = tantivy_docs.next() {
+                        update_document(index, &writer, &document.id, first)?;
+                    }
+                    for passage in tantivy_docs {
+                        insert_document(&writer, passage)?;
+                    }
+                }
+                Mutation::Delete(id) => {
+                    delete_document(index, &writer, id)?;
+                    delete_passages(index, &writer, id)?;
+                }
+                Mutation::DeleteKeyPrefix(prefix) => {
+                    removed += delete_documents_by_key_prefix(index, &writer, &[prefix.as_str()])?;
+                }
+            }
+        }
+        commit(&mut writer)?;
+        writer.wait_merging_threads()?;
+        Ok(removed)
+    }
+
+    /// Whether applying the journal can change an index that had nothing
+    /// to remove by key prefix.
+    fn changes_beyond_prefixes(&self) -> bool {
+        self.mutations
+            .iter()
+            .any(|m| !matches!(m, Mutation::DeleteKeyPrefix(_)))
+    }
+}
+
+/// The outcome of [`commit_journal`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Committed {
+    /// The new ETag of each shard that was flushed. Shards the journal
+    /// changed nothing in are left out.
+    pub etags: BTreeMap<Shard, String>,
+    /// The most flushes attempted for any one shard, including the one that
+    /// succeeded.
+    pub attempts: usize,
+    /// Documents removed by key prefix on the final attempt for each shard.
+    pub removed: usize,
+}
+
+/// Apply `journal` to the bucket's index and flush it with ETag locking,
+/// replaying onto the latest index on a mismatch.
+///
+/// The journal is split by [`Mutation::shard`] and each shard is committed
+/// in turn; a key prefix that spans shards is removed from all of them. A
+/// shard the bucket does not have yet is first built from the objects it
+/// indexes (see [`crate::rebuild::rebuild_shard`]), then the journal is
+/// applied and the shard created with `If-None-Match: *`, so if another
+/// writer creates it first the journal is replayed onto theirs like any
+/// other mismatch. After [`MAX_FLUSH_ATTEMPTS`] mismatches
+/// on one shard, returns [`SearchError::ETagMismatch`]; shards committed
+/// before it stay committed.
+pub async fn commit_journal(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    journal: &Journal,
+) -> Result<Committed, SearchError> {
+    let mut shards: BTreeMap<Shard, Journal> = BTreeMap::new();
+    if journal.mutations.iter().any(|m| m.shard().is_none()) {
+        shards.insert(Shard::Global, Journal::new());
+        for id in list_client_shards(store, bucket).await? {
+            shards.insert(Shard::Client(id), Journal::new());
+        }
+    }
+    for mutation in &journal.mutations {
+        match mutation.shard() {
+            Some(shard) => shards
+                .entry(shard)
+                .or_default()
+                .mutations
+                .push(mutation.clone()),
+            None => {
+                for shard_journal in shards.values_mut() {
+                    shard_journal.mutations.push(mutation.clone());
+                }
+            }
+        }
+    }
+
+    let mut committed = Committed::default();
+    for (shard, shard_journal) in &shards {
+        let (etag, attempts, removed) = commit_shard(store, bucket, *shard, shard_journal).await?;
+        if let Some(etag) = etag {
+            committed.etags.insert(*shard, etag);
+        }
+        committed.attempts = committed.attempts.max(attempts);
+        committed.removed += removed;
+    }
+    Ok(committed)
+}
+
+/// Commit the mutations for one shard. Returns the new ETag, or `None` if
+/// nothing changed, with the flushes attempted and documents removed by key
+/// prefix.
+async fn commit_shard(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shard: Shard,
+    journal: &Journal,
+) -> Result<(Option<String>, usize, usize), SearchError> {
+    for attempt in 1..=MAX_FLUSH_ATTEMPTS {
+        let dir = tempfile::tempdir()?;
+        let mut built = 0;
+        let (index, etag) = match download_shard(store, bucket, shard, dir.path()).await {
+            Ok(loaded) => (loaded.index, Some(loaded.etag)),
+            Err(SearchError::IndexNotFound) => {
+                // Built from what the bucket already holds, so a shard that
+                // is missing, such as a client's after an upgrade from a
+                // single index, does not start out with only this journal.
+                let (index, summary) = build_from_bucket(store, bucket, shard, dir.path()).await?;
+                built = summary.documents;
+                (index, None)
+            }
+            Err(e) => return Err(e),
+        };
+
+        let removed = journal.apply(&index)?;
+        if removed == 0 && built == 0 && !journal.changes_beyond_prefixes() {
+            return Ok((None, attempt, removed));
+        }
+
+        let flushed = match etag {
+            Some(etag) => flush_shard(store, bucket, shard, dir.path(), &etag).await,
+            None => flush_shard_if_absent(store, bucket, shard, dir.path()).await,
+        };
+        match flushed {
+            Ok(etag) => {
+                info!(
+                    %shard,
+                    mutations = journal.len(),
+                    attempts = attempt,
+                    "journal committed"
+                );
+                return Ok((Some(etag), attempt, removed));
+            }
+            Err(SearchError::ETagMismatch) if attempt < MAX_FLUSH_ATTEMPTS => {
+                warn!(%shard, attempt, "index changed during flush, replaying journal");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(SearchError::ETagMismatch)
+}
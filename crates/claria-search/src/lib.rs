@@ -2,8 +2,19 @@
 //!
 //! Tantivy index lifecycle: download from S3, query, mutate, flush back with ETag locking.
 
+pub mod chunk;
+pub mod document;
+pub mod embed;
 pub mod error;
 pub mod flush;
+pub mod hybrid;
 pub mod index;
+pub mod journal;
 pub mod mutate;
+pub mod purge;
 pub mod query;
+pub mod rebuild;
+pub mod shard;
+pub mod sync;
+pub mod synonyms;
+pub mod vector;
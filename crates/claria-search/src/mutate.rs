@@ -1,9 +1,15 @@
+use tantivy::collector::DocSetCollector;
+use tantivy::query::AllQuery;
+use tantivy::schema::Value;
 use tantivy::{Index, IndexWriter, Term};
 
-use claria_core::schema::{field, get_field};
+use claria_core::schema::{doc_type, field, get_field};
 
 use crate::error::SearchError;
 
+/// Memory budget for the index writers this crate opens.
+pub const WRITER_HEAP_BYTES: usize = 50_000_000;
+
 /// Insert a new document into the index.
 ///
 /// The caller provides field values as a `TantivyDocument`. The `id` field
@@ -43,6 +49,57 @@ pub fn delete_document(index: &Index, writer: &IndexWriter, id: &str) -> Result<
     Ok(())
 }
 
+/// Delete every passage taken from the document `parent_key`.
+pub fn delete_passages(
+    index: &Index,
+    writer: &IndexWriter,
+    parent_key: &str,
+) -> Result<(), SearchError> {
+    let schema = index.schema();
+    let parent_field = get_field(&schema, field::PARENT_KEY);
+    writer.delete_term(Term::from_field_text(parent_field, parent_key));
+    Ok(())
+}
+
+/// Delete every document whose S3 key starts with one of `prefixes`.
+///
+/// `s3_key` is stored but not indexed, so this scans every document.
+/// Returns the number of documents deleted, not counting passages.
+pub fn delete_documents_by_key_prefix(
+    index: &Index,
+    writer: &IndexWriter,
+    prefixes: &[&str],
+) -> Result<usize, SearchError> {
+    let reader = index.reader()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let id_field = get_field(&schema, field::ID);
+    let s3_key_field = get_field(&schema, field::S3_KEY);
+    let doc_type_field = get_field(&schema, field::DOC_TYPE);
+
+    let mut deleted = 0;
+    for address in searcher.search(&AllQuery, &DocSetCollector)? {
+        let doc = searcher.doc::<tantivy::TantivyDocument>(address)?;
+        let matches = doc
+            .get_first(s3_key_field)
+            .and_then(|v| v.as_str())
+            .is_some_and(|key| prefixes.iter().any(|p| key.starts_with(p)));
+        if let Some(id) = doc.get_first(id_field).and_then(|v| v.as_str())
+            && matches
+        {
+            writer.delete_term(Term::from_field_text(id_field, id));
+            let is_passage = doc
+                .get_first(doc_type_field)
+                .and_then(|v| v.as_str())
+                .is_some_and(|t| t == doc_type::PASSAGE);
+            if !is_passage {
+                deleted += 1;
+            }
+        }
+    }
+    Ok(deleted)
+}
+
 /// Commit all pending changes to the index.
 pub fn commit(writer: &mut IndexWriter) -> Result<(), SearchError> {
     writer.commit()?;
@@ -0,0 +1,29 @@
+use tracing::info;
+
+use claria_storage::store::ObjectStore;
+
+use crate::error::SearchError;
+use crate::journal::{Journal, commit_journal};
+
+/// Remove every indexed document whose S3 key starts with one of `prefixes`
+/// and flush the index back with ETag locking, replaying the removal if
+/// another writer flushed first.
+///
+/// A bucket without an index has nothing to remove and returns 0. Returns
+/// the number of documents removed.
+pub async fn purge_key_prefixes(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    prefixes: &[&str],
+) -> Result<usize, SearchError> {
+    let mut journal = Journal::new();
+    for prefix in prefixes {
+        journal.delete_key_prefix(*prefix);
+    }
+    let deleted = commit_journal(store, bucket, &journal).await?.removed;
+    if deleted == 0 {
+        return Ok(0);
+    }
+    info!(deleted, "purged documents from index");
+    Ok(deleted)
+}
@@ -1,80 +1,379 @@
-use tantivy::collector::TopDocs;
-use tantivy::query::{QueryParser, TermQuery};
-use tantivy::schema::{IndexRecordOption, Value};
-use tantivy::{Index, Term};
+use std::ops::{Bound, Range};
 
-use claria_core::schema::{field, get_field};
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::{
+    BooleanQuery, ConstScoreQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery,
+    TermQuery,
+};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{DocAddress, Index, Order, TantivyDocument, Term};
+
+use claria_core::schema::{CLINICAL_TOKENIZER, doc_type, field, get_field};
 
 use crate::error::SearchError;
 
+/// Longest snippet fragment [`search_with`] returns, in characters.
+const SNIPPET_CHARS: usize = 200;
+
+/// Most passages [`search_with`] returns for each record file hit.
+pub const PASSAGES_PER_FILE: usize = 3;
+
+/// Shortest query word, in characters, matched against client names with
+/// one typo, and with two.
+const FUZZY_ONE_EDIT_CHARS: usize = 4;
+const FUZZY_TWO_EDIT_CHARS: usize = 8;
+
 /// A retrieved document from the index.
 pub struct SearchResult {
     pub id: String,
     pub doc_type: String,
+    pub client_id: Option<String>,
+    pub title: String,
+    pub s3_key: String,
+    /// Unix seconds.
+    pub updated_at: i64,
+    /// Relevance to the text query; 0 when sorted by [`SortOrder::Recency`].
+    pub score: f32,
+    /// Where the text query matched the body, if it did.
+    pub snippet: Option<Snippet>,
+    /// For a record file matched by text, its best passages, best first.
+    pub passages: Vec<PassageMatch>,
+}
+
+/// A passage of a record file that matched the text query. See
+/// [`crate::chunk`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassageMatch {
+    pub ordinal: usize,
+    /// Character offsets of the passage in the file's text, `end` exclusive.
+    pub start: usize,
+    pub end: usize,
+    pub score: f32,
+    pub snippet: Option<Snippet>,
+}
+
+/// A passage found by [`search_passages`], with its full text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassageHit {
+    /// The ID of the record file the passage belongs to.
+    pub parent_key: String,
+    pub client_id: Option<String>,
+    /// The record file's title and key.
     pub title: String,
     pub s3_key: String,
+    pub ordinal: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
     pub score: f32,
 }
 
+/// A fragment of a document's body around the terms that matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub fragment: String,
+    /// Byte ranges of the matched terms within `fragment`.
+    pub highlights: Vec<Range<usize>>,
+}
+
+/// How [`search_with`] orders its results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Best text match first.
+    #[default]
+    Score,
+    /// Most recently updated first.
+    Recency,
+}
+
+/// A range of Unix seconds: `from` inclusive, `to` exclusive. An open end
+/// is unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRange {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+/// A structured query for [`search_with`]: free text combined with exact
+/// filters. Unset filters match everything.
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    /// Parsed with Tantivy's query syntax against title and body. `None`
+    /// or blank matches every document.
+    pub text: Option<String>,
+    pub client_id: Option<String>,
+    /// Any of these document types; empty for every type in
+    /// [`doc_type::ALL`], which leaves out passages.
+    pub doc_types: Vec<String>,
+    pub anonymized: Option<bool>,
+    pub status: Option<String>,
+    pub model_id: Option<String>,
+    pub created_at: TimeRange,
+    pub updated_at: TimeRange,
+    pub sort: SortOrder,
+    /// Matches to skip, for paging through results.
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// The result of [`search_with`].
+pub struct SearchResults {
+    pub hits: Vec<SearchResult>,
+    /// Documents matching the query, beyond the page returned.
+    pub total: usize,
+    /// The `offset` of the next page, or `None` on the last page.
+    pub next_offset: Option<usize>,
+    /// Matching documents per type, ignoring the `doc_types` filter so
+    /// every type can be offered as a refinement. Types with no matches are
+    /// left out.
+    pub doc_type_counts: Vec<(String, usize)>,
+}
+
 /// Full-text search across title and body fields.
 pub fn search(
     index: &Index,
     query_text: &str,
     limit: usize,
 ) -> Result<Vec<SearchResult>, SearchError> {
+    // A blank query matches nothing here, where search_with would match all.
+    if query_text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let query = SearchQuery {
+        text: Some(query_text.to_string()),
+        limit,
+        ..Default::default()
+    };
+    Ok(search_with(index, &query)?.hits)
+}
+
+/// Run a structured query, with per-type facet counts.
+pub fn search_with(index: &Index, query: &SearchQuery) -> Result<SearchResults, SearchError> {
     let reader = index.reader()?;
     let searcher = reader.searcher();
     let schema = index.schema();
 
-    let title_field = get_field(&schema, field::TITLE);
+    // Everything but the doc_type filter, shared by the hits and the facets.
+    let mut clauses: Vec<Box<dyn Query>> = Vec::new();
     let body_field = get_field(&schema, field::BODY);
+    let mut snippets = None;
+    let mut text_query = None;
+    if let Some(text) = query.text.as_deref().filter(|t| !t.trim().is_empty()) {
+        let title_field = get_field(&schema, field::TITLE);
+        let parsed = parse_text(index, vec![title_field, body_field], text)?;
+        let mut generator = SnippetGenerator::create(&searcher, &*parsed, body_field)?;
+        generator.set_max_num_chars(SNIPPET_CHARS);
+        snippets = Some(generator);
+        text_query = Some(parsed.box_clone());
+        match fuzzy_client_names(index, text) {
+            Some(fuzzy) => clauses.push(Box::new(BooleanQuery::new(vec![
+                (Occur::Should, parsed),
+                (Occur::Should, fuzzy),
+            ]))),
+            None => clauses.push(parsed),
+        }
+    }
+    let exact = [
+        (field::CLIENT_ID, query.client_id.as_deref()),
+        (
+            field::ANONYMIZED,
+            query.anonymized.map(|a| if a { "true" } else { "false" }),
+        ),
+        (field::STATUS, query.status.as_deref()),
+        (field::MODEL_ID, query.model_id.as_deref()),
+    ];
+    for (name, value) in exact {
+        if let Some(value) = value {
+            clauses.push(Box::new(term_query(&schema, name, value)));
+        }
+    }
+    for (name, range) in [
+        (field::CREATED_AT, query.created_at),
+        (field::UPDATED_AT, query.updated_at),
+    ] {
+        if range != TimeRange::default() {
+            let field = get_field(&schema, name);
+            let bound = |value: Option<i64>, included: bool| match value {
+                Some(v) if included => Bound::Included(Term::from_field_i64(field, v)),
+                Some(v) => Bound::Excluded(Term::from_field_i64(field, v)),
+                None => Bound::Unbounded,
+            };
+            clauses.push(Box::new(RangeQuery::new(
+                bound(range.from, true),
+                bound(range.to, false),
+            )));
+        }
+    }
 
-    let query_parser = QueryParser::for_index(index, vec![title_field, body_field]);
-    let query = query_parser
-        .parse_query(query_text)
-        .map_err(|e| SearchError::QueryParse(e.to_string()))?;
+    let with_types = |types: &[&str]| -> Box<dyn Query> {
+        let types = if types.is_empty() {
+            &doc_type::ALL[..]
+        } else {
+            types
+        };
+        let mut all: Vec<Box<dyn Query>> = clauses.iter().map(|c| c.box_clone()).collect();
+        let any_type = types
+            .iter()
+            .map(|t| {
+                let q: Box<dyn Query> = Box::new(term_query(&schema, field::DOC_TYPE, t));
+                (Occur::Should, q)
+            })
+            .collect();
+        // A filter, so it must not add to the text score.
+        all.push(Box::new(ConstScoreQuery::new(
+            Box::new(BooleanQuery::new(any_type)),
+            0.0,
+        )));
+        Box::new(BooleanQuery::intersection(all))
+    };
 
-    let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+    let mut doc_type_counts = Vec::new();
+    for doc_type in doc_type::ALL {
+        let count = searcher.search(&*with_types(&[doc_type]), &Count)?;
+        if count > 0 {
+            doc_type_counts.push((doc_type.to_string(), count));
+        }
+    }
 
-    let id_field = get_field(&schema, field::ID);
-    let doc_type_field = get_field(&schema, field::DOC_TYPE);
-    let s3_key_field = get_field(&schema, field::S3_KEY);
+    let types: Vec<&str> = query.doc_types.iter().map(String::as_str).collect();
+    let filtered = with_types(&types);
+    let total = searcher.search(&*filtered, &Count)?;
+    let limit = query.limit.max(1);
+    let page = TopDocs::with_limit(limit).and_offset(query.offset);
+    let addresses: Vec<(f32, DocAddress)> = match query.sort {
+        SortOrder::Score => searcher.search(&*filtered, &page)?,
+        SortOrder::Recency => {
+            let collector = page.order_by_fast_field::<i64>(field::UPDATED_AT, Order::Desc);
+            searcher
+                .search(&*filtered, &collector)?
+                .into_iter()
+                .map(|(_, address)| (0.0, address))
+                .collect()
+        }
+    };
 
-    let mut results = Vec::new();
-    for (score, doc_address) in top_docs {
-        let doc = searcher.doc::<tantivy::TantivyDocument>(doc_address)?;
+    let mut hits = Vec::with_capacity(addresses.len());
+    for (score, address) in addresses {
+        let doc = searcher.doc::<TantivyDocument>(address)?;
+        let mut result = to_result(&schema, &doc, score);
+        if let Some(generator) = &snippets {
+            result.snippet = snippet(generator, &doc);
+        }
+        if let (Some(text_query), Some(generator)) = (&text_query, &snippets)
+            && result.doc_type == doc_type::RECORD_FILE
+        {
+            let in_file = BooleanQuery::intersection(vec![
+                text_query.box_clone(),
+                Box::new(term_query(&schema, field::PARENT_KEY, &result.id)),
+                Box::new(term_query(&schema, field::DOC_TYPE, doc_type::PASSAGE)),
+            ]);
+            for (score, address) in
+                searcher.search(&in_file, &TopDocs::with_limit(PASSAGES_PER_FILE))?
+            {
+                let doc = searcher.doc::<TantivyDocument>(address)?;
+                let (ordinal, start, end) = passage_position(&schema, &doc);
+                result.passages.push(PassageMatch {
+                    ordinal,
+                    start,
+                    end,
+                    score,
+                    snippet: snippet(generator, &doc),
+                });
+            }
+        }
+        hits.push(result);
+    }
+    let next_offset = Some(query.offset + hits.len()).filter(|&next| next < total);
+    Ok(SearchResults {
+        hits,
+        total,
+        next_offset,
+        doc_type_counts,
+    })
+}
 
-        let id = doc
-            .get_first(id_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
-        let doc_type = doc
-            .get_first(doc_type_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
-        let title = doc
-            .get_first(title_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
-        let s3_key = doc
-            .get_first(s3_key_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
+/// Run a structured query against several index shards (see
+/// [`crate::shard`]) and merge the results as if they came from one index.
+///
+/// Each shard is scored on its own term statistics, so scores from
+/// different shards are close to, but not exactly, what one index would
+/// give.
+pub fn search_across(
+    indexes: &[&Index],
+    query: &SearchQuery,
+) -> Result<SearchResults, SearchError> {
+    // Every hit up to the end of the requested page, from each shard.
+    let each = SearchQuery {
+        offset: 0,
+        limit: query.offset + query.limit.max(1),
+        ..query.clone()
+    };
+    let mut hits = Vec::new();
+    let mut total = 0;
+    let mut doc_type_counts: Vec<(String, usize)> = Vec::new();
+    for index in indexes {
+        let results = search_with(index, &each)?;
+        hits.extend(results.hits);
+        total += results.total;
+        for (doc_type, count) in results.doc_type_counts {
+            match doc_type_counts.iter_mut().find(|(t, _)| *t == doc_type) {
+                Some((_, sum)) => *sum += count,
+                None => doc_type_counts.push((doc_type, count)),
+            }
+        }
+    }
+    doc_type_counts.sort_by_key(|(t, _)| doc_type::ALL.iter().position(|a| a == t));
 
-        results.push(SearchResult {
-            id,
-            doc_type,
-            title,
-            s3_key,
-            score,
-        });
+    match query.sort {
+        SortOrder::Score => hits.sort_by(|a, b| b.score.total_cmp(&a.score)),
+        SortOrder::Recency => hits.sort_by_key(|hit| std::cmp::Reverse(hit.updated_at)),
     }
+    let hits: Vec<SearchResult> = hits
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.max(1))
+        .collect();
+    let next_offset = Some(query.offset + hits.len()).filter(|&next| next < total);
+    Ok(SearchResults {
+        hits,
+        total,
+        next_offset,
+        doc_type_counts,
+    })
+}
 
-    Ok(results)
+/// Full-text search over record file passages, for retrieving the parts of
+/// a client's files relevant to a question rather than whole files.
+pub fn search_passages(
+    index: &Index,
+    query_text: &str,
+    client_id: Option<&str>,
+    limit: usize,
+) -> Result<Vec<PassageHit>, SearchError> {
+    if query_text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let reader = index.reader()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+
+    let text_query = parse_text(index, vec![get_field(&schema, field::BODY)], query_text)?;
+    let mut clauses = vec![
+        text_query,
+        Box::new(term_query(&schema, field::DOC_TYPE, doc_type::PASSAGE)) as Box<dyn Query>,
+    ];
+    if let Some(client_id) = client_id {
+        clauses.push(Box::new(term_query(&schema, field::CLIENT_ID, client_id)));
+    }
+    let query = BooleanQuery::intersection(clauses);
+
+    let mut hits = Vec::new();
+    for (score, address) in searcher.search(&query, &TopDocs::with_limit(limit.max(1)))? {
+        let doc = searcher.doc::<TantivyDocument>(address)?;
+        hits.push(passage_hit(&schema, &doc, score));
+    }
+    Ok(hits)
 }
 
 /// Find all documents of a given type.
@@ -87,52 +386,20 @@ pub fn find_by_type(
     let searcher = reader.searcher();
     let schema = index.schema();
 
-    let doc_type_field = get_field(&schema, field::DOC_TYPE);
-    let query = TermQuery::new(
-        Term::from_field_text(doc_type_field, doc_type),
-        IndexRecordOption::Basic,
-    );
-
+    let query = term_query(&schema, field::DOC_TYPE, doc_type);
     let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
 
-    let id_field = get_field(&schema, field::ID);
-    let title_field = get_field(&schema, field::TITLE);
-    let s3_key_field = get_field(&schema, field::S3_KEY);
-
     let mut results = Vec::new();
     for (score, doc_address) in top_docs {
-        let doc = searcher.doc::<tantivy::TantivyDocument>(doc_address)?;
-
-        let id = doc
-            .get_first(id_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
-        let title = doc
-            .get_first(title_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
-        let s3_key = doc
-            .get_first(s3_key_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
-
-        results.push(SearchResult {
-            id,
-            doc_type: doc_type.to_string(),
-            title,
-            s3_key,
-            score,
-        });
+        let doc = searcher.doc::<TantivyDocument>(doc_address)?;
+        results.push(to_result(&schema, &doc, score));
     }
 
     Ok(results)
 }
 
 /// Find a single document by ID.
-pub fn find_by_id(index: &Index, id: &str) -> Result<Option<tantivy::TantivyDocument>, SearchError> {
+pub fn find_by_id(index: &Index, id: &str) -> Result<Option<TantivyDocument>, SearchError> {
     let reader = index.reader()?;
     let searcher = reader.searcher();
     let schema = index.schema();
@@ -146,9 +413,153 @@ pub fn find_by_id(index: &Index, id: &str) -> Result<Option<tantivy::TantivyDocu
     let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
 
     if let Some((_score, doc_address)) = top_docs.first() {
-        let doc = searcher.doc::<tantivy::TantivyDocument>(*doc_address)?;
+        let doc = searcher.doc::<TantivyDocument>(*doc_address)?;
         Ok(Some(doc))
     } else {
         Ok(None)
     }
 }
+
+/// Parse `text` as a query over `fields`.
+///
+/// The parser analyzes each unquoted word on its own, so a synonym phrase
+/// such as "autism spectrum disorder" would never meet the single token it
+/// was indexed as. Every phrase the clinical analyzer replaces is quoted
+/// first, unless it is already inside quotes or part of query syntax.
+fn parse_text(
+    index: &Index,
+    fields: Vec<Field>,
+    text: &str,
+) -> Result<Box<dyn Query>, SearchError> {
+    let mut quoted = String::with_capacity(text.len());
+    let mut copied = 0;
+    if let Some(mut analyzer) = index.tokenizers().get(CLINICAL_TOKENIZER) {
+        let mut stream = analyzer.token_stream(text);
+        while stream.advance() {
+            let token = stream.token();
+            let span = &text[token.offset_from..token.offset_to];
+            let in_quotes = text[..token.offset_from].matches('"').count() % 2 == 1;
+            if token.offset_from < copied
+                || in_quotes
+                || !span.contains(char::is_whitespace)
+                || span.contains(['"', ':'])
+            {
+                continue;
+            }
+            quoted.push_str(&text[copied..token.offset_from]);
+            quoted.push('"');
+            quoted.push_str(span);
+            quoted.push('"');
+            copied = token.offset_to;
+        }
+    }
+    quoted.push_str(&text[copied..]);
+
+    QueryParser::for_index(index, fields)
+        .parse_query(&quoted)
+        .map_err(|e| SearchError::QueryParse(e.to_string()))
+}
+
+/// Client documents whose name is within a typo or two of a word in
+/// `text`, so "Jonathon" still finds Jonathan. `None` if no word is long
+/// enough to match loosely.
+fn fuzzy_client_names(index: &Index, text: &str) -> Option<Box<dyn Query>> {
+    let schema = index.schema();
+    let title = get_field(&schema, field::TITLE);
+    let mut analyzer = index.tokenizers().get(CLINICAL_TOKENIZER)?;
+    let mut names: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    analyzer.token_stream(text).process(&mut |token| {
+        let distance = match token.text.chars().count() {
+            n if n >= FUZZY_TWO_EDIT_CHARS => 2,
+            n if n >= FUZZY_ONE_EDIT_CHARS => 1,
+            _ => return,
+        };
+        let term = Term::from_field_text(title, &token.text);
+        names.push((
+            Occur::Should,
+            Box::new(FuzzyTermQuery::new(term, distance, true)),
+        ));
+    });
+    if names.is_empty() {
+        return None;
+    }
+    Some(Box::new(BooleanQuery::intersection(vec![
+        Box::new(BooleanQuery::new(names)),
+        Box::new(ConstScoreQuery::new(
+            Box::new(term_query(&schema, field::DOC_TYPE, doc_type::CLIENT)),
+            0.0,
+        )),
+    ])))
+}
+
+/// An exact match on a `STRING` field.
+pub(crate) fn term_query(schema: &Schema, name: &str, value: &str) -> TermQuery {
+    TermQuery::new(
+        Term::from_field_text(get_field(schema, name), value),
+        IndexRecordOption::Basic,
+    )
+}
+
+fn snippet(generator: &SnippetGenerator, doc: &TantivyDocument) -> Option<Snippet> {
+    let snippet = generator.snippet_from_doc(doc);
+    (!snippet.is_empty()).then(|| Snippet {
+        fragment: snippet.fragment().to_string(),
+        highlights: snippet.highlighted().to_vec(),
+    })
+}
+
+/// A passage's ordinal and character offsets.
+fn passage_position(schema: &Schema, doc: &TantivyDocument) -> (usize, usize, usize) {
+    let number = |name: &str| {
+        doc.get_first(get_field(schema, name))
+            .and_then(|v| v.as_u64())
+            .unwrap_or_default() as usize
+    };
+    (
+        number(field::CHUNK_ORDINAL),
+        number(field::CHUNK_START),
+        number(field::CHUNK_END),
+    )
+}
+
+pub(crate) fn passage_hit(schema: &Schema, doc: &TantivyDocument, score: f32) -> PassageHit {
+    let text = |name: &str| {
+        doc.get_first(get_field(schema, name))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    let (ordinal, start, end) = passage_position(schema, doc);
+    PassageHit {
+        parent_key: text(field::PARENT_KEY).unwrap_or_default(),
+        client_id: text(field::CLIENT_ID),
+        title: text(field::TITLE).unwrap_or_default(),
+        s3_key: text(field::S3_KEY).unwrap_or_default(),
+        ordinal,
+        start,
+        end,
+        text: text(field::BODY).unwrap_or_default(),
+        score,
+    }
+}
+
+fn to_result(schema: &Schema, doc: &TantivyDocument, score: f32) -> SearchResult {
+    let text = |name: &str| {
+        doc.get_first(get_field(schema, name))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    SearchResult {
+        id: text(field::ID).unwrap_or_default(),
+        doc_type: text(field::DOC_TYPE).unwrap_or_default(),
+        client_id: text(field::CLIENT_ID),
+        title: text(field::TITLE).unwrap_or_default(),
+        s3_key: text(field::S3_KEY).unwrap_or_default(),
+        updated_at: doc
+            .get_first(get_field(schema, field::UPDATED_AT))
+            .and_then(|v| v.as_i64())
+            .unwrap_or_default(),
+        score,
+        snippet: None,
+        passages: Vec::new(),
+    }
+}
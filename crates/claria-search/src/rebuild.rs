@@ -0,0 +1,255 @@
+//! Rebuild the index from scratch.
+//!
+//! The recovery path for a bucket whose index is missing or corrupted:
+//! every indexed object (see [`crate::document`]) is read back from the
+//! bucket into fresh index shards (see [`crate::shard`]), which replace
+//! whatever was stored before. A shard from an older schema is rebuilt on
+//! its own when it is downloaded ([`rebuild_shard`]), and only replaced if
+//! no other writer has flushed it in the meantime.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use tantivy::Index;
+use tracing::{info, warn};
+
+use claria_core::models::synonyms::SynonymDictionary;
+use claria_core::s3_keys;
+use claria_storage::objects;
+use claria_storage::store::ObjectStore;
+
+use crate::document::{INDEXED_PREFIXES, build_document, indexed_key};
+use crate::error::SearchError;
+use crate::flush::{flush_shard, flush_shard_if_absent, flush_shard_unconditional};
+use crate::index::create_empty_index_with;
+use crate::mutate::{WRITER_HEAP_BYTES, commit, insert_document};
+use crate::shard::{Shard, list_client_shards};
+use crate::synonyms::effective_synonyms;
+
+/// Progress of a [`rebuild_index`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuildProgress {
+    pub documents_done: usize,
+    pub documents_total: usize,
+}
+
+/// What a [`rebuild_index`] call indexed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RebuildSummary {
+    pub documents: usize,
+    /// Objects that could not be parsed and were left out.
+    pub skipped: usize,
+}
+
+/// Re-index every object in the bucket into new, empty shards and upload
+/// each over the existing one without an ETag check. The global index is
+/// always written; shards of clients with nothing left to index are
+/// deleted. The new shards use the bucket's current synonyms (see
+/// [`crate::synonyms`]).
+///
+/// `on_progress` is called once the objects to index are known and after
+/// each one. An object whose JSON does not parse is logged and skipped
+/// rather than failing the rebuild.
+pub async fn rebuild_index(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    on_progress: &mut (dyn FnMut(&RebuildProgress) + Send),
+) -> Result<RebuildSummary, SearchError> {
+    let mut shards: BTreeMap<Shard, BTreeSet<String>> = BTreeMap::new();
+    shards.insert(Shard::Global, BTreeSet::new());
+    for prefix in INDEXED_PREFIXES {
+        for key in objects::list_objects(store, bucket, prefix).await? {
+            if let Some(key) = indexed_key(store, bucket, &key).await? {
+                shards.entry(Shard::for_key(&key)).or_default().insert(key);
+            }
+        }
+    }
+
+    let mut progress = RebuildProgress {
+        documents_done: 0,
+        documents_total: shards.values().map(BTreeSet::len).sum(),
+    };
+    on_progress(&progress);
+
+    let synonyms = effective_synonyms(store, bucket).await?;
+    let mut summary = RebuildSummary::default();
+    // One shard at a time, so only one index writer is held in memory.
+    for (shard, keys) in &shards {
+        let dir = tempfile::tempdir()?;
+        build_shard(
+            store,
+            bucket,
+            &synonyms,
+            keys,
+            dir.path(),
+            &mut summary,
+            &mut |done| {
+                progress.documents_done += done;
+                on_progress(&progress);
+            },
+        )
+        .await?;
+        flush_shard_unconditional(store, bucket, *shard, dir.path()).await?;
+    }
+
+    for id in list_client_shards(store, bucket).await? {
+        let shard = Shard::Client(id);
+        if !shards.contains_key(&shard) {
+            objects::delete_object(store, bucket, &shard.object_key()).await?;
+        }
+    }
+    info!(
+        documents = summary.documents,
+        skipped = summary.skipped,
+        shards = shards.len(),
+        "index rebuilt"
+    );
+    Ok(summary)
+}
+
+/// Re-index the objects of one shard into a new, empty shard and upload it
+/// over the version with `expected_etag`. The other shards are left alone.
+///
+/// If another writer flushed the shard since it was read, theirs is kept
+/// and nothing is uploaded; read the shard again to get it. A delete cannot
+/// be made conditional, so a client shard with nothing left to index is
+/// replaced with an empty one.
+pub async fn rebuild_shard(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shard: Shard,
+    expected_etag: &str,
+) -> Result<RebuildSummary, SearchError> {
+    let dir = tempfile::tempdir()?;
+    let (_, summary) = build_from_bucket(store, bucket, shard, dir.path()).await?;
+    match flush_shard(store, bucket, shard, dir.path(), expected_etag).await {
+        Ok(_) => info!(
+            %shard,
+            documents = summary.documents,
+            skipped = summary.skipped,
+            "shard rebuilt"
+        ),
+        Err(SearchError::ETagMismatch) => {
+            warn!(%shard, "shard changed during rebuild, keeping the other writer's");
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(summary)
+}
+
+/// Create the shard of every client with indexed objects but no shard, as
+/// left by an index from before shards (see [`crate::shard`]), whose one
+/// blob held every client's records. Returns how many shards were created.
+///
+/// Shards are created with `If-None-Match: *`; one another writer created
+/// first is kept.
+pub async fn create_missing_client_shards(
+    store: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<usize, SearchError> {
+    let existing: BTreeSet<_> = list_client_shards(store, bucket)
+        .await?
+        .into_iter()
+        .map(Shard::Client)
+        .collect();
+    let missing: BTreeSet<Shard> = objects::list_objects(store, bucket, "records/")
+        .await?
+        .iter()
+        .map(|key| Shard::for_key(key))
+        .filter(|shard| *shard != Shard::Global && !existing.contains(shard))
+        .collect();
+
+    let mut created = 0;
+    for shard in missing {
+        let dir = tempfile::tempdir()?;
+        let (_, summary) = build_from_bucket(store, bucket, shard, dir.path()).await?;
+        if summary.documents == 0 {
+            continue;
+        }
+        match flush_shard_if_absent(store, bucket, shard, dir.path()).await {
+            Ok(_) => created += 1,
+            Err(SearchError::ETagMismatch) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    info!(created, "missing client shards created");
+    Ok(created)
+}
+
+/// Index the objects of one shard into a new index at `dir`, without
+/// uploading it. Used to rebuild a shard and to create one the bucket does
+/// not have yet, such as a client's after an upgrade from a single index.
+pub(crate) async fn build_from_bucket(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    shard: Shard,
+    dir: &Path,
+) -> Result<(Index, RebuildSummary), SearchError> {
+    let prefixes: Vec<String> = match shard {
+        Shard::Global => INDEXED_PREFIXES
+            .iter()
+            .filter(|prefix| Shard::for_prefix(prefix) == Some(Shard::Global))
+            .map(ToString::to_string)
+            .collect(),
+        Shard::Client(id) => vec![s3_keys::client_records_prefix(id)],
+    };
+    let mut keys = BTreeSet::new();
+    for prefix in &prefixes {
+        for key in objects::list_objects(store, bucket, prefix).await? {
+            if let Some(key) = indexed_key(store, bucket, &key).await?
+                && Shard::for_key(&key) == shard
+            {
+                keys.insert(key);
+            }
+        }
+    }
+
+    let synonyms = effective_synonyms(store, bucket).await?;
+    let mut summary = RebuildSummary::default();
+    let index = build_shard(
+        store,
+        bucket,
+        &synonyms,
+        &keys,
+        dir,
+        &mut summary,
+        &mut |_| {},
+    )
+    .await?;
+    Ok((index, summary))
+}
+
+/// Index the documents for `keys` into a new index at `dir`, adding to
+/// `summary` and calling `on_document` after each key. Returns the index.
+async fn build_shard(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    synonyms: &SynonymDictionary,
+    keys: &BTreeSet<String>,
+    dir: &Path,
+    summary: &mut RebuildSummary,
+    on_document: &mut (dyn FnMut(usize) + Send),
+) -> Result<Index, SearchError> {
+    let index = create_empty_index_with(dir, synonyms)?;
+    let mut writer = index.writer(WRITER_HEAP_BYTES)?;
+    for key in keys {
+        match build_document(store, bucket, key).await {
+            Ok(Some(document)) => {
+                for tantivy_doc in document.into_tantivy(&index) {
+                    insert_document(&writer, tantivy_doc)?;
+                }
+                summary.documents += 1;
+            }
+            Ok(None) => {}
+            Err(SearchError::Serialization(e)) => {
+                warn!(key, error = %e, "skipping unreadable object during rebuild");
+                summary.skipped += 1;
+            }
+            Err(e) => return Err(e),
+        }
+        on_document(1);
+    }
+    commit(&mut writer)?;
+    writer.wait_merging_threads()?;
+    Ok(index)
+}
@@ -0,0 +1,99 @@
+//! Per-client index shards.
+//!
+//! The index is split so that opening one client does not download the
+//! whole practice. The global index (`_index/tantivy.tar.zst`) holds
+//! clients, assessments, snippets, goals, templates and reports; each
+//! client's record files, chat histories and their passages go in a shard
+//! of their own (`_index/clients/{id}.tar.zst`). Every shard is a separate
+//! blob with its own ETag, downloaded and flushed independently.
+//!
+//! Client documents stay in the global index so client names can be
+//! searched without opening any shard.
+
+use std::fmt;
+
+use uuid::Uuid;
+
+use claria_core::s3_keys;
+use claria_storage::objects;
+use claria_storage::store::ObjectStore;
+
+use crate::error::SearchError;
+
+/// One of the index blobs in a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Shard {
+    Global,
+    Client(Uuid),
+}
+
+impl Shard {
+    /// The shard the document for `key` is indexed in.
+    pub fn for_key(key: &str) -> Self {
+        key.strip_prefix("records/")
+            .and_then(|rest| rest.split_once('/'))
+            .and_then(|(id, _)| id.parse().ok())
+            .map_or(Self::Global, Self::Client)
+    }
+
+    /// The one shard holding every document whose key starts with
+    /// `prefix`, or `None` if such documents can be in several shards.
+    pub fn for_prefix(prefix: &str) -> Option<Self> {
+        match prefix.strip_prefix("records/") {
+            Some(rest) if rest.contains('/') => Some(Self::for_key(prefix)),
+            Some(_) => None,
+            None if "records/".starts_with(prefix) => None,
+            None => Some(Self::Global),
+        }
+    }
+
+    /// The S3 key of the shard's blob.
+    pub fn object_key(&self) -> String {
+        match self {
+            Self::Global => s3_keys::INDEX.to_string(),
+            Self::Client(id) => s3_keys::client_index(*id),
+        }
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => f.write_str("global"),
+            Self::Client(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// The clients with a shard in the bucket.
+pub async fn list_client_shards(
+    store: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<Vec<Uuid>, SearchError> {
+    let keys = objects::list_objects(store, bucket, s3_keys::CLIENT_INDEXES_PREFIX).await?;
+    Ok(keys
+        .iter()
+        .filter_map(|key| {
+            key.strip_prefix(s3_keys::CLIENT_INDEXES_PREFIX)?
+                .strip_suffix(".tar.zst")?
+                .parse()
+                .ok()
+        })
+        .collect())
+}
+
+/// The shards a search covers: the global index, then the shard of
+/// `client_id`, or of every client if there is none.
+pub async fn shards_for_search(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    client_id: Option<Uuid>,
+) -> Result<Vec<Shard>, SearchError> {
+    let clients = match client_id {
+        Some(id) => vec![id],
+        None => list_client_shards(store, bucket).await?,
+    };
+    Ok(std::iter::once(Shard::Global)
+        .chain(clients.into_iter().map(Shard::Client))
+        .collect())
+}
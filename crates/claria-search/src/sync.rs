@@ -0,0 +1,69 @@
+//! Keep the index in step with the bucket.
+//!
+//! Callers report which keys they wrote, restored or deleted. [`sync_keys`]
+//! reads the current state of each key and journals an upsert or delete of
+//! the document it is indexed as, then commits the whole batch at once.
+//!
+//! See [`crate::document`] for what each key is indexed as.
+
+use std::collections::BTreeSet;
+
+use tracing::info;
+
+use claria_storage::store::ObjectStore;
+
+use crate::document::{build_document, indexed_key};
+use crate::error::SearchError;
+use crate::journal::{Journal, commit_journal};
+
+/// What one [`sync_keys`] batch changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub upserted: usize,
+    pub deleted: usize,
+}
+
+/// Bring the documents for `keys` up to date and flush the index once.
+///
+/// Each key is resolved to what it is indexed as (see [`indexed_key`]) and
+/// re-read from the bucket, so the order of writes within a batch does not
+/// matter. Creates the index if the bucket has none. If another writer
+/// flushes the index first, the batch is replayed onto theirs (see
+/// [`commit_journal`]).
+pub async fn sync_keys(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    keys: &[String],
+) -> Result<SyncSummary, SearchError> {
+    let mut indexed = BTreeSet::new();
+    for key in keys {
+        if let Some(key) = indexed_key(store, bucket, key).await? {
+            indexed.insert(key);
+        }
+    }
+    if indexed.is_empty() {
+        return Ok(SyncSummary::default());
+    }
+
+    let mut journal = Journal::new();
+    let mut summary = SyncSummary::default();
+    for key in &indexed {
+        match build_document(store, bucket, key).await? {
+            Some(document) => {
+                journal.update(document);
+                summary.upserted += 1;
+            }
+            None => {
+                journal.delete(key.as_str());
+                summary.deleted += 1;
+            }
+        }
+    }
+    commit_journal(store, bucket, &journal).await?;
+    info!(
+        upserted = summary.upserted,
+        deleted = summary.deleted,
+        "index synced"
+    );
+    Ok(summary)
+}
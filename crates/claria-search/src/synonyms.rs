@@ -0,0 +1,137 @@
+//! The synonym dictionary the clinical analyzer is built with.
+//!
+//! The effective dictionary is the built-in one (instrument names and a few
+//! common clinical abbreviations) followed by the user's, stored in the
+//! bucket at [`s3_keys::SYNONYMS`]. A copy is kept in each index directory
+//! as [`SYNONYMS_FILE`], so an index is always opened with the dictionary it
+//! was built with; editing the user's dictionary means rebuilding the index.
+
+use std::path::Path;
+
+use tantivy::Index;
+
+use claria_core::models::synonyms::SynonymDictionary;
+use claria_core::s3_keys;
+use claria_core::schema::register_tokenizers;
+use claria_storage::error::StorageError;
+use claria_storage::objects;
+use claria_storage::store::ObjectStore;
+
+use crate::error::SearchError;
+
+/// Name of the dictionary file inside the index directory.
+pub const SYNONYMS_FILE: &str = "claria-synonyms.json";
+
+/// Abbreviations common enough in clinical records to expand for everyone.
+const CLINICAL_TERMS: &[&[&str]] = &[
+    &["ASD", "autism spectrum disorder", "autism", "autistic"],
+    &["ADHD", "attention deficit hyperactivity disorder"],
+    &[
+        "ABA",
+        "applied behavior analysis",
+        "applied behaviour analysis",
+    ],
+    &[
+        "IEP",
+        "individualized education program",
+        "individualised education programme",
+    ],
+    &[
+        "FBA",
+        "functional behavior assessment",
+        "functional behaviour assessment",
+    ],
+    &[
+        "BIP",
+        "behavior intervention plan",
+        "behaviour intervention plan",
+    ],
+];
+
+/// The built-in dictionary: every instrument under its short name, spaced
+/// name, published title and ID, then [`CLINICAL_TERMS`].
+pub fn built_in() -> SynonymDictionary {
+    let instruments = claria_instruments::all_instruments()
+        .iter()
+        .map(|instrument| {
+            vec![
+                instrument.name().to_string(),
+                instrument.name().replace('-', " "),
+                instrument.full_name().to_string(),
+                instrument.id().replace('_', " "),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let clinical = CLINICAL_TERMS
+        .iter()
+        .map(|group| group.iter().map(|term| term.to_string()).collect());
+    SynonymDictionary {
+        groups: instruments.into_iter().chain(clinical).collect(),
+    }
+}
+
+/// The user's dictionary from the bucket, or an empty one if none is saved.
+pub async fn load_synonyms(
+    store: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<SynonymDictionary, SearchError> {
+    match objects::get_object(store, bucket, s3_keys::SYNONYMS).await {
+        Ok(output) => Ok(serde_json::from_slice(&output.body)?),
+        Err(StorageError::NotFound { .. }) => Ok(SynonymDictionary::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Save the user's dictionary to the bucket. The index keeps using the
+/// dictionary it was built with until it is rebuilt.
+pub async fn save_synonyms(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    synonyms: &SynonymDictionary,
+) -> Result<(), SearchError> {
+    let body = serde_json::to_vec_pretty(synonyms)?;
+    objects::put_object(
+        store,
+        bucket,
+        s3_keys::SYNONYMS,
+        body,
+        Some("application/json"),
+    )
+    .await?;
+    Ok(())
+}
+
+/// The built-in dictionary followed by the user's from the bucket.
+pub async fn effective_synonyms(
+    store: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<SynonymDictionary, SearchError> {
+    Ok(built_in().merged(&load_synonyms(store, bucket).await?))
+}
+
+/// Record `synonyms` in `index_dir` and register them on `index`.
+pub(crate) fn write_synonyms(
+    index: &Index,
+    index_dir: &Path,
+    synonyms: &SynonymDictionary,
+) -> Result<(), SearchError> {
+    std::fs::write(
+        index_dir.join(SYNONYMS_FILE),
+        serde_json::to_vec_pretty(synonyms)?,
+    )?;
+    register_tokenizers(index, synonyms);
+    Ok(())
+}
+
+/// Register the dictionary recorded in `index_dir` on `index`. An index
+/// without one predates synonyms and is opened with the built-in
+/// dictionary.
+pub(crate) fn read_synonyms(index: &Index, index_dir: &Path) -> Result<(), SearchError> {
+    let synonyms = match std::fs::read(index_dir.join(SYNONYMS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => built_in(),
+        Err(e) => return Err(e.into()),
+    };
+    register_tokenizers(index, &synonyms);
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Passage vectors stored next to the Tantivy index.
+//!
+//! [`VectorIndex`] holds one embedding per passage in a file in the index
+//! directory, so it travels inside the same tar.zst blob and is flushed
+//! under the same ETag lock. Mutations only touch Tantivy; before a
+//! semantic search, [`refresh_vectors`] embeds passages that are new or
+//! whose text changed and drops vectors of passages that are gone.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tantivy::collector::DocSetCollector;
+use tantivy::schema::Value;
+use tantivy::{Index, TantivyDocument};
+use tracing::info;
+
+use claria_core::schema::{doc_type, field, get_field};
+
+use crate::embed::Embedder;
+use crate::error::SearchError;
+use crate::query::term_query;
+
+/// Name of the vector file inside the index directory.
+pub const VECTORS_FILE: &str = "claria-vectors";
+
+/// Passages embedded per call to [`Embedder::embed`].
+const EMBED_BATCH: usize = 16;
+
+const MAGIC: &[u8; 8] = b"CLRVEC01";
+
+/// One embedded passage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorEntry {
+    pub passage_id: String,
+    pub parent_key: String,
+    pub client_id: Option<String>,
+    /// FNV-1a hash of the passage text the vector was computed from.
+    pub text_hash: u64,
+    #[serde(skip)]
+    pub vector: Vec<f32>,
+}
+
+/// Passage embeddings from one model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorIndex {
+    pub model_id: String,
+    pub dimensions: usize,
+    pub entries: Vec<VectorEntry>,
+}
+
+/// What a [`refresh_vectors`] call changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VectorRefresh {
+    pub embedded: usize,
+    pub removed: usize,
+}
+
+impl VectorRefresh {
+    pub fn changed(&self) -> bool {
+        self.embedded > 0 || self.removed > 0
+    }
+}
+
+impl VectorIndex {
+    /// Read the vector file from `index_dir`, or an empty index if there
+    /// is none.
+    ///
+    /// The file is a magic number, the length of a JSON header, the header
+    /// (every entry but its vector), then each vector as little-endian
+    /// `f32`s in entry order.
+    pub fn open(index_dir: &Path) -> Result<Self, SearchError> {
+        let bytes = match std::fs::read(index_dir.join(VECTORS_FILE)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let corrupted = || SearchError::IndexCorrupted(format!("{VECTORS_FILE} is truncated"));
+
+        let rest = bytes.strip_prefix(MAGIC).ok_or_else(corrupted)?;
+        let (len, rest) = rest.split_first_chunk::<8>().ok_or_else(corrupted)?;
+        let len = u64::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            return Err(corrupted());
+        }
+        let (header, mut floats) = rest.split_at(len);
+        let mut index: Self = serde_json::from_slice(header)?;
+        for entry in &mut index.entries {
+            let (vector, tail) = floats
+                .split_at_checked(index.dimensions * 4)
+                .ok_or_else(corrupted)?;
+            entry.vector = vector
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            floats = tail;
+        }
+        Ok(index)
+    }
+
+    /// Write the vector file into `index_dir`, replacing any there.
+    pub fn save(&self, index_dir: &Path) -> Result<(), SearchError> {
+        let header = serde_json::to_vec(self)?;
+        let mut bytes = Vec::with_capacity(
+            MAGIC.len() + 8 + header.len() + self.entries.len() * self.dimensions * 4,
+        );
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        for entry in &self.entries {
+            for value in &entry.vector {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let tmp = index_dir.join(format!("{VECTORS_FILE}.tmp"));
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(tmp, index_dir.join(VECTORS_FILE))?;
+        Ok(())
+    }
+
+    /// The `limit` entries most similar to `query`, most similar first,
+    /// with their cosine similarity. `client_id` restricts the search to
+    /// one client's passages.
+    pub fn nearest(
+        &self,
+        query: &[f32],
+        client_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<(&VectorEntry, f32)> {
+        let mut scored: Vec<(&VectorEntry, f32)> = self
+            .entries
+            .iter()
+            .filter(|e| client_id.is_none() || e.client_id.as_deref() == client_id)
+            .map(|e| (e, dot(&e.vector, query)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        scored
+    }
+}
+
+/// Bring the vector file in `index_dir` up to date with the passages in
+/// `index`, embedding with `embedder`. Vectors from a different model are
+/// all recomputed.
+pub fn refresh_vectors(
+    index_dir: &Path,
+    index: &Index,
+    embedder: &dyn Embedder,
+) -> Result<VectorRefresh, SearchError> {
+    let mut vectors = VectorIndex::open(index_dir)?;
+    let mut refresh = VectorRefresh::default();
+    if vectors.model_id != embedder.model_id() || vectors.dimensions != embedder.dimensions() {
+        refresh.removed = vectors.entries.len();
+        vectors = VectorIndex {
+            model_id: embedder.model_id().to_string(),
+            dimensions: embedder.dimensions(),
+            entries: Vec::new(),
+        };
+    }
+    let mut existing: HashMap<String, VectorEntry> = vectors
+        .entries
+        .drain(..)
+        .map(|e| (e.passage_id.clone(), e))
+        .collect();
+
+    let reader = index.reader()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let passages = term_query(&schema, field::DOC_TYPE, doc_type::PASSAGE);
+    let mut missing = Vec::new();
+    for address in searcher.search(&passages, &DocSetCollector)? {
+        let doc = searcher.doc::<TantivyDocument>(address)?;
+        let text = |name: &str| {
+            doc.get_first(get_field(&schema, name))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let passage_id = text(field::ID).unwrap_or_default();
+        let body = text(field::BODY).unwrap_or_default();
+        let text_hash = fnv1a(body.as_bytes());
+        match existing.remove(&passage_id) {
+            Some(entry) if entry.text_hash == text_hash => vectors.entries.push(entry),
+            _ => missing.push((
+                VectorEntry {
+                    passage_id,
+                    parent_key: text(field::PARENT_KEY).unwrap_or_default(),
+                    client_id: text(field::CLIENT_ID),
+                    text_hash,
+                    vector: Vec::new(),
+                },
+                body,
+            )),
+        }
+    }
+    refresh.removed += existing.len();
+
+    for batch in missing.chunks_mut(EMBED_BATCH) {
+        let texts: Vec<&str> = batch.iter().map(|(_, body)| body.as_str()).collect();
+        let embedded = embedder.embed(&texts)?;
+        for ((entry, _), vector) in batch.iter_mut().zip(embedded) {
+            entry.vector = vector;
+        }
+    }
+    refresh.embedded = missing.len();
+    vectors
+        .entries
+        .extend(missing.into_iter().map(|(entry, _)| entry));
+
+    if refresh.changed() {
+        vectors
+            .entries
+            .sort_by(|a, b| a.passage_id.cmp(&b.passage_id));
+        vectors.save(index_dir)?;
+        info!(
+            embedded = refresh.embedded,
+            removed = refresh.removed,
+            "passage vectors refreshed"
+        );
+    }
+    Ok(refresh)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// 64-bit FNV-1a, stable across builds unlike `std`'s hasher.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,150 @@
+use claria_core::models::synonyms::SynonymDictionary;
+use claria_core::s3_keys;
+use claria_core::schema::doc_type;
+use claria_search::document::IndexDocument;
+use claria_search::index::{create_empty_index, download_index};
+use claria_search::journal::Journal;
+use claria_search::query::search;
+use claria_search::rebuild::rebuild_index;
+use claria_search::synonyms::{SYNONYMS_FILE, load_synonyms, save_synonyms};
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+fn document(id: &str, kind: &'static str, title: &str, body: &str) -> IndexDocument {
+    IndexDocument {
+        id: id.to_string(),
+        doc_type: kind,
+        title: title.to_string(),
+        body: body.to_string(),
+        s3_key: id.to_string(),
+        updated_at: 1,
+        ..Default::default()
+    }
+}
+
+fn ids(index: &tantivy::Index, text: &str) -> Vec<String> {
+    let mut ids: Vec<String> = search(index, text, 10)
+        .unwrap()
+        .into_iter()
+        .map(|hit| hit.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn instruments_abbreviations_and_stems_match_their_variants() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let mut journal = Journal::new();
+    journal.insert(document(
+        "ados",
+        doc_type::SNIPPET,
+        "Observation",
+        "ADOS-2 module 3 administered",
+    ));
+    journal.insert(document(
+        "asd",
+        doc_type::SNIPPET,
+        "Diagnosis",
+        "Meets criteria for autism spectrum disorder",
+    ));
+    journal.insert(document(
+        "eloping",
+        doc_type::SNIPPET,
+        "Safety",
+        "Ran from the classroom twice, running toward the exit",
+    ));
+    journal.apply(&index).unwrap();
+
+    for query in [
+        "ADOS-2",
+        "ados2",
+        "ADOS 2",
+        "Autism Diagnostic Observation Schedule, Second Edition",
+    ] {
+        assert_eq!(ids(&index, query), ["ados"], "{query}");
+    }
+    for query in ["ASD", "autistic", "\"autism spectrum disorder\""] {
+        assert_eq!(ids(&index, query), ["asd"], "{query}");
+    }
+    assert_eq!(ids(&index, "runs"), ["eloping"]);
+    // Only whole phrases are replaced; "spectrum" on its own is a word.
+    assert!(ids(&index, "spectrum").is_empty());
+}
+
+#[test]
+fn misspelled_client_names_still_find_the_client() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let mut journal = Journal::new();
+    journal.insert(document(
+        "jonathan",
+        doc_type::CLIENT,
+        "Jonathan Whitaker",
+        "",
+    ));
+    journal.insert(document("ana", doc_type::CLIENT, "Ana Li", ""));
+    journal.insert(document("note", doc_type::SNIPPET, "Jonathan", "Whitaker"));
+    journal.apply(&index).unwrap();
+
+    assert_eq!(ids(&index, "Jonathon"), ["jonathan"]);
+    assert_eq!(ids(&index, "Whittaker"), ["jonathan"]);
+    // Short words must match exactly.
+    assert!(ids(&index, "Ann").is_empty());
+    // The exact spelling still finds everything.
+    assert_eq!(ids(&index, "Jonathan"), ["jonathan", "note"]);
+}
+
+#[tokio::test]
+async fn user_synonyms_from_the_bucket_apply_after_rebuild() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    assert_eq!(
+        load_synonyms(&store, BUCKET).await.unwrap(),
+        SynonymDictionary::default()
+    );
+
+    let custom = SynonymDictionary {
+        groups: vec![vec![
+            "PECS".to_string(),
+            "picture exchange communication system".to_string(),
+        ]],
+    };
+    save_synonyms(&store, BUCKET, &custom).await.unwrap();
+    assert_eq!(load_synonyms(&store, BUCKET).await.unwrap(), custom);
+
+    let id = uuid::Uuid::new_v4();
+    let key = s3_keys::snippet(id);
+    let now = "2025-01-01T00:00:00Z";
+    let snippet = serde_json::json!({
+        "id": id, "title": "Communication", "body": "Started PECS after the ADOS-2",
+        "s3_key": key, "created_at": now, "updated_at": now,
+    });
+    objects::put_object(
+        &store,
+        BUCKET,
+        &key,
+        serde_json::to_vec(&snippet).unwrap(),
+        None,
+    )
+    .await
+    .unwrap();
+    rebuild_index(&store, BUCKET, &mut |_| {}).await.unwrap();
+
+    // The downloaded index carries its dictionary, built-ins included.
+    let index_dir = tempfile::tempdir().unwrap();
+    let loaded = download_index(&store, BUCKET, index_dir.path())
+        .await
+        .unwrap();
+    assert!(index_dir.path().join(SYNONYMS_FILE).exists());
+    let key = key.as_str();
+    assert_eq!(
+        ids(&loaded.index, "picture exchange communication system"),
+        [key]
+    );
+    assert_eq!(ids(&loaded.index, "pecs"), [key]);
+    assert_eq!(ids(&loaded.index, "ADOS 2"), [key]);
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use claria_core::s3_keys;
+use claria_search::error::SearchError;
+use claria_search::index::download_shard;
+use claria_search::query::search;
+use claria_search::shard::Shard;
+use claria_search::sync;
+use claria_storage::crypto::{self, MasterKey};
+use claria_storage::encrypted::EncryptedStore;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::store::ObjectStore;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+/// The first bytes of every zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// A store rooted at `dir` that seals every object with `key`, as the app's
+/// is once the practice key is installed.
+fn sealed_store(dir: &std::path::Path, key: MasterKey) -> EncryptedStore {
+    let inner: Arc<dyn ObjectStore> = Arc::new(LocalStore::new(dir));
+    EncryptedStore::new(inner, key)
+}
+
+/// The stored blob of the client's shard, which holds its record files.
+async fn index_blob(store: &LocalStore, id: uuid::Uuid) -> Vec<u8> {
+    objects::get_object(store, BUCKET, &s3_keys::client_index(id))
+        .await
+        .unwrap()
+        .body
+}
+
+#[tokio::test]
+async fn index_is_sealed_and_left_alone_by_another_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let raw = LocalStore::new(dir.path());
+    let key = MasterKey::generate();
+    let store = sealed_store(dir.path(), MasterKey::from_bytes(key.as_bytes()).unwrap());
+    let id = uuid::Uuid::new_v4();
+    let note = s3_keys::client_record_file(id, "notes.txt");
+    // Written in the clear, so that only the index stands in the way of the
+    // other key below.
+    objects::put_object(
+        &raw,
+        BUCKET,
+        &note,
+        b"nocturnal enuresis most nights".to_vec(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    sync::sync_keys(&store, BUCKET, std::slice::from_ref(&note))
+        .await
+        .unwrap();
+    let blob = index_blob(&raw, id).await;
+    assert!(crypto::is_encrypted(&blob));
+    assert!(
+        crypto::open(&key, blob.clone())
+            .unwrap()
+            .starts_with(&ZSTD_MAGIC)
+    );
+
+    let index_dir = tempfile::tempdir().unwrap();
+    let loaded = download_shard(&store, BUCKET, Shard::Client(id), index_dir.path())
+        .await
+        .unwrap();
+    assert_eq!(search(&loaded.index, "enuresis", 10).unwrap().len(), 1);
+
+    // Read without the key, the sealed blob is refused rather than unpacked.
+    let index_dir = tempfile::tempdir().unwrap();
+    let result = download_shard(&raw, BUCKET, Shard::Client(id), index_dir.path()).await;
+    assert!(matches!(result, Err(SearchError::IndexUnreadable(_))));
+
+    // Another key cannot open it, and neither reading nor writing with that
+    // key replaces it.
+    let other = sealed_store(dir.path(), MasterKey::generate());
+    let index_dir = tempfile::tempdir().unwrap();
+    let result = download_shard(&other, BUCKET, Shard::Client(id), index_dir.path()).await;
+    assert!(matches!(result, Err(SearchError::IndexUnreadable(_))));
+    let result = sync::sync_keys(&other, BUCKET, std::slice::from_ref(&note)).await;
+    assert!(matches!(result, Err(SearchError::IndexUnreadable(_))));
+    assert_eq!(index_blob(&raw, id).await, blob);
+}
+
+#[tokio::test]
+async fn an_unsealed_index_is_read_and_sealed_on_the_next_flush() {
+    let dir = tempfile::tempdir().unwrap();
+    let raw = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let notes = [
+        s3_keys::client_record_file(id, "first.txt"),
+        s3_keys::client_record_file(id, "second.txt"),
+    ];
+    for note in &notes {
+        objects::put_object(&raw, BUCKET, note, b"enuresis".to_vec(), None)
+            .await
+            .unwrap();
+    }
+
+    // Indexed before the practice key was installed.
+    sync::sync_keys(&raw, BUCKET, &notes[..1]).await.unwrap();
+    assert!(index_blob(&raw, id).await.starts_with(&ZSTD_MAGIC));
+
+    let store = sealed_store(dir.path(), MasterKey::generate());
+    sync::sync_keys(&store, BUCKET, &notes[1..]).await.unwrap();
+    assert!(crypto::is_encrypted(&index_blob(&raw, id).await));
+    let index_dir = tempfile::tempdir().unwrap();
+    let loaded = download_shard(&store, BUCKET, Shard::Client(id), index_dir.path())
+        .await
+        .unwrap();
+    assert_eq!(search(&loaded.index, "enuresis", 10).unwrap().len(), 2);
+}
@@ -0,0 +1,350 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use claria_core::s3_keys;
+use claria_core::schema::SCHEMA_VERSION;
+use claria_search::error::SearchError;
+use claria_search::flush::flush_shard;
+use claria_search::index::{SCHEMA_VERSION_FILE, download_shard};
+use claria_search::journal::{self, Journal, MAX_FLUSH_ATTEMPTS};
+use claria_search::query::search;
+use claria_search::shard::Shard;
+use claria_search::sync;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects::{
+    self, GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use claria_storage::store::{BoxFuture, ObjectStore, Precondition};
+
+const BUCKET: &str = "123456789012-claria-data";
+
+/// A store where another device syncs `other_key` just before each of the
+/// next `races` conditional flushes or creates of its shard, so those lose
+/// the race. With an `other_body`, the other device writes `other_key` first.
+struct RacingStore {
+    inner: LocalStore,
+    other_key: String,
+    other_body: Option<Vec<u8>>,
+    races: AtomicUsize,
+}
+
+impl ObjectStore for RacingStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        self.inner.get_object(bucket, key, version_id)
+    }
+
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        self.inner.get_object_stream(bucket, key, version_id)
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        self.inner.get_object_if_none_match(bucket, key, etag)
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        self.inner.head_object(bucket, key)
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let race = key == Shard::for_key(&self.other_key).object_key()
+                && !matches!(precondition, Precondition::None)
+                && self
+                    .races
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok();
+            if race {
+                if let Some(body) = &self.other_body {
+                    objects::put_object(&self.inner, bucket, &self.other_key, body.clone(), None)
+                        .await
+                        .unwrap();
+                }
+                sync::sync_keys(&self.inner, bucket, std::slice::from_ref(&self.other_key))
+                    .await
+                    .unwrap();
+            }
+            self.inner
+                .put_object(bucket, key, body, content_type, metadata, precondition)
+                .await
+        })
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .create_multipart_upload(bucket, key, content_type, metadata)
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        self.inner
+            .upload_part(bucket, key, upload_id, part_number, body, is_last)
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        self.inner.list_parts(bucket, key, upload_id)
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .complete_multipart_upload(bucket, key, upload_id, parts, precondition)
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.abort_multipart_upload(bucket, key, upload_id)
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.delete_object(bucket, key)
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.delete_object_version(bucket, key, version_id)
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.delete_object_versions(bucket, versions)
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        self.inner.list_objects(bucket, prefix)
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        self.inner.list_versions(bucket, prefix)
+    }
+
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner.presign_get(bucket, key, expires_in)
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .presign_put(bucket, key, content_type, expires_in)
+    }
+}
+
+/// A bucket with an index holding one note, and a second note written by
+/// another device that is not indexed yet.
+async fn racing_store(dir: &std::path::Path, races: usize) -> (RacingStore, String, String) {
+    let inner = LocalStore::new(dir);
+    let id = uuid::Uuid::new_v4();
+    let ours = s3_keys::client_record_file(id, "ours.txt");
+    let theirs = s3_keys::client_record_file(id, "theirs.txt");
+    for (key, body) in [(&ours, "ours sleep"), (&theirs, "theirs sleep")] {
+        objects::put_object(&inner, BUCKET, key, body.as_bytes().to_vec(), None)
+            .await
+            .unwrap();
+    }
+    // Start from an existing index so flushes are conditional.
+    sync::sync_keys(&inner, BUCKET, std::slice::from_ref(&ours))
+        .await
+        .unwrap();
+    let store = RacingStore {
+        inner,
+        other_key: theirs.clone(),
+        other_body: None,
+        races: AtomicUsize::new(races),
+    };
+    (store, ours, theirs)
+}
+
+async fn indexed_keys(store: &dyn ObjectStore, shard: Shard) -> Vec<String> {
+    let dir = tempfile::tempdir().unwrap();
+    let loaded = download_shard(store, BUCKET, shard, dir.path())
+        .await
+        .unwrap();
+    let mut keys: Vec<_> = search(&loaded.index, "sleep", 10)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.s3_key)
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[tokio::test]
+async fn mismatch_replays_journal_onto_the_other_writers_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let (store, ours, theirs) = racing_store(dir.path(), 1).await;
+
+    let mut journal = Journal::new();
+    journal.delete(ours.as_str());
+    let committed = journal::commit_journal(&store, BUCKET, &journal)
+        .await
+        .unwrap();
+    assert_eq!(committed.attempts, 2);
+
+    // Our delete landed on top of the other device's insert.
+    let shard = Shard::for_key(&ours);
+    assert_eq!(indexed_keys(&store, shard).await, vec![theirs]);
+}
+
+#[tokio::test]
+async fn creating_a_shard_replays_onto_one_created_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let inner = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let ours = s3_keys::client_record_file(id, "ours.txt");
+    let theirs = s3_keys::client_record_file(id, "theirs.txt");
+    for (key, body) in [(&ours, "ours sleep"), (&theirs, "theirs sleep")] {
+        objects::put_object(&inner, BUCKET, key, body.as_bytes().to_vec(), None)
+            .await
+            .unwrap();
+    }
+    // The client has no shard yet; the other device creates it first.
+    let store = RacingStore {
+        inner,
+        other_key: theirs.clone(),
+        other_body: None,
+        races: AtomicUsize::new(1),
+    };
+
+    sync::sync_keys(&store, BUCKET, std::slice::from_ref(&ours))
+        .await
+        .unwrap();
+    let shard = Shard::for_key(&ours);
+    assert_eq!(indexed_keys(&store, shard).await, vec![ours, theirs]);
+}
+
+#[tokio::test]
+async fn gives_up_after_bounded_retries() {
+    let dir = tempfile::tempdir().unwrap();
+    let (store, ours, _) = racing_store(dir.path(), MAX_FLUSH_ATTEMPTS).await;
+
+    let mut journal = Journal::new();
+    journal.delete(ours.as_str());
+    let result = journal::commit_journal(&store, BUCKET, &journal).await;
+    assert!(matches!(result, Err(SearchError::ETagMismatch)));
+}
+
+#[tokio::test]
+async fn rebuild_on_download_keeps_a_shard_flushed_while_it_ran() {
+    let dir = tempfile::tempdir().unwrap();
+    let inner = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let ours = s3_keys::client_record_file(id, "ours.txt");
+    let theirs = s3_keys::client_record_file(id, "theirs.txt");
+    objects::put_object(&inner, BUCKET, &ours, b"ours sleep".to_vec(), None)
+        .await
+        .unwrap();
+    sync::sync_keys(&inner, BUCKET, std::slice::from_ref(&ours))
+        .await
+        .unwrap();
+
+    // Mark the shard as built with an older schema, so downloading it
+    // rebuilds it.
+    let shard = Shard::for_key(&ours);
+    let old_dir = tempfile::tempdir().unwrap();
+    let loaded = download_shard(&inner, BUCKET, shard, old_dir.path())
+        .await
+        .unwrap();
+    std::fs::write(
+        old_dir.path().join(SCHEMA_VERSION_FILE),
+        (SCHEMA_VERSION - 1).to_string(),
+    )
+    .unwrap();
+    flush_shard(&inner, BUCKET, shard, old_dir.path(), &loaded.etag)
+        .await
+        .unwrap();
+
+    // Another device writes and indexes a note after the rebuild has listed
+    // the bucket, just before it flushes the shard.
+    let store = RacingStore {
+        inner,
+        other_key: theirs.clone(),
+        other_body: Some(b"theirs sleep".to_vec()),
+        races: AtomicUsize::new(1),
+    };
+    assert_eq!(indexed_keys(&store, shard).await, vec![ours, theirs]);
+}
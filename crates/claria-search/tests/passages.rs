@@ -0,0 +1,125 @@
+use claria_core::schema::doc_type;
+use claria_search::chunk::{PASSAGE_CHARS, PASSAGE_OVERLAP, split_passages};
+use claria_search::document::IndexDocument;
+use claria_search::index::create_empty_index;
+use claria_search::journal::Journal;
+use claria_search::query::{SearchQuery, search_passages, search_with};
+
+/// `words` filler words, with `marker` as word `at`.
+fn text(words: usize, marker: &str, at: usize) -> String {
+    (0..words)
+        .map(|n| {
+            if n == at {
+                marker.to_string()
+            } else {
+                format!("w{n:04}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn record_file(id: &str, body: &str) -> IndexDocument {
+    IndexDocument {
+        id: id.to_string(),
+        doc_type: doc_type::RECORD_FILE,
+        client_id: Some("a".to_string()),
+        title: id.to_string(),
+        body: body.to_string(),
+        s3_key: id.to_string(),
+        updated_at: 1,
+        chunked: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn passages_overlap_and_break_between_words() {
+    assert!(split_passages("  \n ").is_empty());
+    assert_eq!(split_passages("short note").len(), 1);
+
+    let body = text(1_000, "w0000", 0);
+    let chars: Vec<char> = body.chars().collect();
+    let passages = split_passages(&body);
+    assert!(passages.len() > 1);
+    assert_eq!(passages[0].start, 0);
+    assert_eq!(passages.last().unwrap().end, chars.len());
+    for (n, passage) in passages.iter().enumerate() {
+        assert_eq!(passage.ordinal, n);
+        assert!(passage.end - passage.start <= PASSAGE_CHARS);
+        let slice: String = chars[passage.start..passage.end].iter().collect();
+        assert_eq!(passage.text, slice);
+        assert!(!passage.text.starts_with(' ') && !passage.text.ends_with(' '));
+    }
+    for pair in passages.windows(2) {
+        let overlap = pair[0].end - pair[1].start;
+        assert!(overlap > 0 && overlap <= PASSAGE_OVERLAP);
+    }
+}
+
+#[test]
+fn search_returns_the_best_passages_and_replaces_them_on_update() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let mut journal = Journal::new();
+    journal.insert(record_file("eval", &text(2_000, "enuresis", 1_500)));
+    journal.apply(&index).unwrap();
+
+    // One hit for the file, pointing at the passage holding the match.
+    let query = SearchQuery {
+        text: Some("enuresis".to_string()),
+        limit: 10,
+        ..Default::default()
+    };
+    let results = search_with(&index, &query).unwrap();
+    assert_eq!(results.total, 1);
+    assert_eq!(results.doc_type_counts.len(), 1);
+    let hit = &results.hits[0];
+    assert_eq!(hit.id, "eval");
+    assert!(!hit.passages.is_empty());
+    let best = &hit.passages[0];
+    let offset = 1_500 * "w0000 ".len();
+    assert!(best.start <= offset && offset < best.end);
+    let snippet = best.snippet.as_ref().unwrap();
+    assert_eq!(&snippet.fragment[snippet.highlights[0].clone()], "enuresis");
+
+    let passages = search_passages(&index, "enuresis", Some("a"), 5).unwrap();
+    assert!(!passages.is_empty());
+    assert!(passages.iter().all(|p| p.parent_key == "eval"));
+    assert!(passages[0].text.contains("enuresis"));
+    assert!(
+        search_passages(&index, "enuresis", Some("b"), 5)
+            .unwrap()
+            .is_empty()
+    );
+
+    // A shorter new version leaves none of the old passages behind.
+    let mut journal = Journal::new();
+    journal.update(record_file("eval", "encopresis only"));
+    journal.apply(&index).unwrap();
+    assert!(
+        search_passages(&index, "enuresis", None, 5)
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        search_passages(&index, "w0010", None, 5)
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(
+        search_passages(&index, "encopresis", None, 5)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let mut journal = Journal::new();
+    journal.delete("eval");
+    journal.apply(&index).unwrap();
+    assert!(
+        search_passages(&index, "encopresis", None, 5)
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,152 @@
+use claria_core::schema::doc_type;
+use claria_search::document::IndexDocument;
+use claria_search::index::create_empty_index;
+use claria_search::journal::Journal;
+use claria_search::query::{SearchQuery, SortOrder, TimeRange, search_with};
+
+fn document(
+    id: &str,
+    kind: &'static str,
+    client_id: Option<&str>,
+    body: &str,
+    updated_at: i64,
+) -> IndexDocument {
+    IndexDocument {
+        id: id.to_string(),
+        doc_type: kind,
+        client_id: client_id.map(str::to_string),
+        title: id.to_string(),
+        body: body.to_string(),
+        s3_key: id.to_string(),
+        created_at: Some(updated_at),
+        updated_at,
+        ..Default::default()
+    }
+}
+
+fn ids(results: &claria_search::query::SearchResults) -> Vec<&str> {
+    results.hits.iter().map(|h| h.id.as_str()).collect()
+}
+
+#[test]
+fn filters_facets_and_recency() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let mut journal = Journal::new();
+    for (id, kind, client_id, body, updated_at) in [
+        (
+            "a-note",
+            doc_type::RECORD_FILE,
+            Some("a"),
+            "sleep sleep",
+            100,
+        ),
+        ("a-chat", doc_type::CHAT_HISTORY, Some("a"), "sleep", 300),
+        ("b-note", doc_type::RECORD_FILE, Some("b"), "sleep", 200),
+        ("snippet", doc_type::SNIPPET, None, "appetite", 400),
+    ] {
+        journal.insert(document(id, kind, client_id, body, updated_at));
+    }
+    journal.apply(&index).unwrap();
+
+    // Text and client, with facets across both of client a's types.
+    let query = SearchQuery {
+        text: Some("sleep".to_string()),
+        client_id: Some("a".to_string()),
+        limit: 10,
+        ..Default::default()
+    };
+    let results = search_with(&index, &query).unwrap();
+    assert_eq!(results.total, 2);
+    assert_eq!(results.hits[0].id, "a-note");
+    assert_eq!(results.hits[0].client_id.as_deref(), Some("a"));
+    assert_eq!(
+        results.doc_type_counts,
+        vec![
+            (doc_type::RECORD_FILE.to_string(), 1),
+            (doc_type::CHAT_HISTORY.to_string(), 1),
+        ]
+    );
+
+    // The doc_type filter narrows the hits but not the facets.
+    let query = SearchQuery {
+        doc_types: vec![doc_type::CHAT_HISTORY.to_string()],
+        ..query
+    };
+    let results = search_with(&index, &query).unwrap();
+    assert_eq!(ids(&results), vec!["a-chat"]);
+    assert_eq!(results.doc_type_counts.len(), 2);
+
+    // No text: everything, newest first, limited.
+    let query = SearchQuery {
+        sort: SortOrder::Recency,
+        limit: 3,
+        ..Default::default()
+    };
+    let results = search_with(&index, &query).unwrap();
+    assert_eq!(results.total, 4);
+    assert_eq!(ids(&results), vec!["snippet", "a-chat", "b-note"]);
+
+    // A half-open range on updated_at.
+    let query = SearchQuery {
+        updated_at: TimeRange {
+            from: Some(200),
+            to: Some(400),
+        },
+        sort: SortOrder::Recency,
+        limit: 10,
+        ..Default::default()
+    };
+    let results = search_with(&index, &query).unwrap();
+    assert_eq!(ids(&results), vec!["a-chat", "b-note"]);
+}
+
+#[test]
+fn snippets_highlight_matches_and_pages_cover_every_hit() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let mut journal = Journal::new();
+    for n in 0..5 {
+        let body = format!("Session {n}: parent reports trouble with sleep onset.");
+        journal.insert(document(
+            &format!("note-{n}"),
+            doc_type::RECORD_FILE,
+            None,
+            &body,
+            n,
+        ));
+    }
+    journal.apply(&index).unwrap();
+
+    let mut query = SearchQuery {
+        text: Some("sleep".to_string()),
+        sort: SortOrder::Recency,
+        limit: 2,
+        ..Default::default()
+    };
+    let mut pages = Vec::new();
+    loop {
+        let results = search_with(&index, &query).unwrap();
+        assert_eq!(results.total, 5);
+        for hit in &results.hits {
+            let snippet = hit.snippet.as_ref().unwrap();
+            assert_eq!(snippet.highlights.len(), 1);
+            assert_eq!(&snippet.fragment[snippet.highlights[0].clone()], "sleep");
+        }
+        pages.push(ids(&results).join(","));
+        match results.next_offset {
+            Some(next) => query.offset = next,
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec!["note-4,note-3", "note-2,note-1", "note-0"]);
+
+    // Filter-only queries have nothing to highlight.
+    query.text = None;
+    query.offset = 0;
+    assert!(
+        search_with(&index, &query).unwrap().hits[0]
+            .snippet
+            .is_none()
+    );
+}
@@ -0,0 +1,149 @@
+use claria_core::s3_keys;
+use claria_core::schema::{SCHEMA_VERSION, doc_type};
+use claria_search::error::SearchError;
+use claria_search::flush::flush_index_unconditional;
+use claria_search::index::{
+    SCHEMA_VERSION_FILE, create_empty_index, download_index, download_shards,
+};
+use claria_search::query::search;
+use claria_search::rebuild::{self, RebuildProgress, RebuildSummary};
+use claria_search::shard::Shard;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+async fn put(store: &LocalStore, key: &str, body: &[u8]) {
+    objects::put_object(store, BUCKET, key, body.to_vec(), None)
+        .await
+        .unwrap();
+}
+
+async fn put_json(store: &LocalStore, key: &str, value: serde_json::Value) {
+    put(store, key, &serde_json::to_vec(&value).unwrap()).await;
+}
+
+#[tokio::test]
+async fn rebuild_indexes_everything_and_replaces_a_corrupt_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let client_id = uuid::Uuid::new_v4();
+    let now = "2025-01-01T00:00:00Z";
+
+    put(&store, s3_keys::INDEX, b"not an index").await;
+
+    let client = s3_keys::client(client_id);
+    put_json(
+        &store,
+        &client,
+        serde_json::json!({
+            "id": client_id, "name": "Jane Doe", "created_at": now, "updated_at": now,
+        }),
+    )
+    .await;
+    let eval = s3_keys::client_record_file(client_id, "eval.pdf");
+    put(&store, &eval, b"%PDF").await;
+    put(&store, &format!("{eval}.text"), b"elopement noted").await;
+    let snippet_id = uuid::Uuid::new_v4();
+    let snippet = s3_keys::snippet(snippet_id);
+    put_json(
+        &store,
+        &snippet,
+        serde_json::json!({
+            "id": snippet_id, "title": "Sensory", "body": "seeks vestibular input",
+            "s3_key": snippet, "created_at": now, "updated_at": now,
+        }),
+    )
+    .await;
+    put(&store, &s3_keys::goal(uuid::Uuid::new_v4()), b"{").await;
+
+    let mut reported = Vec::new();
+    let summary = rebuild::rebuild_index(&store, BUCKET, &mut |p| reported.push(*p))
+        .await
+        .unwrap();
+    assert_eq!(
+        summary,
+        RebuildSummary {
+            documents: 3,
+            skipped: 1
+        }
+    );
+    assert_eq!(
+        reported.last(),
+        Some(&RebuildProgress {
+            documents_done: 4,
+            documents_total: 4
+        })
+    );
+
+    let index_dir = tempfile::tempdir().unwrap();
+    let shards = [Shard::Global, Shard::Client(client_id)];
+    let loaded = download_shards(&store, BUCKET, &shards, index_dir.path())
+        .await
+        .unwrap();
+    assert_eq!(loaded.len(), 2);
+    for (query, key, kind, shard) in [
+        ("jane", &client, doc_type::CLIENT, Shard::Global),
+        ("elopement", &eval, doc_type::RECORD_FILE, shards[1]),
+        ("vestibular", &snippet, doc_type::SNIPPET, Shard::Global),
+    ] {
+        for (loaded_shard, loaded) in &loaded {
+            let hits = search(&loaded.index, query, 10).unwrap();
+            if *loaded_shard != shard {
+                assert!(hits.is_empty(), "{query} in {loaded_shard}");
+                continue;
+            }
+            assert_eq!(hits.len(), 1, "{query}");
+            assert_eq!(&hits[0].s3_key, key);
+            assert_eq!(hits[0].doc_type, kind);
+        }
+    }
+}
+
+#[tokio::test]
+async fn download_rebuilds_an_index_with_an_old_schema() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let client_id = uuid::Uuid::new_v4();
+    let now = "2025-01-01T00:00:00Z";
+    let client = s3_keys::client(client_id);
+    put_json(
+        &store,
+        &client,
+        serde_json::json!({
+            "id": client_id, "name": "Jane Doe", "created_at": now, "updated_at": now,
+        }),
+    )
+    .await;
+
+    // An empty index from an older schema, then one from a newer one.
+    for (version, rebuilt) in [(SCHEMA_VERSION - 1, true), (SCHEMA_VERSION + 1, false)] {
+        let old_dir = tempfile::tempdir().unwrap();
+        create_empty_index(old_dir.path()).unwrap();
+        std::fs::write(
+            old_dir.path().join(SCHEMA_VERSION_FILE),
+            version.to_string(),
+        )
+        .unwrap();
+        flush_index_unconditional(&store, BUCKET, old_dir.path())
+            .await
+            .unwrap();
+
+        let index_dir = tempfile::tempdir().unwrap();
+        let result = download_index(&store, BUCKET, index_dir.path()).await;
+        if rebuilt {
+            let loaded = result.unwrap();
+            let hits = search(&loaded.index, "jane", 10).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(
+                std::fs::read_to_string(loaded.index_dir.join(SCHEMA_VERSION_FILE)).unwrap(),
+                SCHEMA_VERSION.to_string()
+            );
+        } else {
+            assert!(matches!(
+                result,
+                Err(SearchError::SchemaMismatch { found, .. }) if found == version
+            ));
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use claria_core::schema::doc_type;
+use claria_search::document::IndexDocument;
+use claria_search::embed::Embedder;
+use claria_search::error::SearchError;
+use claria_search::hybrid::hybrid_search;
+use claria_search::index::create_empty_index;
+use claria_search::journal::Journal;
+use claria_search::vector::{VectorIndex, VectorRefresh, refresh_vectors};
+
+/// Embeds by counting words from a few concept lists, so "insomnia" lands
+/// near "asleep" without sharing a word with it.
+struct ConceptEmbedder {
+    model_id: &'static str,
+}
+
+const CONCEPTS: [&[&str]; 3] = [
+    &["sleep", "asleep", "insomnia", "bedtime", "night"],
+    &["reading", "fluency", "decoding", "phonics"],
+    &["running", "elopement", "wandering", "bolting"],
+];
+
+impl Embedder for ConceptEmbedder {
+    fn model_id(&self) -> &str {
+        self.model_id
+    }
+
+    fn dimensions(&self) -> usize {
+        CONCEPTS.len()
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, SearchError> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let text = text.to_lowercase();
+                let mut vector: Vec<f32> = CONCEPTS
+                    .iter()
+                    .map(|words| {
+                        text.split_whitespace()
+                            .filter(|w| words.contains(w))
+                            .count() as f32
+                    })
+                    .collect();
+                let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt().max(1e-6);
+                vector.iter_mut().for_each(|v| *v /= norm);
+                vector
+            })
+            .collect())
+    }
+}
+
+fn record_file(id: &str, client_id: &str, body: &str) -> IndexDocument {
+    IndexDocument {
+        id: id.to_string(),
+        doc_type: doc_type::RECORD_FILE,
+        client_id: Some(client_id.to_string()),
+        title: id.to_string(),
+        body: body.to_string(),
+        s3_key: id.to_string(),
+        updated_at: 1,
+        chunked: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn refresh_embeds_only_what_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let embedder = ConceptEmbedder {
+        model_id: "concepts",
+    };
+    let mut journal = Journal::new();
+    journal.insert(record_file(
+        "sleep-note",
+        "a",
+        "trouble falling asleep at bedtime",
+    ));
+    journal.insert(record_file(
+        "reading-note",
+        "a",
+        "reading fluency is improving",
+    ));
+    journal.apply(&index).unwrap();
+
+    let refresh = refresh_vectors(dir.path(), &index, &embedder).unwrap();
+    assert_eq!(
+        refresh,
+        VectorRefresh {
+            embedded: 2,
+            removed: 0
+        }
+    );
+    let saved = VectorIndex::open(dir.path()).unwrap();
+    assert_eq!(saved.model_id, "concepts");
+    assert_eq!(saved.entries.len(), 2);
+    assert_eq!(saved.entries[0].vector.len(), 3);
+    assert!(
+        !refresh_vectors(dir.path(), &index, &embedder)
+            .unwrap()
+            .changed()
+    );
+
+    // Changed text is re-embedded, a deleted file's vectors dropped.
+    let mut journal = Journal::new();
+    journal.update(record_file("sleep-note", "a", "night waking most nights"));
+    journal.delete("reading-note");
+    journal.apply(&index).unwrap();
+    let refresh = refresh_vectors(dir.path(), &index, &embedder).unwrap();
+    assert_eq!(
+        refresh,
+        VectorRefresh {
+            embedded: 1,
+            removed: 1
+        }
+    );
+
+    // Another model starts over.
+    let other = ConceptEmbedder { model_id: "other" };
+    let refresh = refresh_vectors(dir.path(), &index, &other).unwrap();
+    assert_eq!(
+        refresh,
+        VectorRefresh {
+            embedded: 1,
+            removed: 1
+        }
+    );
+}
+
+#[test]
+fn hybrid_search_finds_passages_by_meaning_and_by_words() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(dir.path()).unwrap();
+    let embedder = ConceptEmbedder {
+        model_id: "concepts",
+    };
+    let mut journal = Journal::new();
+    journal.insert(record_file(
+        "sleep-note",
+        "a",
+        "trouble falling asleep at bedtime",
+    ));
+    journal.insert(record_file("run-note", "a", "bolting from the classroom"));
+    journal.insert(record_file(
+        "b-sleep-note",
+        "b",
+        "asleep by bedtime most nights",
+    ));
+    journal.apply(&index).unwrap();
+    refresh_vectors(dir.path(), &index, &embedder).unwrap();
+    let vectors = VectorIndex::open(dir.path()).unwrap();
+
+    // No passage contains "insomnia" or "elopement"; meaning finds them.
+    let hits = hybrid_search(&[(&index, &vectors)], &embedder, "insomnia", Some("a"), 5).unwrap();
+    assert_eq!(hits[0].parent_key, "sleep-note");
+    assert_eq!(hits[0].text, "trouble falling asleep at bedtime");
+    assert!(hits.iter().all(|h| h.client_id.as_deref() == Some("a")));
+    let hits = hybrid_search(&[(&index, &vectors)], &embedder, "elopement", None, 5).unwrap();
+    assert_eq!(hits[0].parent_key, "run-note");
+
+    // Where meaning ties, shared words decide.
+    let hits = hybrid_search(&[(&index, &vectors)], &embedder, "falling asleep", None, 5).unwrap();
+    assert_eq!(hits[0].parent_key, "sleep-note");
+    assert_eq!(hits[1].parent_key, "b-sleep-note");
+
+    let other = ConceptEmbedder { model_id: "other" };
+    assert!(matches!(
+        hybrid_search(&[(&index, &vectors)], &other, "insomnia", None, 5),
+        Err(SearchError::Embedding(_))
+    ));
+}
+
+#[test]
+fn hybrid_search_scales_words_across_shards() {
+    let embedder = ConceptEmbedder {
+        model_id: "concepts",
+    };
+    let dirs = [tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap()];
+    let notes = [
+        ("a-note", "a", "fluency fluency fluency"),
+        (
+            "b-note",
+            "b",
+            "fluency came up once in a long session about many other things",
+        ),
+    ];
+    let mut shards = Vec::new();
+    for (dir, (id, client_id, body)) in dirs.iter().zip(notes) {
+        let index = create_empty_index(dir.path()).unwrap();
+        let mut journal = Journal::new();
+        journal.insert(record_file(id, client_id, body));
+        journal.apply(&index).unwrap();
+        refresh_vectors(dir.path(), &index, &embedder).unwrap();
+        shards.push((index, VectorIndex::open(dir.path()).unwrap()));
+    }
+    let shards: Vec<_> = shards
+        .iter()
+        .map(|(index, vectors)| (index, vectors))
+        .collect();
+
+    // Both mean the same; the weaker word match is not scaled up to the
+    // stronger one just because it is the best in its own shard.
+    let hits = hybrid_search(&shards, &embedder, "fluency", None, 5).unwrap();
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].parent_key, "a-note");
+    assert!(hits[1].score < hits[0].score);
+}
@@ -0,0 +1,341 @@
+use claria_core::s3_keys;
+use claria_core::schema::{SCHEMA_VERSION, doc_type};
+use claria_search::document::{IndexDocument, build_document};
+use claria_search::flush::{flush_index_unconditional, flush_shard};
+use claria_search::index::{SCHEMA_VERSION_FILE, ShardCache, create_empty_index, download_shard};
+use claria_search::journal::Journal;
+use claria_search::mutate::{WRITER_HEAP_BYTES, commit, insert_document};
+use claria_search::purge::purge_key_prefixes;
+use claria_search::query::{SearchQuery, SortOrder, search, search_across};
+use claria_search::rebuild::rebuild_index;
+use claria_search::shard::{Shard, list_client_shards};
+use claria_search::sync;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+async fn put(store: &LocalStore, key: &str, body: &[u8]) {
+    objects::put_object(store, BUCKET, key, body.to_vec(), None)
+        .await
+        .unwrap();
+}
+
+/// A client named `name` with one note reading `note`.
+async fn client_with_note(store: &LocalStore, name: &str, note: &str) -> (uuid::Uuid, Vec<String>) {
+    let id = uuid::Uuid::new_v4();
+    let client = s3_keys::client(id);
+    let value = serde_json::json!({
+        "id": id, "name": name,
+        "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+    });
+    put(store, &client, &serde_json::to_vec(&value).unwrap()).await;
+    let file = s3_keys::client_record_file(id, "notes.txt");
+    put(store, &file, note.as_bytes()).await;
+    (id, vec![client, file])
+}
+
+async fn keys_in(store: &LocalStore, shard: Shard, query: &str) -> Vec<String> {
+    let dir = tempfile::tempdir().unwrap();
+    let loaded = download_shard(store, BUCKET, shard, dir.path())
+        .await
+        .unwrap();
+    let mut keys: Vec<_> = search(&loaded.index, query, 10)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.s3_key)
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn keys_and_prefixes_map_to_shards() {
+    let id = uuid::Uuid::new_v4();
+    let client = Shard::Client(id);
+    assert_eq!(
+        Shard::for_key(&s3_keys::client_record_file(id, "a.txt")),
+        client
+    );
+    assert_eq!(Shard::for_key(&s3_keys::chat_history(id, id)), client);
+    assert_eq!(Shard::for_key(&s3_keys::client(id)), Shard::Global);
+    assert_eq!(Shard::for_key(&s3_keys::snippet(id)), Shard::Global);
+    assert_eq!(client.object_key(), s3_keys::client_index(id));
+    assert_eq!(Shard::Global.object_key(), s3_keys::INDEX);
+
+    assert_eq!(
+        Shard::for_prefix(&s3_keys::client_records_prefix(id)),
+        Some(client)
+    );
+    assert_eq!(
+        Shard::for_prefix(s3_keys::CLIENTS_PREFIX),
+        Some(Shard::Global)
+    );
+    for spanning in ["", "rec", "records/"] {
+        assert_eq!(Shard::for_prefix(spanning), None, "{spanning:?}");
+    }
+}
+
+#[tokio::test]
+async fn record_files_go_to_their_clients_shard() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let (jane, mut keys) = client_with_note(&store, "Jane Doe", "trouble with sleep").await;
+    let (ana, ana_keys) = client_with_note(&store, "Ana Li", "sleep is fine").await;
+    keys.extend(ana_keys);
+    sync::sync_keys(&store, BUCKET, &keys).await.unwrap();
+
+    let mut clients = list_client_shards(&store, BUCKET).await.unwrap();
+    clients.sort();
+    let mut expected = vec![jane, ana];
+    expected.sort();
+    assert_eq!(clients, expected);
+
+    // Names are searchable without opening a shard; notes are not.
+    assert_eq!(
+        keys_in(&store, Shard::Global, "jane").await,
+        [keys[0].clone()]
+    );
+    assert!(keys_in(&store, Shard::Global, "sleep").await.is_empty());
+    assert_eq!(
+        keys_in(&store, Shard::Client(jane), "sleep").await,
+        [keys[1].clone()]
+    );
+    assert_eq!(
+        keys_in(&store, Shard::Client(ana), "sleep").await,
+        [keys[3].clone()]
+    );
+}
+
+#[tokio::test]
+async fn purging_a_client_clears_its_shard_and_global_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let (jane, keys) = client_with_note(&store, "Jane Doe", "trouble with sleep").await;
+    let (ana, ana_keys) = client_with_note(&store, "Ana Li", "sleep is fine").await;
+    sync::sync_keys(&store, BUCKET, &[keys.clone(), ana_keys].concat())
+        .await
+        .unwrap();
+
+    let records = s3_keys::client_records_prefix(jane);
+    let removed = purge_key_prefixes(&store, BUCKET, &[&records, &keys[0]])
+        .await
+        .unwrap();
+    assert_eq!(removed, 2);
+    assert!(keys_in(&store, Shard::Global, "jane").await.is_empty());
+    assert!(
+        keys_in(&store, Shard::Client(jane), "sleep")
+            .await
+            .is_empty()
+    );
+    assert_eq!(keys_in(&store, Shard::Client(ana), "sleep").await.len(), 1);
+
+    // A prefix that spans shards reaches every one of them.
+    let removed = purge_key_prefixes(&store, BUCKET, &["records/"])
+        .await
+        .unwrap();
+    assert_eq!(removed, 1);
+    assert!(
+        keys_in(&store, Shard::Client(ana), "sleep")
+            .await
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn rebuild_deletes_shards_of_clients_with_nothing_indexed() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let (jane, _) = client_with_note(&store, "Jane Doe", "trouble with sleep").await;
+    let gone = uuid::Uuid::new_v4();
+    put(&store, &s3_keys::client_index(gone), b"stale").await;
+
+    rebuild_index(&store, BUCKET, &mut |_| {}).await.unwrap();
+    assert_eq!(list_client_shards(&store, BUCKET).await.unwrap(), [jane]);
+}
+
+#[tokio::test]
+async fn a_stale_shard_is_rebuilt_on_its_own() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let (jane, keys) = client_with_note(&store, "Jane Doe", "trouble with sleep").await;
+    let (ana, ana_keys) = client_with_note(&store, "Ana Li", "sleep is fine").await;
+    sync::sync_keys(&store, BUCKET, &[keys.clone(), ana_keys].concat())
+        .await
+        .unwrap();
+
+    // Mark Jane's shard as built with an older schema.
+    let stale = Shard::Client(jane);
+    let old_dir = tempfile::tempdir().unwrap();
+    let loaded = download_shard(&store, BUCKET, stale, old_dir.path())
+        .await
+        .unwrap();
+    std::fs::write(
+        old_dir.path().join(SCHEMA_VERSION_FILE),
+        (SCHEMA_VERSION - 1).to_string(),
+    )
+    .unwrap();
+    flush_shard(&store, BUCKET, stale, old_dir.path(), &loaded.etag)
+        .await
+        .unwrap();
+
+    let etag = |shard: Shard| {
+        let store = &store;
+        async move {
+            objects::head_object(store, BUCKET, &shard.object_key())
+                .await
+                .unwrap()
+                .etag
+        }
+    };
+    let untouched = [Shard::Global, Shard::Client(ana)];
+    let mut before = Vec::new();
+    for shard in untouched {
+        before.push(etag(shard).await);
+    }
+
+    assert_eq!(keys_in(&store, stale, "sleep").await, [keys[1].clone()]);
+    for (shard, before) in untouched.into_iter().zip(before) {
+        assert_eq!(etag(shard).await, before, "{shard}");
+    }
+}
+
+#[tokio::test]
+async fn upgrading_from_a_single_index_keeps_record_files_searchable() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let (jane, keys) = client_with_note(&store, "Jane Doe", "trouble with sleep").await;
+    let (ana, ana_keys) = client_with_note(&store, "Ana Li", "sleep is fine").await;
+
+    // A version 5 index: one blob holding every document, record files
+    // included, and no client shards.
+    let old_dir = tempfile::tempdir().unwrap();
+    let index = create_empty_index(old_dir.path()).unwrap();
+    let mut writer = index.writer(WRITER_HEAP_BYTES).unwrap();
+    for key in keys.iter().chain(&ana_keys) {
+        let document = build_document(&store, BUCKET, key).await.unwrap().unwrap();
+        for doc in document.into_tantivy(&index) {
+            insert_document(&writer, doc).unwrap();
+        }
+    }
+    commit(&mut writer).unwrap();
+    writer.wait_merging_threads().unwrap();
+    std::fs::write(old_dir.path().join(SCHEMA_VERSION_FILE), "5").unwrap();
+    flush_index_unconditional(&store, BUCKET, old_dir.path())
+        .await
+        .unwrap();
+    assert!(list_client_shards(&store, BUCKET).await.unwrap().is_empty());
+
+    // The first write after the upgrade creates Jane's shard, which must
+    // hold the note indexed before it too.
+    let intake = s3_keys::client_record_file(jane, "intake.txt");
+    put(&store, &intake, b"sleep onset is delayed").await;
+    sync::sync_keys(&store, BUCKET, std::slice::from_ref(&intake))
+        .await
+        .unwrap();
+    assert_eq!(
+        keys_in(&store, Shard::Client(jane), "sleep").await,
+        [intake, keys[1].clone()]
+    );
+
+    // The global index is rebuilt without the record files, and the shards
+    // of clients nothing was written for since are created alongside.
+    assert_eq!(
+        keys_in(&store, Shard::Global, "jane").await,
+        [keys[0].clone()]
+    );
+    assert!(keys_in(&store, Shard::Global, "sleep").await.is_empty());
+    assert_eq!(
+        keys_in(&store, Shard::Client(ana), "sleep").await,
+        [ana_keys[1].clone()]
+    );
+}
+
+#[tokio::test]
+async fn shard_cache_only_downloads_changed_shards() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let (jane, keys) = client_with_note(&store, "Jane Doe", "trouble with sleep").await;
+    let (ana, ana_keys) = client_with_note(&store, "Ana Li", "sleep is fine").await;
+    sync::sync_keys(&store, BUCKET, &[keys.clone(), ana_keys].concat())
+        .await
+        .unwrap();
+    let shards = [Shard::Global, Shard::Client(jane), Shard::Client(ana)];
+
+    // Mark each extracted copy, so a copy downloaded again is told apart.
+    let mut cache = ShardCache::new().unwrap();
+    let loaded = cache
+        .download_shards(&store, BUCKET, &shards)
+        .await
+        .unwrap();
+    for (_, loaded) in &loaded {
+        std::fs::write(loaded.index_dir.join("marker"), b"").unwrap();
+    }
+
+    let note = s3_keys::client_record_file(jane, "notes.txt");
+    put(&store, &note, b"trouble with appetite").await;
+    sync::sync_keys(&store, BUCKET, &[note]).await.unwrap();
+
+    let loaded = cache
+        .download_shards(&store, BUCKET, &shards)
+        .await
+        .unwrap();
+    for (shard, loaded) in &loaded {
+        let kept = loaded.index_dir.join("marker").exists();
+        assert_eq!(kept, *shard != Shard::Client(jane), "{shard}");
+    }
+    let (_, jane_shard) = &loaded[1];
+    assert!(search(&jane_shard.index, "sleep", 10).unwrap().is_empty());
+    assert_eq!(search(&jane_shard.index, "appetite", 10).unwrap().len(), 1);
+}
+
+#[test]
+fn search_across_merges_and_pages_through_shards() {
+    let document = |id: &str, updated_at: i64| IndexDocument {
+        id: id.to_string(),
+        doc_type: doc_type::SNIPPET,
+        title: "Sleep".to_string(),
+        body: "sleep".to_string(),
+        s3_key: id.to_string(),
+        updated_at,
+        ..Default::default()
+    };
+    let dirs = [tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap()];
+    let indexes: Vec<_> = dirs
+        .iter()
+        .map(|dir| create_empty_index(dir.path()).unwrap())
+        .collect();
+    for (index, documents) in indexes
+        .iter()
+        .zip([[("a", 4), ("c", 2)], [("b", 3), ("d", 1)]])
+    {
+        let mut journal = Journal::new();
+        for (id, updated_at) in documents {
+            journal.insert(document(id, updated_at));
+        }
+        journal.apply(index).unwrap();
+    }
+    let indexes: Vec<_> = indexes.iter().collect();
+
+    let mut query = SearchQuery {
+        text: Some("sleep".to_string()),
+        sort: SortOrder::Recency,
+        limit: 2,
+        ..Default::default()
+    };
+    let mut pages = Vec::new();
+    loop {
+        let results = search_across(&indexes, &query).unwrap();
+        assert_eq!(results.total, 4);
+        assert_eq!(
+            results.doc_type_counts,
+            [(doc_type::SNIPPET.to_string(), 4)]
+        );
+        pages.push(results.hits.into_iter().map(|h| h.id).collect::<Vec<_>>());
+        match results.next_offset {
+            Some(next) => query.offset = next,
+            None => break,
+        }
+    }
+    assert_eq!(pages, [["a", "b"], ["c", "d"]]);
+}
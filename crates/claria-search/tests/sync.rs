@@ -0,0 +1,107 @@
+use claria_core::s3_keys;
+use claria_core::schema::doc_type;
+use claria_search::index::download_shards;
+use claria_search::query::{SearchQuery, search_across};
+use claria_search::shard::shards_for_search;
+use claria_search::sync::{self, SyncSummary};
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+async fn put(store: &LocalStore, key: &str, body: &[u8]) {
+    objects::put_object(store, BUCKET, key, body.to_vec(), None)
+        .await
+        .unwrap();
+}
+
+async fn hits(store: &LocalStore, query: &str) -> Vec<(String, String)> {
+    let dir = tempfile::tempdir().unwrap();
+    let shards = shards_for_search(store, BUCKET, None).await.unwrap();
+    let loaded = download_shards(store, BUCKET, &shards, dir.path())
+        .await
+        .unwrap();
+    let indexes: Vec<_> = loaded.iter().map(|(_, loaded)| &loaded.index).collect();
+    let query = SearchQuery {
+        text: Some(query.to_string()),
+        limit: 10,
+        ..Default::default()
+    };
+    let mut hits: Vec<_> = search_across(&indexes, &query)
+        .unwrap()
+        .hits
+        .into_iter()
+        .map(|r| (r.s3_key, r.doc_type))
+        .collect();
+    hits.sort();
+    hits
+}
+
+#[tokio::test]
+async fn sync_indexes_records_and_follows_sidecars() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+
+    let client = s3_keys::client(id);
+    put(
+        &store,
+        &client,
+        &serde_json::to_vec(&serde_json::json!({
+            "id": id,
+            "name": "Jane Doe",
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap(),
+    )
+    .await;
+    let note = s3_keys::client_record_file(id, "notes.txt");
+    put(&store, &note, b"reports trouble with sleep").await;
+    let eval = s3_keys::client_record_file(id, "eval.pdf");
+    put(&store, &eval, b"%PDF").await;
+    let sidecar = format!("{eval}.text");
+    put(&store, &sidecar, b"ADOS-2 administered, elopement noted").await;
+    // Not indexed.
+    put(&store, s3_keys::SYSTEM_PROMPT, b"sleep").await;
+
+    let keys = [&client, &note, &eval, &sidecar, s3_keys::SYSTEM_PROMPT];
+    let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    let summary = sync::sync_keys(&store, BUCKET, &keys).await.unwrap();
+    assert_eq!(
+        summary,
+        SyncSummary {
+            upserted: 3,
+            deleted: 0
+        }
+    );
+
+    assert_eq!(
+        hits(&store, "jane").await,
+        vec![(client.clone(), doc_type::CLIENT.to_string())]
+    );
+    assert_eq!(
+        hits(&store, "sleep").await,
+        vec![(note.clone(), doc_type::RECORD_FILE.to_string())]
+    );
+    assert_eq!(
+        hits(&store, "elopement").await,
+        vec![(eval.clone(), doc_type::RECORD_FILE.to_string())]
+    );
+
+    // Rewriting the sidecar re-indexes its file.
+    put(&store, &sidecar, b"no concerns").await;
+    sync::sync_keys(&store, BUCKET, std::slice::from_ref(&sidecar))
+        .await
+        .unwrap();
+    assert!(hits(&store, "elopement").await.is_empty());
+    assert_eq!(hits(&store, "concerns").await.len(), 1);
+
+    // Deleting the note removes it.
+    objects::delete_object(&store, BUCKET, &note).await.unwrap();
+    let summary = sync::sync_keys(&store, BUCKET, std::slice::from_ref(&note))
+        .await
+        .unwrap();
+    assert_eq!(summary.deleted, 1);
+    assert!(hits(&store, "sleep").await.is_empty());
+}
@@ -0,0 +1,663 @@
+//! Full-bucket backups as encrypted `tar.zst` archives.
+//!
+//! [`export`] walks the bucket — the current objects, or with
+//! [`ExportOptions::include_versions`] every version and delete marker — and
+//! streams each body into a tar archive that is compressed with zstd and
+//! sealed with the practice master key, using the segmented envelope format
+//! of [`crate::crypto`]:
+//!
+//! ```text
+//! objects/{n:08}   one entry per archived version, oldest first per key
+//! manifest.json    keys, version IDs, ETags, SHA-256 hashes and sizes
+//! ```
+//!
+//! Bodies are read through the store passed in, so with an `EncryptedStore`
+//! they are archived as plaintext inside the sealed archive and encrypted
+//! again on import. While an entry is being added its body is staged in a
+//! private temporary directory, since tar needs each entry's size up front.
+//!
+//! [`import`] restores an archive into an empty bucket, such as one freshly
+//! provisioned in a new AWS account or region. Every entry is checked
+//! against the manifest before anything is written. Versions are replayed
+//! oldest first so history stays in order, though the restored versions get
+//! new version IDs, ETags and timestamps.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+use crate::crypto::{self, DataKey, MasterKey, Opener};
+use crate::error::StorageError;
+use crate::objects::{self, Metadata, UploadOptions};
+use crate::store::ObjectStore;
+
+const FORMAT: u32 = 1;
+const MANIFEST_PATH: &str = "manifest.json";
+const OBJECTS_DIR: &str = "objects";
+
+/// Plaintext bytes per sealed segment of the archive.
+const SEGMENT_SIZE: usize = 1024 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+
+/// The table of contents of a backup archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    /// The bucket the backup was taken from.
+    pub bucket: String,
+    pub created_at: jiff::Timestamp,
+    pub include_versions: bool,
+    /// In archive order: oldest first within each key.
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// One archived version or delete marker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub key: String,
+    /// Path of the body in the archive; `None` for a delete marker.
+    pub path: Option<String>,
+    pub version_id: Option<String>,
+    pub etag: Option<String>,
+    /// Hex SHA-256 of the archived body.
+    pub sha256: Option<String>,
+    pub size: u64,
+    pub content_type: Option<String>,
+    pub last_modified: Option<String>,
+    pub is_delete_marker: bool,
+}
+
+impl Manifest {
+    /// Archived versions, excluding delete markers.
+    pub fn objects(&self) -> usize {
+        self.entries.iter().filter(|e| !e.is_delete_marker).count()
+    }
+
+    pub fn delete_markers(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_delete_marker).count()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+}
+
+/// Options for [`export`].
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Archive every version and delete marker, not just current objects.
+    pub include_versions: bool,
+}
+
+/// Progress of an [`export`] or [`import`].
+#[derive(Debug, Clone)]
+pub struct BackupProgress {
+    pub entries_done: usize,
+    pub entries_total: usize,
+    pub bytes_done: u64,
+}
+
+/// Write a backup of `bucket` to `path`, sealed with `key`.
+///
+/// The archive is written next to `path` and renamed into place once
+/// complete, so a failed export never leaves a partial archive. Objects
+/// deleted while the export runs are left out.
+pub async fn export(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    path: &Path,
+    key: &MasterKey,
+    options: &ExportOptions,
+    on_progress: &mut (dyn FnMut(&BackupProgress) + Send),
+) -> Result<Manifest, StorageError> {
+    let planned = plan(store, bucket, options.include_versions).await?;
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".part");
+    let tmp_path = path.with_file_name(tmp_name);
+    let staging = StagingDir::create()?;
+
+    let mut manifest = Manifest {
+        format: FORMAT,
+        bucket: bucket.to_string(),
+        created_at: jiff::Timestamp::now(),
+        include_versions: options.include_versions,
+        entries: Vec::with_capacity(planned.len()),
+    };
+
+    let result = async {
+        let file = std::fs::File::create(&tmp_path)?;
+        let sealer = SealingWriter::new(file, key)?;
+        let mut tar = tar::Builder::new(zstd::Encoder::new(sealer, ZSTD_LEVEL)?);
+
+        let total = planned.len();
+        let mut bytes_done = 0;
+        for (n, entry) in planned.into_iter().enumerate() {
+            if let Some(entry) =
+                archive_entry(store, bucket, &mut tar, staging.path(), n, entry).await?
+            {
+                bytes_done += entry.size;
+                manifest.entries.push(entry);
+            }
+            on_progress(&BackupProgress {
+                entries_done: n + 1,
+                entries_total: total,
+                bytes_done,
+            });
+        }
+
+        let body = serde_json::to_vec_pretty(&manifest)?;
+        tar.append_data(
+            &mut entry_header(body.len() as u64),
+            MANIFEST_PATH,
+            &body[..],
+        )?;
+        let file = tar.into_inner()?.finish()?.finish()?;
+        file.sync_all()?;
+        Ok::<_, StorageError>(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            std::fs::rename(&tmp_path, path)?;
+            tracing::info!(
+                bucket,
+                objects = manifest.objects(),
+                delete_markers = manifest.delete_markers(),
+                bytes = manifest.bytes(),
+                "backup exported"
+            );
+            Ok(manifest)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Restore the backup at `path` into `bucket`, which must be empty.
+///
+/// The archive is read twice: once to check every entry against the
+/// manifest, and again to write the objects.
+pub async fn import(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    path: &Path,
+    key: &MasterKey,
+    on_progress: &mut (dyn FnMut(&BackupProgress) + Send),
+) -> Result<Manifest, StorageError> {
+    if !store.list_objects(bucket, "").await?.is_empty() {
+        return Err(StorageError::Backup(format!(
+            "bucket {bucket} is not empty; backups can only be restored into an empty bucket"
+        )));
+    }
+
+    let manifest = {
+        let (path, key) = (path.to_path_buf(), key.clone());
+        tokio::task::spawn_blocking(move || verify(&path, &key))
+            .await
+            .map_err(|e| StorageError::Backup(e.to_string()))??
+    };
+
+    // The archive is read on a blocking thread, which stages one body at a
+    // time for the upload loop below.
+    let staging = StagingDir::create()?;
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let reader = {
+        let (path, key, dir) = (
+            path.to_path_buf(),
+            key.clone(),
+            staging.path().to_path_buf(),
+        );
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = stage_entries(&path, &key, &dir, &tx) {
+                let _ = tx.blocking_send(Err(e));
+            }
+        })
+    };
+
+    let total = manifest.entries.len();
+    let mut bytes_done = 0;
+    for (n, entry) in manifest.entries.iter().enumerate() {
+        if entry.is_delete_marker {
+            objects::delete_object(store, bucket, &entry.key).await?;
+        } else {
+            let staged: StagedEntry = rx.recv().await.ok_or_else(|| {
+                StorageError::Backup("archive ended before the manifest".to_string())
+            })??;
+            check_entry(entry, &staged.path, &staged.sha256, staged.size)?;
+
+            let mut metadata = Metadata::new();
+            if let Some(sha256) = &entry.sha256 {
+                metadata.insert(objects::SHA256_METADATA_KEY.to_string(), sha256.clone());
+            }
+            let options = UploadOptions {
+                content_type: entry.content_type.as_deref(),
+                metadata,
+                ..Default::default()
+            };
+            objects::upload_file(
+                store,
+                bucket,
+                &entry.key,
+                &staged.file,
+                &options,
+                &mut |_| {},
+            )
+            .await?;
+            std::fs::remove_file(&staged.file)?;
+            bytes_done += entry.size;
+        }
+        on_progress(&BackupProgress {
+            entries_done: n + 1,
+            entries_total: total,
+            bytes_done,
+        });
+    }
+
+    drop(rx);
+    reader
+        .await
+        .map_err(|e| StorageError::Backup(e.to_string()))?;
+
+    tracing::info!(
+        bucket,
+        source_bucket = manifest.bucket,
+        objects = manifest.objects(),
+        delete_markers = manifest.delete_markers(),
+        "backup imported"
+    );
+    Ok(manifest)
+}
+
+/// Read a backup's manifest, checking every entry's hash and size.
+pub fn verify(path: &Path, key: &MasterKey) -> Result<Manifest, StorageError> {
+    let mut archive = open_archive(path, key)?;
+
+    let mut manifest: Option<Manifest> = None;
+    let mut bodies: HashMap<String, (String, u64)> = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        if entry_path == MANIFEST_PATH {
+            let mut body = Vec::new();
+            entry.read_to_end(&mut body)?;
+            manifest = Some(serde_json::from_slice(&body)?);
+        } else {
+            let (sha256, size) = hash_reader(&mut entry, &mut std::io::sink())?;
+            bodies.insert(entry_path, (sha256, size));
+        }
+    }
+
+    let manifest =
+        manifest.ok_or_else(|| StorageError::Backup("archive has no manifest".to_string()))?;
+    if manifest.format != FORMAT {
+        return Err(StorageError::Backup(format!(
+            "unsupported backup format {}",
+            manifest.format
+        )));
+    }
+    for entry in manifest.entries.iter().filter(|e| !e.is_delete_marker) {
+        let entry_path = entry.path.as_deref().unwrap_or_default();
+        let (sha256, size) = bodies.remove(entry_path).ok_or_else(|| {
+            StorageError::Backup(format!("archive is missing the body of {}", entry.key))
+        })?;
+        check_entry(entry, entry_path, &sha256, size)?;
+    }
+    if !bodies.is_empty() {
+        return Err(StorageError::Backup(format!(
+            "archive has {} entries not in its manifest",
+            bodies.len()
+        )));
+    }
+    Ok(manifest)
+}
+
+/// A version or delete marker to archive.
+struct Planned {
+    key: String,
+    version_id: Option<String>,
+    last_modified: Option<String>,
+    is_delete_marker: bool,
+}
+
+/// List what to archive, oldest first within each key.
+async fn plan(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    include_versions: bool,
+) -> Result<Vec<Planned>, StorageError> {
+    if !include_versions {
+        let listed = objects::list_objects_with_metadata(store, bucket, "").await?;
+        return Ok(listed
+            .into_iter()
+            .map(|o| Planned {
+                key: o.key,
+                version_id: None,
+                last_modified: o.last_modified,
+                is_delete_marker: false,
+            })
+            .collect());
+    }
+
+    let mut versions = store.list_versions(bucket, "").await?;
+    // Listings are newest first within a key; reversing first keeps that
+    // order for versions whose times tie or do not parse.
+    versions.reverse();
+    versions.sort_by_cached_key(|v| {
+        let time = v
+            .last_modified
+            .as_deref()
+            .and_then(|t| t.parse::<jiff::Timestamp>().ok());
+        (v.key.clone(), time)
+    });
+    Ok(versions
+        .into_iter()
+        .map(|v| Planned {
+            key: v.key,
+            version_id: Some(v.version_id),
+            last_modified: v.last_modified,
+            is_delete_marker: v.is_delete_marker,
+        })
+        .collect())
+}
+
+/// Add one planned version to the archive and return its manifest entry,
+/// or `None` if it was deleted after the listing.
+async fn archive_entry<W: Write>(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    tar: &mut tar::Builder<W>,
+    staging: &Path,
+    n: usize,
+    planned: Planned,
+) -> Result<Option<ManifestEntry>, StorageError> {
+    if planned.is_delete_marker {
+        return Ok(Some(ManifestEntry {
+            key: planned.key,
+            path: None,
+            version_id: planned.version_id,
+            etag: None,
+            sha256: None,
+            size: 0,
+            content_type: None,
+            last_modified: planned.last_modified,
+            is_delete_marker: true,
+        }));
+    }
+
+    let mut stream = match store
+        .get_object_stream(bucket, &planned.key, planned.version_id.as_deref())
+        .await
+    {
+        Ok(stream) => stream,
+        Err(StorageError::NotFound { .. }) => {
+            tracing::warn!(key = planned.key, "deleted during backup, skipping");
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+
+    let staged_path = staging.join(format!("{n:08}"));
+    let mut staged = tokio::fs::File::create(&staged_path).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    while let Some(chunk) = stream.body.next_chunk().await? {
+        hasher.update(&chunk);
+        staged.write_all(&chunk).await?;
+        size += chunk.len() as u64;
+    }
+    staged.flush().await?;
+    drop(staged);
+
+    let entry_path = format!("{OBJECTS_DIR}/{n:08}");
+    tar.append_data(
+        &mut entry_header(size),
+        &entry_path,
+        std::fs::File::open(&staged_path)?,
+    )?;
+    std::fs::remove_file(&staged_path)?;
+
+    Ok(Some(ManifestEntry {
+        key: planned.key,
+        path: Some(entry_path),
+        version_id: planned.version_id,
+        etag: stream.etag,
+        sha256: Some(format!("{:x}", hasher.finalize())),
+        size,
+        content_type: stream.content_type,
+        last_modified: planned.last_modified,
+        is_delete_marker: false,
+    }))
+}
+
+fn entry_header(size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o600);
+    header.set_entry_type(tar::EntryType::Regular);
+    header
+}
+
+/// A body staged for upload by [`stage_entries`].
+struct StagedEntry {
+    /// Path of the entry in the archive.
+    path: String,
+    /// The staged file.
+    file: PathBuf,
+    sha256: String,
+    size: u64,
+}
+
+/// Copy each object entry of the archive to a file in `dir` and send it
+/// down `tx`, stopping early if the receiver is dropped.
+fn stage_entries(
+    path: &Path,
+    key: &MasterKey,
+    dir: &Path,
+    tx: &tokio::sync::mpsc::Sender<Result<StagedEntry, StorageError>>,
+) -> Result<(), StorageError> {
+    let mut archive = open_archive(path, key)?;
+    for (n, entry) in archive.entries()?.enumerate() {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        if entry_path == MANIFEST_PATH {
+            continue;
+        }
+        let file = dir.join(format!("{n:08}"));
+        let (sha256, size) = hash_reader(&mut entry, &mut std::fs::File::create(&file)?)?;
+        let staged = StagedEntry {
+            path: entry_path,
+            file,
+            sha256,
+            size,
+        };
+        if tx.blocking_send(Ok(staged)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Check an archived body against its manifest entry.
+fn check_entry(
+    entry: &ManifestEntry,
+    entry_path: &str,
+    sha256: &str,
+    size: u64,
+) -> Result<(), StorageError> {
+    if entry.path.as_deref() != Some(entry_path) {
+        return Err(StorageError::Backup(format!(
+            "archive entry {entry_path} is out of order"
+        )));
+    }
+    if entry.sha256.as_deref() != Some(sha256) || entry.size != size {
+        return Err(StorageError::Backup(format!(
+            "archived body of {} does not match the manifest",
+            entry.key
+        )));
+    }
+    Ok(())
+}
+
+/// Copy `reader` to `out`, returning the hex SHA-256 and length.
+fn hash_reader(
+    reader: &mut impl Read,
+    out: &mut impl Write,
+) -> Result<(String, u64), StorageError> {
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        out.write_all(&buf[..n])?;
+        size += n as u64;
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+type ArchiveReader = tar::Archive<zstd::Decoder<'static, std::io::BufReader<OpeningReader>>>;
+
+/// Open a sealed archive for reading. Unsealed files are refused.
+fn open_archive(path: &Path, key: &MasterKey) -> Result<ArchiveReader, StorageError> {
+    let mut file = std::fs::File::open(path)?;
+    let mut magic = [0u8; 8];
+    let n = file.read(&mut magic)?;
+    if !crypto::is_encrypted(&magic[..n]) {
+        return Err(StorageError::Backup(
+            "not a sealed Claria backup".to_string(),
+        ));
+    }
+    file.rewind()?;
+
+    let reader = OpeningReader {
+        inner: file,
+        opener: Some(Opener::new(key.clone())),
+        buf: Vec::new(),
+        pos: 0,
+    };
+    Ok(tar::Archive::new(zstd::Decoder::new(reader)?))
+}
+
+/// Seals everything written to it as a segmented envelope. Call
+/// [`SealingWriter::finish`] to seal the final segment.
+struct SealingWriter<W: Write> {
+    inner: W,
+    key: MasterKey,
+    dek: DataKey,
+    buf: Vec<u8>,
+    index: u64,
+}
+
+impl<W: Write> SealingWriter<W> {
+    fn new(mut inner: W, key: &MasterKey) -> Result<Self, StorageError> {
+        let dek = DataKey::generate();
+        inner.write_all(&crypto::segmented_header(key, &dek)?)?;
+        Ok(Self {
+            inner,
+            key: key.clone(),
+            dek,
+            buf: Vec::with_capacity(SEGMENT_SIZE),
+            index: 0,
+        })
+    }
+
+    fn seal(&mut self, plaintext: &[u8], is_last: bool) -> std::io::Result<()> {
+        let sealed = crypto::seal_segment(&self.key, &self.dek, self.index, is_last, plaintext)
+            .map_err(std::io::Error::other)?;
+        self.index += 1;
+        self.inner.write_all(&sealed)
+    }
+
+    fn finish(mut self) -> std::io::Result<W> {
+        let rest = std::mem::take(&mut self.buf);
+        self.seal(&rest, true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for SealingWriter<W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        // Only seal once more bytes follow, since the final segment is
+        // marked as such.
+        while self.buf.len() > SEGMENT_SIZE {
+            let rest = self.buf.split_off(SEGMENT_SIZE);
+            let segment = std::mem::replace(&mut self.buf, rest);
+            self.seal(&segment, false)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts a sealed file as it is read.
+struct OpeningReader {
+    inner: std::fs::File,
+    /// `None` once the file is exhausted and the opener finished.
+    opener: Option<Opener>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for OpeningReader {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if self.pos < self.buf.len() {
+                let n = out.len().min(self.buf.len() - self.pos);
+                out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+                self.pos += n;
+                return Ok(n);
+            }
+            if self.opener.is_none() {
+                return Ok(0);
+            }
+
+            let mut chunk = vec![0u8; 64 * 1024];
+            let n = self.inner.read(&mut chunk)?;
+            let chunk = (n > 0).then(|| &chunk[..n]);
+            self.buf = Opener::feed(&mut self.opener, chunk).map_err(std::io::Error::other)?;
+            self.pos = 0;
+        }
+    }
+}
+
+/// A private temporary directory for staged plaintext bodies, removed on
+/// drop.
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn create() -> Result<Self, StorageError> {
+        let path =
+            std::env::temp_dir().join(format!("claria-backup-{}", uuid::Uuid::new_v4().simple()));
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&path)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,519 @@
+//! Encrypted on-disk read-through cache for small objects.
+//!
+//! [`CachedStore`] keeps a copy of every object it reads in a local
+//! directory. A later read of the latest version revalidates the copy with a
+//! conditional `If-None-Match` GET, so an unchanged object costs a 304 with
+//! no body instead of a full download. Reads of a specific version are
+//! served from the cache outright, since versions never change.
+//!
+//! Entries may contain PHI, so each file is sealed with
+//! [`crypto::seal_with_header`] under a cache key held on the clinician's
+//! machine, whether or not the bucket itself is encrypted. Files that fail
+//! to open are discarded and refetched. The directory is capped at a byte
+//! budget; once it grows past it, the least recently used entries are
+//! evicted.
+//!
+//! Writes and deletes through the store drop the affected entry. A change
+//! made by another device is picked up on the next revalidation.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{self, MasterKey};
+use crate::error::StorageError;
+use crate::objects::{
+    GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use crate::store::{BoxFuture, ObjectStore, Precondition};
+
+/// Default byte budget for the cache directory.
+pub const DEFAULT_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Objects larger than this fraction of the budget are never cached, so one
+/// big download (such as the search index) cannot flush everything else.
+const MAX_ENTRY_FRACTION: u64 = 8;
+
+/// Once over budget, evict down to this share of it (in percent) so the
+/// next few inserts do not each trigger a scan.
+const EVICT_TO_PERCENT: u64 = 90;
+
+/// What is stored alongside the body in an entry file.
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryMeta {
+    bucket: String,
+    key: String,
+    version_id: Option<String>,
+    etag: Option<String>,
+    content_type: Option<String>,
+    /// Missing from entries written before metadata was cached, which are
+    /// then fetched again.
+    #[serde(default)]
+    metadata: Option<Metadata>,
+}
+
+/// An encrypted directory of cached object bodies.
+pub struct ObjectCache {
+    dir: PathBuf,
+    key: MasterKey,
+    max_bytes: u64,
+    /// Serialises eviction scans.
+    evict_lock: Mutex<()>,
+}
+
+impl ObjectCache {
+    /// Open (creating if needed) a cache directory whose entries are sealed
+    /// with `key`.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        key: MasterKey,
+        max_bytes: u64,
+    ) -> Result<Self, StorageError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            key,
+            max_bytes,
+            evict_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up an entry. `version_id` of `None` means the latest version.
+    pub fn get(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+    ) -> Option<GetObjectOutput> {
+        let path = self.entry_path(bucket, key, version_id);
+        let sealed = std::fs::read(&path).ok()?;
+
+        match self.decode(sealed, bucket, key, version_id) {
+            Ok(output) => {
+                // Mark as recently used for eviction.
+                if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+                Some(output)
+            }
+            Err(e) => {
+                tracing::warn!(key, error = %e, "discarding unreadable cache entry");
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Store an entry, then evict if the cache is over budget. Failures are
+    /// logged rather than returned: the cache is only an optimisation.
+    pub fn insert(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+        output: &GetObjectOutput,
+    ) {
+        if output.body.len() as u64 > self.max_bytes / MAX_ENTRY_FRACTION {
+            return;
+        }
+        if let Err(e) = self.write_entry(bucket, key, version_id, output) {
+            tracing::warn!(key, error = %e, "failed to write cache entry");
+            return;
+        }
+        if let Err(e) = self.evict() {
+            tracing::warn!(error = %e, "cache eviction failed");
+        }
+    }
+
+    /// Drop an entry, if present.
+    pub fn remove(&self, bucket: &str, key: &str, version_id: Option<&str>) {
+        let _ = std::fs::remove_file(self.entry_path(bucket, key, version_id));
+    }
+
+    /// Remove every entry.
+    pub fn clear(&self) -> Result<(), StorageError> {
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Total bytes of entry files currently on disk.
+    pub fn size(&self) -> Result<u64, StorageError> {
+        Ok(self.entries()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Entry files are named by a hash of bucket, key and version, so no
+    /// part of a key (which may contain a client's name) reaches the
+    /// filesystem in the clear.
+    fn entry_path(&self, bucket: &str, key: &str, version_id: Option<&str>) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(bucket.as_bytes());
+        hasher.update([0]);
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        hasher.update(version_id.unwrap_or_default().as_bytes());
+        let name: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        self.dir.join(name)
+    }
+
+    fn write_entry(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+        output: &GetObjectOutput,
+    ) -> Result<(), StorageError> {
+        let meta = serde_json::to_vec(&EntryMeta {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            version_id: version_id.map(str::to_string),
+            etag: output.etag.clone(),
+            content_type: output.content_type.clone(),
+            metadata: Some(output.metadata.clone()),
+        })?;
+        let sealed = crypto::seal_with_header(&self.key, &meta, &output.body)?;
+
+        let path = self.entry_path(bucket, key, version_id);
+        let tmp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        std::fs::write(&tmp_path, sealed)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    fn decode(
+        &self,
+        sealed: Vec<u8>,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+    ) -> Result<GetObjectOutput, StorageError> {
+        let (meta, body) = crypto::open_with_header(&self.key, sealed)?;
+        let meta: EntryMeta = serde_json::from_slice(&meta)?;
+
+        if meta.bucket != bucket || meta.key != key || meta.version_id.as_deref() != version_id {
+            return Err(StorageError::Decryption(
+                "cache entry belongs to a different object".into(),
+            ));
+        }
+
+        let metadata = meta.metadata.ok_or_else(|| {
+            StorageError::Decryption("cache entry predates metadata caching".into())
+        })?;
+        Ok(GetObjectOutput {
+            body,
+            etag: meta.etag,
+            content_type: meta.content_type,
+            metadata,
+        })
+    }
+
+    /// Every entry file with its size and last-used time.
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>, StorageError> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            // Entries can disappear mid-scan when another command evicts.
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_file() {
+                let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                entries.push((entry.path(), meta.len(), modified));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Delete least recently used entries until the cache is back under
+    /// budget.
+    fn evict(&self) -> Result<(), StorageError> {
+        let _guard = self.evict_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        let target = self.max_bytes / 100 * EVICT_TO_PERCENT;
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        let mut evicted = 0;
+        for (path, size, _) in entries {
+            if total <= target {
+                break;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+            total -= size;
+            evicted += 1;
+        }
+
+        tracing::debug!(evicted, remaining_bytes = total, "object cache evicted");
+        Ok(())
+    }
+}
+
+/// Wraps another store and caches whole-object reads in an [`ObjectCache`].
+pub struct CachedStore {
+    inner: Arc<dyn ObjectStore>,
+    cache: ObjectCache,
+}
+
+impl CachedStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, cache: ObjectCache) -> Self {
+        Self { inner, cache }
+    }
+
+    pub fn cache(&self) -> &ObjectCache {
+        &self.cache
+    }
+
+    /// Revalidate a cached copy of the latest version, falling back to a
+    /// plain GET when there is nothing usable to revalidate.
+    async fn get_latest(&self, bucket: &str, key: &str) -> Result<GetObjectOutput, StorageError> {
+        let cached = self.cache.get(bucket, key, None);
+        let etag = cached.as_ref().and_then(|c| c.etag.clone());
+
+        let fresh = match (cached, etag) {
+            (Some(cached), Some(etag)) => {
+                match self
+                    .inner
+                    .get_object_if_none_match(bucket, key, &etag)
+                    .await
+                {
+                    Ok(None) => return Ok(cached),
+                    Ok(Some(fresh)) => fresh,
+                    Err(e @ StorageError::NotFound { .. }) => {
+                        self.cache.remove(bucket, key, None);
+                        return Err(e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            _ => self.inner.get_object(bucket, key, None).await?,
+        };
+
+        self.cache.insert(bucket, key, None, &fresh);
+        Ok(fresh)
+    }
+}
+
+impl ObjectStore for CachedStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        Box::pin(async move {
+            let Some(version_id) = version_id else {
+                return self.get_latest(bucket, key).await;
+            };
+
+            if let Some(cached) = self.cache.get(bucket, key, Some(version_id)) {
+                return Ok(cached);
+            }
+            let output = self.inner.get_object(bucket, key, Some(version_id)).await?;
+            self.cache.insert(bucket, key, Some(version_id), &output);
+            Ok(output)
+        })
+    }
+
+    /// Not cached: streams are for objects too large to hold in memory.
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        self.inner.get_object_stream(bucket, key, version_id)
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        self.inner.get_object_if_none_match(bucket, key, etag)
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        self.inner.head_object(bucket, key)
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            self.cache.remove(bucket, key, None);
+            self.inner
+                .put_object(bucket, key, body, content_type, metadata, precondition)
+                .await
+        })
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .create_multipart_upload(bucket, key, content_type, metadata)
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        self.inner
+            .upload_part(bucket, key, upload_id, part_number, body, is_last)
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        self.inner.list_parts(bucket, key, upload_id)
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            self.cache.remove(bucket, key, None);
+            self.inner
+                .complete_multipart_upload(bucket, key, upload_id, parts, precondition)
+                .await
+        })
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.abort_multipart_upload(bucket, key, upload_id)
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.cache.remove(bucket, key, None);
+            self.inner.delete_object(bucket, key).await
+        })
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.cache.remove(bucket, key, None);
+            self.cache.remove(bucket, key, Some(version_id));
+            self.inner
+                .delete_object_version(bucket, key, version_id)
+                .await
+        })
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            for v in versions {
+                self.cache.remove(bucket, &v.key, None);
+                self.cache.remove(bucket, &v.key, Some(&v.version_id));
+            }
+            self.inner.delete_object_versions(bucket, versions).await
+        })
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        self.inner.list_objects(bucket, prefix)
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        self.inner.list_versions(bucket, prefix)
+    }
+
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner.presign_get(bucket, key, expires_in)
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .presign_put(bucket, key, content_type, expires_in)
+    }
+}
@@ -0,0 +1,566 @@
+//! Client-side envelope encryption for object bodies.
+//!
+//! Every object gets a fresh random AES-256-GCM data key (DEK). The body is
+//! encrypted with the DEK, and the DEK itself is encrypted ("wrapped") with
+//! the practice master key, which never leaves the clinician's machine. The
+//! wrapped DEK travels in a fixed-size header in front of the ciphertext:
+//!
+//! ```text
+//! magic "CLRENC" | version (1) | master key id (8) |
+//! wrap nonce (12) | wrapped DEK + tag (48) | data nonce (12) | ciphertext + tag
+//! ```
+//!
+//! The magic, version and key id are authenticated as associated data on
+//! both layers. Bodies that do not start with the magic are treated as
+//! legacy plaintext, so buckets written before encryption was enabled stay
+//! readable.
+//!
+//! Objects uploaded in parts use a segmented variant (format version 2) so
+//! each part can be encrypted, and each download chunk decrypted, on its own:
+//!
+//! ```text
+//! magic "CLRENC" | version (2) | master key id (8) |
+//! wrap nonce (12) | wrapped DEK + tag (48) |
+//! { segment length (4, BE) | nonce (12) | ciphertext + tag } ...
+//! ```
+//!
+//! Each segment's associated data also covers its index and whether it is
+//! the final segment, so segments cannot be reordered, dropped or truncated
+//! without failing authentication.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::error::StorageError;
+
+const MAGIC: &[u8; 6] = b"CLRENC";
+const FORMAT_VERSION: u8 = 1;
+const SEGMENTED_FORMAT_VERSION: u8 = 2;
+
+const KEY_LEN: usize = 32;
+const KEY_ID_LEN: usize = 8;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const WRAPPED_KEY_LEN: usize = KEY_LEN + TAG_LEN;
+
+/// Bytes covered as associated data: magic, version and key id.
+const AAD_LEN: usize = MAGIC.len() + 1 + KEY_ID_LEN;
+/// Total header length in front of the body ciphertext.
+pub const HEADER_LEN: usize = AAD_LEN + NONCE_LEN + WRAPPED_KEY_LEN + NONCE_LEN;
+/// Header length of the segmented format, before the first segment.
+const SEGMENTED_HEADER_LEN: usize = AAD_LEN + NONCE_LEN + WRAPPED_KEY_LEN;
+/// Bytes in front of each segment's ciphertext: length prefix and nonce.
+const SEGMENT_PREFIX_LEN: usize = 4 + NONCE_LEN;
+
+/// The practice master key that wraps every per-object data key.
+///
+/// The key bytes are zeroed when the value is dropped.
+#[derive(Clone)]
+pub struct MasterKey([u8; KEY_LEN]);
+
+impl MasterKey {
+    /// Generate a new random master key.
+    pub fn generate() -> Self {
+        let key = Aes256Gcm::generate_key(OsRng);
+        Self(key.into())
+    }
+
+    /// Load a master key from its raw 32-byte form.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        let bytes: [u8; KEY_LEN] = bytes.try_into().map_err(|_| {
+            StorageError::Encryption(format!(
+                "master key must be {KEY_LEN} bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+        Ok(Self(bytes))
+    }
+
+    /// The raw key bytes, for persisting the key locally.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// A short, non-secret identifier for this key, recorded in every
+    /// envelope so a wrong key is reported as such rather than as corruption.
+    pub fn key_id(&self) -> [u8; KEY_ID_LEN] {
+        let digest = Sha256::digest(self.0);
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&digest[..KEY_ID_LEN]);
+        id
+    }
+
+    /// The key id as lowercase hex, for display.
+    pub fn key_id_hex(&self) -> String {
+        hex(&self.key_id())
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+impl Drop for MasterKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MasterKey")
+            .field(&self.key_id_hex())
+            .finish()
+    }
+}
+
+/// Whether a body carries an envelope header (as opposed to legacy plaintext).
+pub fn is_encrypted(body: &[u8]) -> bool {
+    body.starts_with(MAGIC)
+}
+
+/// Encrypt `plaintext` under a fresh data key wrapped by `master`.
+pub fn seal(master: &MasterKey, plaintext: &[u8]) -> Result<Vec<u8>, StorageError> {
+    let aad = header_aad(master, FORMAT_VERSION);
+    let dek = DataKey::generate();
+    let wrapped = wrap(master, &dek, &aad)?;
+
+    let data_nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = dek
+        .cipher()
+        .encrypt(
+            &data_nonce,
+            Payload {
+                msg: plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| StorageError::Encryption("failed to encrypt object body".to_string()))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(&aad);
+    out.extend_from_slice(&wrapped);
+    out.extend_from_slice(&data_nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// A per-object data key. Zeroed on drop.
+pub struct DataKey([u8; KEY_LEN]);
+
+impl DataKey {
+    /// A fresh random data key.
+    pub fn generate() -> Self {
+        Self(Aes256Gcm::generate_key(OsRng).into())
+    }
+
+    /// Derive the data key for a multipart upload from the master key and
+    /// the upload ID, so an interrupted upload can be resumed without
+    /// persisting the key.
+    pub fn for_upload(master: &MasterKey, upload_id: &str) -> Result<Self, StorageError> {
+        let mut mac =
+            <Hmac<Sha256> as KeyInit>::new_from_slice(master.as_bytes()).map_err(|_| {
+                StorageError::Encryption("failed to derive upload data key".to_string())
+            })?;
+        mac.update(b"claria-multipart-data-key\0");
+        mac.update(upload_id.as_bytes());
+        Ok(Self(mac.finalize().into_bytes().into()))
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+impl Drop for DataKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// The header of a segmented object, wrapping `dek` under `master`. It must
+/// be followed by the segments produced by [`seal_segment`].
+pub fn segmented_header(master: &MasterKey, dek: &DataKey) -> Result<Vec<u8>, StorageError> {
+    let aad = header_aad(master, SEGMENTED_FORMAT_VERSION);
+    let mut out = Vec::with_capacity(SEGMENTED_HEADER_LEN);
+    out.extend_from_slice(&aad);
+    out.extend_from_slice(&wrap(master, dek, &aad)?);
+    Ok(out)
+}
+
+/// Encrypt one segment of a segmented object. Segments are numbered from 0
+/// and exactly one, the final one, must have `is_last` set.
+pub fn seal_segment(
+    master: &MasterKey,
+    dek: &DataKey,
+    index: u64,
+    is_last: bool,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, StorageError> {
+    let aad = segment_aad(
+        &header_aad(master, SEGMENTED_FORMAT_VERSION),
+        index,
+        is_last,
+    );
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = dek
+        .cipher()
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| StorageError::Encryption("failed to encrypt segment".to_string()))?;
+    let len = u32::try_from(ciphertext.len())
+        .map_err(|_| StorageError::Encryption("segment is too large".to_string()))?;
+
+    let mut out = Vec::with_capacity(SEGMENT_PREFIX_LEN + ciphertext.len());
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Plaintext length of part `part_number` of a segmented object whose
+/// sealed part is `sealed_len` bytes, as uploaded by
+/// [`EncryptedStore`](crate::encrypted::EncryptedStore): one segment per
+/// part, with the header in front of the first.
+pub fn part_plaintext_len(part_number: i32, sealed_len: i64) -> i64 {
+    let header = if part_number == 1 {
+        SEGMENTED_HEADER_LEN
+    } else {
+        0
+    };
+    sealed_len - (header + SEGMENT_PREFIX_LEN + TAG_LEN) as i64
+}
+
+fn header_aad(master: &MasterKey, version: u8) -> [u8; AAD_LEN] {
+    let mut aad = [0u8; AAD_LEN];
+    aad[..MAGIC.len()].copy_from_slice(MAGIC);
+    aad[MAGIC.len()] = version;
+    aad[MAGIC.len() + 1..].copy_from_slice(&master.key_id());
+    aad
+}
+
+fn segment_aad(header_aad: &[u8], index: u64, is_last: bool) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header_aad.len() + 9);
+    aad.extend_from_slice(header_aad);
+    aad.extend_from_slice(&index.to_be_bytes());
+    aad.push(u8::from(is_last));
+    aad
+}
+
+/// Wrap `dek` under `master`, returning the wrap nonce and wrapped key.
+fn wrap(master: &MasterKey, dek: &DataKey, aad: &[u8]) -> Result<Vec<u8>, StorageError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let wrapped = master
+        .cipher()
+        .encrypt(&nonce, Payload { msg: &dek.0, aad })
+        .map_err(|_| StorageError::Encryption("failed to wrap data key".to_string()))?;
+    let mut out = Vec::with_capacity(NONCE_LEN + WRAPPED_KEY_LEN);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&wrapped);
+    Ok(out)
+}
+
+/// Check the header's version and key id, then unwrap the data key that
+/// follows the associated data.
+fn unwrap_header(master: &MasterKey, header: &[u8]) -> Result<DataKey, StorageError> {
+    let (aad, rest) = header.split_at(AAD_LEN);
+    let key_id = &aad[MAGIC.len() + 1..];
+    if key_id != master.key_id() {
+        return Err(StorageError::Decryption(format!(
+            "object was encrypted with master key {}, but the local key is {}",
+            hex(key_id),
+            master.key_id_hex()
+        )));
+    }
+
+    let (wrap_nonce, wrapped) = rest.split_at(NONCE_LEN);
+    let mut bytes = master
+        .cipher()
+        .decrypt(
+            Nonce::from_slice(wrap_nonce),
+            Payload {
+                msg: &wrapped[..WRAPPED_KEY_LEN],
+                aad,
+            },
+        )
+        .map_err(|_| StorageError::Decryption("failed to unwrap data key".to_string()))?;
+    let key = DataKey::from_slice(&bytes);
+    bytes.zeroize();
+    key
+}
+
+impl DataKey {
+    fn from_slice(bytes: &[u8]) -> Result<Self, StorageError> {
+        let bytes: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| StorageError::Decryption("data key has the wrong length".to_string()))?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Seal a serialized header together with a body, for files kept on the
+/// clinician's machine (the object cache, the offline outbox). Layout before
+/// sealing: header length (4, BE) | header | body.
+pub fn seal_with_header(
+    key: &MasterKey,
+    header: &[u8],
+    body: &[u8],
+) -> Result<Vec<u8>, StorageError> {
+    let header_len = u32::try_from(header.len())
+        .map_err(|_| StorageError::Encryption("header too large".to_string()))?;
+
+    let mut plain = Vec::with_capacity(4 + header.len() + body.len());
+    plain.extend_from_slice(&header_len.to_be_bytes());
+    plain.extend_from_slice(header);
+    plain.extend_from_slice(body);
+    seal(key, &plain)
+}
+
+/// Open a file written by [`seal_with_header`] and return the header and
+/// body. Unlike [`open`], plaintext is refused: such files are always
+/// sealed, so an unsealed one has been tampered with.
+pub fn open_with_header(
+    key: &MasterKey,
+    sealed: Vec<u8>,
+) -> Result<(Vec<u8>, Vec<u8>), StorageError> {
+    if !is_encrypted(&sealed) {
+        return Err(StorageError::Decryption("file is not sealed".to_string()));
+    }
+    let mut plain = open(key, sealed)?;
+
+    let truncated = || StorageError::Decryption("sealed file is truncated".to_string());
+    let header_len = plain
+        .get(..4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(truncated)? as usize;
+    let header_end = 4 + header_len;
+    if plain.len() < header_end {
+        return Err(truncated());
+    }
+    let body = plain.split_off(header_end);
+    plain.drain(..4);
+    Ok((plain, body))
+}
+
+/// Decrypt an enveloped body, or return legacy plaintext unchanged.
+pub fn open(master: &MasterKey, body: Vec<u8>) -> Result<Vec<u8>, StorageError> {
+    if !is_encrypted(&body) {
+        return Ok(body);
+    }
+    if body.len() > MAGIC.len() && body[MAGIC.len()] == SEGMENTED_FORMAT_VERSION {
+        let mut opener = Opener::new(master.clone());
+        let mut plaintext = opener.push(&body)?;
+        plaintext.extend(opener.finish()?);
+        return Ok(plaintext);
+    }
+    if body.len() < HEADER_LEN + TAG_LEN {
+        return Err(StorageError::Decryption(
+            "encrypted object is truncated".to_string(),
+        ));
+    }
+
+    let version = body[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(StorageError::Decryption(format!(
+            "unsupported encryption format version {version}"
+        )));
+    }
+
+    let dek = unwrap_header(master, &body[..AAD_LEN + NONCE_LEN + WRAPPED_KEY_LEN])?;
+    let aad = &body[..AAD_LEN];
+    let data_nonce = &body[HEADER_LEN - NONCE_LEN..HEADER_LEN];
+    dek.cipher()
+        .decrypt(
+            Nonce::from_slice(data_nonce),
+            Payload {
+                msg: &body[HEADER_LEN..],
+                aad,
+            },
+        )
+        .map_err(|_| StorageError::Decryption("object body failed authentication".to_string()))
+}
+
+/// Incremental decryption for streamed downloads.
+///
+/// Feed body chunks to [`Opener::push`] as they arrive, then call
+/// [`Opener::finish`]. Segmented objects are decrypted a segment at a time;
+/// single-envelope objects are buffered until the end, and legacy plaintext
+/// passes straight through.
+pub struct Opener {
+    master: MasterKey,
+    buf: Vec<u8>,
+    state: OpenerState,
+}
+
+enum OpenerState {
+    /// Not enough bytes yet to tell which format the body is in.
+    Sniffing,
+    Plaintext,
+    /// A single-envelope body, opened as a whole by `finish`.
+    Whole,
+    Segmented {
+        aad: [u8; AAD_LEN],
+        dek: DataKey,
+        next_index: u64,
+    },
+}
+
+impl Opener {
+    pub fn new(master: MasterKey) -> Self {
+        Self {
+            master,
+            buf: Vec::new(),
+            state: OpenerState::Sniffing,
+        }
+    }
+
+    /// Consume a chunk of the body and return whatever plaintext it completes.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<u8>, StorageError> {
+        if let OpenerState::Plaintext = self.state {
+            return Ok(chunk.to_vec());
+        }
+        self.buf.extend_from_slice(chunk);
+
+        if let OpenerState::Sniffing = self.state {
+            if self.buf.len() <= MAGIC.len() {
+                if !MAGIC.starts_with(&self.buf) {
+                    self.state = OpenerState::Plaintext;
+                    return Ok(std::mem::take(&mut self.buf));
+                }
+                return Ok(Vec::new());
+            }
+            if !is_encrypted(&self.buf) {
+                self.state = OpenerState::Plaintext;
+                return Ok(std::mem::take(&mut self.buf));
+            }
+            if self.buf[MAGIC.len()] != SEGMENTED_FORMAT_VERSION {
+                self.state = OpenerState::Whole;
+                return Ok(Vec::new());
+            }
+            if self.buf.len() < SEGMENTED_HEADER_LEN {
+                return Ok(Vec::new());
+            }
+            let dek = unwrap_header(&self.master, &self.buf[..SEGMENTED_HEADER_LEN])?;
+            let mut aad = [0u8; AAD_LEN];
+            aad.copy_from_slice(&self.buf[..AAD_LEN]);
+            self.buf.drain(..SEGMENTED_HEADER_LEN);
+            self.state = OpenerState::Segmented {
+                aad,
+                dek,
+                next_index: 0,
+            };
+        }
+
+        match self.state {
+            // A segment is only known not to be the last once bytes after it
+            // have arrived, so the trailing complete segment stays buffered.
+            OpenerState::Segmented { .. } => self.open_segments(false),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Finish the body and return the remaining plaintext.
+    pub fn finish(mut self) -> Result<Vec<u8>, StorageError> {
+        match &self.state {
+            OpenerState::Sniffing if is_encrypted(&self.buf) => Err(StorageError::Decryption(
+                "encrypted object is truncated".to_string(),
+            )),
+            OpenerState::Sniffing | OpenerState::Plaintext => Ok(std::mem::take(&mut self.buf)),
+            OpenerState::Whole => open(&self.master, std::mem::take(&mut self.buf)),
+            OpenerState::Segmented { .. } => {
+                let plaintext = self.open_segments(true)?;
+                let opened_any = matches!(
+                    self.state,
+                    OpenerState::Segmented { next_index, .. } if next_index > 0
+                );
+                if !self.buf.is_empty() || !opened_any {
+                    return Err(StorageError::Decryption(
+                        "encrypted object is truncated".to_string(),
+                    ));
+                }
+                Ok(plaintext)
+            }
+        }
+    }
+
+    /// Feed the next chunk of a streamed body to `opener`, or finish it
+    /// once the body has ended (`chunk` is `None`), leaving `None` behind.
+    /// A finished opener yields nothing further. Shared by the readers that
+    /// decrypt as they go.
+    pub fn feed(opener: &mut Option<Self>, chunk: Option<&[u8]>) -> Result<Vec<u8>, StorageError> {
+        match (chunk, opener) {
+            (Some(chunk), Some(opener)) => opener.push(chunk),
+            (None, opener) => opener.take().map_or_else(|| Ok(Vec::new()), Self::finish),
+            (Some(_), None) => Ok(Vec::new()),
+        }
+    }
+
+    /// Decrypt every complete buffered segment. The final one is only opened
+    /// when `at_end` is set, and is then authenticated as the last segment.
+    fn open_segments(&mut self, at_end: bool) -> Result<Vec<u8>, StorageError> {
+        let OpenerState::Segmented {
+            aad,
+            dek,
+            next_index,
+        } = &mut self.state
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut plaintext = Vec::new();
+        let mut offset = 0;
+        loop {
+            let rest = &self.buf[offset..];
+            if rest.len() < SEGMENT_PREFIX_LEN {
+                break;
+            }
+            let len = rest
+                .get(..4)
+                .and_then(|prefix| prefix.try_into().ok())
+                .map(u32::from_be_bytes)
+                .ok_or_else(|| {
+                    StorageError::Decryption("segment length prefix is truncated".to_string())
+                })? as usize;
+            let segment_len = SEGMENT_PREFIX_LEN + len;
+            if rest.len() < segment_len {
+                break;
+            }
+            let is_last = rest.len() == segment_len;
+            if is_last && !at_end {
+                break;
+            }
+
+            let nonce = &rest[4..SEGMENT_PREFIX_LEN];
+            let segment = dek
+                .cipher()
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: &rest[SEGMENT_PREFIX_LEN..segment_len],
+                        aad: &segment_aad(aad, *next_index, is_last),
+                    },
+                )
+                .map_err(|_| {
+                    StorageError::Decryption(format!("segment {next_index} failed authentication"))
+                })?;
+            plaintext.extend_from_slice(&segment);
+            *next_index += 1;
+            offset += segment_len;
+        }
+        self.buf.drain(..offset);
+        Ok(plaintext)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
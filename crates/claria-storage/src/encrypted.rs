@@ -0,0 +1,316 @@
+//! An [`ObjectStore`] decorator that encrypts bodies on the client.
+//!
+//! Writes are always sealed with [`crypto::seal`]; reads go through
+//! [`crypto::open`], which passes legacy plaintext through unchanged. Old
+//! objects are therefore migrated lazily: the next time a record, sidecar,
+//! chat history or the search index is saved, it is written encrypted.
+//!
+//! ETags returned by this store are those of the ciphertext, so `If-Match`
+//! round-trips behave exactly as on the inner store.
+//!
+//! Multipart uploads use the segmented envelope: each part becomes one
+//! segment under a data key derived from the upload ID, and the first part
+//! carries the header. Streamed reads decrypt segment by segment.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::crypto::{self, DataKey, MasterKey, Opener};
+use crate::error::StorageError;
+use crate::objects::{
+    GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use crate::store::{BoxFuture, ObjectBody, ObjectStore, Precondition};
+
+/// Decrypts an inner body as it streams.
+struct DecryptingBody {
+    inner: Box<dyn ObjectBody>,
+    /// `None` once the inner body is exhausted and the opener finished.
+    opener: Option<Opener>,
+}
+
+impl ObjectBody for DecryptingBody {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, StorageError>> {
+        Box::pin(async move {
+            while self.opener.is_some() {
+                let chunk = self.inner.next_chunk().await?;
+                let plaintext = Opener::feed(&mut self.opener, chunk.as_deref())?;
+                if !plaintext.is_empty() {
+                    return Ok(Some(plaintext));
+                }
+            }
+            Ok(None)
+        })
+    }
+}
+
+/// Wraps another store and encrypts every object body with a practice
+/// master key held locally.
+pub struct EncryptedStore {
+    inner: Arc<dyn ObjectStore>,
+    master_key: MasterKey,
+}
+
+impl EncryptedStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, master_key: MasterKey) -> Self {
+        Self { inner, master_key }
+    }
+
+    /// The underlying store, which reads and writes raw (possibly encrypted)
+    /// bytes. Only for callers that must hand plaintext to an AWS service
+    /// directly, such as Transcribe.
+    pub fn inner(&self) -> &Arc<dyn ObjectStore> {
+        &self.inner
+    }
+
+    /// The master key used to wrap data keys.
+    pub fn master_key(&self) -> &MasterKey {
+        &self.master_key
+    }
+}
+
+impl ObjectStore for EncryptedStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        Box::pin(async move {
+            let output = self.inner.get_object(bucket, key, version_id).await?;
+            Ok(GetObjectOutput {
+                body: crypto::open(&self.master_key, output.body)?,
+                etag: output.etag,
+                content_type: output.content_type,
+                metadata: output.metadata,
+            })
+        })
+    }
+
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        Box::pin(async move {
+            let stream = self
+                .inner
+                .get_object_stream(bucket, key, version_id)
+                .await?;
+            Ok(GetObjectStream {
+                body: Box::new(DecryptingBody {
+                    inner: stream.body,
+                    opener: Some(Opener::new(self.master_key.clone())),
+                }),
+                etag: stream.etag,
+                content_type: stream.content_type,
+                content_length: stream.content_length,
+            })
+        })
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        Box::pin(async move {
+            let Some(output) = self
+                .inner
+                .get_object_if_none_match(bucket, key, etag)
+                .await?
+            else {
+                return Ok(None);
+            };
+            Ok(Some(GetObjectOutput {
+                body: crypto::open(&self.master_key, output.body)?,
+                etag: output.etag,
+                content_type: output.content_type,
+                metadata: output.metadata,
+            }))
+        })
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        self.inner.head_object(bucket, key)
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let sealed = crypto::seal(&self.master_key, &body)?;
+            self.inner
+                .put_object(bucket, key, sealed, content_type, metadata, precondition)
+                .await
+        })
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .create_multipart_upload(bucket, key, content_type, metadata)
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        Box::pin(async move {
+            let index = u64::try_from(part_number - 1).map_err(|_| {
+                StorageError::MultipartUpload(format!("part number {part_number} is out of range"))
+            })?;
+            let dek = DataKey::for_upload(&self.master_key, upload_id)?;
+
+            let mut sealed = if part_number == 1 {
+                crypto::segmented_header(&self.master_key, &dek)?
+            } else {
+                Vec::new()
+            };
+            sealed.extend(crypto::seal_segment(
+                &self.master_key,
+                &dek,
+                index,
+                is_last,
+                &body,
+            )?);
+
+            let mut part = self
+                .inner
+                .upload_part(bucket, key, upload_id, part_number, sealed, is_last)
+                .await?;
+            part.size = body.len() as i64;
+            Ok(part)
+        })
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        Box::pin(async move {
+            let mut parts = self.inner.list_parts(bucket, key, upload_id).await?;
+            for part in &mut parts {
+                part.size = crypto::part_plaintext_len(part.part_number, part.size);
+            }
+            Ok(parts)
+        })
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .complete_multipart_upload(bucket, key, upload_id, parts, precondition)
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.abort_multipart_upload(bucket, key, upload_id)
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.delete_object(bucket, key)
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.delete_object_version(bucket, key, version_id)
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.inner.delete_object_versions(bucket, versions)
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        self.inner.list_objects(bucket, prefix)
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        self.inner.list_versions(bucket, prefix)
+    }
+
+    /// Refused: a presigned GET would serve ciphertext the recipient cannot
+    /// decrypt.
+    fn presign_get<'a>(
+        &'a self,
+        _bucket: &'a str,
+        _key: &'a str,
+        _expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async {
+            Err(StorageError::Presign(
+                "presigned URLs cannot be used with client-side encryption".to_string(),
+            ))
+        })
+    }
+
+    /// Refused: a presigned PUT would bypass encryption entirely.
+    fn presign_put<'a>(
+        &'a self,
+        _bucket: &'a str,
+        _key: &'a str,
+        _content_type: Option<&'a str>,
+        _expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async {
+            Err(StorageError::Presign(
+                "presigned URLs cannot be used with client-side encryption".to_string(),
+            ))
+        })
+    }
+}
@@ -11,12 +11,28 @@ pub enum StorageError {
     #[error("precondition failed for key: {key}")]
     PreconditionFailed { key: String },
 
+    /// The service asked us to slow down (`SlowDown`, HTTP 429 or 503).
+    #[error("request throttled: {0}")]
+    Throttled(String),
+
+    /// The credentials are missing, expired or lack permission.
+    #[error("access denied: {0}")]
+    AccessDenied(String),
+
+    /// The request never got a usable response: a timeout, a dropped
+    /// connection, or a transient 5xx from the service.
+    #[error("network error: {0}")]
+    Network(String),
+
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
     #[error("S3 GetObject error: {0}")]
     GetObject(String),
 
+    #[error("S3 HeadObject error: {0}")]
+    HeadObject(String),
+
     #[error("S3 PutObject error: {0}")]
     PutObject(String),
 
@@ -29,9 +45,31 @@ pub enum StorageError {
     #[error("S3 ListObjectVersions error: {0}")]
     ListObjectVersions(String),
 
+    #[error("S3 multipart upload error: {0}")]
+    MultipartUpload(String),
+
     #[error("S3 presign error: {0}")]
     Presign(String),
 
     #[error("AWS config error: {0}")]
     Config(String),
+
+    #[error("encryption error: {0}")]
+    Encryption(String),
+
+    #[error("decryption error: {0}")]
+    Decryption(String),
+
+    #[error("backup archive error: {0}")]
+    Backup(String),
+
+    #[error("local store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl StorageError {
+    /// Whether the same request may succeed if sent again.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Throttled(_) | Self::Network(_))
+    }
 }
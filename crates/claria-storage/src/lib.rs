@@ -1,8 +1,26 @@
 //! claria-storage
 //!
-//! S3 operations. Thin wrapper around the AWS S3 SDK.
+//! Object storage operations. Thin wrappers over an [`store::ObjectStore`],
+//! implemented for the AWS S3 SDK client and for a local directory, with
+//! optional client-side envelope encryption, retries and a local read-through
+//! cache layered on top.
 
+pub mod backup;
+pub mod cache;
 pub mod client;
+pub mod crypto;
+pub mod encrypted;
 pub mod error;
+pub mod local;
 pub mod objects;
+pub mod outbox;
+pub mod purge;
+pub mod repository;
+pub mod restore;
+pub mod retry;
+pub mod s3;
+pub mod scrub;
+pub mod share;
 pub mod state;
+pub mod store;
+pub mod usage;
@@ -0,0 +1,924 @@
+//! A versioned object store backed by a local directory.
+//!
+//! Emulates the parts of S3 that Claria relies on so record workflows can
+//! run without an AWS account: content-MD5 ETags, `If-Match` preconditions,
+//! delete markers, and version listing. Each key gets its own directory
+//! holding one file per version plus a `versions.json` log:
+//!
+//! ```text
+//! {root}/{bucket}/{percent-encoded key}/versions.json
+//! {root}/{bucket}/{percent-encoded key}/{version_id}
+//! ```
+//!
+//! A key whose encoded name would be too long for a file name is stored
+//! under `~{SHA-256 of the key}` instead, with the key itself in a `key`
+//! file beside its versions.
+//!
+//! In-progress multipart uploads live outside any bucket, one directory per
+//! upload holding the numbered parts:
+//!
+//! ```text
+//! {root}/.uploads/{upload_id}/upload.json
+//! {root}/.uploads/{upload_id}/{part_number}
+//! ```
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use md5::{Digest, Md5};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::io::AsyncReadExt;
+
+use crate::error::StorageError;
+use crate::objects::{
+    GetObjectOutput, GetObjectStream, MIN_PART_SIZE, Metadata, ObjectHead, ObjectMeta,
+    ObjectVersion, UploadedPart, VersionRef,
+};
+use crate::store::{BoxFuture, ObjectBody, ObjectStore, Precondition};
+
+/// Characters escaped in key directory names. `/` must be escaped so the
+/// bucket directory stays flat, and `.` so a key can never become `.` or `..`.
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_');
+
+const VERSIONS_FILE: &str = "versions.json";
+
+/// Longest key directory name written as is. Most filesystems allow 255
+/// bytes per name.
+const MAX_NAME_LEN: usize = 255;
+
+/// Prefix of hashed key directory names. `~` is always percent-encoded in
+/// key names, so the two never collide.
+const HASHED_PREFIX: char = '~';
+
+/// File in a hashed key directory holding the key it stores.
+const KEY_FILE: &str = "key";
+
+/// Directory under the root holding in-progress multipart uploads. S3 bucket
+/// names cannot start with `.`, so this never collides with a bucket.
+const UPLOADS_DIR: &str = ".uploads";
+const UPLOAD_FILE: &str = "upload.json";
+
+/// Chunk size for streamed reads.
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// One entry in a key's `versions.json` log, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VersionRecord {
+    version_id: String,
+    etag: Option<String>,
+    size: i64,
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
+    last_modified: jiff::Timestamp,
+    is_delete_marker: bool,
+}
+
+/// The target of an in-progress multipart upload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingUpload {
+    bucket: String,
+    key: String,
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
+}
+
+/// A streamed version file.
+struct LocalBody(tokio::fs::File);
+
+impl ObjectBody for LocalBody {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, StorageError>> {
+        Box::pin(async move {
+            let mut buf = vec![0u8; READ_CHUNK_SIZE];
+            let n = self.0.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buf.truncate(n);
+            Ok(Some(buf))
+        })
+    }
+}
+
+/// Local-directory implementation of [`ObjectStore`].
+///
+/// Every request runs its file I/O on Tokio's blocking thread pool, so a
+/// slow disk never stalls the async runtime.
+pub struct LocalStore {
+    dir: Arc<LocalDir>,
+}
+
+/// The directory behind a [`LocalStore`], shared with its blocking tasks.
+struct LocalDir {
+    root: PathBuf,
+    /// Serializes writes so precondition checks and log updates are atomic
+    /// within this process.
+    write_lock: Mutex<()>,
+}
+
+impl LocalStore {
+    /// Open (or lazily create) a store rooted at `root`. Each bucket is a
+    /// subdirectory of the root.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Arc::new(LocalDir {
+                root: root.into(),
+                write_lock: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// The directory this store reads and writes.
+    pub fn root(&self) -> &Path {
+        &self.dir.root
+    }
+
+    /// Run `f` against the directory on the blocking thread pool.
+    fn blocking<'a, T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&LocalDir) -> Result<T, StorageError> + Send + 'static,
+    ) -> BoxFuture<'a, Result<T, StorageError>> {
+        let dir = Arc::clone(&self.dir);
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || f(&dir))
+                .await
+                .map_err(|e| StorageError::Io(std::io::Error::other(e)))?
+        })
+    }
+}
+
+impl LocalDir {
+    fn bucket_dir(&self, bucket: &str) -> PathBuf {
+        self.root.join(bucket)
+    }
+
+    fn key_dir(&self, bucket: &str, key: &str) -> PathBuf {
+        let name = utf8_percent_encode(key, KEY_ENCODE_SET).to_string();
+        if name.len() <= MAX_NAME_LEN {
+            return self.bucket_dir(bucket).join(name);
+        }
+        self.bucket_dir(bucket)
+            .join(format!("{HASHED_PREFIX}{:x}", Sha256::digest(key)))
+    }
+
+    /// The key's directory, created if need be along with the `key` file
+    /// of a hashed name.
+    fn create_key_dir(&self, bucket: &str, key: &str) -> Result<PathBuf, StorageError> {
+        let dir = self.key_dir(bucket, key);
+        std::fs::create_dir_all(&dir)?;
+        let hashed = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(HASHED_PREFIX));
+        if hashed && !dir.join(KEY_FILE).exists() {
+            write_atomic(&dir.join(KEY_FILE), key.as_bytes())?;
+        }
+        Ok(dir)
+    }
+
+    /// The key stored in the key directory called `name`.
+    fn key_for_name(&self, bucket: &str, name: &str) -> Option<String> {
+        if !name.starts_with(HASHED_PREFIX) {
+            return Some(percent_decode_str(name).decode_utf8_lossy().into_owned());
+        }
+        std::fs::read_to_string(self.bucket_dir(bucket).join(name).join(KEY_FILE)).ok()
+    }
+
+    fn read_log(&self, bucket: &str, key: &str) -> Result<Vec<VersionRecord>, StorageError> {
+        let path = self.key_dir(bucket, key).join(VERSIONS_FILE);
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(StorageError::Io(e)),
+        }
+    }
+
+    /// Atomically replace a key's version log (tmp + rename).
+    fn write_log(
+        &self,
+        bucket: &str,
+        key: &str,
+        log: &[VersionRecord],
+    ) -> Result<(), StorageError> {
+        let dir = self.create_key_dir(bucket, key)?;
+        write_atomic(&dir.join(VERSIONS_FILE), &serde_json::to_vec_pretty(log)?)
+    }
+
+    /// All keys in a bucket that start with `prefix`, sorted like an S3 listing.
+    fn keys_with_prefix(&self, bucket: &str, prefix: &str) -> Result<Vec<String>, StorageError> {
+        let entries = match std::fs::read_dir(self.bucket_dir(bucket)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(StorageError::Io(e)),
+        };
+
+        let mut keys = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let Some(key) = self.key_for_name(bucket, name) else {
+                continue;
+            };
+            if key.starts_with(prefix) {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Find the record for the latest version of a key, or a specific
+    /// version, refusing delete markers.
+    fn resolve_version(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+    ) -> Result<VersionRecord, StorageError> {
+        let log = self.read_log(bucket, key)?;
+        let not_found = || StorageError::NotFound {
+            key: key.to_string(),
+        };
+
+        let record = match version_id {
+            Some(v) => log
+                .into_iter()
+                .find(|r| r.version_id == v)
+                .ok_or_else(not_found)?,
+            None => log
+                .into_iter()
+                .next_back()
+                .filter(|r| !r.is_delete_marker)
+                .ok_or_else(not_found)?,
+        };
+        if record.is_delete_marker {
+            return Err(StorageError::GetObject(format!(
+                "version {} of {key} is a delete marker",
+                record.version_id
+            )));
+        }
+        Ok(record)
+    }
+
+    fn get_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+    ) -> Result<GetObjectOutput, StorageError> {
+        let record = self.resolve_version(bucket, key, version_id)?;
+        let body = std::fs::read(self.key_dir(bucket, key).join(&record.version_id))?;
+        Ok(GetObjectOutput {
+            body,
+            etag: record.etag,
+            content_type: record.content_type,
+            metadata: record.metadata,
+        })
+    }
+
+    fn get_if_none_match_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        etag: &str,
+    ) -> Result<Option<GetObjectOutput>, StorageError> {
+        let record = self.resolve_version(bucket, key, None)?;
+        if record.etag.as_deref() == Some(etag) {
+            return Ok(None);
+        }
+        let body = std::fs::read(self.key_dir(bucket, key).join(&record.version_id))?;
+        Ok(Some(GetObjectOutput {
+            body,
+            etag: record.etag,
+            content_type: record.content_type,
+            metadata: record.metadata,
+        }))
+    }
+
+    fn head_sync(&self, bucket: &str, key: &str) -> Result<ObjectHead, StorageError> {
+        let record = self.resolve_version(bucket, key, None)?;
+        Ok(ObjectHead {
+            etag: record.etag,
+            content_type: record.content_type,
+            metadata: record.metadata,
+        })
+    }
+
+    fn get_stream_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<&str>,
+    ) -> Result<GetObjectStream, StorageError> {
+        let record = self.resolve_version(bucket, key, version_id)?;
+        let file = std::fs::File::open(self.key_dir(bucket, key).join(&record.version_id))?;
+        Ok(GetObjectStream {
+            body: Box::new(LocalBody(tokio::fs::File::from_std(file))),
+            etag: record.etag,
+            content_type: record.content_type,
+            content_length: Some(record.size),
+        })
+    }
+
+    fn put_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        content_type: Option<&str>,
+        metadata: &Metadata,
+        precondition: &Precondition,
+    ) -> Result<String, StorageError> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut log = self.read_log(bucket, key)?;
+
+        check_precondition(&log, key, precondition)?;
+
+        let etag = format!("\"{:x}\"", Md5::digest(&body));
+        let version_id = uuid::Uuid::new_v4().simple().to_string();
+
+        let dir = self.create_key_dir(bucket, key)?;
+        write_atomic(&dir.join(&version_id), &body)?;
+
+        log.push(VersionRecord {
+            version_id,
+            etag: Some(etag.clone()),
+            size: body.len() as i64,
+            content_type: content_type.map(|s| s.to_string()),
+            metadata: metadata.clone(),
+            last_modified: jiff::Timestamp::now(),
+            is_delete_marker: false,
+        });
+        self.write_log(bucket, key, &log)?;
+
+        Ok(etag)
+    }
+
+    fn upload_dir(&self, upload_id: &str) -> PathBuf {
+        self.root
+            .join(UPLOADS_DIR)
+            .join(utf8_percent_encode(upload_id, KEY_ENCODE_SET).to_string())
+    }
+
+    /// Load an in-progress upload, checking it targets `bucket`/`key`.
+    fn pending_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<PendingUpload, StorageError> {
+        let path = self.upload_dir(upload_id).join(UPLOAD_FILE);
+        let pending: PendingUpload = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(StorageError::MultipartUpload(format!(
+                    "no such upload: {upload_id}"
+                )));
+            }
+            Err(e) => return Err(StorageError::Io(e)),
+        };
+        if pending.bucket != bucket || pending.key != key {
+            return Err(StorageError::MultipartUpload(format!(
+                "upload {upload_id} is for a different object"
+            )));
+        }
+        Ok(pending)
+    }
+
+    fn create_upload_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<&str>,
+        metadata: &Metadata,
+    ) -> Result<String, StorageError> {
+        let upload_id = uuid::Uuid::new_v4().simple().to_string();
+        let dir = self.upload_dir(&upload_id);
+        std::fs::create_dir_all(&dir)?;
+        let pending = PendingUpload {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            content_type: content_type.map(|s| s.to_string()),
+            metadata: metadata.clone(),
+        };
+        std::fs::write(dir.join(UPLOAD_FILE), serde_json::to_vec_pretty(&pending)?)?;
+        Ok(upload_id)
+    }
+
+    fn upload_part_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<UploadedPart, StorageError> {
+        self.pending_upload(bucket, key, upload_id)?;
+        if !(1..=10_000).contains(&part_number) {
+            return Err(StorageError::MultipartUpload(format!(
+                "part number {part_number} is out of range"
+            )));
+        }
+
+        // Write then rename so an interrupted write never leaves a part
+        // that list_parts would report as complete.
+        let dir = self.upload_dir(upload_id);
+        let tmp_path = dir.join(format!("{part_number}.tmp"));
+        std::fs::write(&tmp_path, &body)?;
+        std::fs::rename(&tmp_path, dir.join(part_number.to_string()))?;
+
+        Ok(UploadedPart {
+            part_number,
+            etag: format!("\"{:x}\"", Md5::digest(&body)),
+            size: body.len() as i64,
+        })
+    }
+
+    fn list_parts_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<Vec<UploadedPart>, StorageError> {
+        self.pending_upload(bucket, key, upload_id)?;
+
+        let mut parts = Vec::new();
+        for entry in std::fs::read_dir(self.upload_dir(upload_id))? {
+            let entry = entry?;
+            let Some(part_number) = entry
+                .file_name()
+                .to_str()
+                .and_then(|n| n.parse::<i32>().ok())
+            else {
+                continue;
+            };
+            let body = std::fs::read(entry.path())?;
+            parts.push(UploadedPart {
+                part_number,
+                etag: format!("\"{:x}\"", Md5::digest(&body)),
+                size: body.len() as i64,
+            });
+        }
+        parts.sort_by_key(|p| p.part_number);
+        Ok(parts)
+    }
+
+    fn complete_upload_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+        precondition: &Precondition,
+    ) -> Result<String, StorageError> {
+        let pending = self.pending_upload(bucket, key, upload_id)?;
+        if parts.is_empty() {
+            return Err(StorageError::MultipartUpload(
+                "cannot complete an upload with no parts".to_string(),
+            ));
+        }
+        if parts
+            .windows(2)
+            .any(|w| w[0].part_number >= w[1].part_number)
+        {
+            return Err(StorageError::MultipartUpload(
+                "parts must be listed in ascending order".to_string(),
+            ));
+        }
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut log = self.read_log(bucket, key)?;
+        check_precondition(&log, key, precondition)?;
+
+        let key_dir = self.create_key_dir(bucket, key)?;
+        let version_id = uuid::Uuid::new_v4().simple().to_string();
+        let tmp_path = key_dir.join(format!("{version_id}.tmp"));
+        let assembled = std::fs::File::create(&tmp_path)
+            .map_err(StorageError::from)
+            .and_then(|mut out| {
+                let assembled = Self::assemble_parts(&self.upload_dir(upload_id), parts, &mut out)?;
+                out.sync_all()?;
+                std::fs::rename(&tmp_path, key_dir.join(&version_id))?;
+                Ok(assembled)
+            });
+        let (size, part_digests) = match assembled {
+            Ok(assembled) => assembled,
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        };
+
+        // Like S3, the ETag of a multipart object is the MD5 of the
+        // concatenated part MD5s, suffixed with the part count.
+        let etag = format!("\"{:x}-{}\"", Md5::digest(&part_digests), parts.len());
+        log.push(VersionRecord {
+            version_id,
+            etag: Some(etag.clone()),
+            size,
+            content_type: pending.content_type,
+            metadata: pending.metadata,
+            last_modified: jiff::Timestamp::now(),
+            is_delete_marker: false,
+        });
+        self.write_log(bucket, key, &log)?;
+        std::fs::remove_dir_all(self.upload_dir(upload_id))?;
+
+        Ok(etag)
+    }
+
+    /// Append the listed parts to `out`, checking each part's ETag and the
+    /// minimum part size. Returns the total size and the concatenated part
+    /// MD5 digests.
+    fn assemble_parts(
+        upload_dir: &Path,
+        parts: &[UploadedPart],
+        out: &mut std::fs::File,
+    ) -> Result<(i64, Vec<u8>), StorageError> {
+        let mut part_digests = Vec::with_capacity(parts.len() * 16);
+        let mut size = 0i64;
+        for (i, part) in parts.iter().enumerate() {
+            let body =
+                std::fs::read(upload_dir.join(part.part_number.to_string())).map_err(|_| {
+                    StorageError::MultipartUpload(format!(
+                        "part {} was never uploaded",
+                        part.part_number
+                    ))
+                })?;
+            let digest = Md5::digest(&body);
+            if format!("\"{digest:x}\"") != part.etag {
+                return Err(StorageError::MultipartUpload(format!(
+                    "ETag mismatch for part {}",
+                    part.part_number
+                )));
+            }
+            if i + 1 < parts.len() && body.len() < MIN_PART_SIZE {
+                return Err(StorageError::MultipartUpload(format!(
+                    "part {} is smaller than the {MIN_PART_SIZE}-byte minimum",
+                    part.part_number
+                )));
+            }
+            out.write_all(&body)?;
+            part_digests.extend_from_slice(&digest);
+            size += body.len() as i64;
+        }
+        Ok((size, part_digests))
+    }
+
+    fn abort_upload_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), StorageError> {
+        self.pending_upload(bucket, key, upload_id)?;
+        std::fs::remove_dir_all(self.upload_dir(upload_id))?;
+        Ok(())
+    }
+
+    fn delete_sync(&self, bucket: &str, key: &str) -> Result<(), StorageError> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut log = self.read_log(bucket, key)?;
+
+        // Like a versioned S3 bucket, deleting always adds a delete marker —
+        // even for a key that never existed.
+        log.push(VersionRecord {
+            version_id: uuid::Uuid::new_v4().simple().to_string(),
+            etag: None,
+            size: 0,
+            content_type: None,
+            metadata: Metadata::new(),
+            last_modified: jiff::Timestamp::now(),
+            is_delete_marker: true,
+        });
+        self.write_log(bucket, key, &log)
+    }
+
+    fn delete_version_sync(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: &str,
+    ) -> Result<(), StorageError> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut log = self.read_log(bucket, key)?;
+
+        // S3 treats deleting an unknown version ID as a no-op.
+        let Some(index) = log.iter().position(|r| r.version_id == version_id) else {
+            return Ok(());
+        };
+        let record = log.remove(index);
+        if !record.is_delete_marker {
+            std::fs::remove_file(self.key_dir(bucket, key).join(&record.version_id))?;
+        }
+
+        if log.is_empty() {
+            std::fs::remove_dir_all(self.key_dir(bucket, key))?;
+            Ok(())
+        } else {
+            self.write_log(bucket, key, &log)
+        }
+    }
+
+    fn list_objects_sync(
+        &self,
+        bucket: &str,
+        prefix: &str,
+    ) -> Result<Vec<ObjectMeta>, StorageError> {
+        let mut objects = Vec::new();
+        for key in self.keys_with_prefix(bucket, prefix)? {
+            let log = self.read_log(bucket, &key)?;
+            if let Some(latest) = log.last().filter(|r| !r.is_delete_marker) {
+                objects.push(ObjectMeta {
+                    size: latest.size,
+                    last_modified: Some(latest.last_modified.to_string()),
+                    key,
+                });
+            }
+        }
+        Ok(objects)
+    }
+
+    fn list_versions_sync(
+        &self,
+        bucket: &str,
+        prefix: &str,
+    ) -> Result<Vec<ObjectVersion>, StorageError> {
+        let mut versions = Vec::new();
+        for key in self.keys_with_prefix(bucket, prefix)? {
+            let log = self.read_log(bucket, &key)?;
+            let latest_index = log.len().saturating_sub(1);
+            // Newest first, matching S3's per-key ordering.
+            for (i, record) in log.iter().enumerate().rev() {
+                versions.push(ObjectVersion {
+                    key: key.clone(),
+                    version_id: record.version_id.clone(),
+                    size: record.size,
+                    last_modified: Some(record.last_modified.to_string()),
+                    is_latest: i == latest_index,
+                    is_delete_marker: record.is_delete_marker,
+                });
+            }
+        }
+        Ok(versions)
+    }
+}
+
+/// Write `body` to `path` through a temporary file, so a crash never leaves
+/// it truncated.
+fn write_atomic(path: &Path, body: &[u8]) -> Result<(), StorageError> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(body)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Check a write's precondition against the key's version log, mirroring
+/// S3: `If-Match` on a missing object is a 404, a different ETag is a 412,
+/// and `If-None-Match: *` on an existing object is a 412.
+fn check_precondition(
+    log: &[VersionRecord],
+    key: &str,
+    precondition: &Precondition,
+) -> Result<(), StorageError> {
+    let current = log.last().filter(|r| !r.is_delete_marker);
+    let holds = match precondition {
+        Precondition::None => true,
+        Precondition::Absent => current.is_none(),
+        Precondition::IfMatch(expected) => {
+            let current = current.ok_or_else(|| StorageError::NotFound {
+                key: key.to_string(),
+            })?;
+            current.etag.as_deref() == Some(expected.as_str())
+        }
+    };
+    if holds {
+        Ok(())
+    } else {
+        Err(StorageError::PreconditionFailed {
+            key: key.to_string(),
+        })
+    }
+}
+
+impl ObjectStore for LocalStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let version_id = version_id.map(str::to_string);
+        self.blocking(move |dir| dir.get_sync(&bucket, &key, version_id.as_deref()))
+    }
+
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let version_id = version_id.map(str::to_string);
+        self.blocking(move |dir| dir.get_stream_sync(&bucket, &key, version_id.as_deref()))
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        let (bucket, key, etag) = (bucket.to_string(), key.to_string(), etag.to_string());
+        self.blocking(move |dir| dir.get_if_none_match_sync(&bucket, &key, &etag))
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        self.blocking(move |dir| dir.head_sync(&bucket, &key))
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        let (bucket, key, metadata) = (bucket.to_string(), key.to_string(), metadata.clone());
+        let (content_type, precondition) = (content_type.map(str::to_string), precondition.clone());
+        self.blocking(move |dir| {
+            dir.put_sync(
+                &bucket,
+                &key,
+                body,
+                content_type.as_deref(),
+                &metadata,
+                &precondition,
+            )
+        })
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        let (bucket, key, metadata) = (bucket.to_string(), key.to_string(), metadata.clone());
+        let content_type = content_type.map(str::to_string);
+        self.blocking(move |dir| {
+            dir.create_upload_sync(&bucket, &key, content_type.as_deref(), &metadata)
+        })
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        _is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let upload_id = upload_id.to_string();
+        self.blocking(move |dir| dir.upload_part_sync(&bucket, &key, &upload_id, part_number, body))
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let upload_id = upload_id.to_string();
+        self.blocking(move |dir| dir.list_parts_sync(&bucket, &key, &upload_id))
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let (upload_id, parts) = (upload_id.to_string(), parts.to_vec());
+        let precondition = precondition.clone();
+        self.blocking(move |dir| {
+            dir.complete_upload_sync(&bucket, &key, &upload_id, &parts, &precondition)
+        })
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let upload_id = upload_id.to_string();
+        self.blocking(move |dir| dir.abort_upload_sync(&bucket, &key, &upload_id))
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        self.blocking(move |dir| dir.delete_sync(&bucket, &key))
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        let version_id = version_id.to_string();
+        self.blocking(move |dir| dir.delete_version_sync(&bucket, &key, &version_id))
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        let (bucket, versions) = (bucket.to_string(), versions.to_vec());
+        self.blocking(move |dir| {
+            for v in &versions {
+                dir.delete_version_sync(&bucket, &v.key, &v.version_id)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        let (bucket, prefix) = (bucket.to_string(), prefix.to_string());
+        self.blocking(move |dir| dir.list_objects_sync(&bucket, &prefix))
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        let (bucket, prefix) = (bucket.to_string(), prefix.to_string());
+        self.blocking(move |dir| dir.list_versions_sync(&bucket, &prefix))
+    }
+
+    fn presign_get<'a>(
+        &'a self,
+        _bucket: &'a str,
+        _key: &'a str,
+        _expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async {
+            Err(StorageError::Presign(
+                "presigned URLs are not supported by the local object store".to_string(),
+            ))
+        })
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        _bucket: &'a str,
+        _key: &'a str,
+        _content_type: Option<&'a str>,
+        _expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async {
+            Err(StorageError::Presign(
+                "presigned URLs are not supported by the local object store".to_string(),
+            ))
+        })
+    }
+}
@@ -1,138 +1,155 @@
-use aws_sdk_s3::Client;
-use aws_sdk_s3::presigning::PresigningConfig;
-use aws_smithy_types::byte_stream::ByteStream;
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::time::Duration;
 
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
 use crate::error::StorageError;
+use crate::store::{ObjectBody, ObjectStore, Precondition};
 
 /// Result of a GET operation, including the body and ETag.
 pub struct GetObjectOutput {
     pub body: Vec<u8>,
     pub etag: Option<String>,
     pub content_type: Option<String>,
+    pub metadata: Metadata,
+}
+
+/// User-defined metadata stored with an object (`x-amz-meta-*` headers on
+/// S3). Keys are lowercase.
+pub type Metadata = BTreeMap<String, String>;
+
+/// The headers of the latest version of an object, returned by
+/// [`head_object`].
+pub struct ObjectHead {
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+    pub metadata: Metadata,
 }
 
-/// Get an object from S3.
+/// Get an object from the store.
 pub async fn get_object(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
 ) -> Result<GetObjectOutput, StorageError> {
-    let resp = client
-        .get_object()
-        .bucket(bucket)
-        .key(key)
-        .send()
-        .await
-        .map_err(|e| {
-            let err = e.into_service_error();
-            if err.is_no_such_key() {
-                StorageError::NotFound {
-                    key: key.to_string(),
-                }
-            } else {
-                StorageError::GetObject(err.to_string())
-            }
-        })?;
-
-    let etag = resp.e_tag().map(|s| s.to_string());
-    let content_type = resp.content_type().map(|s| s.to_string());
-    let body = resp
-        .body
-        .collect()
-        .await
-        .map_err(|e| StorageError::GetObject(e.to_string()))?
-        .into_bytes()
-        .to_vec();
+    store.get_object(bucket, key, None).await
+}
 
-    Ok(GetObjectOutput {
-        body,
-        etag,
-        content_type,
-    })
+/// Get an object only if it has changed since it was read at `etag`.
+/// Returns `None` when the stored copy is still current.
+pub async fn get_object_if_none_match(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    etag: &str,
+) -> Result<Option<GetObjectOutput>, StorageError> {
+    store.get_object_if_none_match(bucket, key, etag).await
 }
 
-/// Put an object to S3. Returns the new ETag.
+/// Put an object to the store. Returns the new ETag.
 pub async fn put_object(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
     body: Vec<u8>,
     content_type: Option<&str>,
 ) -> Result<String, StorageError> {
-    let mut req = client
-        .put_object()
-        .bucket(bucket)
-        .key(key)
-        .body(ByteStream::from(body));
-
-    if let Some(ct) = content_type {
-        req = req.content_type(ct);
-    }
+    store
+        .put_object(
+            bucket,
+            key,
+            body,
+            content_type,
+            &Metadata::new(),
+            &Precondition::None,
+        )
+        .await
+}
 
-    let resp = req
-        .send()
+/// Put an object to the store with user metadata. Returns the new ETag.
+pub async fn put_object_with_metadata(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    body: Vec<u8>,
+    content_type: Option<&str>,
+    metadata: &Metadata,
+) -> Result<String, StorageError> {
+    store
+        .put_object(
+            bucket,
+            key,
+            body,
+            content_type,
+            metadata,
+            &Precondition::None,
+        )
         .await
-        .map_err(|e| StorageError::PutObject(e.into_service_error().to_string()))?;
+}
 
-    Ok(resp.e_tag().unwrap_or_default().to_string())
+/// Read the ETag, content type and user metadata of an object without
+/// fetching its body.
+pub async fn head_object(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+) -> Result<ObjectHead, StorageError> {
+    store.head_object(bucket, key).await
 }
 
-/// Put an object to S3 with an If-Match precondition (ETag optimistic locking).
+/// Put an object with an If-Match precondition (ETag optimistic locking).
 /// Returns the new ETag on success, or `StorageError::PreconditionFailed` if the
 /// ETag doesn't match.
 pub async fn put_object_if_match(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
     body: Vec<u8>,
     content_type: Option<&str>,
     expected_etag: &str,
 ) -> Result<String, StorageError> {
-    let mut req = client
-        .put_object()
-        .bucket(bucket)
-        .key(key)
-        .body(ByteStream::from(body))
-        .if_match(expected_etag);
-
-    if let Some(ct) = content_type {
-        req = req.content_type(ct);
-    }
-
-    let resp = req.send().await.map_err(|e| {
-        let err = e.into_service_error();
-        // S3 returns 412 Precondition Failed when If-Match doesn't match
-        if err
-            .to_string()
-            .contains("PreconditionFailed")
-        {
-            StorageError::PreconditionFailed {
-                key: key.to_string(),
-            }
-        } else {
-            StorageError::PutObject(err.to_string())
-        }
-    })?;
+    store
+        .put_object(
+            bucket,
+            key,
+            body,
+            content_type,
+            &Metadata::new(),
+            &Precondition::IfMatch(expected_etag.to_string()),
+        )
+        .await
+}
 
-    Ok(resp.e_tag().unwrap_or_default().to_string())
+/// Create an object with an If-None-Match: * precondition. Returns the new
+/// ETag, or `StorageError::PreconditionFailed` if the object already exists.
+pub async fn put_object_if_absent(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    body: Vec<u8>,
+    content_type: Option<&str>,
+) -> Result<String, StorageError> {
+    store
+        .put_object(
+            bucket,
+            key,
+            body,
+            content_type,
+            &Metadata::new(),
+            &Precondition::Absent,
+        )
+        .await
 }
 
-/// Delete an object from S3.
+/// Delete an object from the store.
 pub async fn delete_object(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
 ) -> Result<(), StorageError> {
-    client
-        .delete_object()
-        .bucket(bucket)
-        .key(key)
-        .send()
-        .await
-        .map_err(|e| StorageError::DeleteObject(e.into_service_error().to_string()))?;
-
-    Ok(())
+    store.delete_object(bucket, key).await
 }
 
 /// Delete all objects under a prefix.
@@ -140,19 +157,19 @@ pub async fn delete_object(
 /// Lists all keys with the given prefix and deletes each one.
 /// Returns the number of objects deleted.
 pub async fn delete_objects_by_prefix(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     prefix: &str,
 ) -> Result<usize, StorageError> {
-    let keys = list_objects(client, bucket, prefix).await?;
+    let keys = list_objects(store, bucket, prefix).await?;
     let count = keys.len();
     for key in &keys {
-        delete_object(client, bucket, key).await?;
+        delete_object(store, bucket, key).await?;
     }
     Ok(count)
 }
 
-/// Metadata for a single S3 object, returned by [`list_objects_with_metadata`].
+/// Metadata for a single object, returned by [`list_objects_with_metadata`].
 pub struct ObjectMeta {
     pub key: String,
     pub size: i64,
@@ -161,94 +178,30 @@ pub struct ObjectMeta {
 
 /// List objects under a prefix with size and last-modified metadata.
 pub async fn list_objects_with_metadata(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     prefix: &str,
 ) -> Result<Vec<ObjectMeta>, StorageError> {
-    let mut objects = Vec::new();
-    let mut continuation_token: Option<String> = None;
-
-    loop {
-        let mut req = client
-            .list_objects_v2()
-            .bucket(bucket)
-            .prefix(prefix);
-
-        if let Some(token) = &continuation_token {
-            req = req.continuation_token(token);
-        }
-
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| StorageError::ListObjects(e.into_service_error().to_string()))?;
-
-        for obj in resp.contents() {
-            if let Some(key) = obj.key() {
-                objects.push(ObjectMeta {
-                    key: key.to_string(),
-                    size: obj.size().unwrap_or(0),
-                    last_modified: obj.last_modified().map(|t| t.to_string()),
-                });
-            }
-        }
-
-        if resp.is_truncated() == Some(true) {
-            continuation_token = resp.next_continuation_token().map(|s| s.to_string());
-        } else {
-            break;
-        }
-    }
-
-    Ok(objects)
+    store.list_objects(bucket, prefix).await
 }
 
 /// List objects under a prefix. Returns keys.
 pub async fn list_objects(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     prefix: &str,
 ) -> Result<Vec<String>, StorageError> {
-    let mut keys = Vec::new();
-    let mut continuation_token: Option<String> = None;
-
-    loop {
-        let mut req = client
-            .list_objects_v2()
-            .bucket(bucket)
-            .prefix(prefix);
-
-        if let Some(token) = &continuation_token {
-            req = req.continuation_token(token);
-        }
-
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| StorageError::ListObjects(e.into_service_error().to_string()))?;
-
-        for obj in resp.contents() {
-            if let Some(key) = obj.key() {
-                keys.push(key.to_string());
-            }
-        }
-
-        if resp.is_truncated() == Some(true) {
-            continuation_token = resp.next_continuation_token().map(|s| s.to_string());
-        } else {
-            break;
-        }
-    }
-
-    Ok(keys)
+    let objects = store.list_objects(bucket, prefix).await?;
+    Ok(objects.into_iter().map(|o| o.key).collect())
 }
 
 // ---------------------------------------------------------------------------
 // Versioning operations
 // ---------------------------------------------------------------------------
 
-/// Metadata for a single version of an S3 object.
+/// Metadata for a single version of an object.
 pub struct ObjectVersion {
+    pub key: String,
     pub version_id: String,
     pub size: i64,
     pub last_modified: Option<String>,
@@ -265,159 +218,442 @@ pub struct DeletedObject {
 
 /// List all versions of a specific object (identified by exact key).
 pub async fn list_object_versions(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
 ) -> Result<Vec<ObjectVersion>, StorageError> {
-    let mut versions = Vec::new();
-    let mut key_marker: Option<String> = None;
-    let mut version_id_marker: Option<String> = None;
+    let versions = store.list_versions(bucket, key).await?;
 
-    loop {
-        let mut req = client
-            .list_object_versions()
-            .bucket(bucket)
-            .prefix(key);
+    // Only include versions for the exact key (prefix match may return more).
+    Ok(versions.into_iter().filter(|v| v.key == key).collect())
+}
 
-        if let Some(km) = &key_marker {
-            req = req.key_marker(km);
-        }
-        if let Some(vm) = &version_id_marker {
-            req = req.version_id_marker(vm);
-        }
+/// List objects under a prefix that have been deleted (have a delete marker as the latest version).
+pub async fn list_deleted_objects(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    prefix: &str,
+) -> Result<Vec<DeletedObject>, StorageError> {
+    let versions = store.list_versions(bucket, prefix).await?;
+
+    Ok(versions
+        .into_iter()
+        .filter(|v| v.is_delete_marker && v.is_latest)
+        .map(|v| DeletedObject {
+            key: v.key,
+            version_id: v.version_id,
+            last_modified: v.last_modified,
+        })
+        .collect())
+}
 
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| StorageError::ListObjectVersions(e.into_service_error().to_string()))?;
-
-        for v in resp.versions() {
-            // Only include versions for the exact key (prefix match may return more).
-            if v.key() == Some(key) {
-                versions.push(ObjectVersion {
-                    version_id: v.version_id().unwrap_or_default().to_string(),
-                    size: v.size().unwrap_or(0),
-                    last_modified: v.last_modified().map(|t| t.to_string()),
-                    is_latest: v.is_latest().unwrap_or(false),
-                    is_delete_marker: false,
-                });
-            }
-        }
+/// Get a specific version of an object.
+pub async fn get_object_version(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    version_id: &str,
+) -> Result<GetObjectOutput, StorageError> {
+    store.get_object(bucket, key, Some(version_id)).await
+}
 
-        for dm in resp.delete_markers() {
-            if dm.key() == Some(key) {
-                versions.push(ObjectVersion {
-                    version_id: dm.version_id().unwrap_or_default().to_string(),
-                    size: 0,
-                    last_modified: dm.last_modified().map(|t| t.to_string()),
-                    is_latest: dm.is_latest().unwrap_or(false),
-                    is_delete_marker: true,
-                });
-            }
-        }
+/// Permanently delete a specific version (or delete marker) of an object.
+pub async fn delete_object_version(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    version_id: &str,
+) -> Result<(), StorageError> {
+    store.delete_object_version(bucket, key, version_id).await
+}
 
-        if resp.is_truncated() == Some(true) {
-            key_marker = resp.next_key_marker().map(|s| s.to_string());
-            version_id_marker = resp.next_version_id_marker().map(|s| s.to_string());
-        } else {
-            break;
+/// S3's limit on the number of versions one `DeleteObjects` request may remove.
+pub const MAX_DELETE_BATCH: usize = 1000;
+
+/// Names one version (or delete marker) of an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRef {
+    pub key: String,
+    pub version_id: String,
+}
+
+/// Permanently delete many versions and delete markers, batching them into
+/// as few requests as possible.
+pub async fn delete_object_versions(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    versions: &[VersionRef],
+) -> Result<(), StorageError> {
+    for batch in versions.chunks(MAX_DELETE_BATCH) {
+        store.delete_object_versions(bucket, batch).await?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Streaming and multipart transfers
+// ---------------------------------------------------------------------------
+
+/// S3's minimum size for every part of a multipart upload except the last.
+pub const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Part size used by [`upload_file`] unless overridden. Files no larger than
+/// one part are uploaded with a single PUT.
+pub const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Result of a streaming GET. Pull the body with
+/// [`ObjectBody::next_chunk`] or write it out with [`download_to_file`].
+pub struct GetObjectStream {
+    pub body: Box<dyn ObjectBody>,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+    /// Size of the stored object, when the store reports it.
+    pub content_length: Option<i64>,
+}
+
+/// A part of a multipart upload that the store has accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedPart {
+    pub part_number: i32,
+    pub etag: String,
+    pub size: i64,
+}
+
+/// Options for [`upload_file`].
+pub struct UploadOptions<'a> {
+    pub content_type: Option<&'a str>,
+    /// User metadata to store with the object, such as its
+    /// [`SHA256_METADATA_KEY`] hash.
+    pub metadata: Metadata,
+    /// Size of each part; at least [`MIN_PART_SIZE`].
+    pub part_size: usize,
+    /// Continue this multipart upload instead of starting a new one. Parts
+    /// the store already holds are skipped.
+    pub resume: Option<ResumeUpload<'a>>,
+    /// Checked when the object is written, by the single PUT or when the
+    /// multipart upload is completed. A multipart upload whose precondition
+    /// fails is aborted.
+    pub precondition: Precondition,
+}
+
+/// An interrupted multipart upload for [`upload_file`] to continue.
+///
+/// It is only continued if the file still hashes to `sha256` and every part
+/// the store holds has the size the current part layout gives it; otherwise
+/// it is aborted and the file uploaded from scratch.
+#[derive(Debug, Clone, Copy)]
+pub struct ResumeUpload<'a> {
+    pub upload_id: &'a str,
+    /// Hex SHA-256 of the file when the upload was started.
+    pub sha256: &'a str,
+}
+
+impl Default for UploadOptions<'_> {
+    fn default() -> Self {
+        Self {
+            content_type: None,
+            metadata: Metadata::new(),
+            part_size: DEFAULT_PART_SIZE,
+            resume: None,
+            precondition: Precondition::None,
         }
     }
+}
 
-    Ok(versions)
+/// Progress of an [`upload_file`] call.
+#[derive(Debug, Clone)]
+pub struct UploadProgress {
+    /// The multipart upload ID, or `None` for a single-PUT upload. Persist
+    /// it to resume the upload after an interruption.
+    pub upload_id: Option<String>,
+    pub bytes_uploaded: u64,
+    pub total_bytes: u64,
 }
 
-/// List objects under a prefix that have been deleted (have a delete marker as the latest version).
-pub async fn list_deleted_objects(
-    client: &Client,
+/// Progress of a [`download_to_file`] call.
+#[derive(Debug, Clone)]
+pub struct DownloadProgress {
+    pub bytes_downloaded: u64,
+    /// Size of the stored object, when known. For encrypted objects this is
+    /// the ciphertext size and slightly exceeds the bytes written.
+    pub total_bytes: Option<u64>,
+}
+
+/// Upload a file from disk without reading it into memory.
+///
+/// Files larger than `options.part_size` are sent as a multipart upload one
+/// part at a time. `on_progress` is called once the upload ID is known and
+/// after every part. If a part fails the upload is left in place so it can
+/// be resumed with `options.resume`; call [`abort_upload`] to discard it
+/// instead. Returns the new ETag.
+pub async fn upload_file(
+    store: &dyn ObjectStore,
     bucket: &str,
-    prefix: &str,
-) -> Result<Vec<DeletedObject>, StorageError> {
-    let mut deleted = Vec::new();
-    let mut key_marker: Option<String> = None;
-    let mut version_id_marker: Option<String> = None;
+    key: &str,
+    path: &Path,
+    options: &UploadOptions<'_>,
+    on_progress: &mut (dyn FnMut(&UploadProgress) + Send),
+) -> Result<String, StorageError> {
+    if options.part_size < MIN_PART_SIZE {
+        return Err(StorageError::MultipartUpload(format!(
+            "part size {} is below the {MIN_PART_SIZE}-byte minimum",
+            options.part_size
+        )));
+    }
 
-    loop {
-        let mut req = client
-            .list_object_versions()
-            .bucket(bucket)
-            .prefix(prefix);
+    let mut file = tokio::fs::File::open(path).await?;
+    let total_bytes = file.metadata().await?.len();
+    let part_size = options.part_size as u64;
+
+    if total_bytes <= part_size {
+        let mut body = Vec::with_capacity(total_bytes as usize);
+        file.read_to_end(&mut body).await?;
+        let etag = store
+            .put_object(
+                bucket,
+                key,
+                body,
+                options.content_type,
+                &options.metadata,
+                &options.precondition,
+            )
+            .await?;
+        on_progress(&UploadProgress {
+            upload_id: None,
+            bytes_uploaded: total_bytes,
+            total_bytes,
+        });
+        return Ok(etag);
+    }
+
+    let part_count = total_bytes.div_ceil(part_size);
+    let part_len = |number: u64| part_size.min(total_bytes - (number - 1) * part_size);
 
-        if let Some(km) = &key_marker {
-            req = req.key_marker(km);
+    let resumed = match options.resume {
+        Some(resume) => {
+            resumable_parts(store, bucket, key, path, resume, &|number, size| {
+                (1..=part_count).contains(&number) && size == part_len(number)
+            })
+            .await?
         }
-        if let Some(vm) = &version_id_marker {
-            req = req.version_id_marker(vm);
+        None => None,
+    };
+    let (upload_id, mut parts) = match resumed {
+        Some(resumed) => resumed,
+        None => {
+            let id = store
+                .create_multipart_upload(bucket, key, options.content_type, &options.metadata)
+                .await?;
+            (id, Vec::new())
         }
-
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| StorageError::ListObjectVersions(e.into_service_error().to_string()))?;
-
-        for dm in resp.delete_markers() {
-            if dm.is_latest().unwrap_or(false)
-                && let Some(key) = dm.key()
-            {
-                deleted.push(DeletedObject {
-                    key: key.to_string(),
-                    version_id: dm.version_id().unwrap_or_default().to_string(),
-                    last_modified: dm.last_modified().map(|t| t.to_string()),
-                });
-            }
+    };
+
+    let mut bytes_uploaded: u64 = parts.iter().map(|p| p.size as u64).sum();
+    on_progress(&UploadProgress {
+        upload_id: Some(upload_id.clone()),
+        bytes_uploaded,
+        total_bytes,
+    });
+
+    for number in 1..=part_count {
+        if parts.iter().any(|p| p.part_number as u64 == number) {
+            continue;
         }
 
-        if resp.is_truncated() == Some(true) {
-            key_marker = resp.next_key_marker().map(|s| s.to_string());
-            version_id_marker = resp.next_version_id_marker().map(|s| s.to_string());
-        } else {
-            break;
+        let len = part_len(number);
+        let mut body = vec![0u8; len as usize];
+        file.seek(std::io::SeekFrom::Start((number - 1) * part_size))
+            .await?;
+        file.read_exact(&mut body).await?;
+
+        let part = store
+            .upload_part(
+                bucket,
+                key,
+                &upload_id,
+                number as i32,
+                body,
+                number == part_count,
+            )
+            .await?;
+        parts.push(part);
+
+        bytes_uploaded += len;
+        on_progress(&UploadProgress {
+            upload_id: Some(upload_id.clone()),
+            bytes_uploaded,
+            total_bytes,
+        });
+    }
+
+    parts.sort_by_key(|p| p.part_number);
+    let completed = store
+        .complete_multipart_upload(bucket, key, &upload_id, &parts, &options.precondition)
+        .await;
+    if let Err(StorageError::PreconditionFailed { .. } | StorageError::NotFound { .. }) = &completed
+        && let Err(e) = store.abort_multipart_upload(bucket, key, &upload_id).await
+    {
+        tracing::warn!(key, upload_id, error = %e, "failed to abort conflicting upload");
+    }
+    completed
+}
+
+/// The upload ID and parts of `resume` if it can be continued: the file at
+/// `path` is unchanged and `fits(part_number, size)` holds for every part
+/// already uploaded. An upload that cannot be continued is aborted.
+async fn resumable_parts(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    path: &Path,
+    resume: ResumeUpload<'_>,
+    fits: &(dyn Fn(u64, u64) -> bool + Sync),
+) -> Result<Option<(String, Vec<UploadedPart>)>, StorageError> {
+    let upload_id = resume.upload_id;
+    let parts = match store.list_parts(bucket, key, upload_id).await {
+        Ok(parts) => parts,
+        Err(e) => {
+            tracing::warn!(key, upload_id, error = %e, "cannot resume upload, starting over");
+            return Ok(None);
         }
+    };
+
+    let reason = if sha256_file(path).await? != resume.sha256 {
+        Some("file changed since the upload started")
+    } else if !parts
+        .iter()
+        .all(|p| p.part_number >= 1 && p.size >= 0 && fits(p.part_number as u64, p.size as u64))
+    {
+        Some("uploaded parts do not match the part layout")
+    } else {
+        None
+    };
+    let Some(reason) = reason else {
+        return Ok(Some((upload_id.to_string(), parts)));
+    };
+
+    tracing::warn!(
+        key,
+        upload_id,
+        reason,
+        "cannot resume upload, starting over"
+    );
+    if let Err(e) = store.abort_multipart_upload(bucket, key, upload_id).await {
+        tracing::warn!(key, upload_id, error = %e, "failed to abort stale upload");
     }
+    Ok(None)
+}
 
-    Ok(deleted)
+/// Abandon a multipart upload started by [`upload_file`].
+pub async fn abort_upload(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<(), StorageError> {
+    store.abort_multipart_upload(bucket, key, upload_id).await
 }
 
-/// Get a specific version of an object.
-pub async fn get_object_version(
-    client: &Client,
+/// Stream an object (or a specific version) to a file on disk.
+///
+/// The body is written to a temporary file next to `path` and renamed into
+/// place once complete, so a failed download never leaves a partial file.
+/// Returns the number of bytes written.
+pub async fn download_to_file(
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
-    version_id: &str,
-) -> Result<GetObjectOutput, StorageError> {
-    let resp = client
-        .get_object()
-        .bucket(bucket)
-        .key(key)
-        .version_id(version_id)
-        .send()
-        .await
-        .map_err(|e| {
-            let err = e.into_service_error();
-            if err.is_no_such_key() {
-                StorageError::NotFound {
-                    key: key.to_string(),
-                }
-            } else {
-                StorageError::GetObject(err.to_string())
-            }
-        })?;
-
-    let etag = resp.e_tag().map(|s| s.to_string());
-    let content_type = resp.content_type().map(|s| s.to_string());
-    let body = resp
-        .body
-        .collect()
-        .await
-        .map_err(|e| StorageError::GetObject(e.to_string()))?
-        .into_bytes()
-        .to_vec();
+    version_id: Option<&str>,
+    path: &Path,
+    on_progress: &mut (dyn FnMut(&DownloadProgress) + Send),
+) -> Result<u64, StorageError> {
+    let mut stream = store.get_object_stream(bucket, key, version_id).await?;
+    let total_bytes = stream.content_length.map(|n| n as u64);
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".part");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        let mut written = 0u64;
+        while let Some(chunk) = stream.body.next_chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+            on_progress(&DownloadProgress {
+                bytes_downloaded: written,
+                total_bytes,
+            });
+        }
+        file.flush().await?;
+        file.sync_all().await?;
+        Ok::<_, StorageError>(written)
+    }
+    .await;
+
+    match result {
+        Ok(written) => {
+            tokio::fs::rename(&tmp_path, path).await?;
+            Ok(written)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            Err(e)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Content hashing
+// ---------------------------------------------------------------------------
 
-    Ok(GetObjectOutput {
-        body,
-        etag,
-        content_type,
-    })
+/// User metadata key holding the hex SHA-256 of an object's plaintext.
+///
+/// On an encrypted bucket the hash is still of the plaintext, so anyone who
+/// can read object metadata can tell whether they hold an identical file,
+/// but learns nothing else about its contents.
+pub const SHA256_METADATA_KEY: &str = "sha256";
+
+/// Hex SHA-256 of a file, read in chunks.
+pub async fn sha256_file(path: &Path) -> Result<String, StorageError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Keys under `prefix` whose [`SHA256_METADATA_KEY`] metadata equals
+/// `sha256`, in listing order.
+///
+/// Listings do not carry user metadata, so this sends one HEAD per object
+/// under the prefix; keep the prefix narrow, such as one client's record.
+/// Objects uploaded before hashes were recorded never match.
+pub async fn find_by_sha256(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    prefix: &str,
+    sha256: &str,
+) -> Result<Vec<String>, StorageError> {
+    let mut matches = Vec::new();
+    for key in list_objects(store, bucket, prefix).await? {
+        let head = match store.head_object(bucket, &key).await {
+            Ok(head) => head,
+            // Deleted between the listing and the HEAD.
+            Err(StorageError::NotFound { .. }) => continue,
+            Err(e) => return Err(e),
+        };
+        if head.metadata.get(SHA256_METADATA_KEY).map(String::as_str) == Some(sha256) {
+            matches.push(key);
+        }
+    }
+    Ok(matches)
 }
 
 // ---------------------------------------------------------------------------
@@ -426,50 +662,23 @@ pub async fn get_object_version(
 
 /// Generate a presigned GET URL for an object.
 pub async fn presign_get(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
     expires_in: Duration,
 ) -> Result<String, StorageError> {
-    let presign_config = PresigningConfig::builder()
-        .expires_in(expires_in)
-        .build()
-        .map_err(|e| StorageError::Presign(e.to_string()))?;
-
-    let presigned = client
-        .get_object()
-        .bucket(bucket)
-        .key(key)
-        .presigned(presign_config)
-        .await
-        .map_err(|e| StorageError::Presign(e.to_string()))?;
-
-    Ok(presigned.uri().to_string())
+    store.presign_get(bucket, key, expires_in).await
 }
 
 /// Generate a presigned PUT URL for uploading an object.
 pub async fn presign_put(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
     content_type: Option<&str>,
     expires_in: Duration,
 ) -> Result<String, StorageError> {
-    let presign_config = PresigningConfig::builder()
-        .expires_in(expires_in)
-        .build()
-        .map_err(|e| StorageError::Presign(e.to_string()))?;
-
-    let mut req = client.put_object().bucket(bucket).key(key);
-
-    if let Some(ct) = content_type {
-        req = req.content_type(ct);
-    }
-
-    let presigned = req
-        .presigned(presign_config)
+    store
+        .presign_put(bucket, key, content_type, expires_in)
         .await
-        .map_err(|e| StorageError::Presign(e.to_string()))?;
-
-    Ok(presigned.uri().to_string())
 }
@@ -0,0 +1,404 @@
+//! Durable queue of writes made while offline.
+//!
+//! When a write cannot reach the store (see [`StorageError::is_retryable`])
+//! it is added to an [`Outbox`], a directory on the clinician's machine, and
+//! replayed in order once the connection returns. Each entry carries the
+//! precondition it was made under — usually the ETag of the version the
+//! clinician was editing — so an object changed elsewhere in the meantime
+//! is reported as a conflict instead of being overwritten.
+//!
+//! Entries hold record contents and filenames, so every entry file is
+//! sealed with [`crypto::seal_with_header`] under a key held locally. File
+//! uploads are queued by path and streamed from the original file at replay,
+//! after checking that the file still has the SHA-256 it had when queued.
+//!
+//! Preconditions are sent with the write itself (`If-Match`, or
+//! `If-None-Match: *` for a new file), so the store rejects a conflicting
+//! write atomically.
+//!
+//! An `Outbox` does no locking of its own; callers that enqueue and replay
+//! concurrently must serialise those calls.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{self, MasterKey};
+use crate::error::StorageError;
+use crate::objects::{self, Metadata, UploadOptions};
+use crate::store::ObjectStore;
+
+pub use crate::store::Precondition;
+
+const ENTRY_EXTENSION: &str = "entry";
+
+/// Where a queued entry stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryStatus {
+    /// Waiting to be replayed.
+    Pending,
+    /// The precondition no longer holds: the object was changed or deleted
+    /// elsewhere. Needs a [`Resolution`] before it is tried again.
+    Conflict,
+    /// Replay failed for a reason other than connectivity, such as the
+    /// source file of a queued upload having been removed.
+    Failed { message: String },
+}
+
+/// One queued write. The body of a queued put is kept alongside it in the
+/// entry file and read back with [`Outbox::body`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// Sorts in queue order.
+    pub id: String,
+    pub bucket: String,
+    pub key: String,
+    pub content_type: Option<String>,
+    /// For a queued upload, the local file to stream from.
+    pub source_path: Option<PathBuf>,
+    /// For a queued upload, the hex SHA-256 of the file when it was queued.
+    /// Replay fails rather than upload a file that has changed since.
+    #[serde(default)]
+    pub source_sha256: Option<String>,
+    pub precondition: Precondition,
+    pub queued_at: jiff::Timestamp,
+    pub status: EntryStatus,
+}
+
+/// How to settle a conflicted or failed entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// Write this version over whatever is in the store now.
+    Overwrite,
+    /// Drop the queued write and keep the store's version.
+    Discard,
+    /// Write this version to a new key instead, leaving the other alone.
+    SaveAs { key: String },
+}
+
+/// What one [`Outbox::replay`] pass did.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaySummary {
+    /// Entries written to the store, in order.
+    pub synced: Vec<OutboxEntry>,
+    /// Entries newly marked [`EntryStatus::Conflict`].
+    pub conflicts: usize,
+    /// Entries newly marked [`EntryStatus::Failed`].
+    pub failed: usize,
+    /// Entries still queued afterwards, of any status.
+    pub remaining: usize,
+    /// Whether replay stopped early because the store was unreachable.
+    pub offline: bool,
+}
+
+/// A directory of sealed, queued writes.
+pub struct Outbox {
+    dir: PathBuf,
+    key: MasterKey,
+}
+
+impl Outbox {
+    /// Open (creating if needed) an outbox directory whose entries are
+    /// sealed with `key`.
+    pub fn open(dir: impl Into<PathBuf>, key: MasterKey) -> Result<Self, StorageError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir, key })
+    }
+
+    /// Queue a put of `body`.
+    ///
+    /// If a put to the same key is already pending, its body is replaced
+    /// instead, keeping its place in the queue and its original
+    /// precondition: the store only ever needs to see the latest text.
+    pub fn enqueue(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        content_type: Option<&str>,
+        precondition: Precondition,
+    ) -> Result<OutboxEntry, StorageError> {
+        let pending = self.entries()?.into_iter().find(|e| {
+            e.bucket == bucket
+                && e.key == key
+                && e.source_path.is_none()
+                && e.status == EntryStatus::Pending
+        });
+        let entry = match pending {
+            Some(mut entry) => {
+                entry.content_type = content_type.map(str::to_string);
+                entry
+            }
+            None => OutboxEntry {
+                id: new_entry_id(),
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                content_type: content_type.map(str::to_string),
+                source_path: None,
+                source_sha256: None,
+                precondition,
+                queued_at: jiff::Timestamp::now(),
+                status: EntryStatus::Pending,
+            },
+        };
+
+        self.write_entry(&entry, &body)?;
+        tracing::info!(id = %entry.id, "write queued for sync");
+        Ok(entry)
+    }
+
+    /// Queue an upload of a local file, read when the entry is replayed.
+    ///
+    /// The file is hashed now, so a file edited or replaced before replay is
+    /// reported as failed instead of uploaded in its new state.
+    pub fn enqueue_file(
+        &self,
+        bucket: &str,
+        key: &str,
+        source_path: &Path,
+        content_type: Option<&str>,
+        precondition: Precondition,
+    ) -> Result<OutboxEntry, StorageError> {
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(source_path)?, &mut hasher)?;
+
+        let entry = OutboxEntry {
+            id: new_entry_id(),
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            content_type: content_type.map(str::to_string),
+            source_path: Some(source_path.to_path_buf()),
+            source_sha256: Some(format!("{:x}", hasher.finalize())),
+            precondition,
+            queued_at: jiff::Timestamp::now(),
+            status: EntryStatus::Pending,
+        };
+        self.write_entry(&entry, &[])?;
+        tracing::info!(id = %entry.id, "upload queued for sync");
+        Ok(entry)
+    }
+
+    /// Every queued entry, oldest first.
+    pub fn entries(&self) -> Result<Vec<OutboxEntry>, StorageError> {
+        let mut ids = Vec::new();
+        for dir_entry in std::fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|ext| ext == ENTRY_EXTENSION)
+                && let Some(id) = path.file_stem().and_then(|s| s.to_str())
+            {
+                ids.push(id.to_string());
+            }
+        }
+        ids.sort();
+
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in ids {
+            match self.read_entry(&id) {
+                Ok((entry, _)) => entries.push(entry),
+                // Replayed and removed while we were listing.
+                Err(StorageError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Whether anything is queued for `key`. Later writes to such a key
+    /// must be queued too, so they replay after the earlier ones.
+    pub fn has_entries_for(&self, bucket: &str, key: &str) -> Result<bool, StorageError> {
+        Ok(self
+            .entries()?
+            .iter()
+            .any(|e| e.bucket == bucket && e.key == key))
+    }
+
+    /// The body of a queued put.
+    pub fn body(&self, id: &str) -> Result<Vec<u8>, StorageError> {
+        Ok(self.read_entry(id)?.1)
+    }
+
+    /// The body of the newest queued put to `key`, i.e. what the object
+    /// will contain once the outbox is replayed.
+    pub fn queued_body(&self, bucket: &str, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let newest = self
+            .entries()?
+            .into_iter()
+            .rev()
+            .find(|e| e.bucket == bucket && e.key == key && e.source_path.is_none());
+        newest.map(|e| self.body(&e.id)).transpose()
+    }
+
+    /// Settle a conflicted or failed entry. A queued upload is then made
+    /// from its source file as it is now, even if it has changed.
+    pub fn resolve(&self, id: &str, resolution: Resolution) -> Result<(), StorageError> {
+        let (mut entry, body) = self.read_entry(id)?;
+        entry.source_sha256 = None;
+        match resolution {
+            Resolution::Discard => return self.remove(id),
+            Resolution::Overwrite => entry.precondition = Precondition::None,
+            Resolution::SaveAs { key } => {
+                entry.key = key;
+                entry.precondition = Precondition::Absent;
+            }
+        }
+        entry.status = EntryStatus::Pending;
+        self.write_entry(&entry, &body)
+    }
+
+    /// Drop an entry without writing it.
+    pub fn remove(&self, id: &str) -> Result<(), StorageError> {
+        match std::fs::remove_file(self.entry_path(id)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write pending entries to the store, oldest first.
+    ///
+    /// An entry whose precondition fails is marked as a conflict, and any
+    /// later entries for the same key are held back until it is resolved.
+    /// Replay stops at the first retryable error, leaving the rest queued.
+    /// An access error is returned as-is, since nothing else will succeed
+    /// either.
+    pub async fn replay(&self, store: &dyn ObjectStore) -> Result<ReplaySummary, StorageError> {
+        let mut summary = ReplaySummary::default();
+        let mut held: HashSet<(String, String)> = HashSet::new();
+
+        for mut entry in self.entries()? {
+            let target = (entry.bucket.clone(), entry.key.clone());
+            if entry.status != EntryStatus::Pending || held.contains(&target) {
+                held.insert(target);
+                summary.remaining += 1;
+                continue;
+            }
+            if summary.offline {
+                summary.remaining += 1;
+                continue;
+            }
+
+            match self.apply(store, &entry).await {
+                Ok(()) => {
+                    self.remove(&entry.id)?;
+                    summary.synced.push(entry);
+                    continue;
+                }
+                Err(e) if e.is_retryable() => {
+                    tracing::info!(error = %e, "store unreachable, outbox replay paused");
+                    summary.offline = true;
+                }
+                Err(StorageError::PreconditionFailed { .. } | StorageError::NotFound { .. }) => {
+                    entry.status = EntryStatus::Conflict;
+                    summary.conflicts += 1;
+                }
+                Err(e @ StorageError::AccessDenied(_)) => return Err(e),
+                Err(e) => {
+                    entry.status = EntryStatus::Failed {
+                        message: e.to_string(),
+                    };
+                    summary.failed += 1;
+                }
+            }
+
+            if entry.status != EntryStatus::Pending {
+                let body = self.body(&entry.id)?;
+                self.write_entry(&entry, &body)?;
+                held.insert(target);
+            }
+            summary.remaining += 1;
+        }
+
+        tracing::info!(
+            synced = summary.synced.len(),
+            conflicts = summary.conflicts,
+            failed = summary.failed,
+            remaining = summary.remaining,
+            offline = summary.offline,
+            "outbox replayed"
+        );
+        Ok(summary)
+    }
+
+    /// Perform one entry's write. A failed precondition is reported as
+    /// `PreconditionFailed`, or `NotFound` when an edited object has since
+    /// been deleted.
+    async fn apply(
+        &self,
+        store: &dyn ObjectStore,
+        entry: &OutboxEntry,
+    ) -> Result<(), StorageError> {
+        if let Some(source_path) = &entry.source_path {
+            let sha256 = objects::sha256_file(source_path).await?;
+            if entry
+                .source_sha256
+                .as_ref()
+                .is_some_and(|queued| *queued != sha256)
+            {
+                return Err(StorageError::Io(std::io::Error::other(format!(
+                    "{} changed after its upload was queued",
+                    source_path.display()
+                ))));
+            }
+            let options = UploadOptions {
+                content_type: entry.content_type.as_deref(),
+                metadata: Metadata::from([(objects::SHA256_METADATA_KEY.to_string(), sha256)]),
+                precondition: entry.precondition.clone(),
+                ..Default::default()
+            };
+            objects::upload_file(
+                store,
+                &entry.bucket,
+                &entry.key,
+                source_path,
+                &options,
+                &mut |_| {},
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let body = self.body(&entry.id)?;
+        store
+            .put_object(
+                &entry.bucket,
+                &entry.key,
+                body,
+                entry.content_type.as_deref(),
+                &Metadata::new(),
+                &entry.precondition,
+            )
+            .await?;
+        Ok(())
+    }
+
+    fn entry_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.{ENTRY_EXTENSION}"))
+    }
+
+    fn write_entry(&self, entry: &OutboxEntry, body: &[u8]) -> Result<(), StorageError> {
+        let meta = serde_json::to_vec(entry)?;
+        let sealed = crypto::seal_with_header(&self.key, &meta, body)?;
+
+        let tmp_path = self.dir.join(format!("{}.tmp", entry.id));
+        std::fs::write(&tmp_path, sealed)?;
+        std::fs::rename(&tmp_path, self.entry_path(&entry.id))?;
+        Ok(())
+    }
+
+    fn read_entry(&self, id: &str) -> Result<(OutboxEntry, Vec<u8>), StorageError> {
+        let sealed = std::fs::read(self.entry_path(id))?;
+        let (meta, body) = crypto::open_with_header(&self.key, sealed)?;
+        Ok((serde_json::from_slice(&meta)?, body))
+    }
+}
+
+/// A new entry ID that sorts after every earlier one: the queue time in
+/// nanoseconds, plus a random suffix in case two land on the same tick.
+fn new_entry_id() -> String {
+    let nanos = jiff::Timestamp::now().as_nanosecond();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!("{nanos:024}-{}", &suffix[..8])
+}
@@ -0,0 +1,96 @@
+//! Permanent purge of a client from the versioned bucket.
+//!
+//! `delete_client` only adds delete markers, so every earlier version of a
+//! client's record stays in the bucket. A purge removes all of it: every
+//! version and delete marker under `records/{id}/`, of `clients/{id}.json`
+//! and of the client's search index shard, deleted in `DeleteObjects`
+//! batches. It cannot be undone.
+//!
+//! What remains is a tombstone at `_audit/purges/{id}.json` recording when
+//! the purge happened and how much was removed. It deliberately holds no
+//! names, filenames or contents — only the client's random ID and counts.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::StorageError;
+use crate::objects::{self, VersionRef};
+use crate::store::ObjectStore;
+
+/// The audit record written after a purge. Contains no PHI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeTombstone {
+    pub client_id: Uuid,
+    pub purged_at: jiff::Timestamp,
+    /// Distinct keys that had at least one version.
+    pub objects: usize,
+    pub versions: usize,
+    pub delete_markers: usize,
+    pub bytes: i64,
+}
+
+/// Permanently delete every version of a client's record, client JSON and
+/// search index shard, then write a [`PurgeTombstone`].
+///
+/// Entries in the global search index are not touched here; callers remove
+/// them separately.
+pub async fn purge_client(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    client_id: Uuid,
+) -> Result<PurgeTombstone, StorageError> {
+    let records_prefix = claria_core::s3_keys::client_records_prefix(client_id);
+    let client_key = claria_core::s3_keys::client(client_id);
+
+    let mut versions = store.list_versions(bucket, &records_prefix).await?;
+    versions.extend(objects::list_object_versions(store, bucket, &client_key).await?);
+    let index_key = claria_core::s3_keys::client_index(client_id);
+    versions.extend(objects::list_object_versions(store, bucket, &index_key).await?);
+
+    let mut keys = std::collections::HashSet::new();
+    let mut delete_markers = 0;
+    let mut bytes = 0;
+    let refs: Vec<VersionRef> = versions
+        .into_iter()
+        .map(|v| {
+            if v.is_delete_marker {
+                delete_markers += 1;
+            }
+            bytes += v.size;
+            keys.insert(v.key.clone());
+            VersionRef {
+                key: v.key,
+                version_id: v.version_id,
+            }
+        })
+        .collect();
+
+    objects::delete_object_versions(store, bucket, &refs).await?;
+
+    let tombstone = PurgeTombstone {
+        client_id,
+        purged_at: jiff::Timestamp::now(),
+        objects: keys.len(),
+        versions: refs.len() - delete_markers,
+        delete_markers,
+        bytes,
+    };
+    let body = serde_json::to_vec_pretty(&tombstone)?;
+    objects::put_object(
+        store,
+        bucket,
+        &claria_core::s3_keys::purge_tombstone(client_id),
+        body,
+        Some("application/json"),
+    )
+    .await?;
+
+    tracing::info!(
+        %client_id,
+        objects = tombstone.objects,
+        versions = tombstone.versions,
+        delete_markers = tombstone.delete_markers,
+        "client purged"
+    );
+    Ok(tombstone)
+}
@@ -0,0 +1,219 @@
+//! Typed CRUD over the JSON models in `claria_core::models`.
+//!
+//! Each record is one JSON object at a key derived from its ID (see
+//! `claria_core::s3_keys`). Reads return the record with its ETag, and
+//! updates pass that ETag back so a concurrent edit from another device
+//! fails with `StorageError::PreconditionFailed` instead of being lost.
+//! `updated_at` is stamped on every write.
+//!
+//! `BedrockTransaction` is not a [`Record`]: it is stored beside the report
+//! it belongs to, at `s3_keys::report_transaction(report_id)`, so its key
+//! cannot be derived from its own ID, and it has no `updated_at`. Read and
+//! write it with [`state::load_state`] and [`state::save_state_if_match`].
+
+use std::marker::PhantomData;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use claria_core::models::assessment::Assessment;
+use claria_core::models::goal::Goal;
+use claria_core::models::report::Report;
+use claria_core::models::snippet::TextSnippet;
+use claria_core::models::template::Template;
+use claria_core::s3_keys;
+
+use crate::error::StorageError;
+use crate::objects;
+use crate::state;
+use crate::store::ObjectStore;
+
+/// A model stored as one JSON object per record.
+pub trait Record: Serialize + DeserializeOwned + Send + Sync {
+    /// Prefix every record of this type is stored under.
+    const PREFIX: &'static str;
+
+    /// The object key for the record with this ID.
+    fn key(id: Uuid) -> String;
+
+    fn id(&self) -> Uuid;
+
+    /// Set `updated_at`.
+    fn touch(&mut self, now: jiff::Timestamp);
+}
+
+/// A record together with the ETag it was read or written at.
+#[derive(Debug, Clone)]
+pub struct Stored<T> {
+    pub record: T,
+    pub etag: String,
+}
+
+/// Create, read, update and delete records of type `T` in a bucket.
+pub struct Repository<'a, T> {
+    store: &'a dyn ObjectStore,
+    bucket: &'a str,
+    _record: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Record> Repository<'a, T> {
+    pub fn new(store: &'a dyn ObjectStore, bucket: &'a str) -> Self {
+        Self {
+            store,
+            bucket,
+            _record: PhantomData,
+        }
+    }
+
+    /// Write a new record. IDs are random UUIDs, so no check is made for an
+    /// existing record with the same ID.
+    pub async fn create(&self, mut record: T) -> Result<Stored<T>, StorageError> {
+        record.touch(jiff::Timestamp::now());
+        let etag =
+            state::save_state(self.store, self.bucket, &T::key(record.id()), &record).await?;
+        Ok(Stored { record, etag })
+    }
+
+    /// Load a record by ID.
+    pub async fn get(&self, id: Uuid) -> Result<Stored<T>, StorageError> {
+        let (record, etag) = state::load_state(self.store, self.bucket, &T::key(id)).await?;
+        Ok(Stored { record, etag })
+    }
+
+    /// Load every record of this type. Objects under the prefix that are not
+    /// records (such as template bodies) are skipped, as are records that no
+    /// longer parse, which are logged.
+    pub async fn list(&self) -> Result<Vec<Stored<T>>, StorageError> {
+        let keys = objects::list_objects(self.store, self.bucket, T::PREFIX).await?;
+
+        let mut records = Vec::new();
+        for key in keys.iter().filter(|k| is_record_key::<T>(k)) {
+            match state::load_state(self.store, self.bucket, key).await {
+                Ok((record, etag)) => records.push(Stored { record, etag }),
+                // Deleted between the listing and the read.
+                Err(StorageError::NotFound { .. }) => {}
+                Err(StorageError::Serialization(e)) => {
+                    tracing::warn!(key, error = %e, "skipping unparseable record");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Write a changed record, provided it has not been modified since it
+    /// was read at `expected_etag`.
+    pub async fn update(
+        &self,
+        mut record: T,
+        expected_etag: &str,
+    ) -> Result<Stored<T>, StorageError> {
+        record.touch(jiff::Timestamp::now());
+        let etag = state::save_state_if_match(
+            self.store,
+            self.bucket,
+            &T::key(record.id()),
+            &record,
+            expected_etag,
+        )
+        .await?;
+        Ok(Stored { record, etag })
+    }
+
+    /// Delete a record. On a versioned bucket this adds a delete marker, so
+    /// the record can still be restored.
+    pub async fn delete(&self, id: Uuid) -> Result<(), StorageError> {
+        objects::delete_object(self.store, self.bucket, &T::key(id)).await
+    }
+}
+
+/// Whether `key` is the record key of some ID, rather than another object
+/// sharing the prefix.
+fn is_record_key<T: Record>(key: &str) -> bool {
+    key.strip_prefix(T::PREFIX)
+        .and_then(|rest| rest.get(..36))
+        .and_then(|id| id.parse::<Uuid>().ok())
+        .is_some_and(|id| T::key(id) == key)
+}
+
+impl Record for Assessment {
+    const PREFIX: &'static str = s3_keys::ASSESSMENTS_PREFIX;
+
+    fn key(id: Uuid) -> String {
+        s3_keys::assessment(id)
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn touch(&mut self, now: jiff::Timestamp) {
+        self.updated_at = now;
+    }
+}
+
+impl Record for Goal {
+    const PREFIX: &'static str = s3_keys::GOALS_PREFIX;
+
+    fn key(id: Uuid) -> String {
+        s3_keys::goal(id)
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn touch(&mut self, now: jiff::Timestamp) {
+        self.updated_at = now;
+    }
+}
+
+impl Record for TextSnippet {
+    const PREFIX: &'static str = s3_keys::SNIPPETS_PREFIX;
+
+    fn key(id: Uuid) -> String {
+        s3_keys::snippet(id)
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn touch(&mut self, now: jiff::Timestamp) {
+        self.updated_at = now;
+    }
+}
+
+/// Template metadata. The Tera body at `s3_key` is stored separately.
+impl Record for Template {
+    const PREFIX: &'static str = s3_keys::TEMPLATES_PREFIX;
+
+    fn key(id: Uuid) -> String {
+        s3_keys::template_metadata(id)
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn touch(&mut self, now: jiff::Timestamp) {
+        self.updated_at = now;
+    }
+}
+
+impl Record for Report {
+    const PREFIX: &'static str = s3_keys::REPORTS_PREFIX;
+
+    fn key(id: Uuid) -> String {
+        s3_keys::report(id)
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn touch(&mut self, now: jiff::Timestamp) {
+        self.updated_at = now;
+    }
+}
@@ -0,0 +1,195 @@
+//! Point-in-time restore of a whole client record.
+//!
+//! Rebuilds every object under `records/{id}/` (record files, sidecars,
+//! chat histories) plus `clients/{id}.json` to the state it had at a given
+//! moment, using the bucket's version history. Like the single-file restore
+//! commands, nothing is rewound: an object is restored by putting the old
+//! version's content back as a new version, and an object that did not
+//! exist at that moment is deleted with a new delete marker. The full
+//! history therefore survives the restore.
+//!
+//! [`plan_client_restore`] is a dry run that only lists versions;
+//! [`restore_client_as_of`] computes the same plan and applies it.
+
+use jiff::Timestamp;
+use uuid::Uuid;
+
+use crate::error::StorageError;
+use crate::objects::{self, ObjectVersion};
+use crate::store::ObjectStore;
+
+/// What a restore will do to one object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreAction {
+    /// Put `version_id`'s content back as the current version.
+    Restore { version_id: String },
+    /// The object did not exist at the restore point; add a delete marker.
+    Delete,
+    /// The object is already in its restore-point state.
+    Unchanged,
+}
+
+/// One object in a [`RestorePlan`].
+#[derive(Debug, Clone)]
+pub struct RestoreEntry {
+    pub key: String,
+    pub action: RestoreAction,
+    /// The current version, or `None` if the object is currently deleted.
+    pub current_version_id: Option<String>,
+}
+
+/// The dry-run listing of a point-in-time restore.
+#[derive(Debug, Clone)]
+pub struct RestorePlan {
+    pub as_of: Timestamp,
+    /// Every key with history in scope, sorted by key.
+    pub entries: Vec<RestoreEntry>,
+}
+
+impl RestorePlan {
+    /// Whether applying the plan would write anything.
+    pub fn is_noop(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|e| e.action == RestoreAction::Unchanged)
+    }
+}
+
+/// Counts of what [`restore_client_as_of`] wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    pub restored: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+/// Work out how to bring a client's record back to its state at `as_of`
+/// without writing anything.
+pub async fn plan_client_restore(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    client_id: Uuid,
+    as_of: Timestamp,
+) -> Result<RestorePlan, StorageError> {
+    let records_prefix = claria_core::s3_keys::client_records_prefix(client_id);
+    let client_key = claria_core::s3_keys::client(client_id);
+
+    let mut versions = store.list_versions(bucket, &records_prefix).await?;
+    versions.extend(objects::list_object_versions(store, bucket, &client_key).await?);
+
+    Ok(plan_from_versions(versions, as_of))
+}
+
+/// Restore a client's record to its state at `as_of`.
+///
+/// Objects are processed in key order and the first failure stops the
+/// restore. Running it again is safe: objects restored by the first attempt
+/// are restored once more from the same version.
+pub async fn restore_client_as_of(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    client_id: Uuid,
+    as_of: Timestamp,
+) -> Result<RestoreSummary, StorageError> {
+    let plan = plan_client_restore(store, bucket, client_id, as_of).await?;
+    apply_plan(store, bucket, &plan).await
+}
+
+/// Apply a plan produced by [`plan_client_restore`].
+pub async fn apply_plan(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    plan: &RestorePlan,
+) -> Result<RestoreSummary, StorageError> {
+    let mut summary = RestoreSummary::default();
+    for entry in &plan.entries {
+        match &entry.action {
+            RestoreAction::Restore { version_id } => {
+                let output =
+                    objects::get_object_version(store, bucket, &entry.key, version_id).await?;
+                objects::put_object_with_metadata(
+                    store,
+                    bucket,
+                    &entry.key,
+                    output.body,
+                    output.content_type.as_deref(),
+                    &output.metadata,
+                )
+                .await?;
+                summary.restored += 1;
+            }
+            RestoreAction::Delete => {
+                objects::delete_object(store, bucket, &entry.key).await?;
+                summary.deleted += 1;
+            }
+            RestoreAction::Unchanged => summary.unchanged += 1,
+        }
+    }
+
+    tracing::info!(
+        as_of = %plan.as_of,
+        restored = summary.restored,
+        deleted = summary.deleted,
+        unchanged = summary.unchanged,
+        "point-in-time restore applied"
+    );
+    Ok(summary)
+}
+
+/// Group a version listing by key and decide each key's action.
+///
+/// S3 lists a page's object versions before its delete markers, so each
+/// key's versions are sorted newest first, the latest winning a tie and
+/// listing order breaking the rest. The first one at or before `as_of` is
+/// then the one that was current at that moment.
+fn plan_from_versions(versions: Vec<ObjectVersion>, as_of: Timestamp) -> RestorePlan {
+    let mut by_key: std::collections::BTreeMap<String, Vec<ObjectVersion>> =
+        std::collections::BTreeMap::new();
+    for v in versions {
+        by_key.entry(v.key.clone()).or_default().push(v);
+    }
+
+    let entries = by_key
+        .into_iter()
+        .map(|(key, mut versions)| {
+            versions.sort_by_cached_key(|v| (std::cmp::Reverse(version_time(v)), !v.is_latest));
+            let current = versions
+                .iter()
+                .find(|v| v.is_latest)
+                .filter(|v| !v.is_delete_marker);
+            let target = versions
+                .iter()
+                .find(|v| version_time(v).is_some_and(|t| t <= as_of))
+                .filter(|v| !v.is_delete_marker);
+
+            let action = match (current, target) {
+                (Some(c), Some(t)) if c.version_id == t.version_id => RestoreAction::Unchanged,
+                (_, Some(t)) => RestoreAction::Restore {
+                    version_id: t.version_id.clone(),
+                },
+                (Some(_), None) => RestoreAction::Delete,
+                (None, None) => RestoreAction::Unchanged,
+            };
+
+            RestoreEntry {
+                key,
+                action,
+                current_version_id: current.map(|v| v.version_id.clone()),
+            }
+        })
+        .collect();
+
+    RestorePlan { as_of, entries }
+}
+
+fn version_time(version: &ObjectVersion) -> Option<Timestamp> {
+    let parsed = version.last_modified.as_deref()?.parse().ok();
+    if parsed.is_none() {
+        tracing::warn!(
+            key = %version.key,
+            version_id = %version.version_id,
+            "unparseable last-modified time, ignoring version"
+        );
+    }
+    parsed
+}
@@ -0,0 +1,347 @@
+//! Retry with jittered exponential backoff.
+//!
+//! [`RetryingStore`] wraps another store and re-sends any request that
+//! fails with a retryable error ([`StorageError::is_retryable`]: throttling
+//! and network failures). Everything else — missing keys, failed
+//! preconditions, access problems — is returned on the first attempt.
+//!
+//! The S3 client handed to a `RetryingStore` should have the SDK's own
+//! retries disabled, otherwise every attempt here is itself retried.
+//!
+//! Conditional writes are retried too. If an attempt reached S3 but its
+//! response was lost, the retry sees the new ETag and fails with
+//! `PreconditionFailed`; callers already treat that as a conflict and reload,
+//! so nothing is overwritten.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::StorageError;
+use crate::objects::{
+    GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use crate::store::{BoxFuture, ObjectStore, Precondition};
+
+/// How often and how patiently to retry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first. `1` disables retrying.
+    pub max_attempts: u32,
+    /// Upper bound of the delay before the first retry; doubles each retry.
+    pub base_delay: Duration,
+    /// Cap on the delay between attempts.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Five attempts over roughly 6 seconds at worst — enough to ride out a
+    /// Wi-Fi drop or an S3 `SlowDown` without leaving the UI hanging.
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before retry number `retry` (starting at 0): a uniformly
+    /// random duration up to `base_delay * 2^retry`, capped at `max_delay`
+    /// ("full jitter"), so clients that failed together do not retry in
+    /// lockstep.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        ceiling.mul_f64(fastrand::f64())
+    }
+}
+
+/// Run `attempt` until it succeeds, fails with a non-retryable error, or
+/// the policy's attempts are used up. `operation` and `key` are only used
+/// for logging.
+pub async fn retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    operation: &str,
+    key: &str,
+    mut attempt: F,
+) -> Result<T, StorageError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, StorageError>>,
+{
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            Err(e) if e.is_retryable() && retries + 1 < policy.max_attempts => {
+                let delay = policy.delay(retries);
+                tracing::warn!(
+                    operation,
+                    key,
+                    attempt = retries + 1,
+                    delay_ms = delay.as_millis() as u64,
+                    error = %e,
+                    "storage request failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Wraps another store and retries throttled and failed-network requests
+/// according to a [`RetryPolicy`].
+pub struct RetryingStore {
+    inner: Arc<dyn ObjectStore>,
+    policy: RetryPolicy,
+}
+
+impl RetryingStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+}
+
+impl ObjectStore for RetryingStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        Box::pin(retry(&self.policy, "GetObject", key, move || {
+            self.inner.get_object(bucket, key, version_id)
+        }))
+    }
+
+    /// Only the request is retried. A failure while reading the body is
+    /// returned to the caller, which has already consumed part of it.
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        Box::pin(retry(&self.policy, "GetObject", key, move || {
+            self.inner.get_object_stream(bucket, key, version_id)
+        }))
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        Box::pin(retry(&self.policy, "GetObject", key, move || {
+            self.inner.get_object_if_none_match(bucket, key, etag)
+        }))
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        Box::pin(retry(&self.policy, "HeadObject", key, move || {
+            self.inner.head_object(bucket, key)
+        }))
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            retry(&self.policy, "PutObject", key, || {
+                self.inner.put_object(
+                    bucket,
+                    key,
+                    body.clone(),
+                    content_type,
+                    metadata,
+                    precondition,
+                )
+            })
+            .await
+        })
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(retry(
+            &self.policy,
+            "CreateMultipartUpload",
+            key,
+            move || {
+                self.inner
+                    .create_multipart_upload(bucket, key, content_type, metadata)
+            },
+        ))
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        Box::pin(async move {
+            retry(&self.policy, "UploadPart", key, || {
+                self.inner
+                    .upload_part(bucket, key, upload_id, part_number, body.clone(), is_last)
+            })
+            .await
+        })
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        Box::pin(retry(&self.policy, "ListParts", key, move || {
+            self.inner.list_parts(bucket, key, upload_id)
+        }))
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(retry(
+            &self.policy,
+            "CompleteMultipartUpload",
+            key,
+            move || {
+                self.inner
+                    .complete_multipart_upload(bucket, key, upload_id, parts, precondition)
+            },
+        ))
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(retry(
+            &self.policy,
+            "AbortMultipartUpload",
+            key,
+            move || self.inner.abort_multipart_upload(bucket, key, upload_id),
+        ))
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(retry(&self.policy, "DeleteObject", key, move || {
+            self.inner.delete_object(bucket, key)
+        }))
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(retry(&self.policy, "DeleteObject", key, move || {
+            self.inner.delete_object_version(bucket, key, version_id)
+        }))
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(retry(&self.policy, "DeleteObjects", bucket, move || {
+            self.inner.delete_object_versions(bucket, versions)
+        }))
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        Box::pin(retry(&self.policy, "ListObjectsV2", prefix, move || {
+            self.inner.list_objects(bucket, prefix)
+        }))
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        Box::pin(retry(
+            &self.policy,
+            "ListObjectVersions",
+            prefix,
+            move || self.inner.list_versions(bucket, prefix),
+        ))
+    }
+
+    /// Presigning is local; nothing to retry.
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner.presign_get(bucket, key, expires_in)
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.inner
+            .presign_put(bucket, key, content_type, expires_in)
+    }
+}
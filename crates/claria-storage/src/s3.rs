@@ -0,0 +1,669 @@
+use std::time::Duration;
+
+use aws_sdk_s3::Client;
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::get_object::GetObjectOutput as GetObjectOutputResponse;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier};
+use aws_smithy_types::byte_stream::ByteStream;
+
+use crate::error::StorageError;
+use crate::objects::{
+    GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use crate::store::{BoxFuture, ObjectBody, ObjectStore, Precondition};
+
+/// A streaming S3 response body.
+struct S3Body(ByteStream);
+
+impl ObjectBody for S3Body {
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, StorageError>> {
+        Box::pin(async move {
+            let chunk = self
+                .0
+                .try_next()
+                .await
+                .map_err(|e| StorageError::Network(e.to_string()))?;
+            Ok(chunk.map(|bytes| bytes.to_vec()))
+        })
+    }
+}
+
+const THROTTLING_CODES: &[&str] = &[
+    "SlowDown",
+    "Throttling",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "TooManyRequests",
+];
+
+const ACCESS_DENIED_CODES: &[&str] = &[
+    "AccessDenied",
+    "AllAccessDisabled",
+    "InvalidAccessKeyId",
+    "SignatureDoesNotMatch",
+    "ExpiredToken",
+    "InvalidToken",
+];
+
+/// Map a failed S3 request to a [`StorageError`].
+///
+/// Missing keys, failed preconditions, throttling, access problems and
+/// network failures get their own variants, decided by the S3 error code
+/// with the HTTP status as a fallback. Any other failure is wrapped with
+/// `other`. `key` is the key (or prefix) the request was for.
+fn classify<E>(err: SdkError<E>, key: &str, other: fn(String) -> StorageError) -> StorageError
+where
+    E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+{
+    let service = match err {
+        SdkError::ServiceError(service) => service,
+        SdkError::TimeoutError(_) | SdkError::ResponseError(_) => {
+            return StorageError::Network(DisplayErrorContext(&err).to_string());
+        }
+        SdkError::DispatchFailure(ref failure) if failure.is_io() || failure.is_timeout() => {
+            return StorageError::Network(DisplayErrorContext(&err).to_string());
+        }
+        _ => return other(DisplayErrorContext(&err).to_string()),
+    };
+
+    let status = service.raw().status().as_u16();
+    let err = service.into_err();
+    let code = err.code().unwrap_or_default();
+
+    if matches!(code, "NoSuchKey" | "NoSuchVersion" | "NotFound") {
+        StorageError::NotFound {
+            key: key.to_string(),
+        }
+    } else if matches!(code, "PreconditionFailed" | "ConditionalRequestConflict") || status == 412 {
+        StorageError::PreconditionFailed {
+            key: key.to_string(),
+        }
+    } else if THROTTLING_CODES.contains(&code) || matches!(status, 429 | 503) {
+        StorageError::Throttled(err.to_string())
+    } else if ACCESS_DENIED_CODES.contains(&code) || status == 403 {
+        StorageError::AccessDenied(err.to_string())
+    } else if matches!(code, "InternalError" | "RequestTimeout")
+        || matches!(status, 500 | 502 | 504)
+    {
+        StorageError::Network(err.to_string())
+    } else {
+        other(err.to_string())
+    }
+}
+
+/// Read a whole GetObject response into memory.
+async fn collect_output(resp: GetObjectOutputResponse) -> Result<GetObjectOutput, StorageError> {
+    let etag = resp.e_tag().map(|s| s.to_string());
+    let content_type = resp.content_type().map(|s| s.to_string());
+    let metadata = resp
+        .metadata()
+        .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+    let body = resp
+        .body
+        .collect()
+        .await
+        .map_err(|e| StorageError::Network(e.to_string()))?
+        .into_bytes()
+        .to_vec();
+
+    Ok(GetObjectOutput {
+        body,
+        etag,
+        content_type,
+        metadata,
+    })
+}
+
+/// The S3 backend. Every request goes straight to the AWS SDK.
+impl ObjectStore for Client {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        Box::pin(async move {
+            let mut req = self.get_object().bucket(bucket).key(key);
+
+            if let Some(v) = version_id {
+                req = req.version_id(v);
+            }
+
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::GetObject))?;
+
+            collect_output(resp).await
+        })
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        Box::pin(async move {
+            let resp = match self
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .if_none_match(etag)
+                .send()
+                .await
+            {
+                Ok(resp) => resp,
+                // S3 answers an unchanged object with 304 Not Modified.
+                Err(SdkError::ServiceError(e)) if e.raw().status().as_u16() == 304 => {
+                    return Ok(None);
+                }
+                Err(e) => return Err(classify(e, key, StorageError::GetObject)),
+            };
+
+            collect_output(resp).await.map(Some)
+        })
+    }
+
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        Box::pin(async move {
+            let mut req = self.get_object().bucket(bucket).key(key);
+
+            if let Some(v) = version_id {
+                req = req.version_id(v);
+            }
+
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::GetObject))?;
+
+            Ok(GetObjectStream {
+                etag: resp.e_tag().map(|s| s.to_string()),
+                content_type: resp.content_type().map(|s| s.to_string()),
+                content_length: resp.content_length(),
+                body: Box::new(S3Body(resp.body)),
+            })
+        })
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        Box::pin(async move {
+            let resp = self
+                .head_object()
+                .bucket(bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::HeadObject))?;
+
+            Ok(ObjectHead {
+                etag: resp.e_tag().map(|s| s.to_string()),
+                content_type: resp.content_type().map(|s| s.to_string()),
+                metadata: resp
+                    .metadata()
+                    .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+                    .unwrap_or_default(),
+            })
+        })
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let mut req = self
+                .put_object()
+                .bucket(bucket)
+                .key(key)
+                .body(ByteStream::from(body));
+
+            if let Some(ct) = content_type {
+                req = req.content_type(ct);
+            }
+            for (name, value) in metadata {
+                req = req.metadata(name, value);
+            }
+            match precondition {
+                Precondition::None => {}
+                Precondition::Absent => req = req.if_none_match("*"),
+                Precondition::IfMatch(etag) => req = req.if_match(etag),
+            }
+
+            // S3 returns 412 Precondition Failed when the precondition does
+            // not hold, or 409 if a concurrent conditional write is in flight.
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::PutObject))?;
+
+            Ok(resp.e_tag().unwrap_or_default().to_string())
+        })
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let mut req = self.create_multipart_upload().bucket(bucket).key(key);
+
+            if let Some(ct) = content_type {
+                req = req.content_type(ct);
+            }
+            for (name, value) in metadata {
+                req = req.metadata(name, value);
+            }
+
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::MultipartUpload))?;
+
+            resp.upload_id().map(|s| s.to_string()).ok_or_else(|| {
+                StorageError::MultipartUpload("CreateMultipartUpload returned no upload ID".into())
+            })
+        })
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        _is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        Box::pin(async move {
+            let size = body.len() as i64;
+            let resp = self
+                .upload_part()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(body))
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::MultipartUpload))?;
+
+            Ok(UploadedPart {
+                part_number,
+                etag: resp.e_tag().unwrap_or_default().to_string(),
+                size,
+            })
+        })
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        Box::pin(async move {
+            let mut parts = Vec::new();
+            let mut marker: Option<String> = None;
+
+            loop {
+                let mut req = self
+                    .list_parts()
+                    .bucket(bucket)
+                    .key(key)
+                    .upload_id(upload_id);
+
+                if let Some(m) = &marker {
+                    req = req.part_number_marker(m);
+                }
+
+                let resp = req
+                    .send()
+                    .await
+                    .map_err(|e| classify(e, key, StorageError::MultipartUpload))?;
+
+                for p in resp.parts() {
+                    parts.push(UploadedPart {
+                        part_number: p.part_number().unwrap_or_default(),
+                        etag: p.e_tag().unwrap_or_default().to_string(),
+                        size: p.size().unwrap_or(0),
+                    });
+                }
+
+                if resp.is_truncated() == Some(true) {
+                    marker = resp.next_part_number_marker().map(|s| s.to_string());
+                } else {
+                    break;
+                }
+            }
+
+            Ok(parts)
+        })
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let completed = CompletedMultipartUpload::builder()
+                .set_parts(Some(
+                    parts
+                        .iter()
+                        .map(|p| {
+                            CompletedPart::builder()
+                                .part_number(p.part_number)
+                                .e_tag(&p.etag)
+                                .build()
+                        })
+                        .collect(),
+                ))
+                .build();
+
+            let mut req = self
+                .complete_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .multipart_upload(completed);
+            match precondition {
+                Precondition::None => {}
+                Precondition::Absent => req = req.if_none_match("*"),
+                Precondition::IfMatch(etag) => req = req.if_match(etag),
+            }
+
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::MultipartUpload))?;
+
+            Ok(resp.e_tag().unwrap_or_default().to_string())
+        })
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::MultipartUpload))?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.delete_object()
+                .bucket(bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::DeleteObject))?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.delete_object()
+                .bucket(bucket)
+                .key(key)
+                .version_id(version_id)
+                .send()
+                .await
+                .map_err(|e| classify(e, key, StorageError::DeleteObject))?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            if versions.is_empty() {
+                return Ok(());
+            }
+
+            let objects = versions
+                .iter()
+                .map(|v| {
+                    ObjectIdentifier::builder()
+                        .key(&v.key)
+                        .version_id(&v.version_id)
+                        .build()
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| StorageError::DeleteObject(e.to_string()))?;
+            let delete = Delete::builder()
+                .set_objects(Some(objects))
+                .quiet(true)
+                .build()
+                .map_err(|e| StorageError::DeleteObject(e.to_string()))?;
+
+            let resp = self
+                .delete_objects()
+                .bucket(bucket)
+                .delete(delete)
+                .send()
+                .await
+                .map_err(|e| classify(e, bucket, StorageError::DeleteObject))?;
+
+            // DeleteObjects reports per-key failures in a successful response.
+            if let Some(first) = resp.errors().first() {
+                return Err(StorageError::DeleteObject(format!(
+                    "{} of {} versions were not deleted; first failure on {}: {}",
+                    resp.errors().len(),
+                    versions.len(),
+                    first.key().unwrap_or_default(),
+                    first.message().or(first.code()).unwrap_or("unknown error"),
+                )));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        Box::pin(async move {
+            let mut objects = Vec::new();
+            let mut continuation_token: Option<String> = None;
+
+            loop {
+                let mut req = self.list_objects_v2().bucket(bucket).prefix(prefix);
+
+                if let Some(token) = &continuation_token {
+                    req = req.continuation_token(token);
+                }
+
+                let resp = req
+                    .send()
+                    .await
+                    .map_err(|e| classify(e, prefix, StorageError::ListObjects))?;
+
+                for obj in resp.contents() {
+                    if let Some(key) = obj.key() {
+                        objects.push(ObjectMeta {
+                            key: key.to_string(),
+                            size: obj.size().unwrap_or(0),
+                            last_modified: obj.last_modified().map(|t| t.to_string()),
+                        });
+                    }
+                }
+
+                if resp.is_truncated() == Some(true) {
+                    continuation_token = resp.next_continuation_token().map(|s| s.to_string());
+                } else {
+                    break;
+                }
+            }
+
+            Ok(objects)
+        })
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        Box::pin(async move {
+            let mut versions = Vec::new();
+            let mut key_marker: Option<String> = None;
+            let mut version_id_marker: Option<String> = None;
+
+            loop {
+                let mut req = self.list_object_versions().bucket(bucket).prefix(prefix);
+
+                if let Some(km) = &key_marker {
+                    req = req.key_marker(km);
+                }
+                if let Some(vm) = &version_id_marker {
+                    req = req.version_id_marker(vm);
+                }
+
+                let resp = req
+                    .send()
+                    .await
+                    .map_err(|e| classify(e, prefix, StorageError::ListObjectVersions))?;
+
+                for v in resp.versions() {
+                    if let Some(key) = v.key() {
+                        versions.push(ObjectVersion {
+                            key: key.to_string(),
+                            version_id: v.version_id().unwrap_or_default().to_string(),
+                            size: v.size().unwrap_or(0),
+                            last_modified: v.last_modified().map(|t| t.to_string()),
+                            is_latest: v.is_latest().unwrap_or(false),
+                            is_delete_marker: false,
+                        });
+                    }
+                }
+
+                for dm in resp.delete_markers() {
+                    if let Some(key) = dm.key() {
+                        versions.push(ObjectVersion {
+                            key: key.to_string(),
+                            version_id: dm.version_id().unwrap_or_default().to_string(),
+                            size: 0,
+                            last_modified: dm.last_modified().map(|t| t.to_string()),
+                            is_latest: dm.is_latest().unwrap_or(false),
+                            is_delete_marker: true,
+                        });
+                    }
+                }
+
+                if resp.is_truncated() == Some(true) {
+                    key_marker = resp.next_key_marker().map(|s| s.to_string());
+                    version_id_marker = resp.next_version_id_marker().map(|s| s.to_string());
+                } else {
+                    break;
+                }
+            }
+
+            Ok(versions)
+        })
+    }
+
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let presign_config = PresigningConfig::builder()
+                .expires_in(expires_in)
+                .build()
+                .map_err(|e| StorageError::Presign(e.to_string()))?;
+
+            let presigned = self
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .presigned(presign_config)
+                .await
+                .map_err(|e| StorageError::Presign(e.to_string()))?;
+
+            Ok(presigned.uri().to_string())
+        })
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            let presign_config = PresigningConfig::builder()
+                .expires_in(expires_in)
+                .build()
+                .map_err(|e| StorageError::Presign(e.to_string()))?;
+
+            let mut req = self.put_object().bucket(bucket).key(key);
+
+            if let Some(ct) = content_type {
+                req = req.content_type(ct);
+            }
+
+            let presigned = req
+                .presigned(presign_config)
+                .await
+                .map_err(|e| StorageError::Presign(e.to_string()))?;
+
+            Ok(presigned.uri().to_string())
+        })
+    }
+}
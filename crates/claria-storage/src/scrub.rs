@@ -0,0 +1,323 @@
+//! Integrity scrub of client records, client JSON and reports.
+//!
+//! Record files and their `.text` sidecars are written separately, so they
+//! can drift apart: a sidecar outlives its file, extraction never ran, or a
+//! partial delete leaves record files behind without a client. [`scan`]
+//! walks `records/`, `clients/` and `reports/` and classifies each such
+//! inconsistency without changing anything, which makes it the dry run.
+//!
+//! [`repair`] then fixes one issue at a time. Orphans are deleted (with
+//! delete markers, so they can still be restored) and unparseable JSON is
+//! moved to `_quarantine/`. Missing sidecars need Bedrock or Transcribe to
+//! regenerate, so [`Repair::Reextract`] is left to the caller.
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use claria_core::models::chat_history::ChatHistory;
+use claria_core::models::client::Client;
+use claria_core::models::report::Report;
+use claria_core::s3_keys;
+
+use crate::error::StorageError;
+use crate::objects;
+use crate::store::ObjectStore;
+
+/// What is wrong with an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A `.text` sidecar whose record file no longer exists.
+    OrphanedSidecar,
+    /// A record file that should have a `.text` sidecar but has none.
+    MissingSidecar,
+    /// A record file or chat history under a client that no longer exists.
+    OrphanedRecord,
+    /// Chat history, client or report JSON that no longer parses.
+    UnparseableJson,
+    /// JSON that parses but names an ID or object that does not match or
+    /// does not exist.
+    BrokenReference,
+}
+
+/// How an issue can be fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repair {
+    /// Regenerate the sidecar from the record file. Done by the caller.
+    Reextract,
+    /// Delete the object.
+    Delete,
+    /// Move the object to `_quarantine/`.
+    Quarantine,
+}
+
+/// One inconsistency found by [`scan`].
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub key: String,
+    pub kind: IssueKind,
+    /// `None` when the issue needs a person to look at it.
+    pub repair: Option<Repair>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScrubReport {
+    /// Objects looked at.
+    pub scanned: usize,
+    pub issues: Vec<Issue>,
+}
+
+/// Find inconsistencies under `records/`, `clients/` and `reports/`.
+///
+/// `needs_sidecar` says whether a record file with this filename should
+/// have a `.text` sidecar, which depends on what the caller can extract.
+pub async fn scan(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    needs_sidecar: &(dyn Fn(&str) -> bool + Sync),
+) -> Result<ScrubReport, StorageError> {
+    let mut report = ScrubReport::default();
+
+    let clients = scan_clients(store, bucket, &mut report).await?;
+    scan_records(store, bucket, &clients, needs_sidecar, &mut report).await?;
+    scan_reports(store, bucket, &mut report).await?;
+
+    tracing::info!(
+        bucket,
+        scanned = report.scanned,
+        issues = report.issues.len(),
+        "integrity scrub finished"
+    );
+    Ok(report)
+}
+
+/// Apply an issue's repair. Returns `false` if there is nothing this
+/// function can do, which is the case for [`Repair::Reextract`] and for
+/// issues with no repair.
+pub async fn repair(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    issue: &Issue,
+) -> Result<bool, StorageError> {
+    match issue.repair {
+        Some(Repair::Delete) => {
+            objects::delete_object(store, bucket, &issue.key).await?;
+        }
+        Some(Repair::Quarantine) => {
+            let output = match objects::get_object(store, bucket, &issue.key).await {
+                Ok(output) => output,
+                // Already gone; nothing left to quarantine.
+                Err(StorageError::NotFound { .. }) => return Ok(true),
+                Err(e) => return Err(e),
+            };
+            objects::put_object(
+                store,
+                bucket,
+                &s3_keys::quarantine(&issue.key),
+                output.body,
+                output.content_type.as_deref(),
+            )
+            .await?;
+            objects::delete_object(store, bucket, &issue.key).await?;
+        }
+        Some(Repair::Reextract) | None => return Ok(false),
+    }
+    tracing::info!(key = issue.key, kind = ?issue.kind, "scrub repaired object");
+    Ok(true)
+}
+
+/// Check every client JSON and return the IDs of the clients that exist.
+async fn scan_clients(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    report: &mut ScrubReport,
+) -> Result<HashSet<Uuid>, StorageError> {
+    let mut clients = HashSet::new();
+    for key in objects::list_objects(store, bucket, s3_keys::CLIENTS_PREFIX).await? {
+        report.scanned += 1;
+        let Some(id) = key
+            .strip_prefix(s3_keys::CLIENTS_PREFIX)
+            .and_then(|rest| rest.strip_suffix(".json"))
+            .and_then(|id| id.parse::<Uuid>().ok())
+        else {
+            continue;
+        };
+        // Even a client whose JSON is broken still owns its records.
+        clients.insert(id);
+
+        let Some(client) = read_json::<Client>(store, bucket, &key, report).await? else {
+            continue;
+        };
+        if client.id != id {
+            report.issues.push(Issue {
+                key,
+                kind: IssueKind::BrokenReference,
+                repair: None,
+                detail: format!("client JSON has ID {}", client.id),
+            });
+        }
+    }
+    Ok(clients)
+}
+
+async fn scan_records(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    clients: &HashSet<Uuid>,
+    needs_sidecar: &(dyn Fn(&str) -> bool + Sync),
+    report: &mut ScrubReport,
+) -> Result<(), StorageError> {
+    let keys = objects::list_objects(store, bucket, "records/").await?;
+    let existing: HashSet<&str> = keys.iter().map(String::as_str).collect();
+
+    for key in &keys {
+        report.scanned += 1;
+        let Some((id, rest)) = key
+            .strip_prefix("records/")
+            .and_then(|rest| rest.split_once('/'))
+            .and_then(|(id, rest)| Some((id.parse::<Uuid>().ok()?, rest)))
+        else {
+            continue;
+        };
+
+        if !clients.contains(&id) {
+            report.issues.push(Issue {
+                key: key.clone(),
+                kind: IssueKind::OrphanedRecord,
+                repair: Some(Repair::Delete),
+                detail: format!("client {id} does not exist"),
+            });
+            continue;
+        }
+
+        if rest.starts_with("chat-history/") {
+            let Some(history) = read_json::<ChatHistory>(store, bucket, key, report).await? else {
+                continue;
+            };
+            if history.client_id != id {
+                report.issues.push(Issue {
+                    key: key.clone(),
+                    kind: IssueKind::BrokenReference,
+                    repair: None,
+                    detail: format!("chat history belongs to client {}", history.client_id),
+                });
+            }
+        } else if let Some(base) = key.strip_suffix(".text") {
+            // A `.text` file with no parent may be a sidecar left behind or
+            // a file the clinician named that way. Only call it orphaned if
+            // the parent ever existed.
+            if !existing.contains(base) && was_ever_present(store, bucket, base).await? {
+                report.issues.push(Issue {
+                    key: key.clone(),
+                    kind: IssueKind::OrphanedSidecar,
+                    repair: Some(Repair::Delete),
+                    detail: format!("{} was deleted", filename(base)),
+                });
+            }
+        } else if needs_sidecar(rest) && !existing.contains(format!("{key}.text").as_str()) {
+            report.issues.push(Issue {
+                key: key.clone(),
+                kind: IssueKind::MissingSidecar,
+                repair: Some(Repair::Reextract),
+                detail: "text was never extracted".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+async fn scan_reports(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    report: &mut ScrubReport,
+) -> Result<(), StorageError> {
+    let keys = objects::list_objects(store, bucket, s3_keys::REPORTS_PREFIX).await?;
+    let existing: HashSet<&str> = keys.iter().map(String::as_str).collect();
+    let templates: HashSet<String> =
+        objects::list_objects(store, bucket, s3_keys::TEMPLATES_PREFIX)
+            .await?
+            .into_iter()
+            .collect();
+
+    // Reports are a folder of objects; only `report.json` names others.
+    let mut metadata = Vec::new();
+    for key in &keys {
+        report.scanned += 1;
+        if let Some(id) = key
+            .strip_prefix(s3_keys::REPORTS_PREFIX)
+            .and_then(|rest| rest.strip_suffix("/report.json"))
+            .and_then(|id| id.parse::<Uuid>().ok())
+        {
+            metadata.push((key, id));
+        }
+    }
+
+    for (key, id) in metadata {
+        let Some(stored) = read_json::<Report>(store, bucket, key, report).await? else {
+            continue;
+        };
+        let mut broken = Vec::new();
+        if stored.id != id {
+            broken.push(format!("report JSON has ID {}", stored.id));
+        }
+        if !templates.contains(&s3_keys::template_metadata(stored.template_id)) {
+            broken.push(format!("template {} does not exist", stored.template_id));
+        }
+        if !stored.s3_key.is_empty() && !existing.contains(stored.s3_key.as_str()) {
+            broken.push(format!("{} does not exist", stored.s3_key));
+        }
+        if !broken.is_empty() {
+            report.issues.push(Issue {
+                key: key.to_string(),
+                kind: IssueKind::BrokenReference,
+                repair: None,
+                detail: broken.join("; "),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Read and parse a JSON object, recording an [`IssueKind::UnparseableJson`]
+/// issue if it does not parse. Objects deleted since the listing are
+/// skipped.
+async fn read_json<T: serde::de::DeserializeOwned>(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+    report: &mut ScrubReport,
+) -> Result<Option<T>, StorageError> {
+    let body = match objects::get_object(store, bucket, key).await {
+        Ok(output) => output.body,
+        Err(StorageError::NotFound { .. }) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    match serde_json::from_slice(&body) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            report.issues.push(Issue {
+                key: key.to_string(),
+                kind: IssueKind::UnparseableJson,
+                repair: Some(Repair::Quarantine),
+                detail: e.to_string(),
+            });
+            Ok(None)
+        }
+    }
+}
+
+/// Whether `key` has any version or delete marker.
+async fn was_ever_present(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    key: &str,
+) -> Result<bool, StorageError> {
+    Ok(!objects::list_object_versions(store, bucket, key)
+        .await?
+        .is_empty())
+}
+
+fn filename(key: &str) -> &str {
+    key.rsplit_once('/').map_or(key, |(_, name)| name)
+}
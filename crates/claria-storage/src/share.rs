@@ -0,0 +1,330 @@
+//! Time-limited share links for record files and reports.
+//!
+//! Recipients such as a referring provider have neither AWS credentials nor
+//! the practice master key, so a share is an unencrypted copy of the file at
+//! `_shares/{id}/{filename}` reached through a presigned GET URL. The copy
+//! is written through a store without client-side encryption (the bucket's
+//! own server-side encryption still applies) and is permanently deleted when
+//! the share is revoked or cleaned up after it expires.
+//!
+//! Every share is recorded at `_audit/shares/{id}.json` — who shared which
+//! object, with whom, when, and until when — through the regular (encrypted)
+//! store. Records are kept after revocation and expiry as the audit trail.
+//!
+//! Links are issued with [`issue_link`], and every one is added to the
+//! share's record with who issued it and until when. Presigned URLs are
+//! limited to [`MAX_EXPIRY`] by S3 and can be fetched any number of times
+//! until they expire; S3 keeps no count. A share with a download limit
+//! therefore counts links instead of fetches: each of its links lasts at
+//! most [`LIMITED_LINK_EXPIRY`], at most `max_downloads` of them are issued,
+//! and the share stops being outstanding once the last one expires.
+//!
+//! Copies left behind by shares that simply expire are removed by
+//! [`remove_expired`] and, should the app not run, by the bucket lifecycle
+//! rule on [`s3_keys::SHARES_PREFIX`].
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use claria_core::s3_keys;
+
+use crate::error::StorageError;
+use crate::objects::{self, VersionRef};
+use crate::state;
+use crate::store::ObjectStore;
+
+/// The longest a presigned URL can stay valid (a SigV4 limit).
+pub const MAX_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long each link to a share with a download limit stays valid: long
+/// enough to open the message and download the file once.
+pub const LIMITED_LINK_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// What to share, with whom and for how long.
+#[derive(Debug, Clone)]
+pub struct ShareRequest<'a> {
+    /// Key of the record file or report to share.
+    pub source_key: &'a str,
+    /// Filename the recipient downloads it as.
+    pub filename: &'a str,
+    /// The client the file belongs to, if any.
+    pub client_id: Option<Uuid>,
+    pub recipient: &'a str,
+    /// The identity issuing the share, such as an IAM ARN.
+    pub created_by: &'a str,
+    pub expires_in: Duration,
+    /// How many links may be issued, each counted as one download.
+    pub max_downloads: Option<u32>,
+}
+
+/// The audit record of one share.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Share {
+    pub id: Uuid,
+    pub source_key: String,
+    /// Key of the unencrypted copy the link points at.
+    pub shared_key: String,
+    pub filename: String,
+    pub client_id: Option<Uuid>,
+    pub recipient: String,
+    pub created_by: String,
+    pub created_at: jiff::Timestamp,
+    pub expires_at: jiff::Timestamp,
+    /// How many links may be issued, if limited.
+    #[serde(default)]
+    pub max_downloads: Option<u32>,
+    /// Every link issued for the share, oldest first.
+    #[serde(default)]
+    pub links: Vec<IssuedLink>,
+    pub revoked_at: Option<jiff::Timestamp>,
+    pub revoked_by: Option<String>,
+}
+
+/// One presigned URL issued for a share. The URL itself is not stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedLink {
+    pub issued_by: String,
+    pub issued_at: jiff::Timestamp,
+    pub expires_at: jiff::Timestamp,
+}
+
+impl Share {
+    /// How many more links may be issued, or `None` if unlimited.
+    pub fn downloads_left(&self) -> Option<u32> {
+        let issued = u32::try_from(self.links.len()).unwrap_or(u32::MAX);
+        self.max_downloads.map(|max| max.saturating_sub(issued))
+    }
+
+    /// When the last link that can still be issued, or already was, stops
+    /// working: the share's expiry, or the last link's once the download
+    /// limit is used up.
+    pub fn active_until(&self) -> jiff::Timestamp {
+        match (self.downloads_left(), self.links.last()) {
+            (Some(0), Some(last)) => last.expires_at.min(self.expires_at),
+            _ => self.expires_at,
+        }
+    }
+
+    /// Not revoked, and a link to it may still work.
+    pub fn is_outstanding(&self, now: jiff::Timestamp) -> bool {
+        self.revoked_at.is_none() && now < self.active_until()
+    }
+}
+
+/// Copy the object at `request.source_key` to a new share and record it.
+///
+/// `store` reads the source and writes the audit record; `public` writes
+/// the copy and must not encrypt it. Use [`issue_link`] to get the link.
+pub async fn create_share(
+    store: &dyn ObjectStore,
+    public: &dyn ObjectStore,
+    bucket: &str,
+    request: &ShareRequest<'_>,
+) -> Result<Share, StorageError> {
+    if request.expires_in.is_zero() || request.expires_in > MAX_EXPIRY {
+        return Err(StorageError::Presign(format!(
+            "share links can last at most {} days",
+            MAX_EXPIRY.as_secs() / 86_400
+        )));
+    }
+    if request.max_downloads == Some(0) {
+        return Err(StorageError::Presign(
+            "a download limit must allow at least one download".to_string(),
+        ));
+    }
+    let expires_in = jiff::SignedDuration::try_from(request.expires_in)
+        .map_err(|e| StorageError::Presign(e.to_string()))?;
+
+    let id = Uuid::new_v4();
+    let source = objects::get_object(store, bucket, request.source_key).await?;
+    let shared_key = s3_keys::shared_file(id, request.filename);
+    objects::put_object(
+        public,
+        bucket,
+        &shared_key,
+        source.body,
+        source.content_type.as_deref(),
+    )
+    .await?;
+
+    let created_at = jiff::Timestamp::now();
+    let share = Share {
+        id,
+        source_key: request.source_key.to_string(),
+        shared_key,
+        filename: request.filename.to_string(),
+        client_id: request.client_id,
+        recipient: request.recipient.to_string(),
+        created_by: request.created_by.to_string(),
+        created_at,
+        expires_at: created_at
+            .checked_add(expires_in)
+            .map_err(|e| StorageError::Presign(e.to_string()))?,
+        max_downloads: request.max_downloads,
+        links: Vec::new(),
+        revoked_at: None,
+        revoked_by: None,
+    };
+    state::save_state(store, bucket, &s3_keys::share_record(id), &share).await?;
+
+    tracing::info!(
+        share_id = %id,
+        source_key = share.source_key,
+        created_by = share.created_by,
+        expires_at = %share.expires_at,
+        max_downloads = ?share.max_downloads,
+        "share created"
+    );
+    Ok(share)
+}
+
+/// Issue a presigned URL for an outstanding share and add it to the
+/// share's record.
+///
+/// The URL lasts until the share expires, or at most
+/// [`LIMITED_LINK_EXPIRY`] if the share has a download limit, in which case
+/// it counts as one download. Once the limit is used up no more links are
+/// issued. The record is written with its ETag, so two devices issuing the
+/// last link at once cannot both succeed: the loser gets
+/// [`StorageError::PreconditionFailed`] and its URL is never returned.
+pub async fn issue_link(
+    store: &dyn ObjectStore,
+    public: &dyn ObjectStore,
+    bucket: &str,
+    id: Uuid,
+    issued_by: &str,
+) -> Result<(Share, String), StorageError> {
+    let key = s3_keys::share_record(id);
+    let (mut share, etag): (Share, String) = state::load_state(store, bucket, &key).await?;
+
+    let now = jiff::Timestamp::now();
+    let remaining = now.duration_until(share.expires_at);
+    if share.revoked_at.is_some() || remaining.is_negative() || remaining.is_zero() {
+        return Err(StorageError::Presign(format!(
+            "share {id} is no longer active"
+        )));
+    }
+    if share.downloads_left() == Some(0) {
+        return Err(StorageError::Presign(format!(
+            "share {id} has no downloads left"
+        )));
+    }
+
+    let limit = if share.max_downloads.is_some() {
+        LIMITED_LINK_EXPIRY
+    } else {
+        MAX_EXPIRY
+    };
+    let expires_in = Duration::try_from(remaining)
+        .map_err(|e| StorageError::Presign(e.to_string()))?
+        .min(limit);
+    let url = objects::presign_get(public, bucket, &share.shared_key, expires_in).await?;
+
+    let expires_at = jiff::SignedDuration::try_from(expires_in)
+        .and_then(|d| now.checked_add(d))
+        .map_err(|e| StorageError::Presign(e.to_string()))?;
+    share.links.push(IssuedLink {
+        issued_by: issued_by.to_string(),
+        issued_at: now,
+        expires_at,
+    });
+    state::save_state_if_match(store, bucket, &key, &share, &etag).await?;
+
+    tracing::info!(
+        share_id = %id,
+        issued_by,
+        expires_at = %expires_at,
+        downloads_left = ?share.downloads_left(),
+        "share link issued"
+    );
+    Ok((share, url))
+}
+
+/// Every share ever issued, newest first.
+pub async fn list_shares(
+    store: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<Vec<Share>, StorageError> {
+    let keys = objects::list_objects(store, bucket, s3_keys::SHARES_AUDIT_PREFIX).await?;
+
+    let mut shares = Vec::with_capacity(keys.len());
+    for key in &keys {
+        match state::load_state::<Share>(store, bucket, key).await {
+            Ok((share, _)) => shares.push(share),
+            Err(StorageError::NotFound { .. }) => {}
+            Err(StorageError::Serialization(e)) => {
+                tracing::warn!(key, error = %e, "skipping unparseable share record");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    shares.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(shares)
+}
+
+/// Revoke a share: permanently delete its copy, so the link stops working,
+/// and stamp the record. Revoking an already revoked share is a no-op.
+pub async fn revoke_share(
+    store: &dyn ObjectStore,
+    public: &dyn ObjectStore,
+    bucket: &str,
+    id: Uuid,
+    revoked_by: &str,
+) -> Result<Share, StorageError> {
+    let key = s3_keys::share_record(id);
+    let (mut share, etag): (Share, String) = state::load_state(store, bucket, &key).await?;
+    if share.revoked_at.is_some() {
+        return Ok(share);
+    }
+
+    delete_copy(public, bucket, &share).await?;
+    share.revoked_at = Some(jiff::Timestamp::now());
+    share.revoked_by = Some(revoked_by.to_string());
+    state::save_state_if_match(store, bucket, &key, &share, &etag).await?;
+
+    tracing::info!(share_id = %id, revoked_by, "share revoked");
+    Ok(share)
+}
+
+/// Delete the copies of shares that have expired or whose download limit
+/// is used up and last link expired. Returns how many were removed. The
+/// links stopped working by then; this only reclaims the unencrypted copies.
+pub async fn remove_expired(
+    store: &dyn ObjectStore,
+    public: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<usize, StorageError> {
+    let now = jiff::Timestamp::now();
+    let mut removed = 0;
+    for share in list_shares(store, bucket).await? {
+        if share.revoked_at.is_none() && !share.is_outstanding(now) {
+            removed += delete_copy(public, bucket, &share).await?;
+        }
+    }
+    Ok(removed)
+}
+
+/// Permanently delete every version of a share's copy. Returns 1 if there
+/// was anything to delete.
+async fn delete_copy(
+    public: &dyn ObjectStore,
+    bucket: &str,
+    share: &Share,
+) -> Result<usize, StorageError> {
+    let versions: Vec<VersionRef> =
+        objects::list_object_versions(public, bucket, &share.shared_key)
+            .await?
+            .into_iter()
+            .map(|v| VersionRef {
+                key: v.key,
+                version_id: v.version_id,
+            })
+            .collect();
+    if versions.is_empty() {
+        return Ok(0);
+    }
+    objects::delete_object_versions(public, bucket, &versions).await?;
+    Ok(1)
+}
@@ -1,35 +1,35 @@
-use aws_sdk_s3::Client;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::error::StorageError;
 use crate::objects;
+use crate::store::ObjectStore;
 
-/// Load a JSON state file from S3. Returns the deserialized value and its ETag.
+/// Load a JSON state file from the object store. Returns the deserialized value and its ETag.
 pub async fn load_state<T: DeserializeOwned>(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
 ) -> Result<(T, String), StorageError> {
-    let output = objects::get_object(client, bucket, key).await?;
+    let output = objects::get_object(store, bucket, key).await?;
     let value: T = serde_json::from_slice(&output.body)?;
     let etag = output.etag.unwrap_or_default();
     Ok((value, etag))
 }
 
-/// Save a JSON state file to S3. Returns the new ETag.
+/// Save a JSON state file to the object store. Returns the new ETag.
 pub async fn save_state<T: Serialize>(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
     value: &T,
 ) -> Result<String, StorageError> {
     let body = serde_json::to_vec_pretty(value)?;
-    objects::put_object(client, bucket, key, body, Some("application/json")).await
+    objects::put_object(store, bucket, key, body, Some("application/json")).await
 }
 
-/// Save a JSON state file to S3 with ETag optimistic locking.
+/// Save a JSON state file to the object store with ETag optimistic locking.
 pub async fn save_state_if_match<T: Serialize>(
-    client: &Client,
+    store: &dyn ObjectStore,
     bucket: &str,
     key: &str,
     value: &T,
@@ -37,7 +37,7 @@ pub async fn save_state_if_match<T: Serialize>(
 ) -> Result<String, StorageError> {
     let body = serde_json::to_vec_pretty(value)?;
     objects::put_object_if_match(
-        client,
+        store,
         bucket,
         key,
         body,
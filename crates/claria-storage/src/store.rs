@@ -0,0 +1,212 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+use crate::objects::{
+    GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The condition a write must meet, checked by the store atomically with the
+/// write: S3's `If-Match` and `If-None-Match: *` headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precondition {
+    /// Write regardless of what is in the store.
+    #[default]
+    None,
+    /// The object must not exist (a new file). A delete marker as the
+    /// latest version counts as absent.
+    Absent,
+    /// The object's ETag must still be this one (an edit).
+    IfMatch(String),
+}
+
+/// A pull-based object body returned by [`ObjectStore::get_object_stream`].
+pub trait ObjectBody: Send {
+    /// The next chunk of the body, or `None` once it is exhausted.
+    fn next_chunk(&mut self) -> BoxFuture<'_, Result<Option<Vec<u8>>, StorageError>>;
+}
+
+/// A versioned object store backing the Claria data bucket.
+///
+/// Implemented for `aws_sdk_s3::Client` (see [`crate::s3`]) and for
+/// [`crate::local::LocalStore`], a directory on disk that emulates the S3
+/// semantics Claria depends on: ETags, `If-Match` and `If-None-Match`
+/// preconditions, delete markers and version listing.
+/// [`crate::encrypted::EncryptedStore`] wraps either one to encrypt bodies
+/// on the client,
+/// [`crate::retry::RetryingStore`] to retry throttled and failed-network
+/// requests, and [`crate::cache::CachedStore`] to keep revalidated copies of
+/// small objects on disk.
+///
+/// The free functions in [`crate::objects`] and [`crate::state`] take a
+/// `&dyn ObjectStore`, so an `&aws_sdk_s3::Client` can be passed directly.
+pub trait ObjectStore: Send + Sync {
+    /// Fetch the latest version of an object, or a specific version when
+    /// `version_id` is set. A delete marker as the latest version is reported
+    /// as `StorageError::NotFound`.
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>>;
+
+    /// Like [`ObjectStore::get_object`], but yields the body in chunks so
+    /// large objects never have to fit in memory.
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>>;
+
+    /// Fetch the latest version of an object unless its ETag still equals
+    /// `etag` (a conditional `If-None-Match` GET), in which case `None` is
+    /// returned without transferring the body.
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>>;
+
+    /// Read the headers of the latest version of an object without its
+    /// body. A delete marker is reported as `StorageError::NotFound`.
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>>;
+
+    /// Write a new version of an object, with the given user metadata, and
+    /// return its ETag.
+    ///
+    /// The write only succeeds if `precondition` holds; otherwise
+    /// `StorageError::PreconditionFailed` is returned, or
+    /// `StorageError::NotFound` for [`Precondition::IfMatch`] on a missing
+    /// object.
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+
+    /// Start a multipart upload and return its upload ID. The metadata is
+    /// stored with the object once the upload completes.
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+
+    /// Upload one part of a multipart upload. Parts are numbered from 1 and
+    /// every part except the last must be at least
+    /// [`MIN_PART_SIZE`](crate::objects::MIN_PART_SIZE) bytes. `is_last`
+    /// marks the final part for stores that frame part bodies; re-uploading
+    /// a part number replaces it.
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>>;
+
+    /// List the parts already uploaded for an in-progress multipart upload,
+    /// ordered by part number. Used to resume an interrupted upload.
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>>;
+
+    /// Assemble the given parts into a new object version and return its
+    /// ETag, if `precondition` holds as for [`ObjectStore::put_object`]. A
+    /// failed precondition leaves the upload in place.
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+
+    /// Abandon a multipart upload and discard its uploaded parts.
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// Delete an object. On a versioned bucket this adds a delete marker.
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// Permanently delete one version (or delete marker) of an object.
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// Permanently delete a batch of versions and delete markers in one
+    /// request. At most [`MAX_DELETE_BATCH`](crate::objects::MAX_DELETE_BATCH)
+    /// versions may be passed; unknown version IDs are ignored.
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// List the current (non-deleted) objects under a prefix.
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>>;
+
+    /// List every version and delete marker under a prefix.
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>>;
+
+    /// Generate a presigned GET URL for an object.
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+
+    /// Generate a presigned PUT URL for uploading an object.
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>>;
+}
@@ -0,0 +1,171 @@
+//! Storage usage accounting from version listings.
+//!
+//! S3 bills for every stored version, not just the current one, so usage is
+//! tallied from `ListObjectVersions` rather than `list_objects_with_metadata`.
+//! Within a client's record, current bytes are further broken down into
+//! `.text` sidecars and chat histories, the two kinds of objects Claria
+//! writes alongside the clinician's own files.
+//!
+//! Sizes are as stored, so with client-side encryption they include the
+//! envelope overhead S3 actually bills for.
+
+use std::collections::{BTreeMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::error::StorageError;
+use crate::objects::{self, ObjectVersion};
+use crate::store::ObjectStore;
+
+/// Totals for a set of objects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Current (non-deleted) objects.
+    pub objects: usize,
+    /// Bytes in the latest version of every current object.
+    pub current_bytes: i64,
+    /// Part of `current_bytes` held by `.text` sidecars.
+    pub sidecar_bytes: i64,
+    /// Part of `current_bytes` held by chat histories.
+    pub chat_history_bytes: i64,
+    /// Earlier versions, including the last version of deleted objects.
+    pub noncurrent_versions: usize,
+    pub noncurrent_bytes: i64,
+    pub delete_markers: usize,
+}
+
+impl StorageUsage {
+    /// Every stored byte, current and noncurrent.
+    pub fn total_bytes(&self) -> i64 {
+        self.current_bytes + self.noncurrent_bytes
+    }
+
+    /// Add another set of totals to this one.
+    pub fn add(&mut self, other: &StorageUsage) {
+        self.objects += other.objects;
+        self.current_bytes += other.current_bytes;
+        self.sidecar_bytes += other.sidecar_bytes;
+        self.chat_history_bytes += other.chat_history_bytes;
+        self.noncurrent_versions += other.noncurrent_versions;
+        self.noncurrent_bytes += other.noncurrent_bytes;
+        self.delete_markers += other.delete_markers;
+    }
+
+    fn count(&mut self, version: &ObjectVersion, kind: Kind) {
+        if version.is_delete_marker {
+            self.delete_markers += 1;
+        } else if version.is_latest {
+            self.objects += 1;
+            self.current_bytes += version.size;
+            match kind {
+                Kind::Sidecar => self.sidecar_bytes += version.size,
+                Kind::ChatHistory => self.chat_history_bytes += version.size,
+                Kind::File => {}
+            }
+        } else {
+            self.noncurrent_versions += 1;
+            self.noncurrent_bytes += version.size;
+        }
+    }
+}
+
+/// Usage across the whole bucket.
+#[derive(Debug, Clone, Default)]
+pub struct UsageReport {
+    /// Each client's record and client JSON.
+    pub clients: BTreeMap<Uuid, StorageUsage>,
+    /// Everything else, by top-level prefix (such as `templates/` or
+    /// `_index/`). Objects at the root are under `""`.
+    pub other: BTreeMap<String, StorageUsage>,
+    pub total: StorageUsage,
+}
+
+/// Usage of one client's record, including its client JSON.
+pub async fn client_usage(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    client_id: Uuid,
+) -> Result<StorageUsage, StorageError> {
+    let records_prefix = claria_core::s3_keys::client_records_prefix(client_id);
+    let client_key = claria_core::s3_keys::client(client_id);
+
+    let mut versions = store.list_versions(bucket, &records_prefix).await?;
+    versions.extend(objects::list_object_versions(store, bucket, &client_key).await?);
+
+    let report = tally(&versions);
+    Ok(report.clients.get(&client_id).cloned().unwrap_or_default())
+}
+
+/// Usage of the whole bucket, per client and per top-level prefix.
+pub async fn bucket_usage(
+    store: &dyn ObjectStore,
+    bucket: &str,
+) -> Result<UsageReport, StorageError> {
+    let versions = store.list_versions(bucket, "").await?;
+    Ok(tally(&versions))
+}
+
+enum Kind {
+    File,
+    Sidecar,
+    ChatHistory,
+}
+
+fn tally(versions: &[ObjectVersion]) -> UsageReport {
+    // A `.text` object is only a sidecar if the file it belongs to exists
+    // (or once did); otherwise it is a plain text file of the same name.
+    let keys: HashSet<&str> = versions.iter().map(|v| v.key.as_str()).collect();
+
+    let mut report = UsageReport::default();
+    for version in versions {
+        match client_of(&version.key) {
+            Some((client_id, rest)) => {
+                let kind = if rest.starts_with("chat-history/") {
+                    Kind::ChatHistory
+                } else if version
+                    .key
+                    .strip_suffix(".text")
+                    .is_some_and(|base| keys.contains(base))
+                {
+                    Kind::Sidecar
+                } else {
+                    Kind::File
+                };
+                report
+                    .clients
+                    .entry(client_id)
+                    .or_default()
+                    .count(version, kind);
+            }
+            None => {
+                let prefix = match version.key.split_once('/') {
+                    Some((top, _)) => format!("{top}/"),
+                    None => String::new(),
+                };
+                report
+                    .other
+                    .entry(prefix)
+                    .or_default()
+                    .count(version, Kind::File);
+            }
+        }
+    }
+
+    for usage in report.clients.values().chain(report.other.values()) {
+        report.total.add(usage);
+    }
+    report
+}
+
+/// The client a key belongs to, and the rest of the key within the
+/// client's record (empty for the client JSON).
+fn client_of(key: &str) -> Option<(Uuid, &str)> {
+    if let Some(rest) = key.strip_prefix("records/") {
+        let (id, rest) = rest.split_once('/')?;
+        return Some((id.parse().ok()?, rest));
+    }
+    let id = key
+        .strip_prefix(claria_core::s3_keys::CLIENTS_PREFIX)?
+        .strip_suffix(".json")?;
+    Some((id.parse().ok()?, ""))
+}
@@ -0,0 +1,171 @@
+use claria_storage::backup::{self, ExportOptions};
+use claria_storage::crypto::MasterKey;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+
+const BUCKET: &str = "123456789012-claria-data";
+const NEW_BUCKET: &str = "210987654321-claria-data";
+
+/// Incompressible bytes, so the archive spans several sealed segments.
+fn noise(len: usize) -> Vec<u8> {
+    let mut x = 0x9e37_79b9_7f4a_7c15u64;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+async fn seed(store: &LocalStore) {
+    objects::put_object(store, BUCKET, "clients/a.json", b"{\"v\":1}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(store, BUCKET, "clients/a.json", b"{\"v\":2}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(store, BUCKET, "records/old.txt", b"gone".to_vec(), None)
+        .await
+        .unwrap();
+    objects::delete_object(store, BUCKET, "records/old.txt")
+        .await
+        .unwrap();
+    objects::put_object(
+        store,
+        BUCKET,
+        "records/big.bin",
+        noise(3 * 1024 * 1024),
+        Some("application/octet-stream"),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn round_trips_every_version_into_an_empty_bucket() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let key = MasterKey::generate();
+    let archive = dir.path().join("practice.tar.zst");
+    seed(&store).await;
+
+    let options = ExportOptions {
+        include_versions: true,
+    };
+    let mut progress = Vec::new();
+    let manifest = backup::export(&store, BUCKET, &archive, &key, &options, &mut |p| {
+        progress.push(p.entries_done)
+    })
+    .await
+    .unwrap();
+    assert_eq!(manifest.objects(), 4);
+    assert_eq!(manifest.delete_markers(), 1);
+    assert_eq!(progress.last(), Some(&5));
+
+    // The archive is sealed, so it reveals nothing about its contents.
+    let sealed = std::fs::read(&archive).unwrap();
+    assert!(!sealed.windows(14).any(|w| w == b"clients/a.json"));
+
+    let imported = backup::import(&store, NEW_BUCKET, &archive, &key, &mut |_| {})
+        .await
+        .unwrap();
+    assert_eq!(imported.entries.len(), 5);
+
+    let a = objects::get_object(&store, NEW_BUCKET, "clients/a.json")
+        .await
+        .unwrap();
+    assert_eq!(a.body, b"{\"v\":2}");
+    let versions = objects::list_object_versions(&store, NEW_BUCKET, "clients/a.json")
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 2);
+
+    assert!(matches!(
+        objects::get_object(&store, NEW_BUCKET, "records/old.txt").await,
+        Err(StorageError::NotFound { .. })
+    ));
+    let big = objects::get_object(&store, NEW_BUCKET, "records/big.bin")
+        .await
+        .unwrap();
+    assert_eq!(big.body, noise(3 * 1024 * 1024));
+    assert_eq!(
+        big.content_type.as_deref(),
+        Some("application/octet-stream")
+    );
+}
+
+#[tokio::test]
+async fn refuses_a_non_empty_bucket() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let key = MasterKey::generate();
+    let archive = dir.path().join("practice.tar.zst");
+    seed(&store).await;
+
+    backup::export(
+        &store,
+        BUCKET,
+        &archive,
+        &key,
+        &ExportOptions::default(),
+        &mut |_| {},
+    )
+    .await
+    .unwrap();
+
+    let err = backup::import(&store, BUCKET, &archive, &key, &mut |_| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(err, StorageError::Backup(_)), "{err}");
+}
+
+#[tokio::test]
+async fn refuses_tampered_or_unsealed_archives() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let key = MasterKey::generate();
+    let archive = dir.path().join("practice.tar.zst");
+    seed(&store).await;
+
+    backup::export(
+        &store,
+        BUCKET,
+        &archive,
+        &key,
+        &ExportOptions::default(),
+        &mut |_| {},
+    )
+    .await
+    .unwrap();
+
+    assert!(backup::verify(&archive, &MasterKey::generate()).is_err());
+
+    let mut sealed = std::fs::read(&archive).unwrap();
+    let mid = sealed.len() / 2;
+    sealed[mid] ^= 0x01;
+    let tampered = dir.path().join("tampered.tar.zst");
+    std::fs::write(&tampered, &sealed).unwrap();
+    assert!(
+        backup::import(&store, NEW_BUCKET, &tampered, &key, &mut |_| {})
+            .await
+            .is_err()
+    );
+
+    let plain = dir.path().join("plain.tar.zst");
+    std::fs::write(&plain, b"not an archive").unwrap();
+    let err = backup::import(&store, NEW_BUCKET, &plain, &key, &mut |_| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(err, StorageError::Backup(_)), "{err}");
+
+    // Nothing was written by the failed imports.
+    assert!(
+        objects::list_objects(&store, NEW_BUCKET, "")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use claria_storage::cache::{CachedStore, ObjectCache};
+use claria_storage::crypto::MasterKey;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::store::ObjectStore;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+struct Fixture {
+    _dirs: (tempfile::TempDir, tempfile::TempDir),
+    inner: Arc<LocalStore>,
+    cached: CachedStore,
+}
+
+fn fixture(max_bytes: u64) -> Fixture {
+    let store_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let inner = Arc::new(LocalStore::new(store_dir.path()));
+    let cache = ObjectCache::open(cache_dir.path(), MasterKey::generate(), max_bytes).unwrap();
+    Fixture {
+        cached: CachedStore::new(inner.clone(), cache),
+        inner,
+        _dirs: (store_dir, cache_dir),
+    }
+}
+
+#[tokio::test]
+async fn revalidates_latest_against_the_store() {
+    let f = fixture(1024 * 1024);
+    let key = "records/c1/notes.txt";
+
+    objects::put_object(&*f.inner, BUCKET, key, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        objects::get_object(&f.cached, BUCKET, key)
+            .await
+            .unwrap()
+            .body,
+        b"v1"
+    );
+    assert!(f.cached.cache().get(BUCKET, key, None).is_some());
+
+    // Another device writes a new version behind the cache's back.
+    objects::put_object(&*f.inner, BUCKET, key, b"v2".to_vec(), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        objects::get_object(&f.cached, BUCKET, key)
+            .await
+            .unwrap()
+            .body,
+        b"v2"
+    );
+
+    objects::delete_object(&*f.inner, BUCKET, key)
+        .await
+        .unwrap();
+    assert!(matches!(
+        objects::get_object(&f.cached, BUCKET, key).await,
+        Err(StorageError::NotFound { .. })
+    ));
+    assert!(f.cached.cache().get(BUCKET, key, None).is_none());
+}
+
+#[tokio::test]
+async fn versions_are_served_from_cache() {
+    let f = fixture(1024 * 1024);
+    let key = "records/c1/notes.txt";
+
+    objects::put_object(&*f.inner, BUCKET, key, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    let version_id = objects::list_object_versions(&*f.inner, BUCKET, key)
+        .await
+        .unwrap()[0]
+        .version_id
+        .clone();
+    f.cached
+        .get_object(BUCKET, key, Some(&version_id))
+        .await
+        .unwrap();
+
+    // Gone from the store, still readable through the cache.
+    f.inner
+        .delete_object_version(BUCKET, key, &version_id)
+        .await
+        .unwrap();
+    let output = f
+        .cached
+        .get_object(BUCKET, key, Some(&version_id))
+        .await
+        .unwrap();
+    assert_eq!(output.body, b"v1");
+}
+
+#[tokio::test]
+async fn entries_are_encrypted_and_named_opaquely() {
+    let f = fixture(1024 * 1024);
+    let key = "records/c1/Jane Doe intake.txt";
+
+    objects::put_object(
+        &*f.inner,
+        BUCKET,
+        key,
+        b"presenting concerns".to_vec(),
+        None,
+    )
+    .await
+    .unwrap();
+    objects::get_object(&f.cached, BUCKET, key).await.unwrap();
+
+    let entries: Vec<_> = std::fs::read_dir(f.cached.cache().dir())
+        .unwrap()
+        .map(|e| e.unwrap())
+        .collect();
+    assert_eq!(entries.len(), 1);
+    let name = entries[0].file_name().into_string().unwrap();
+    assert!(!name.contains("Jane"));
+    let contents = std::fs::read(entries[0].path()).unwrap();
+    assert!(!contents.windows(10).any(|w| w == b"presenting"));
+}
+
+#[tokio::test]
+async fn writes_through_the_store_drop_the_entry() {
+    let f = fixture(1024 * 1024);
+    let key = "clients/c1.json";
+
+    objects::put_object(&f.cached, BUCKET, key, b"{}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::get_object(&f.cached, BUCKET, key).await.unwrap();
+    assert!(f.cached.cache().get(BUCKET, key, None).is_some());
+
+    objects::put_object(&f.cached, BUCKET, key, b"{\"a\":1}".to_vec(), None)
+        .await
+        .unwrap();
+    assert!(f.cached.cache().get(BUCKET, key, None).is_none());
+}
+
+#[tokio::test]
+async fn evicts_down_to_the_budget() {
+    let f = fixture(16 * 1024);
+
+    for i in 0..20 {
+        let key = format!("records/c1/{i}.txt");
+        objects::put_object(&*f.inner, BUCKET, &key, vec![b'x'; 1024], None)
+            .await
+            .unwrap();
+        objects::get_object(&f.cached, BUCKET, &key).await.unwrap();
+    }
+
+    assert!(f.cached.cache().size().unwrap() <= 16 * 1024);
+    // The most recent read survives.
+    assert!(
+        f.cached
+            .cache()
+            .get(BUCKET, "records/c1/19.txt", None)
+            .is_some()
+    );
+}
@@ -0,0 +1,258 @@
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+fn store() -> (tempfile::TempDir, LocalStore) {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let store = LocalStore::new(dir.path());
+    (dir, store)
+}
+
+#[tokio::test]
+async fn put_then_get_round_trips_body_and_etag() {
+    let (_dir, store) = store();
+
+    let etag = objects::put_object(
+        &store,
+        BUCKET,
+        "clients/a.json",
+        b"{}".to_vec(),
+        Some("application/json"),
+    )
+    .await
+    .unwrap();
+    let output = objects::get_object(&store, BUCKET, "clients/a.json")
+        .await
+        .unwrap();
+
+    assert_eq!(output.body, b"{}");
+    assert_eq!(output.etag.as_deref(), Some(etag.as_str()));
+    assert_eq!(output.content_type.as_deref(), Some("application/json"));
+    // Single-part S3 ETags are the quoted MD5 of the body.
+    assert_eq!(etag, "\"99914b932bd37a50b983c5e7c90ae93b\"");
+}
+
+#[tokio::test]
+async fn missing_key_is_not_found() {
+    let (_dir, store) = store();
+
+    let err = objects::get_object(&store, BUCKET, "clients/missing.json")
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, StorageError::NotFound { .. }));
+}
+
+#[tokio::test]
+async fn if_absent_only_creates() {
+    let (_dir, store) = store();
+    let key = "_index/clients/c1.tar.zst";
+
+    objects::put_object_if_absent(&store, BUCKET, key, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    let err = objects::put_object_if_absent(&store, BUCKET, key, b"v2".to_vec(), None)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, StorageError::PreconditionFailed { .. }));
+
+    // A delete marker does not count as existing.
+    objects::delete_object(&store, BUCKET, key).await.unwrap();
+    objects::put_object_if_absent(&store, BUCKET, key, b"v3".to_vec(), None)
+        .await
+        .unwrap();
+    let output = objects::get_object(&store, BUCKET, key).await.unwrap();
+    assert_eq!(output.body, b"v3");
+}
+
+#[tokio::test]
+async fn if_match_enforces_current_etag() {
+    let (_dir, store) = store();
+    let key = "_index/tantivy.tar.zst";
+
+    let first = objects::put_object(&store, BUCKET, key, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    let second = objects::put_object_if_match(&store, BUCKET, key, b"v2".to_vec(), None, &first)
+        .await
+        .unwrap();
+
+    // The first ETag is now stale.
+    let err = objects::put_object_if_match(&store, BUCKET, key, b"v3".to_vec(), None, &first)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, StorageError::PreconditionFailed { .. }));
+
+    let output = objects::get_object(&store, BUCKET, key).await.unwrap();
+    assert_eq!(output.body, b"v2");
+    assert_eq!(output.etag.as_deref(), Some(second.as_str()));
+}
+
+#[tokio::test]
+async fn if_match_on_missing_key_is_not_found() {
+    let (_dir, store) = store();
+
+    let err =
+        objects::put_object_if_match(&store, BUCKET, "a.json", b"x".to_vec(), None, "\"abc\"")
+            .await
+            .err()
+            .unwrap();
+    assert!(matches!(err, StorageError::NotFound { .. }));
+}
+
+#[tokio::test]
+async fn if_none_match_skips_unchanged_body() {
+    let (_dir, store) = store();
+
+    let etag = objects::put_object(&store, BUCKET, "records/a.txt", b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    let unchanged = objects::get_object_if_none_match(&store, BUCKET, "records/a.txt", &etag)
+        .await
+        .unwrap();
+    assert!(unchanged.is_none());
+
+    objects::put_object(&store, BUCKET, "records/a.txt", b"v2".to_vec(), None)
+        .await
+        .unwrap();
+    let changed = objects::get_object_if_none_match(&store, BUCKET, "records/a.txt", &etag)
+        .await
+        .unwrap()
+        .expect("body changed");
+    assert_eq!(changed.body, b"v2");
+}
+
+#[tokio::test]
+async fn delete_adds_marker_and_hides_object() {
+    let (_dir, store) = store();
+    let key = "records/c1/notes.txt";
+
+    objects::put_object(&store, BUCKET, key, b"hello".to_vec(), Some("text/plain"))
+        .await
+        .unwrap();
+    objects::delete_object(&store, BUCKET, key).await.unwrap();
+
+    assert!(matches!(
+        objects::get_object(&store, BUCKET, key).await,
+        Err(StorageError::NotFound { .. })
+    ));
+    assert!(
+        objects::list_objects(&store, BUCKET, "records/c1/")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let deleted = objects::list_deleted_objects(&store, BUCKET, "records/c1/")
+        .await
+        .unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].key, key);
+
+    let versions = objects::list_object_versions(&store, BUCKET, key)
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 2);
+    assert!(versions[0].is_latest && versions[0].is_delete_marker);
+    assert!(!versions[1].is_latest && !versions[1].is_delete_marker);
+
+    // The pre-delete version is still readable by ID.
+    let old = objects::get_object_version(&store, BUCKET, key, &versions[1].version_id)
+        .await
+        .unwrap();
+    assert_eq!(old.body, b"hello");
+}
+
+#[tokio::test]
+async fn versions_are_listed_newest_first_for_exact_key() {
+    let (_dir, store) = store();
+    let key = "records/c1/report.pdf";
+
+    for body in [b"one".to_vec(), b"two".to_vec(), b"three".to_vec()] {
+        objects::put_object(&store, BUCKET, key, body, None)
+            .await
+            .unwrap();
+    }
+    // A sidecar shares the prefix but must not appear in the exact-key listing.
+    objects::put_object(
+        &store,
+        BUCKET,
+        &format!("{key}.text"),
+        b"text".to_vec(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let versions = objects::list_object_versions(&store, BUCKET, key)
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 3);
+    assert!(versions.iter().all(|v| v.key == key));
+    assert!(versions[0].is_latest);
+    assert_eq!(versions[0].size, 5);
+    assert_eq!(versions[2].size, 3);
+}
+
+#[tokio::test]
+async fn list_objects_filters_by_prefix_in_key_order() {
+    let (_dir, store) = store();
+
+    for key in [
+        "records/c2/b.txt",
+        "records/c1/z.txt",
+        "records/c1/a.txt",
+        "clients/c1.json",
+    ] {
+        objects::put_object(&store, BUCKET, key, b"x".to_vec(), None)
+            .await
+            .unwrap();
+    }
+
+    let keys = objects::list_objects(&store, BUCKET, "records/c1/")
+        .await
+        .unwrap();
+    assert_eq!(keys, vec!["records/c1/a.txt", "records/c1/z.txt"]);
+
+    let removed = objects::delete_objects_by_prefix(&store, BUCKET, "records/")
+        .await
+        .unwrap();
+    assert_eq!(removed, 3);
+    assert_eq!(
+        objects::list_objects(&store, BUCKET, "").await.unwrap(),
+        vec!["clients/c1.json"]
+    );
+}
+
+#[tokio::test]
+async fn keys_too_long_for_a_file_name_are_stored_and_listed() {
+    let (dir, store) = store();
+    let key = format!("records/c1/{}.txt", "é".repeat(100));
+
+    objects::put_object(&store, BUCKET, &key, b"long".to_vec(), None)
+        .await
+        .unwrap();
+    let output = objects::get_object(&store, BUCKET, &key).await.unwrap();
+    assert_eq!(output.body, b"long");
+    assert_eq!(
+        objects::list_objects(&store, BUCKET, "records/")
+            .await
+            .unwrap(),
+        vec![key.clone()]
+    );
+    for entry in std::fs::read_dir(dir.path().join(BUCKET)).unwrap() {
+        assert!(entry.unwrap().file_name().len() <= 255);
+    }
+
+    objects::delete_object(&store, BUCKET, &key).await.unwrap();
+    assert!(
+        objects::list_objects(&store, BUCKET, "records/")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
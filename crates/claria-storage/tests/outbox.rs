@@ -0,0 +1,325 @@
+use claria_storage::crypto::MasterKey;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::outbox::{EntryStatus, Outbox, Precondition, Resolution};
+
+const BUCKET: &str = "123456789012-claria-data";
+
+struct Fixture {
+    _store_dir: tempfile::TempDir,
+    outbox_dir: tempfile::TempDir,
+    store: LocalStore,
+    outbox: Outbox,
+}
+
+fn fixture() -> Fixture {
+    let store_dir = tempfile::tempdir().unwrap();
+    let outbox_dir = tempfile::tempdir().unwrap();
+    Fixture {
+        store: LocalStore::new(store_dir.path()),
+        outbox: Outbox::open(outbox_dir.path(), MasterKey::generate()).unwrap(),
+        _store_dir: store_dir,
+        outbox_dir,
+    }
+}
+
+#[tokio::test]
+async fn replays_queued_writes_in_order() {
+    let f = fixture();
+
+    f.outbox
+        .enqueue(
+            BUCKET,
+            "records/c1/a.txt",
+            b"a".to_vec(),
+            Some("text/plain"),
+            Precondition::Absent,
+        )
+        .unwrap();
+    f.outbox
+        .enqueue(
+            BUCKET,
+            "records/c1/b.txt",
+            b"b".to_vec(),
+            None,
+            Precondition::None,
+        )
+        .unwrap();
+    // A second edit of a pending file replaces the queued body.
+    f.outbox
+        .enqueue(
+            BUCKET,
+            "records/c1/a.txt",
+            b"a2".to_vec(),
+            Some("text/plain"),
+            Precondition::None,
+        )
+        .unwrap();
+    assert_eq!(f.outbox.entries().unwrap().len(), 2);
+    assert_eq!(
+        f.outbox
+            .queued_body(BUCKET, "records/c1/a.txt")
+            .unwrap()
+            .unwrap(),
+        b"a2"
+    );
+
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    let synced: Vec<_> = summary.synced.iter().map(|e| e.key.as_str()).collect();
+    assert_eq!(synced, ["records/c1/a.txt", "records/c1/b.txt"]);
+    assert_eq!(summary.remaining, 0);
+    assert!(f.outbox.entries().unwrap().is_empty());
+
+    let a = objects::get_object(&f.store, BUCKET, "records/c1/a.txt")
+        .await
+        .unwrap();
+    assert_eq!(a.body, b"a2");
+    assert_eq!(a.content_type.as_deref(), Some("text/plain"));
+}
+
+#[tokio::test]
+async fn stale_etag_is_a_conflict_that_holds_later_writes() {
+    let f = fixture();
+    let key = "records/c1/notes.txt";
+
+    let base = objects::put_object(&f.store, BUCKET, key, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    // Edited offline on this machine...
+    f.outbox
+        .enqueue(
+            BUCKET,
+            key,
+            b"mine".to_vec(),
+            None,
+            Precondition::IfMatch(base),
+        )
+        .unwrap();
+    // ...and online on another.
+    objects::put_object(&f.store, BUCKET, key, b"theirs".to_vec(), None)
+        .await
+        .unwrap();
+
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert!(summary.synced.is_empty());
+    assert_eq!(summary.conflicts, 1);
+    let entries = f.outbox.entries().unwrap();
+    assert_eq!(entries[0].status, EntryStatus::Conflict);
+
+    // A later edit queues behind the conflict rather than replacing it.
+    f.outbox
+        .enqueue(
+            BUCKET,
+            key,
+            b"mine again".to_vec(),
+            None,
+            Precondition::None,
+        )
+        .unwrap();
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert!(summary.synced.is_empty());
+    assert_eq!(summary.remaining, 2);
+    assert_eq!(
+        objects::get_object(&f.store, BUCKET, key)
+            .await
+            .unwrap()
+            .body,
+        b"theirs"
+    );
+
+    f.outbox
+        .resolve(
+            &entries[0].id,
+            Resolution::SaveAs {
+                key: "records/c1/notes (conflicted copy).txt".into(),
+            },
+        )
+        .unwrap();
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(summary.synced.len(), 2);
+    assert_eq!(
+        objects::get_object(&f.store, BUCKET, "records/c1/notes (conflicted copy).txt")
+            .await
+            .unwrap()
+            .body,
+        b"mine"
+    );
+    assert_eq!(
+        objects::get_object(&f.store, BUCKET, key)
+            .await
+            .unwrap()
+            .body,
+        b"mine again"
+    );
+}
+
+#[tokio::test]
+async fn new_file_that_appeared_elsewhere_conflicts() {
+    let f = fixture();
+    let key = "records/c1/intake.txt";
+
+    f.outbox
+        .enqueue(BUCKET, key, b"mine".to_vec(), None, Precondition::Absent)
+        .unwrap();
+    objects::put_object(&f.store, BUCKET, key, b"theirs".to_vec(), None)
+        .await
+        .unwrap();
+
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(summary.conflicts, 1);
+
+    let id = f.outbox.entries().unwrap()[0].id.clone();
+    f.outbox.resolve(&id, Resolution::Overwrite).unwrap();
+    f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(
+        objects::get_object(&f.store, BUCKET, key)
+            .await
+            .unwrap()
+            .body,
+        b"mine"
+    );
+}
+
+#[tokio::test]
+async fn queued_upload_streams_from_the_source_file() {
+    let f = fixture();
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = source_dir.path().join("referral.pdf");
+    std::fs::write(&source, b"%PDF-1.7").unwrap();
+    let removed = source_dir.path().join("gone.pdf");
+    std::fs::write(&removed, b"%PDF-1.4").unwrap();
+
+    f.outbox
+        .enqueue_file(
+            BUCKET,
+            "records/c1/referral.pdf",
+            &source,
+            Some("application/pdf"),
+            Precondition::Absent,
+        )
+        .unwrap();
+    f.outbox
+        .enqueue_file(
+            BUCKET,
+            "records/c1/gone.pdf",
+            &removed,
+            None,
+            Precondition::Absent,
+        )
+        .unwrap();
+    std::fs::remove_file(&removed).unwrap();
+
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(summary.synced.len(), 1);
+    assert_eq!(summary.failed, 1);
+    assert!(matches!(
+        f.outbox.entries().unwrap()[0].status,
+        EntryStatus::Failed { .. }
+    ));
+    assert_eq!(
+        objects::get_object(&f.store, BUCKET, "records/c1/referral.pdf")
+            .await
+            .unwrap()
+            .body,
+        b"%PDF-1.7"
+    );
+}
+
+#[tokio::test]
+async fn queued_upload_of_a_file_changed_since_fails() {
+    let f = fixture();
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = source_dir.path().join("referral.pdf");
+    std::fs::write(&source, b"%PDF-1.7").unwrap();
+    let key = "records/c1/referral.pdf";
+
+    f.outbox
+        .enqueue_file(BUCKET, key, &source, None, Precondition::Absent)
+        .unwrap();
+    std::fs::write(&source, b"%PDF-1.7 edited").unwrap();
+
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(summary.failed, 1);
+    assert!(objects::get_object(&f.store, BUCKET, key).await.is_err());
+
+    // Retrying accepts the file as it is now.
+    let id = f.outbox.entries().unwrap()[0].id.clone();
+    f.outbox.resolve(&id, Resolution::Overwrite).unwrap();
+    f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(
+        objects::get_object(&f.store, BUCKET, key)
+            .await
+            .unwrap()
+            .body,
+        b"%PDF-1.7 edited"
+    );
+}
+
+#[tokio::test]
+async fn queued_upload_over_a_version_replaced_elsewhere_conflicts() {
+    let f = fixture();
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = source_dir.path().join("referral.pdf");
+    std::fs::write(&source, b"mine").unwrap();
+    let key = "records/c1/referral.pdf";
+
+    let seen = objects::put_object(&f.store, BUCKET, key, b"original".to_vec(), None)
+        .await
+        .unwrap();
+    f.outbox
+        .enqueue_file(BUCKET, key, &source, None, Precondition::IfMatch(seen))
+        .unwrap();
+    f.outbox
+        .enqueue_file(
+            BUCKET,
+            "records/c1/new.pdf",
+            &source,
+            None,
+            Precondition::Absent,
+        )
+        .unwrap();
+    objects::put_object(&f.store, BUCKET, key, b"theirs".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(
+        &f.store,
+        BUCKET,
+        "records/c1/new.pdf",
+        b"theirs".to_vec(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let summary = f.outbox.replay(&f.store).await.unwrap();
+    assert_eq!(summary.conflicts, 2);
+    for key in [key, "records/c1/new.pdf"] {
+        assert_eq!(
+            objects::get_object(&f.store, BUCKET, key)
+                .await
+                .unwrap()
+                .body,
+            b"theirs"
+        );
+    }
+}
+
+#[test]
+fn entry_files_are_sealed() {
+    let f = fixture();
+    f.outbox
+        .enqueue(
+            BUCKET,
+            "records/c1/Jane Doe.txt",
+            b"session notes".to_vec(),
+            None,
+            Precondition::None,
+        )
+        .unwrap();
+
+    for entry in std::fs::read_dir(f.outbox_dir.path()).unwrap() {
+        let contents = std::fs::read(entry.unwrap().path()).unwrap();
+        assert!(!contents.windows(4).any(|w| w == b"Jane"));
+        assert!(!contents.windows(7).any(|w| w == b"session"));
+    }
+}
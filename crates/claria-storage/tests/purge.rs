@@ -0,0 +1,98 @@
+use claria_storage::local::LocalStore;
+use claria_storage::objects::{self, VersionRef};
+use claria_storage::purge;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+#[tokio::test]
+async fn purge_removes_every_version_and_leaves_a_tombstone() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let other = uuid::Uuid::new_v4();
+    let client_key = claria_core::s3_keys::client(id);
+    let notes = claria_core::s3_keys::client_record_file(id, "Jane Doe intake.txt");
+    let other_notes = claria_core::s3_keys::client_record_file(other, "notes.txt");
+    let shard = claria_core::s3_keys::client_index(id);
+
+    objects::put_object(&store, BUCKET, &client_key, b"{\"name\":\"Jane\"}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &notes, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &notes, b"v2".to_vec(), None)
+        .await
+        .unwrap();
+    objects::delete_object(&store, BUCKET, &notes).await.unwrap();
+    objects::delete_object(&store, BUCKET, &client_key)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &other_notes, b"keep".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &shard, b"index".to_vec(), None)
+        .await
+        .unwrap();
+
+    let tombstone = purge::purge_client(&store, BUCKET, id).await.unwrap();
+    assert_eq!(tombstone.objects, 3);
+    assert_eq!(tombstone.versions, 4);
+    assert_eq!(tombstone.delete_markers, 2);
+
+    for key in [&client_key, &notes, &shard] {
+        let versions = objects::list_object_versions(&store, BUCKET, key)
+            .await
+            .unwrap();
+        assert!(versions.is_empty(), "{key} still has versions");
+    }
+    assert!(objects::get_object(&store, BUCKET, &other_notes).await.is_ok());
+
+    // The tombstone carries no names or filenames.
+    let body = objects::get_object(&store, BUCKET, &claria_core::s3_keys::purge_tombstone(id))
+        .await
+        .unwrap()
+        .body;
+    let text = String::from_utf8(body).unwrap();
+    assert!(text.contains(&id.to_string()));
+    assert!(!text.contains("Jane"));
+}
+
+#[tokio::test]
+async fn batched_delete_ignores_unknown_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+
+    objects::put_object(&store, BUCKET, "records/a", b"a".to_vec(), None)
+        .await
+        .unwrap();
+    let version_id = objects::list_object_versions(&store, BUCKET, "records/a")
+        .await
+        .unwrap()[0]
+        .version_id
+        .clone();
+
+    objects::delete_object_versions(
+        &store,
+        BUCKET,
+        &[
+            VersionRef {
+                key: "records/a".to_string(),
+                version_id,
+            },
+            VersionRef {
+                key: "records/b".to_string(),
+                version_id: "missing".to_string(),
+            },
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert!(
+        objects::list_object_versions(&store, BUCKET, "records/a")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,91 @@
+use claria_core::models::goal::Goal;
+use claria_core::models::template::Template;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::repository::Repository;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+fn goal(title: &str) -> Goal {
+    let id = uuid::Uuid::new_v4();
+    Goal {
+        id,
+        title: title.to_string(),
+        description: String::new(),
+        recommendations: Vec::new(),
+        s3_key: claria_core::s3_keys::goal(id),
+        created_at: jiff::Timestamp::UNIX_EPOCH,
+        updated_at: jiff::Timestamp::UNIX_EPOCH,
+    }
+}
+
+#[tokio::test]
+async fn create_get_update_and_delete() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let repo = Repository::<Goal>::new(&store, BUCKET);
+
+    let created = repo.create(goal("Reading fluency")).await.unwrap();
+    assert!(created.record.updated_at > jiff::Timestamp::UNIX_EPOCH);
+
+    let id = created.record.id;
+    let mut loaded = repo.get(id).await.unwrap();
+    assert_eq!(loaded.record.title, "Reading fluency");
+    assert_eq!(loaded.etag, created.etag);
+
+    loaded.record.title = "Reading comprehension".to_string();
+    let updated = repo
+        .update(loaded.record.clone(), &loaded.etag)
+        .await
+        .unwrap();
+    assert_ne!(updated.etag, loaded.etag);
+    assert!(updated.record.updated_at >= created.record.updated_at);
+
+    // A second writer still holding the old ETag is rejected.
+    let stale = repo.update(loaded.record, &created.etag).await;
+    assert!(matches!(
+        stale,
+        Err(StorageError::PreconditionFailed { .. })
+    ));
+    assert_eq!(
+        repo.get(id).await.unwrap().record.title,
+        "Reading comprehension"
+    );
+
+    repo.delete(id).await.unwrap();
+    assert!(matches!(
+        repo.get(id).await,
+        Err(StorageError::NotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn list_skips_objects_that_are_not_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let repo = Repository::<Template>::new(&store, BUCKET);
+
+    let id = uuid::Uuid::new_v4();
+    let body_key = claria_core::s3_keys::template(id);
+    repo.create(Template {
+        id,
+        name: "Psychoeducational".to_string(),
+        description: String::new(),
+        s3_key: body_key.clone(),
+        created_at: jiff::Timestamp::now(),
+        updated_at: jiff::Timestamp::now(),
+    })
+    .await
+    .unwrap();
+    objects::put_object(&store, BUCKET, &body_key, b"{{ name }}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, "templates/README.txt", b"x".to_vec(), None)
+        .await
+        .unwrap();
+
+    let listed = repo.list().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].record.id, id);
+}
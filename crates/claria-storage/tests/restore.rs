@@ -0,0 +1,369 @@
+use std::time::Duration;
+
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects::{
+    self, GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use claria_storage::restore::{self, RestoreAction};
+use claria_storage::store::{BoxFuture, ObjectStore, Precondition};
+
+const BUCKET: &str = "123456789012-claria-data";
+
+/// A timestamp strictly between the writes before and after it.
+async fn checkpoint() -> jiff::Timestamp {
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let now = jiff::Timestamp::now();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    now
+}
+
+#[tokio::test]
+async fn plan_lists_restores_deletes_and_untouched_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let client_key = claria_core::s3_keys::client(id);
+    let notes = claria_core::s3_keys::client_record_file(id, "notes.txt");
+    let referral = claria_core::s3_keys::client_record_file(id, "referral.pdf");
+    let later = claria_core::s3_keys::client_record_file(id, "later.txt");
+
+    objects::put_object(&store, BUCKET, &client_key, b"{}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &notes, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &referral, b"pdf".to_vec(), None)
+        .await
+        .unwrap();
+    let as_of = checkpoint().await;
+
+    objects::put_object(&store, BUCKET, &notes, b"v2".to_vec(), None)
+        .await
+        .unwrap();
+    objects::delete_object(&store, BUCKET, &referral)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &later, b"new".to_vec(), None)
+        .await
+        .unwrap();
+
+    let plan = restore::plan_client_restore(&store, BUCKET, id, as_of)
+        .await
+        .unwrap();
+    let action = |key: &str| {
+        plan.entries
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.action.clone())
+            .unwrap()
+    };
+
+    assert_eq!(plan.entries.len(), 4);
+    assert_eq!(action(&client_key), RestoreAction::Unchanged);
+    assert!(matches!(action(&notes), RestoreAction::Restore { .. }));
+    assert!(matches!(action(&referral), RestoreAction::Restore { .. }));
+    assert_eq!(action(&later), RestoreAction::Delete);
+
+    // The dry run wrote nothing.
+    assert_eq!(
+        objects::get_object(&store, BUCKET, &notes)
+            .await
+            .unwrap()
+            .body,
+        b"v2"
+    );
+}
+
+#[tokio::test]
+async fn restore_rebuilds_the_record_and_keeps_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let client_key = claria_core::s3_keys::client(id);
+    let notes = claria_core::s3_keys::client_record_file(id, "notes.txt");
+    let chat = claria_core::s3_keys::chat_history(id, uuid::Uuid::new_v4());
+
+    objects::put_object(&store, BUCKET, &client_key, b"{}".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &notes, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    let as_of = checkpoint().await;
+
+    objects::put_object(&store, BUCKET, &notes, b"v2".to_vec(), None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &chat, b"[]".to_vec(), None)
+        .await
+        .unwrap();
+    objects::delete_object(&store, BUCKET, &client_key)
+        .await
+        .unwrap();
+
+    let summary = restore::restore_client_as_of(&store, BUCKET, id, as_of)
+        .await
+        .unwrap();
+    assert_eq!((summary.restored, summary.deleted), (2, 1));
+
+    assert_eq!(
+        objects::get_object(&store, BUCKET, &notes)
+            .await
+            .unwrap()
+            .body,
+        b"v1"
+    );
+    assert!(
+        objects::get_object(&store, BUCKET, &client_key)
+            .await
+            .is_ok()
+    );
+    assert!(objects::get_object(&store, BUCKET, &chat).await.is_err());
+
+    // v1, v2 and the restored copy of v1.
+    let versions = objects::list_object_versions(&store, BUCKET, &notes)
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 3);
+
+    // A second pass finds nothing left to do.
+    let plan = restore::plan_client_restore(&store, BUCKET, id, jiff::Timestamp::now())
+        .await
+        .unwrap();
+    assert!(plan.is_noop());
+}
+
+/// A store that lists versions the way one S3 page does: every object
+/// version first, then every delete marker.
+struct S3OrderStore(LocalStore);
+
+impl ObjectStore for S3OrderStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        self.0.get_object(bucket, key, version_id)
+    }
+
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        self.0.get_object_stream(bucket, key, version_id)
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        self.0.get_object_if_none_match(bucket, key, etag)
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        self.0.head_object(bucket, key)
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0
+            .put_object(bucket, key, body, content_type, metadata, precondition)
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0
+            .create_multipart_upload(bucket, key, content_type, metadata)
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        self.0
+            .upload_part(bucket, key, upload_id, part_number, body, is_last)
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        self.0.list_parts(bucket, key, upload_id)
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0
+            .complete_multipart_upload(bucket, key, upload_id, parts, precondition)
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.abort_multipart_upload(bucket, key, upload_id)
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.delete_object(bucket, key)
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.delete_object_version(bucket, key, version_id)
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.delete_object_versions(bucket, versions)
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        self.0.list_objects(bucket, prefix)
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        Box::pin(async move {
+            let (mut versions, markers): (Vec<_>, Vec<_>) = self
+                .0
+                .list_versions(bucket, prefix)
+                .await?
+                .into_iter()
+                .partition(|v| !v.is_delete_marker);
+            versions.extend(markers);
+            Ok(versions)
+        })
+    }
+
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0.presign_get(bucket, key, expires_in)
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0.presign_put(bucket, key, content_type, expires_in)
+    }
+}
+
+#[tokio::test]
+async fn delete_markers_listed_after_versions_still_count_as_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = S3OrderStore(LocalStore::new(dir.path()));
+    let id = uuid::Uuid::new_v4();
+    let notes = claria_core::s3_keys::client_record_file(id, "notes.txt");
+
+    objects::put_object(&store, BUCKET, &notes, b"v1".to_vec(), None)
+        .await
+        .unwrap();
+    checkpoint().await;
+    objects::delete_object(&store, BUCKET, &notes)
+        .await
+        .unwrap();
+    let as_of = checkpoint().await;
+    objects::put_object(&store, BUCKET, &notes, b"v2".to_vec(), None)
+        .await
+        .unwrap();
+
+    let versions = store.list_versions(BUCKET, &notes).await.unwrap();
+    assert!(versions.last().unwrap().is_delete_marker);
+
+    let plan = restore::plan_client_restore(&store, BUCKET, id, as_of)
+        .await
+        .unwrap();
+    let entry = plan.entries.iter().find(|e| e.key == notes).unwrap();
+    assert_eq!(entry.action, RestoreAction::Delete);
+}
+
+#[tokio::test]
+async fn restore_keeps_file_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let notes = claria_core::s3_keys::client_record_file(id, "notes.txt");
+    let metadata = Metadata::from([(
+        objects::SHA256_METADATA_KEY.to_string(),
+        "abc123".to_string(),
+    )]);
+
+    objects::put_object_with_metadata(&store, BUCKET, &notes, b"v1".to_vec(), None, &metadata)
+        .await
+        .unwrap();
+    let as_of = checkpoint().await;
+    objects::put_object(&store, BUCKET, &notes, b"v2".to_vec(), None)
+        .await
+        .unwrap();
+
+    restore::restore_client_as_of(&store, BUCKET, id, as_of)
+        .await
+        .unwrap();
+    let head = objects::head_object(&store, BUCKET, &notes).await.unwrap();
+    assert_eq!(head.metadata, metadata);
+}
@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use claria_storage::error::StorageError;
+use claria_storage::retry::{self, RetryPolicy};
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(5),
+    }
+}
+
+#[tokio::test]
+async fn retries_transient_failures_until_success() {
+    let calls = AtomicU32::new(0);
+    let result = retry::retry(&fast_policy(5), "PutObject", "k", || async {
+        match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(StorageError::Network("connection reset".into())),
+            1 => Err(StorageError::Throttled("SlowDown".into())),
+            _ => Ok("etag"),
+        }
+    })
+    .await;
+
+    assert_eq!(result.unwrap(), "etag");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let calls = AtomicU32::new(0);
+    let result: Result<(), _> = retry::retry(&fast_policy(3), "GetObject", "k", || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err(StorageError::Network("timed out".into()))
+    })
+    .await;
+
+    assert!(matches!(result, Err(StorageError::Network(_))));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_permanent_failures() {
+    for err in [
+        StorageError::PreconditionFailed { key: "k".into() },
+        StorageError::NotFound { key: "k".into() },
+        StorageError::AccessDenied("expired token".into()),
+    ] {
+        let calls = AtomicU32::new(0);
+        let mut err = Some(err);
+        let result: Result<(), _> = retry::retry(&fast_policy(5), "PutObject", "k", || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let err = err.take().unwrap();
+            async move { Err(err) }
+        })
+        .await;
+
+        assert!(!result.unwrap_err().is_retryable());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[test]
+fn delay_is_jittered_and_capped() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(400),
+    };
+    for _ in 0..100 {
+        assert!(policy.delay(0) <= Duration::from_millis(100));
+        assert!(policy.delay(1) <= Duration::from_millis(200));
+        assert!(policy.delay(8) <= Duration::from_millis(400));
+    }
+}
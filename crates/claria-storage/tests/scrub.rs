@@ -0,0 +1,125 @@
+use claria_core::s3_keys;
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::scrub::{self, IssueKind, Repair};
+
+const BUCKET: &str = "123456789012-claria-data";
+
+fn needs_sidecar(filename: &str) -> bool {
+    filename.ends_with(".pdf")
+}
+
+async fn put(store: &LocalStore, key: &str, body: &[u8]) {
+    objects::put_object(store, BUCKET, key, body.to_vec(), None)
+        .await
+        .unwrap();
+}
+
+fn client_json(id: uuid::Uuid) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "id": id,
+        "name": "Jane",
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z",
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn scan_classifies_issues_without_changing_anything() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let gone = uuid::Uuid::new_v4();
+
+    put(&store, &s3_keys::client(id), &client_json(id)).await;
+    // Healthy: a PDF with its sidecar, and a text file named `.text`.
+    let intake = s3_keys::client_record_file(id, "intake.pdf");
+    put(&store, &intake, b"%PDF").await;
+    put(&store, &format!("{intake}.text"), b"intake").await;
+    put(&store, &s3_keys::client_record_file(id, "notes.text"), b"n").await;
+    // A PDF that was never extracted.
+    let letter = s3_keys::client_record_file(id, "letter.pdf");
+    put(&store, &letter, b"%PDF").await;
+    // A sidecar whose PDF was deleted.
+    let old = s3_keys::client_record_file(id, "old.pdf");
+    put(&store, &old, b"%PDF").await;
+    put(&store, &format!("{old}.text"), b"old").await;
+    objects::delete_object(&store, BUCKET, &old).await.unwrap();
+    // A chat history that no longer parses.
+    let chat = s3_keys::chat_history(id, uuid::Uuid::new_v4());
+    put(&store, &chat, b"{\"messages\":").await;
+    // Records left behind by a client that no longer exists.
+    let stray = s3_keys::client_record_file(gone, "stray.txt");
+    put(&store, &stray, b"s").await;
+
+    let report = scrub::scan(&store, BUCKET, &needs_sidecar).await.unwrap();
+    let mut found: Vec<_> = report
+        .issues
+        .iter()
+        .map(|i| (i.key.clone(), i.kind, i.repair))
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut expected = vec![
+        (
+            letter.clone(),
+            IssueKind::MissingSidecar,
+            Some(Repair::Reextract),
+        ),
+        (
+            format!("{old}.text"),
+            IssueKind::OrphanedSidecar,
+            Some(Repair::Delete),
+        ),
+        (
+            chat.clone(),
+            IssueKind::UnparseableJson,
+            Some(Repair::Quarantine),
+        ),
+        (
+            stray.clone(),
+            IssueKind::OrphanedRecord,
+            Some(Repair::Delete),
+        ),
+    ];
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(found, expected);
+
+    // Scanning is a dry run.
+    assert!(objects::get_object(&store, BUCKET, &chat).await.is_ok());
+    assert!(objects::get_object(&store, BUCKET, &stray).await.is_ok());
+}
+
+#[tokio::test]
+async fn repair_deletes_orphans_and_quarantines_bad_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+
+    put(&store, &s3_keys::client(id), &client_json(id)).await;
+    let chat = s3_keys::chat_history(id, uuid::Uuid::new_v4());
+    put(&store, &chat, b"not json").await;
+    let letter = s3_keys::client_record_file(id, "letter.pdf");
+    put(&store, &letter, b"%PDF").await;
+    let stray = s3_keys::client_record_file(uuid::Uuid::new_v4(), "stray.txt");
+    put(&store, &stray, b"s").await;
+
+    let report = scrub::scan(&store, BUCKET, &needs_sidecar).await.unwrap();
+    assert_eq!(report.issues.len(), 3);
+    for issue in &report.issues {
+        let repaired = scrub::repair(&store, BUCKET, issue).await.unwrap();
+        assert_eq!(repaired, issue.repair != Some(Repair::Reextract));
+    }
+
+    let quarantined = objects::get_object(&store, BUCKET, &s3_keys::quarantine(&chat))
+        .await
+        .unwrap();
+    assert_eq!(quarantined.body, b"not json");
+    assert!(objects::get_object(&store, BUCKET, &chat).await.is_err());
+    assert!(objects::get_object(&store, BUCKET, &stray).await.is_err());
+
+    // Only the missing sidecar, which needs extraction, is left.
+    let report = scrub::scan(&store, BUCKET, &needs_sidecar).await.unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].kind, IssueKind::MissingSidecar);
+}
@@ -0,0 +1,398 @@
+use std::time::Duration;
+
+use claria_core::s3_keys;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects::{
+    self, GetObjectOutput, GetObjectStream, Metadata, ObjectHead, ObjectMeta, ObjectVersion,
+    UploadedPart, VersionRef,
+};
+use claria_storage::share::{self, ShareRequest};
+use claria_storage::state;
+use claria_storage::store::{BoxFuture, ObjectStore, Precondition};
+
+const BUCKET: &str = "123456789012-claria-data";
+
+fn request(source_key: &str, expires_in: Duration) -> ShareRequest<'_> {
+    ShareRequest {
+        source_key,
+        filename: "report.pdf",
+        client_id: None,
+        recipient: "dr.smith@example.com",
+        created_by: "arn:aws:iam::123456789012:user/clinician",
+        expires_in,
+        max_downloads: None,
+    }
+}
+
+#[tokio::test]
+async fn share_copies_file_and_revoke_removes_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let source = s3_keys::client_record_file(id, "report.pdf");
+    objects::put_object(&store, BUCKET, &source, b"%PDF".to_vec(), None)
+        .await
+        .unwrap();
+
+    let mut req = request(&source, Duration::from_secs(3600));
+    req.client_id = Some(id);
+    let created = share::create_share(&store, &store, BUCKET, &req)
+        .await
+        .unwrap();
+    let copy = objects::get_object(&store, BUCKET, &created.shared_key)
+        .await
+        .unwrap();
+    assert_eq!(copy.body, b"%PDF");
+
+    let shares = share::list_shares(&store, BUCKET).await.unwrap();
+    assert_eq!(shares.len(), 1);
+    assert_eq!(shares[0].id, created.id);
+    assert_eq!(shares[0].client_id, Some(id));
+    assert!(shares[0].is_outstanding(jiff::Timestamp::now()));
+
+    let revoked = share::revoke_share(&store, &store, BUCKET, created.id, "someone")
+        .await
+        .unwrap();
+    assert!(!revoked.is_outstanding(jiff::Timestamp::now()));
+    // The copy is gone for good, not just hidden behind a delete marker.
+    assert!(
+        objects::list_object_versions(&store, BUCKET, &created.shared_key)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    // The record stays as the audit trail.
+    let shares = share::list_shares(&store, BUCKET).await.unwrap();
+    assert_eq!(shares[0].revoked_by.as_deref(), Some("someone"));
+    assert!(matches!(
+        share::issue_link(&store, &store, BUCKET, created.id, "someone").await,
+        Err(StorageError::Presign(_))
+    ));
+}
+
+#[tokio::test]
+async fn expiry_is_bounded_and_expired_copies_are_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let source = s3_keys::client_record_file(uuid::Uuid::new_v4(), "report.pdf");
+    objects::put_object(&store, BUCKET, &source, b"%PDF".to_vec(), None)
+        .await
+        .unwrap();
+
+    for expires_in in [Duration::ZERO, share::MAX_EXPIRY + Duration::from_secs(1)] {
+        let result =
+            share::create_share(&store, &store, BUCKET, &request(&source, expires_in)).await;
+        assert!(matches!(result, Err(StorageError::Presign(_))));
+    }
+    assert!(share::list_shares(&store, BUCKET).await.unwrap().is_empty());
+
+    let mut created = share::create_share(
+        &store,
+        &store,
+        BUCKET,
+        &request(&source, Duration::from_secs(60)),
+    )
+    .await
+    .unwrap();
+    // Backdate the record so it has expired.
+    created.expires_at = jiff::Timestamp::now() - jiff::SignedDuration::from_secs(1);
+    state::save_state(&store, BUCKET, &s3_keys::share_record(created.id), &created)
+        .await
+        .unwrap();
+
+    let removed = share::remove_expired(&store, &store, BUCKET).await.unwrap();
+    assert_eq!(removed, 1);
+    assert!(
+        objects::get_object(&store, BUCKET, &created.shared_key)
+            .await
+            .is_err()
+    );
+    let shares = share::list_shares(&store, BUCKET).await.unwrap();
+    assert!(!shares[0].is_outstanding(jiff::Timestamp::now()));
+    assert_eq!(
+        share::remove_expired(&store, &store, BUCKET).await.unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn links_are_recorded_and_counted_against_the_download_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let public = PresigningStore(LocalStore::new(dir.path()));
+    let source = s3_keys::client_record_file(uuid::Uuid::new_v4(), "report.pdf");
+    objects::put_object(&store, BUCKET, &source, b"%PDF".to_vec(), None)
+        .await
+        .unwrap();
+
+    let mut req = request(&source, Duration::from_secs(24 * 3600));
+    req.max_downloads = Some(0);
+    assert!(matches!(
+        share::create_share(&store, &public, BUCKET, &req).await,
+        Err(StorageError::Presign(_))
+    ));
+
+    req.max_downloads = Some(2);
+    let created = share::create_share(&store, &public, BUCKET, &req)
+        .await
+        .unwrap();
+    assert_eq!(created.downloads_left(), Some(2));
+
+    let (first, url) = share::issue_link(&store, &public, BUCKET, created.id, "alice")
+        .await
+        .unwrap();
+    // Limited shares hand out short links, whatever the share's expiry.
+    assert!(url.ends_with(&format!(
+        "X-Amz-Expires={}",
+        share::LIMITED_LINK_EXPIRY.as_secs()
+    )));
+    assert_eq!(first.downloads_left(), Some(1));
+    assert_eq!(first.links[0].issued_by, "alice");
+
+    let (second, _) = share::issue_link(&store, &public, BUCKET, created.id, "bob")
+        .await
+        .unwrap();
+    assert_eq!(second.downloads_left(), Some(0));
+    // Still outstanding while the last link works.
+    assert!(second.is_outstanding(jiff::Timestamp::now()));
+    assert!(matches!(
+        share::issue_link(&store, &public, BUCKET, created.id, "alice").await,
+        Err(StorageError::Presign(_))
+    ));
+
+    // Every issued link is in the audit record.
+    let shares = share::list_shares(&store, BUCKET).await.unwrap();
+    let issued_by: Vec<_> = shares[0]
+        .links
+        .iter()
+        .map(|l| l.issued_by.as_str())
+        .collect();
+    assert_eq!(issued_by, ["alice", "bob"]);
+
+    // Once the last link has expired the share is done and its copy goes,
+    // even though the share itself has a day left.
+    let mut used_up = second;
+    for link in &mut used_up.links {
+        link.expires_at = jiff::Timestamp::now() - jiff::SignedDuration::from_secs(1);
+    }
+    state::save_state(&store, BUCKET, &s3_keys::share_record(used_up.id), &used_up)
+        .await
+        .unwrap();
+    assert!(!used_up.is_outstanding(jiff::Timestamp::now()));
+    assert_eq!(
+        share::remove_expired(&store, &public, BUCKET)
+            .await
+            .unwrap(),
+        1
+    );
+    assert!(
+        objects::get_object(&store, BUCKET, &used_up.shared_key)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn unlimited_links_last_until_the_share_expires() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let public = PresigningStore(LocalStore::new(dir.path()));
+    let source = s3_keys::client_record_file(uuid::Uuid::new_v4(), "report.pdf");
+    objects::put_object(&store, BUCKET, &source, b"%PDF".to_vec(), None)
+        .await
+        .unwrap();
+
+    let created = share::create_share(
+        &store,
+        &public,
+        BUCKET,
+        &request(&source, Duration::from_secs(3 * 3600)),
+    )
+    .await
+    .unwrap();
+    for _ in 0..3 {
+        share::issue_link(&store, &public, BUCKET, created.id, "alice")
+            .await
+            .unwrap();
+    }
+    let shares = share::list_shares(&store, BUCKET).await.unwrap();
+    assert_eq!(shares[0].links.len(), 3);
+    assert_eq!(shares[0].downloads_left(), None);
+    assert!(
+        shares[0].links[0].expires_at
+            > jiff::Timestamp::now() + jiff::SignedDuration::from_hours(2)
+    );
+}
+
+/// A store that presigns URLs, which [`LocalStore`] cannot.
+struct PresigningStore(LocalStore);
+
+impl ObjectStore for PresigningStore {
+    fn get_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectOutput, StorageError>> {
+        self.0.get_object(bucket, key, version_id)
+    }
+
+    fn get_object_stream<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<GetObjectStream, StorageError>> {
+        self.0.get_object_stream(bucket, key, version_id)
+    }
+
+    fn get_object_if_none_match<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        etag: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GetObjectOutput>, StorageError>> {
+        self.0.get_object_if_none_match(bucket, key, etag)
+    }
+
+    fn head_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<ObjectHead, StorageError>> {
+        self.0.head_object(bucket, key)
+    }
+
+    fn put_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        body: Vec<u8>,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0
+            .put_object(bucket, key, body, content_type, metadata, precondition)
+    }
+
+    fn create_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        metadata: &'a Metadata,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0
+            .create_multipart_upload(bucket, key, content_type, metadata)
+    }
+
+    fn upload_part<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        part_number: i32,
+        body: Vec<u8>,
+        is_last: bool,
+    ) -> BoxFuture<'a, Result<UploadedPart, StorageError>> {
+        self.0
+            .upload_part(bucket, key, upload_id, part_number, body, is_last)
+    }
+
+    fn list_parts<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<UploadedPart>, StorageError>> {
+        self.0.list_parts(bucket, key, upload_id)
+    }
+
+    fn complete_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+        parts: &'a [UploadedPart],
+        precondition: &'a Precondition,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0
+            .complete_multipart_upload(bucket, key, upload_id, parts, precondition)
+    }
+
+    fn abort_multipart_upload<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        upload_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.abort_multipart_upload(bucket, key, upload_id)
+    }
+
+    fn delete_object<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.delete_object(bucket, key)
+    }
+
+    fn delete_object_version<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        version_id: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.delete_object_version(bucket, key, version_id)
+    }
+
+    fn delete_object_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        versions: &'a [VersionRef],
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        self.0.delete_object_versions(bucket, versions)
+    }
+
+    fn list_objects<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectMeta>, StorageError>> {
+        self.0.list_objects(bucket, prefix)
+    }
+
+    fn list_versions<'a>(
+        &'a self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ObjectVersion>, StorageError>> {
+        self.0.list_versions(bucket, prefix)
+    }
+
+    fn presign_get<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        Box::pin(async move {
+            Ok(format!(
+                "https://{bucket}.s3.amazonaws.com/{key}?X-Amz-Expires={}",
+                expires_in.as_secs()
+            ))
+        })
+    }
+
+    fn presign_put<'a>(
+        &'a self,
+        bucket: &'a str,
+        key: &'a str,
+        content_type: Option<&'a str>,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<String, StorageError>> {
+        self.0.presign_put(bucket, key, content_type, expires_in)
+    }
+}
@@ -0,0 +1,388 @@
+use std::sync::Arc;
+
+use sha2::Digest;
+
+use claria_storage::crypto::{self, MasterKey};
+use claria_storage::encrypted::EncryptedStore;
+use claria_storage::error::StorageError;
+use claria_storage::local::LocalStore;
+use claria_storage::objects::{
+    self, MIN_PART_SIZE, Metadata, ResumeUpload, UploadOptions, UploadProgress,
+};
+use claria_storage::store::{ObjectStore, Precondition};
+
+const BUCKET: &str = "123456789012-claria-data";
+const KEY: &str = "records/c1/session.wav";
+
+/// Write a file of `len` bytes with a position-dependent pattern, so a
+/// misordered part would be caught.
+fn recording(dir: &std::path::Path, len: usize) -> (std::path::PathBuf, Vec<u8>) {
+    let body: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+    let path = dir.join("session.wav");
+    std::fs::write(&path, &body).unwrap();
+    (path, body)
+}
+
+fn options() -> UploadOptions<'static> {
+    UploadOptions {
+        content_type: Some("audio/wav"),
+        metadata: Metadata::new(),
+        part_size: MIN_PART_SIZE,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn small_files_use_a_single_put() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let (path, body) = recording(dir.path(), 1024);
+
+    let mut progress = Vec::new();
+    let etag = objects::upload_file(&store, BUCKET, KEY, &path, &options(), &mut |p| {
+        progress.push(p.clone())
+    })
+    .await
+    .unwrap();
+
+    assert!(!etag.contains('-'));
+    assert_eq!(progress.len(), 1);
+    assert!(progress[0].upload_id.is_none());
+    assert_eq!(
+        objects::get_object(&store, BUCKET, KEY).await.unwrap().body,
+        body
+    );
+}
+
+#[tokio::test]
+async fn multipart_upload_and_streamed_download_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let len = 2 * MIN_PART_SIZE + 12_345;
+    let (path, body) = recording(dir.path(), len);
+
+    let mut progress: Vec<UploadProgress> = Vec::new();
+    let etag = objects::upload_file(&store, BUCKET, KEY, &path, &options(), &mut |p| {
+        progress.push(p.clone())
+    })
+    .await
+    .unwrap();
+
+    // S3-style multipart ETag: digest of part digests plus the part count.
+    assert!(etag.ends_with("-3\""));
+    // One report once the upload ID is known, then one per part.
+    assert_eq!(progress.len(), 4);
+    assert!(progress.iter().all(|p| p.upload_id.is_some()));
+    assert!(
+        progress
+            .windows(2)
+            .all(|w| w[0].bytes_uploaded < w[1].bytes_uploaded)
+    );
+    assert_eq!(progress.last().unwrap().bytes_uploaded, len as u64);
+
+    let out = dir.path().join("download.wav");
+    let mut downloaded = 0;
+    let written = objects::download_to_file(&store, BUCKET, KEY, None, &out, &mut |p| {
+        downloaded = p.bytes_downloaded
+    })
+    .await
+    .unwrap();
+    assert_eq!(written, len as u64);
+    assert_eq!(downloaded, len as u64);
+    assert_eq!(std::fs::read(&out).unwrap(), body);
+}
+
+#[tokio::test]
+async fn uploads_carry_sha256_metadata_for_duplicate_lookup() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let (path, body) = recording(dir.path(), MIN_PART_SIZE + 1);
+
+    let sha256 = objects::sha256_file(&path).await.unwrap();
+    assert_eq!(sha256, format!("{:x}", sha2::Sha256::digest(&body)));
+
+    let options = UploadOptions {
+        metadata: Metadata::from([(objects::SHA256_METADATA_KEY.to_string(), sha256.clone())]),
+        ..options()
+    };
+    // Once as a multipart upload, once as a single PUT, plus an unrelated file.
+    objects::upload_file(&store, BUCKET, KEY, &path, &options, &mut |_| {})
+        .await
+        .unwrap();
+    let single = UploadOptions {
+        part_size: 2 * MIN_PART_SIZE,
+        ..options
+    };
+    objects::upload_file(
+        &store,
+        BUCKET,
+        "records/c1/copy.wav",
+        &path,
+        &single,
+        &mut |_| {},
+    )
+    .await
+    .unwrap();
+    objects::put_object(&store, BUCKET, "records/c1/notes.txt", b"hi".to_vec(), None)
+        .await
+        .unwrap();
+
+    let head = objects::head_object(&store, BUCKET, KEY).await.unwrap();
+    assert_eq!(
+        head.metadata.get(objects::SHA256_METADATA_KEY),
+        Some(&sha256)
+    );
+    assert_eq!(head.content_type.as_deref(), Some("audio/wav"));
+
+    let found = objects::find_by_sha256(&store, BUCKET, "records/c1/", &sha256)
+        .await
+        .unwrap();
+    assert_eq!(found, ["records/c1/copy.wav", KEY]);
+    assert!(
+        objects::find_by_sha256(&store, BUCKET, "records/c2/", &sha256)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn interrupted_upload_resumes_from_missing_parts() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let len = 2 * MIN_PART_SIZE + 1;
+    let (path, body) = recording(dir.path(), len);
+
+    // Simulate an upload that died after its first part.
+    let upload_id = store
+        .create_multipart_upload(BUCKET, KEY, Some("audio/wav"), &Metadata::new())
+        .await
+        .unwrap();
+    store
+        .upload_part(
+            BUCKET,
+            KEY,
+            &upload_id,
+            1,
+            body[..MIN_PART_SIZE].to_vec(),
+            false,
+        )
+        .await
+        .unwrap();
+
+    let mut progress: Vec<UploadProgress> = Vec::new();
+    let sha256 = objects::sha256_file(&path).await.unwrap();
+    let resume = UploadOptions {
+        resume: Some(ResumeUpload {
+            upload_id: &upload_id,
+            sha256: &sha256,
+        }),
+        ..options()
+    };
+    objects::upload_file(&store, BUCKET, KEY, &path, &resume, &mut |p| {
+        progress.push(p.clone())
+    })
+    .await
+    .unwrap();
+
+    // The first report already counts the part uploaded before the interruption.
+    assert_eq!(progress[0].upload_id.as_deref(), Some(upload_id.as_str()));
+    assert_eq!(progress[0].bytes_uploaded, MIN_PART_SIZE as u64);
+    assert_eq!(progress.len(), 3);
+    assert_eq!(
+        objects::get_object(&store, BUCKET, KEY).await.unwrap().body,
+        body
+    );
+}
+
+#[tokio::test]
+async fn resume_starts_over_when_the_file_or_part_layout_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("store"));
+    let len = 2 * MIN_PART_SIZE + 1;
+    let (path, body) = recording(dir.path(), len);
+    let sha256 = objects::sha256_file(&path).await.unwrap();
+
+    // One upload of an older version of the file, one whose first part was
+    // cut with a larger part size.
+    let stale = [
+        ("0".repeat(64), MIN_PART_SIZE),
+        (sha256.clone(), MIN_PART_SIZE + 1),
+    ];
+    for (started_with, part_len) in stale {
+        let upload_id = store
+            .create_multipart_upload(BUCKET, KEY, Some("audio/wav"), &Metadata::new())
+            .await
+            .unwrap();
+        store
+            .upload_part(BUCKET, KEY, &upload_id, 1, body[..part_len].to_vec(), false)
+            .await
+            .unwrap();
+
+        let mut progress: Vec<UploadProgress> = Vec::new();
+        let resume = UploadOptions {
+            resume: Some(ResumeUpload {
+                upload_id: &upload_id,
+                sha256: &started_with,
+            }),
+            ..options()
+        };
+        objects::upload_file(&store, BUCKET, KEY, &path, &resume, &mut |p| {
+            progress.push(p.clone())
+        })
+        .await
+        .unwrap();
+
+        assert_ne!(progress[0].upload_id.as_deref(), Some(upload_id.as_str()));
+        assert_eq!(progress[0].bytes_uploaded, 0);
+        assert!(store.list_parts(BUCKET, KEY, &upload_id).await.is_err());
+        assert_eq!(
+            objects::get_object(&store, BUCKET, KEY).await.unwrap().body,
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn encrypted_uploads_resume_from_missing_parts() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = EncryptedStore::new(
+        Arc::new(LocalStore::new(dir.path().join("store"))),
+        MasterKey::generate(),
+    );
+    let len = 2 * MIN_PART_SIZE + 1;
+    let (path, body) = recording(dir.path(), len);
+    let sha256 = objects::sha256_file(&path).await.unwrap();
+
+    let upload_id = store
+        .create_multipart_upload(BUCKET, KEY, Some("audio/wav"), &Metadata::new())
+        .await
+        .unwrap();
+    store
+        .upload_part(
+            BUCKET,
+            KEY,
+            &upload_id,
+            1,
+            body[..MIN_PART_SIZE].to_vec(),
+            false,
+        )
+        .await
+        .unwrap();
+
+    let mut progress: Vec<UploadProgress> = Vec::new();
+    let resume = UploadOptions {
+        resume: Some(ResumeUpload {
+            upload_id: &upload_id,
+            sha256: &sha256,
+        }),
+        ..options()
+    };
+    objects::upload_file(&store, BUCKET, KEY, &path, &resume, &mut |p| {
+        progress.push(p.clone())
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(progress[0].upload_id.as_deref(), Some(upload_id.as_str()));
+    assert_eq!(progress[0].bytes_uploaded, MIN_PART_SIZE as u64);
+    assert_eq!(
+        objects::get_object(&store, BUCKET, KEY).await.unwrap().body,
+        body
+    );
+}
+
+#[tokio::test]
+async fn encrypted_multipart_objects_are_segmented_and_authenticated() {
+    let dir = tempfile::tempdir().unwrap();
+    let raw = Arc::new(LocalStore::new(dir.path().join("store")));
+    let store = EncryptedStore::new(raw.clone(), MasterKey::generate());
+    let len = 2 * MIN_PART_SIZE + 99;
+    let (path, body) = recording(dir.path(), len);
+
+    objects::upload_file(&store, BUCKET, KEY, &path, &options(), &mut |_| {})
+        .await
+        .unwrap();
+
+    let at_rest = objects::get_object(&*raw, BUCKET, KEY).await.unwrap();
+    assert!(crypto::is_encrypted(&at_rest.body));
+
+    // Whole-object reads and streamed reads both decrypt.
+    assert_eq!(
+        objects::get_object(&store, BUCKET, KEY).await.unwrap().body,
+        body
+    );
+    let out = dir.path().join("download.wav");
+    objects::download_to_file(&store, BUCKET, KEY, None, &out, &mut |_| {})
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&out).unwrap(), body);
+
+    // Dropping the final part must not yield a silently shorter recording.
+    let upload_id = store
+        .create_multipart_upload(BUCKET, "records/c1/cut.wav", None, &Metadata::new())
+        .await
+        .unwrap();
+    let mut parts = Vec::new();
+    for (i, chunk) in body.chunks(MIN_PART_SIZE).enumerate() {
+        let is_last = (i + 1) * MIN_PART_SIZE >= len;
+        parts.push(
+            store
+                .upload_part(
+                    BUCKET,
+                    "records/c1/cut.wav",
+                    &upload_id,
+                    i as i32 + 1,
+                    chunk.to_vec(),
+                    is_last,
+                )
+                .await
+                .unwrap(),
+        );
+    }
+    store
+        .complete_multipart_upload(
+            BUCKET,
+            "records/c1/cut.wav",
+            &upload_id,
+            &parts[..2],
+            &Precondition::None,
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        objects::get_object(&store, BUCKET, "records/c1/cut.wav").await,
+        Err(StorageError::Decryption(_))
+    ));
+}
+
+#[tokio::test]
+async fn parts_below_the_minimum_size_are_rejected_on_complete() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+
+    let upload_id = store
+        .create_multipart_upload(BUCKET, KEY, None, &Metadata::new())
+        .await
+        .unwrap();
+    let mut parts = Vec::new();
+    for n in 1..=2 {
+        parts.push(
+            store
+                .upload_part(BUCKET, KEY, &upload_id, n, b"tiny".to_vec(), n == 2)
+                .await
+                .unwrap(),
+        );
+    }
+
+    assert!(matches!(
+        store
+            .complete_multipart_upload(BUCKET, KEY, &upload_id, &parts, &Precondition::None)
+            .await,
+        Err(StorageError::MultipartUpload(_))
+    ));
+    assert_eq!(
+        store.list_parts(BUCKET, KEY, &upload_id).await.unwrap(),
+        parts
+    );
+}
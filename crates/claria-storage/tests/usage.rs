@@ -0,0 +1,74 @@
+use claria_storage::local::LocalStore;
+use claria_storage::objects;
+use claria_storage::usage;
+
+const BUCKET: &str = "123456789012-claria-data";
+
+#[tokio::test]
+async fn usage_breaks_down_clients_and_prefixes() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let id = uuid::Uuid::new_v4();
+    let other = uuid::Uuid::new_v4();
+    let intake = claria_core::s3_keys::client_record_file(id, "intake.pdf");
+    let chat = claria_core::s3_keys::chat_history(id, uuid::Uuid::new_v4());
+
+    objects::put_object(
+        &store,
+        BUCKET,
+        &claria_core::s3_keys::client(id),
+        vec![0; 10],
+        None,
+    )
+    .await
+    .unwrap();
+    objects::put_object(&store, BUCKET, &intake, vec![0; 100], None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &intake, vec![0; 120], None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &format!("{intake}.text"), vec![0; 30], None)
+        .await
+        .unwrap();
+    objects::put_object(&store, BUCKET, &chat, vec![0; 40], None)
+        .await
+        .unwrap();
+    // A text file whose name happens to end in `.text` is not a sidecar.
+    let notes = claria_core::s3_keys::client_record_file(id, "notes.text");
+    objects::put_object(&store, BUCKET, &notes, vec![0; 5], None)
+        .await
+        .unwrap();
+    let old = claria_core::s3_keys::client_record_file(other, "old.txt");
+    objects::put_object(&store, BUCKET, &old, vec![0; 50], None)
+        .await
+        .unwrap();
+    objects::delete_object(&store, BUCKET, &old).await.unwrap();
+    objects::put_object(
+        &store,
+        BUCKET,
+        claria_core::s3_keys::SYSTEM_PROMPT,
+        vec![0; 7],
+        None,
+    )
+    .await
+    .unwrap();
+
+    let client = usage::client_usage(&store, BUCKET, id).await.unwrap();
+    assert_eq!(client.objects, 5);
+    assert_eq!(client.current_bytes, 10 + 120 + 30 + 40 + 5);
+    assert_eq!(client.sidecar_bytes, 30);
+    assert_eq!(client.chat_history_bytes, 40);
+    assert_eq!(client.noncurrent_versions, 1);
+    assert_eq!(client.noncurrent_bytes, 100);
+    assert_eq!(client.delete_markers, 0);
+
+    let report = usage::bucket_usage(&store, BUCKET).await.unwrap();
+    assert_eq!(report.clients[&id], client);
+    let deleted = &report.clients[&other];
+    assert_eq!(deleted.objects, 0);
+    assert_eq!(deleted.noncurrent_bytes, 50);
+    assert_eq!(deleted.delete_markers, 1);
+    assert_eq!(report.other["claria-prompts/"].current_bytes, 7);
+    assert_eq!(report.total.total_bytes(), 205 + 100 + 50 + 7);
+}